@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+use crate::types::query_tuning::QueryTuning;
+
+/// Portal admin JSON-RPC endpoints, used for changing the configuration of the running node.
+#[rpc(client, server, namespace = "portal")]
+pub trait AdminApi {
+    /// Updates the storage capacity (in megabytes) of the provided subnetwork. If none is provided,
+    /// the capacity is the total storage capacity, which is split between the enabled subnetworks
+    /// according to `--storage-allocation`. Lowering the capacity prunes the content if necessary.
+    #[method(name = "setStorageCapacity")]
+    async fn set_storage_capacity(
+        &self,
+        capacity_mb: u64,
+        subnetwork: Option<String>,
+    ) -> RpcResult<bool>;
+
+    /// Updates the query parameters that are set in `tuning`, of the provided subnetwork, or of
+    /// all enabled subnetworks if none is provided. Returns the resulting query parameters of each
+    /// updated subnetwork.
+    #[method(name = "setQueryTuning")]
+    async fn set_query_tuning(
+        &self,
+        tuning: QueryTuning,
+        subnetwork: Option<String>,
+    ) -> RpcResult<HashMap<String, QueryTuning>>;
+}
@@ -5,7 +5,7 @@ use crate::{
         enr::Enr,
         portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
     },
-    BeaconContentValue, RoutingTableInfo,
+    BeaconContentValue, RoutingTableDetail, RoutingTableInfo,
 };
 use discv5::enr::NodeId;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
@@ -17,6 +17,11 @@ pub trait BeaconNetworkApi {
     #[method(name = "beaconRoutingTableInfo")]
     async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo>;
 
+    /// Returns the details of each node in the overlay routing table, including the activity
+    /// observed for the node.
+    #[method(name = "beaconRoutingTableDetail")]
+    async fn routing_table_detail(&self) -> RpcResult<RoutingTableDetail>;
+
     /// Returns the node data radios
     #[method(name = "beaconRadius")]
     async fn radius(&self) -> RpcResult<DataRadius>;
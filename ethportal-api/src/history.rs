@@ -2,10 +2,13 @@ use crate::{
     types::{
         content_key::history::HistoryContentKey,
         enr::Enr,
-        history::{ContentInfo, PaginateLocalContentInfo, TraceContentInfo},
+        history::{
+            ContentInfo, GetRangeInfo, HistoryContentType, PaginateLocalContentInfo,
+            TraceContentInfo,
+        },
         portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
     },
-    HistoryContentValue, RoutingTableInfo,
+    HistoryContentValue, RoutingTableDetail, RoutingTableInfo,
 };
 use discv5::enr::NodeId;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
@@ -17,6 +20,11 @@ pub trait HistoryNetworkApi {
     #[method(name = "historyRoutingTableInfo")]
     async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo>;
 
+    /// Returns the details of each node in the overlay routing table, including the activity
+    /// observed for the node.
+    #[method(name = "historyRoutingTableDetail")]
+    async fn routing_table_detail(&self) -> RpcResult<RoutingTableDetail>;
+
     /// Returns the node data radios
     #[method(name = "historyRadius")]
     async fn radius(&self) -> RpcResult<DataRadius>;
@@ -80,6 +88,17 @@ pub trait HistoryNetworkApi {
         limit: u64,
     ) -> RpcResult<PaginateLocalContentInfo>;
 
+    /// Fetch the selected content types for the inclusive range of pre-merge block numbers.
+    /// Block hashes are resolved through the epoch accumulators. Large ranges are split into
+    /// pages, the response contains the block number to continue from.
+    #[method(name = "historyGetRange")]
+    async fn get_range(
+        &self,
+        start_block: u64,
+        end_block: u64,
+        content_types: Vec<HistoryContentType>,
+    ) -> RpcResult<GetRangeInfo>;
+
     /// Send the provided content value to interested peers. Clients may choose to send to some or
     /// all peers. Return the number of peers that the content was gossiped to.
     #[method(name = "historyGossip")]
@@ -109,6 +128,16 @@ pub trait HistoryNetworkApi {
         content_value: Option<HistoryContentValue>,
     ) -> RpcResult<AcceptInfo>;
 
+    /// Send an OFFER request with the provided content value to the designated peer. Return
+    /// tracing info detailing whether the content was accepted and transferred.
+    #[method(name = "historyTraceOffer")]
+    async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+    ) -> RpcResult<TraceGossipInfo>;
+
     /// Store content key with a content data to the local database.
     #[method(name = "historyStore")]
     async fn store(
@@ -7,12 +7,14 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod admin;
 mod beacon;
 mod dashboard;
 pub mod discv5;
 mod eth;
 mod history;
 pub mod state;
+mod subscription;
 #[cfg(test)]
 mod test_utils;
 pub mod types;
@@ -20,10 +22,12 @@ pub mod utils;
 mod web3;
 
 pub use crate::discv5::{Discv5ApiClient, Discv5ApiServer};
+pub use admin::{AdminApiClient, AdminApiServer};
 pub use beacon::{BeaconNetworkApiClient, BeaconNetworkApiServer};
 pub use eth::{EthApiClient, EthApiServer};
 pub use history::{HistoryNetworkApiClient, HistoryNetworkApiServer};
 pub use state::{StateNetworkApiClient, StateNetworkApiServer};
+pub use subscription::{PortalSubscriptionApiClient, PortalSubscriptionApiServer};
 pub use web3::{Web3ApiClient, Web3ApiServer};
 
 pub use types::content_key::{
@@ -5,7 +5,7 @@ use crate::{
         portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
         state::{ContentInfo, PaginateLocalContentInfo, TraceContentInfo},
     },
-    RoutingTableInfo, StateContentValue,
+    RoutingTableDetail, RoutingTableInfo, StateContentValue,
 };
 use discv5::enr::NodeId;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
@@ -17,6 +17,11 @@ pub trait StateNetworkApi {
     #[method(name = "stateRoutingTableInfo")]
     async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo>;
 
+    /// Returns the details of each node in the overlay routing table, including the activity
+    /// observed for the node.
+    #[method(name = "stateRoutingTableDetail")]
+    async fn routing_table_detail(&self) -> RpcResult<RoutingTableDetail>;
+
     /// Returns the node data radios
     #[method(name = "stateRadius")]
     async fn radius(&self) -> RpcResult<DataRadius>;
@@ -102,6 +107,16 @@ pub trait StateNetworkApi {
         content_value: Option<StateContentValue>,
     ) -> RpcResult<AcceptInfo>;
 
+    /// Send an OFFER request with the provided content value to the designated peer. Return
+    /// tracing info detailing whether the content was accepted and transferred.
+    #[method(name = "stateTraceOffer")]
+    async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> RpcResult<TraceGossipInfo>;
+
     /// Store content key with a content data to the local database.
     #[method(name = "stateStore")]
     async fn store(
@@ -0,0 +1,15 @@
+use crate::types::subscription::{SubscriptionEvent, SubscriptionKind};
+use jsonrpsee::{core::SubscriptionResult, proc_macros::rpc};
+
+/// Portal event subscription JSON-RPC endpoints, available on the WebSocket and IPC transports.
+#[rpc(client, server, namespace = "portal")]
+pub trait PortalSubscriptionApi {
+    /// Subscribes to the events of the provided kind. The events are sent as
+    /// `portal_subscription` notifications, until `portal_unsubscribe` is called.
+    #[subscription(
+        name = "subscribe" => "subscription",
+        unsubscribe = "unsubscribe",
+        item = SubscriptionEvent
+    )]
+    async fn subscribe(&self, kind: SubscriptionKind) -> SubscriptionResult;
+}
@@ -1,6 +1,7 @@
-use std::str::FromStr;
+use std::{fmt, fs, path::Path, str::FromStr};
 
 use anyhow::anyhow;
+use data_encoding::BASE32_NOPAD;
 
 use crate::types::enr::Enr;
 
@@ -80,6 +81,95 @@ pub enum Bootnodes {
     // DEFAULT_BOOTNODES
     None,
     Custom(Vec<Bootnode>),
+    /// The nodes of an EIP-1459 ENR tree, which are resolved at startup and refreshed
+    /// periodically.
+    EnrTree(EnrTreeLink),
+}
+
+impl Bootnodes {
+    /// Reads the bootnodes from a file, with one ENR per line. Empty lines and the text after a
+    /// `#` are ignored, and a comment after an ENR is used as its alias.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path).map_err(|err| {
+            anyhow!(
+                "Unable to read bootnode file {}: {err}. Bootnodes must be a comma-separated list of ENRs starting with 'enr:', or the path of a file with one ENR per line",
+                path.display()
+            )
+        })?;
+        let mut bootnodes = vec![];
+        for (index, line) in content.lines().enumerate() {
+            let (enr, comment) = match line.split_once('#') {
+                Some((enr, comment)) => (enr.trim(), comment.trim()),
+                None => (line.trim(), ""),
+            };
+            if enr.is_empty() {
+                continue;
+            }
+            let enr = Enr::from_str(enr).map_err(|err| {
+                anyhow!(
+                    "Invalid ENR on line {} of bootnode file {}: {err}",
+                    index + 1,
+                    path.display()
+                )
+            })?;
+            let mut bootnode = Bootnode::from(enr);
+            if !comment.is_empty() {
+                bootnode.alias = comment.to_string();
+            }
+            bootnodes.push(bootnode);
+        }
+        Ok(Bootnodes::Custom(bootnodes))
+    }
+}
+
+/// The prefix of an EIP-1459 ENR tree link.
+pub const ENR_TREE_PREFIX: &str = "enrtree://";
+
+/// A link to an EIP-1459 ENR tree: `enrtree://<public key>@<domain>`, where the public key is the
+/// base32 encoded compressed secp256k1 key that signs the tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnrTreeLink {
+    pub public_key: [u8; 33],
+    pub domain: String,
+}
+
+impl FromStr for EnrTreeLink {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let link = s
+            .strip_prefix(ENR_TREE_PREFIX)
+            .ok_or_else(|| anyhow!("ENR tree link must start with {ENR_TREE_PREFIX}"))?;
+        let (public_key, domain) = link
+            .split_once('@')
+            .ok_or_else(|| anyhow!("ENR tree link must have the form enrtree://<key>@<domain>"))?;
+        if domain.is_empty() {
+            return Err(anyhow!("ENR tree link has an empty domain"));
+        }
+        let public_key = BASE32_NOPAD
+            .decode(public_key.to_ascii_uppercase().as_bytes())
+            .map_err(|err| anyhow!("Invalid ENR tree public key encoding: {err}"))?;
+        secp256k1::PublicKey::from_slice(&public_key)
+            .map_err(|err| anyhow!("Invalid ENR tree public key: {err}"))?;
+        let public_key = public_key
+            .try_into()
+            .map_err(|_| anyhow!("ENR tree public key must be compressed"))?;
+        Ok(Self {
+            public_key,
+            domain: domain.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for EnrTreeLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{ENR_TREE_PREFIX}{}@{}",
+            BASE32_NOPAD.encode(&self.public_key),
+            self.domain
+        )
+    }
 }
 
 impl From<Enr> for Bootnode {
@@ -100,7 +190,8 @@ impl From<Bootnodes> for Vec<Enr> {
     fn from(bootnodes: Bootnodes) -> Self {
         match bootnodes {
             Bootnodes::Default => DEFAULT_BOOTNODES.iter().map(|bn| bn.enr.clone()).collect(),
-            Bootnodes::None => vec![],
+            // The nodes of an ENR tree are only known once the tree is resolved.
+            Bootnodes::None | Bootnodes::EnrTree(_) => vec![],
             Bootnodes::Custom(bootnodes) => bootnodes.iter().map(|bn| bn.enr.clone()).collect(),
         }
     }
@@ -113,6 +204,9 @@ impl FromStr for Bootnodes {
         match s {
             "default" => Ok(Bootnodes::Default),
             "none" => Ok(Bootnodes::None),
+            _ if s.starts_with(ENR_TREE_PREFIX) => Ok(Bootnodes::EnrTree(s.parse()?)),
+            // Anything else than a list of ENRs is a path to a bootnode file.
+            _ if !s.starts_with("enr:") => Bootnodes::from_file(Path::new(s)),
             _ => {
                 let bootnodes: Result<Vec<Enr>, _> = s.split(',').map(Enr::from_str).collect();
                 match bootnodes {
@@ -133,6 +227,7 @@ mod test {
     use super::*;
     use crate::types::cli::TrinConfig;
     use rstest::rstest;
+    use tempfile::TempDir;
 
     #[test_log::test]
     fn test_bootnodes_default_with_testnet_bootnodes() {
@@ -150,6 +245,79 @@ mod test {
         assert_eq!(bootnodes.len(), 0);
     }
 
+    const ENR_ONE: &str = "enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg";
+    const ENR_TWO: &str = "enr:-IS4QPUT9hwV4YfNTxazR2ltch4qKzvX_HwxQBw8gUN3q1MDfNyaD1EHc1wQZRTUzQQD-RVYx3h4nA1Sqk0Wx9DwzNABgmlkgnY0gmlwhM69ZOyJc2VjcDI1NmsxoQLaI-m2CDIjpwcnUf1ESspvOctJLpIrLA8AZ4zbo_1bFIN1ZHCCIyg";
+    const ENR_TREE: &str =
+        "enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE@all.mainnet.ethdisco.net";
+
+    fn write_bootnode_file(temp_dir: &TempDir, name: &str, content: &str) -> std::path::PathBuf {
+        let path = temp_dir.path().join(format!("{name}.txt"));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test_log::test]
+    fn test_bootnodes_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_bootnode_file(
+            &temp_dir,
+            "bootnodes",
+            &format!("# Devnet bootnodes\n\n{ENR_ONE} # devnet-1\n  {ENR_TWO}\n"),
+        );
+        let config =
+            TrinConfig::new_from(["trin", "--bootnodes", path.to_str().unwrap()].iter()).unwrap();
+        let Bootnodes::Custom(bootnodes) = config.bootnodes else {
+            panic!("Bootnodes should be custom");
+        };
+        assert_eq!(bootnodes.len(), 2);
+        assert_eq!(bootnodes[0].enr, Enr::from_str(ENR_ONE).unwrap());
+        assert_eq!(bootnodes[0].alias, "devnet-1");
+        assert_eq!(bootnodes[1].enr, Enr::from_str(ENR_TWO).unwrap());
+        assert_eq!(bootnodes[1].alias, "custom");
+    }
+
+    #[test_log::test]
+    fn test_bootnodes_file_with_invalid_enr() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_bootnode_file(
+            &temp_dir,
+            "invalid-bootnodes",
+            &format!("{ENR_ONE}\nenr:invalid\n"),
+        );
+        let err = Bootnodes::from_file(&path).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test_log::test]
+    fn test_bootnodes_missing_file() {
+        let err = TrinConfig::new_from(["trin", "--bootnodes", "enr-IS4QBISSFfBzsBrjq"].iter())
+            .unwrap_err();
+        assert!(err.to_string().contains("comma-separated list of ENRs"));
+        assert!(err.to_string().contains("path of a file"));
+    }
+
+    #[test_log::test]
+    fn test_bootnodes_enr_tree() {
+        let config = TrinConfig::new_from(["trin", "--bootnodes", ENR_TREE].iter()).unwrap();
+        let Bootnodes::EnrTree(link) = config.bootnodes.clone() else {
+            panic!("Bootnodes should be an ENR tree");
+        };
+        assert_eq!(link.domain, "all.mainnet.ethdisco.net");
+        assert_eq!(link.to_string(), ENR_TREE);
+        let bootnodes: Vec<Enr> = config.bootnodes.into();
+        assert!(bootnodes.is_empty());
+    }
+
+    #[rstest]
+    #[case("enrtree://all.mainnet.ethdisco.net")]
+    #[case("enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE@")]
+    #[case(
+        "enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDP@all.mainnet.ethdisco.net"
+    )]
+    fn test_invalid_enr_tree_link(#[case] link: &str) {
+        assert!(EnrTreeLink::from_str(link).is_err());
+    }
+
     #[rstest]
     #[case("invalid")]
     #[case("enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg,invalid")]
@@ -1,14 +1,30 @@
-use alloy_primitives::B256;
+use alloy_primitives::{Bytes, B256, U256};
 use clap::{
     arg,
     error::{Error, ErrorKind},
-    Args, Parser, Subcommand,
+    parser::ValueSource,
+    Arg, ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand,
+};
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
 };
-use std::{env, ffi::OsString, fmt, net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
 use trin_utils::build_info;
 use url::Url;
 
-use crate::types::bootnodes::Bootnodes;
+use crate::types::{
+    bootnodes::Bootnodes,
+    config_file::{arg_values, toml_value, ConfigFile, OverlayTuning, PruningTuning},
+    distance::Distance,
+    query_tuning::SubnetworkQueryTuning,
+    storage_allocation::StorageAllocation,
+    utp_tuning::UtpConnectionTuning,
+};
 
 pub const DEFAULT_WEB3_IPC_PATH: &str = "/tmp/trin-jsonrpc.ipc";
 pub const DEFAULT_WEB3_HTTP_ADDRESS: &str = "http://127.0.0.1:8545/";
@@ -16,6 +32,13 @@ pub const DEFAULT_WEB3_HTTP_PORT: u16 = 8545;
 pub const DEFAULT_WEB3_WS_PORT: u16 = 8546;
 pub const DEFAULT_DISCOVERY_PORT: u16 = 9009;
 pub const DEFAULT_UTP_TRANSFER_LIMIT: usize = 50;
+pub const DEFAULT_UTP_TRANSFER_LIMIT_MIN: usize = 10;
+pub const DEFAULT_UTP_TRANSFER_LIMIT_MAX: usize = 200;
+pub const DEFAULT_UTP_MIN_THROUGHPUT: u64 = 1024;
+pub const DEFAULT_UTP_STALL_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_UTP_INBOUND_TIMEOUT_SECS: u64 = 300;
+pub const DEFAULT_UTP_MAX_STREAMS_PER_PEER: usize = 8;
+pub const DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS: u64 = 120;
 pub const BEACON_NETWORK: &str = "beacon";
 pub const HISTORY_NETWORK: &str = "history";
 pub const STATE_NETWORK: &str = "state";
@@ -23,6 +46,13 @@ const DEFAULT_SUBNETWORKS: &str = "history";
 pub const DEFAULT_NETWORK: &str = "mainnet";
 pub const DEFAULT_STORAGE_CAPACITY_MB: &str = "100";
 pub const DEFAULT_WEB3_TRANSPORT: &str = "ipc";
+pub const DEFAULT_STORAGE_BACKEND: &str = "sqlite";
+/// Prefix of the env vars that set CLI flags, e.g. `TRIN_WEB3_TRANSPORT` sets `--web3-transport`.
+pub const ENV_VAR_PREFIX: &str = "TRIN_";
+/// CLI flags that can't be set in the config file or with env vars.
+const NON_CONFIGURABLE_ARGS: [&str; 3] = ["config", "help", "version"];
+/// CLI flags whose values are secret, and so are omitted from the effective config.
+const SECRET_ARGS: [&str; 1] = ["private_key"];
 
 use crate::dashboard::grafana::{GrafanaAPI, DASHBOARD_TEMPLATES};
 
@@ -55,6 +85,107 @@ impl FromStr for Web3TransportType {
     }
 }
 
+/// The IP address families that discovery listens on and advertises in the ENR.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum IpMode {
+    /// Listen on IPv4 only.
+    #[default]
+    Ipv4,
+    /// Listen on IPv6 only.
+    Ipv6,
+    /// Listen on both IPv4 and IPv6, on the same port.
+    DualStack,
+}
+
+impl IpMode {
+    /// Returns whether IPv4 is enabled.
+    pub fn has_ipv4(&self) -> bool {
+        matches!(self, Self::Ipv4 | Self::DualStack)
+    }
+
+    /// Returns whether IPv6 is enabled.
+    pub fn has_ipv6(&self) -> bool {
+        matches!(self, Self::Ipv6 | Self::DualStack)
+    }
+
+    /// Returns whether the address family of `ip` is enabled.
+    pub fn supports(&self, ip: &IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(_) => self.has_ipv4(),
+            IpAddr::V6(_) => self.has_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for IpMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Ipv4 => write!(f, "ipv4"),
+            Self::Ipv6 => write!(f, "ipv6"),
+            Self::DualStack => write!(f, "dual-stack"),
+        }
+    }
+}
+
+impl FromStr for IpMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ipv4" => Ok(Self::Ipv4),
+            "ipv6" => Ok(Self::Ipv6),
+            "dual-stack" => Ok(Self::DualStack),
+            _ => Err("Invalid ip-mode arg. Expected 'ipv4', 'ipv6' or 'dual-stack'"),
+        }
+    }
+}
+
+/// The strategy used to select the peers that content is gossiped to. Only peers whose radius
+/// covers the content are selected.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum GossipStrategy {
+    /// The 4 peers closest to the content, and 4 randomly selected farther peers.
+    #[default]
+    Random,
+    /// The `N` peers closest to the content.
+    Closest(usize),
+    /// The `N` peers closest to the content. If fewer than `N` peers in the routing table are
+    /// interested in the content, a FINDNODES query is run to find more peers close to it.
+    Hybrid(usize),
+}
+
+impl fmt::Display for GossipStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Random => write!(f, "random"),
+            Self::Closest(n) => write!(f, "closest:{n}"),
+            Self::Hybrid(n) => write!(f, "hybrid:{n}"),
+        }
+    }
+}
+
+impl FromStr for GossipStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const INVALID: &str =
+            "Invalid gossip-strategy arg. Expected 'random', 'closest:N' or 'hybrid:N'";
+        if s == "random" {
+            return Ok(Self::Random);
+        }
+        let (strategy, n) = s.split_once(':').ok_or(INVALID)?;
+        let n = match n.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(format!("{INVALID}, with N greater than 0")),
+        };
+        match strategy {
+            "closest" => Ok(Self::Closest(n)),
+            "hybrid" => Ok(Self::Hybrid(n)),
+            _ => Err(INVALID.to_string()),
+        }
+    }
+}
+
 const APP_NAME: &str = "trin";
 const VERSION: &str = const_format::formatcp!(
     "{version}-{hash} {build_os} {rust_version}",
@@ -110,17 +241,24 @@ pub struct TrinConfig {
     #[arg(
         default_value = "default",
         long = "bootnodes",
-        help = "One or more comma-delimited base64-encoded ENR's or multiaddr strings of peers to initially add to the local routing table"
+        help = "One or more comma-delimited base64-encoded ENR's or multiaddr strings of peers to initially add to the local routing table, a path to a file with one ENR per line, or an EIP-1459 ENR tree link (enrtree://<key>@<domain>), which is refreshed periodically"
     )]
     pub bootnodes: Bootnodes,
 
     #[arg(
         long = "external-address",
         group = "external-ips",
-        help = "(Only use this if you are behind a NAT) The address which will be advertised to peers (in an ENR). Changing it does not change which port or address trin binds to. Port number is required, ex: 127.0.0.1:9001"
+        help = "(Only use this if you are behind a NAT) The address which will be advertised to peers (in an ENR). Changing it does not change which port or address trin binds to. Port number is required, ex: 127.0.0.1:9001. Its address family must be enabled by --ip-mode. In dual-stack mode, the address of the other family is still detected"
     )]
     pub external_addr: Option<SocketAddr>,
 
+    #[arg(
+        long = "ip-mode",
+        help = "The IP address families that discovery listens on and advertises: 'ipv4', 'ipv6' or 'dual-stack'",
+        default_value_t = IpMode::Ipv4,
+    )]
+    pub ip_mode: IpMode,
+
     #[arg(
         long = "no-stun",
         group = "external-ips",
@@ -137,10 +275,16 @@ pub struct TrinConfig {
     #[arg(
         long = "unsafe-private-key",
         value_parser = check_private_key_length,
-        help = "Hex encoded 32 byte private key (with 0x prefix) (considered unsafe as it's stored in terminal history - keyfile support coming soon)"
+        help = "Hex encoded 32 byte private key (with 0x prefix) (considered unsafe as it's stored in terminal history - use `trin key import` to store it in the encrypted keystore instead)"
     )]
     pub private_key: Option<B256>,
 
+    #[arg(
+        long = "keystore-password-file",
+        help = "File containing the password of the encrypted keystore of the node private key. If not provided, the password is read from the TRIN_KEYSTORE_PASSWORD env var. If a password is provided, a private key stored as plaintext is migrated into the keystore"
+    )]
+    pub keystore_password_file: Option<PathBuf>,
+
     #[arg(
         long,
         value_parser = check_trusted_block_root,
@@ -158,7 +302,7 @@ pub struct TrinConfig {
 
     #[arg(
         long = "network",
-            help = "Choose mainnet or testnet",
+            help = "Choose mainnet, testnet, or the path of a TOML file with the spec of a private network",
             default_value = DEFAULT_NETWORK,
             value_parser = network_parser
         )]
@@ -172,6 +316,35 @@ pub struct TrinConfig {
     )]
     pub mb: u32,
 
+    #[arg(
+        long = "storage-allocation",
+        help = "Comma-separated list of storage capacities allocated to subnetworks, either in megabytes or as a percentage of --mb (e.g. history:80%,state:20%). Subnetworks that are not listed share the unallocated capacity evenly"
+    )]
+    pub storage_allocation: Option<StorageAllocation>,
+
+    #[arg(
+        long = "fixed-radius",
+        value_parser = radius_log2_parser,
+        conflicts_with = "min_radius",
+        help = "Use a fixed data radius of 2^N-1 (N in range 0-256), instead of the radius derived from storage usage"
+    )]
+    pub fixed_radius: Option<Distance>,
+
+    #[arg(
+        long = "min-radius",
+        value_parser = radius_log2_parser,
+        help = "The lowest data radius, 2^N-1 (N in range 0-256), that is used when radius is derived from storage usage"
+    )]
+    pub min_radius: Option<Distance>,
+
+    #[arg(
+        long = "storage-backend",
+        default_value = DEFAULT_STORAGE_BACKEND,
+        value_parser = ["sqlite", "segment"],
+        help = "The backend used for storing content: sqlite or segment (append-only file with in-memory index). When switching backends, the stored content is moved into the new backend on startup"
+    )]
+    pub storage_backend: String,
+
     #[arg(
         long = "enable-metrics-with-url",
         help = "Enable prometheus metrics reporting (provide local IP/Port from which your Prometheus server is configured to fetch metrics)"
@@ -209,6 +382,99 @@ pub struct TrinConfig {
     )]
     pub utp_transfer_limit: usize,
 
+    #[arg(
+        long = "utp-adaptive-transfer-limit",
+        help = "Adapt the limit of uTP transfers to the success rate and latency of the transfers, and to the load of the host, starting from --utp-transfer-limit"
+    )]
+    pub utp_adaptive_transfer_limit: bool,
+
+    #[arg(
+        long = "utp-transfer-limit-min",
+        help = "The lower bound of the adaptive limit of uTP transfers",
+        default_value_t = DEFAULT_UTP_TRANSFER_LIMIT_MIN,
+        value_parser = clap::value_parser!(u64).range(1..).map(|limit| limit as usize),
+        requires = "utp_adaptive_transfer_limit"
+    )]
+    pub utp_transfer_limit_min: usize,
+
+    #[arg(
+        long = "utp-transfer-limit-max",
+        help = "The upper bound of the adaptive limit of uTP transfers",
+        default_value_t = DEFAULT_UTP_TRANSFER_LIMIT_MAX,
+        value_parser = clap::value_parser!(u64).range(1..).map(|limit| limit as usize),
+        requires = "utp_adaptive_transfer_limit"
+    )]
+    pub utp_transfer_limit_max: usize,
+
+    #[arg(
+        long = "utp-min-throughput",
+        help = "The throughput floor of an outbound uTP stream, in bytes per second, measured on the data acknowledged by the peer (0 for no floor). Outbound streams that don't reach the floor within --utp-stall-timeout are aborted",
+        default_value_t = DEFAULT_UTP_MIN_THROUGHPUT
+    )]
+    pub utp_min_throughput: u64,
+
+    #[arg(
+        long = "utp-stall-timeout",
+        help = "The number of seconds an outbound uTP stream may take, in addition to the time it takes to send its data at --utp-min-throughput, before the stream is aborted",
+        default_value_t = DEFAULT_UTP_STALL_TIMEOUT_SECS,
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub utp_stall_timeout: u64,
+
+    #[arg(
+        long = "utp-inbound-timeout",
+        help = "The maximum number of seconds an inbound uTP stream may take. Inbound streams only report their progress when complete, so they are limited by this timeout instead of the throughput floor. Streams exceeding it are counted as stalls of the peer",
+        default_value_t = DEFAULT_UTP_INBOUND_TIMEOUT_SECS,
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub utp_inbound_timeout: u64,
+
+    #[arg(
+        long = "utp-max-streams-per-peer",
+        help = "The maximum number of concurrent uTP streams with a single peer, in each direction",
+        default_value_t = DEFAULT_UTP_MAX_STREAMS_PER_PEER,
+        value_parser = clap::value_parser!(u64).range(1..).map(|limit| limit as usize),
+    )]
+    pub utp_max_streams_per_peer: usize,
+
+    #[command(flatten)]
+    pub utp_conn_tuning: UtpConnectionTuning,
+
+    #[arg(
+        long = "gossip-strategy",
+        help = "The selection of peers that content is gossiped to: 'random' (4 closest and 4 random interested peers), 'closest:N' (N closest interested peers) or 'hybrid:N' (as 'closest:N', with a FINDNODES query if fewer than N interested peers are known)",
+        default_value_t = GossipStrategy::Random,
+    )]
+    pub gossip_strategy: GossipStrategy,
+
+    #[arg(
+        long = "accept-queue-timeout",
+        help = "The number of seconds an accepted content key is kept in the accept queue, to collect fallback peers, before the offer is considered lost",
+        default_value_t = DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS,
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub accept_queue_timeout: u64,
+
+    #[arg(
+        long = "query-tuning",
+        help = "Comma-separated list of query tuning of subnetworks, as <subnetwork>:<key>=<value> (e.g. history:query-parallelism=8,history:query-timeout-ms=30000). Keys are query-parallelism, query-timeout-ms, query-peer-timeout-ms, query-num-results and findnodes-query-distances-per-peer"
+    )]
+    pub query_tuning: Option<SubnetworkQueryTuning>,
+
+    #[arg(
+        long = "config",
+        help = "TOML file that sets CLI flags (by long name, e.g. web3-transport = \"http\"), and the [overlay] and [pruning] tuning. Flags set on the command line or with TRIN_* env vars (e.g. TRIN_WEB3_TRANSPORT) take precedence over the file"
+    )]
+    pub config: Option<PathBuf>,
+
+    /// The overlay network tuning, which can only be set in the config file.
+    #[arg(skip)]
+    pub overlay: OverlayTuning,
+
+    /// The storage pruning tuning, which can only be set in the config file.
+    #[arg(skip)]
+    pub pruning: PruningTuning,
+
     #[command(subcommand)]
     pub command: Option<TrinConfigCommands>,
 }
@@ -224,9 +490,11 @@ impl Default for TrinConfig {
             discovery_port: DEFAULT_DISCOVERY_PORT,
             bootnodes: Bootnodes::Default,
             external_addr: None,
+            ip_mode: IpMode::Ipv4,
             no_stun: false,
             no_upnp: false,
             private_key: None,
+            keystore_password_file: None,
             trusted_block_root: None,
             portal_subnetworks: DEFAULT_SUBNETWORKS
                 .split(',')
@@ -235,6 +503,10 @@ impl Default for TrinConfig {
             mb: DEFAULT_STORAGE_CAPACITY_MB
                 .parse()
                 .expect("Parsing static DEFAULT_STORAGE_CAPACITY_MB to work"),
+            storage_allocation: None,
+            fixed_radius: None,
+            min_radius: None,
+            storage_backend: DEFAULT_STORAGE_BACKEND.to_string(),
             enable_metrics_with_url: None,
             ephemeral: false,
             disable_poke: false,
@@ -242,7 +514,21 @@ impl Default for TrinConfig {
             ws_port: DEFAULT_WEB3_WS_PORT,
             command: None,
             utp_transfer_limit: DEFAULT_UTP_TRANSFER_LIMIT,
+            utp_adaptive_transfer_limit: false,
+            utp_transfer_limit_min: DEFAULT_UTP_TRANSFER_LIMIT_MIN,
+            utp_transfer_limit_max: DEFAULT_UTP_TRANSFER_LIMIT_MAX,
+            utp_min_throughput: DEFAULT_UTP_MIN_THROUGHPUT,
+            utp_stall_timeout: DEFAULT_UTP_STALL_TIMEOUT_SECS,
+            utp_inbound_timeout: DEFAULT_UTP_INBOUND_TIMEOUT_SECS,
+            utp_max_streams_per_peer: DEFAULT_UTP_MAX_STREAMS_PER_PEER,
+            utp_conn_tuning: UtpConnectionTuning::default(),
+            gossip_strategy: GossipStrategy::Random,
+            accept_queue_timeout: DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS,
+            query_tuning: None,
             network: MAINNET.clone(),
+            config: None,
+            overlay: OverlayTuning::default(),
+            pruning: PruningTuning::default(),
         }
     }
 }
@@ -256,7 +542,32 @@ impl TrinConfig {
         I: Iterator<Item = T>,
         T: Into<OsString> + Clone,
     {
-        let config = Self::try_parse_from(args)?;
+        Self::new_from_env(args, |name| env::var(name).ok())
+    }
+
+    /// Parses the config from the args, reading the env vars with `env_var`.
+    fn new_from_env<I, T>(
+        args: I,
+        env_var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, clap::Error>
+    where
+        I: Iterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args: Vec<OsString> = args.map(Into::into).collect();
+        let (matches, config_file) = Self::merged_arg_matches(args, env_var)?;
+        let mut config = Self::from_arg_matches(&matches)?;
+        if let Some(config_file) = config_file {
+            config.overlay = config_file.overlay;
+            config.pruning = config_file.pruning;
+        }
+        // The query tuning of the command line takes precedence over the config file.
+        if let Some(query_tuning) = &config.query_tuning {
+            let overlay = &mut config.overlay;
+            overlay.history = overlay.history.merge(&query_tuning.get(HISTORY_NETWORK));
+            overlay.state = overlay.state.merge(&query_tuning.get(STATE_NETWORK));
+            overlay.beacon = overlay.beacon.merge(&query_tuning.get(BEACON_NETWORK));
+        }
 
         if let Some(TrinConfigCommands::CreateDashboard(dashboard_config)) = config.command {
             if let Err(err) = create_dashboard(dashboard_config) {
@@ -266,6 +577,29 @@ impl TrinConfig {
             std::process::exit(0);
         }
 
+        if let Some(storage_allocation) = &config.storage_allocation {
+            for subnetwork in storage_allocation.subnetworks() {
+                if !config.portal_subnetworks.contains(subnetwork) {
+                    return Err(Error::raw(
+                        ErrorKind::ArgumentConflict,
+                        format!(
+                            "Storage is allocated to {subnetwork} subnetwork, which is not enabled"
+                        ),
+                    ));
+                }
+            }
+            if let Err(err) = storage_allocation.validate(config.mb.into()) {
+                return Err(Error::raw(ErrorKind::ArgumentConflict, err.to_string()));
+            }
+        }
+
+        if config.utp_transfer_limit_min > config.utp_transfer_limit_max {
+            return Err(Error::raw(
+                ErrorKind::ArgumentConflict,
+                "--utp-transfer-limit-min must not be greater than --utp-transfer-limit-max",
+            ));
+        }
+
         match config.web3_transport {
             Web3TransportType::HTTP => {
                 match &config.web3_ipc_path.as_path().display().to_string()[..] {
@@ -290,6 +624,168 @@ impl TrinConfig {
         }
         Ok(config)
     }
+
+    /// Parses the args, and adds the values of the flags that are not set in the args, from the
+    /// env vars (read with `env_var`) or else from the config file. Returns the merged matches
+    /// and the config file.
+    fn merged_arg_matches(
+        args: Vec<OsString>,
+        env_var: impl Fn(&str) -> Option<String>,
+    ) -> Result<(ArgMatches, Option<ConfigFile>), clap::Error> {
+        let command = Self::command();
+        let matches = command.clone().try_get_matches_from(&args)?;
+        let config_file = match matches.get_one::<PathBuf>("config") {
+            Some(path) => Some(ConfigFile::load(path).map_err(|err| {
+                Error::raw(
+                    ErrorKind::InvalidValue,
+                    format!("Invalid config file {}: {err}\n", path.display()),
+                )
+            })?),
+            None => None,
+        };
+
+        let configurable_args: Vec<&Arg> = command
+            .get_arguments()
+            .filter(|arg| !NON_CONFIGURABLE_ARGS.contains(&arg.get_id().as_str()))
+            .collect();
+        if let Some(config_file) = &config_file {
+            for key in config_file.args.keys() {
+                if !configurable_args
+                    .iter()
+                    .any(|arg| arg.get_long() == Some(key.as_str()))
+                {
+                    return Err(Error::raw(
+                        ErrorKind::UnknownArgument,
+                        format!("Unknown flag {key} in config file\n"),
+                    ));
+                }
+            }
+        }
+
+        let mut extra_args = vec![];
+        for arg in configurable_args {
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            let is_set_on_command_line = |arg: &Arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            };
+            // Skip the flag if it, or a conflicting flag, is set on the command line.
+            if is_set_on_command_line(arg)
+                || command
+                    .get_arg_conflicts_with(arg)
+                    .into_iter()
+                    .any(is_set_on_command_line)
+            {
+                continue;
+            }
+            let name = format!("{ENV_VAR_PREFIX}{}", long.to_uppercase().replace('-', "_"));
+            let values = if let Some(value) = env_var(&name) {
+                vec![value]
+            } else if let Some(value) = config_file.as_ref().and_then(|file| file.arg(long)) {
+                arg_values(long, value)
+                    .map_err(|err| Error::raw(ErrorKind::InvalidValue, format!("{err}\n")))?
+            } else {
+                continue;
+            };
+            // Default values are left to clap, so that a dumped config doesn't set flags that
+            // require or conflict with other flags.
+            let default_values: Vec<String> = arg
+                .get_default_values()
+                .iter()
+                .map(|value| value.to_string_lossy().to_string())
+                .collect();
+            if values == default_values {
+                continue;
+            }
+            extra_args.extend(Self::flag_args(arg, long, values)?);
+        }
+        if extra_args.is_empty() {
+            return Ok((matches, config_file));
+        }
+
+        // The extra args are inserted before the args, so that they precede any subcommand.
+        let mut merged_args = args;
+        let tail = merged_args.split_off(1.min(merged_args.len()));
+        merged_args.extend(extra_args);
+        merged_args.extend(tail);
+        let matches = command.try_get_matches_from(merged_args)?;
+        Ok((matches, config_file))
+    }
+
+    /// Returns the command line args that set the flag to the values.
+    fn flag_args(arg: &Arg, long: &str, values: Vec<String>) -> Result<Vec<OsString>, clap::Error> {
+        if matches!(arg.get_action(), ArgAction::SetTrue) {
+            return match values.as_slice() {
+                [value] if value == "true" || value == "1" => Ok(vec![format!("--{long}").into()]),
+                [value] if value == "false" || value == "0" => Ok(vec![]),
+                _ => Err(Error::raw(
+                    ErrorKind::InvalidValue,
+                    format!("Invalid value of {long}: expected true or false\n"),
+                )),
+            };
+        }
+        match arg.get_value_delimiter() {
+            Some(delimiter) if values.len() > 1 => Ok(vec![format!(
+                "--{long}={}",
+                values.join(&delimiter.to_string())
+            )
+            .into()]),
+            _ => Ok(values
+                .into_iter()
+                .map(|value| format!("--{long}={value}").into())
+                .collect()),
+        }
+    }
+
+    /// Returns the effective values of the CLI flags, as set by the args, env vars, config file
+    /// or defaults, in the format of the config file. Flags without a value, and secret flags,
+    /// are omitted.
+    pub fn effective_args<I, T>(args: I) -> Result<toml::Table, clap::Error>
+    where
+        I: Iterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args: Vec<OsString> = args.map(Into::into).collect();
+        let (matches, _) = Self::merged_arg_matches(args, |name| env::var(name).ok())?;
+        let mut table = toml::Table::new();
+        for arg in Self::command().get_arguments() {
+            let id = arg.get_id().as_str();
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            if NON_CONFIGURABLE_ARGS.contains(&id) || SECRET_ARGS.contains(&id) {
+                continue;
+            }
+            if matches!(arg.get_action(), ArgAction::SetTrue) {
+                table.insert(long.to_string(), toml::Value::Boolean(matches.get_flag(id)));
+                continue;
+            }
+            let Some(raw_values) = matches.get_raw(id) else {
+                continue;
+            };
+            let values: Vec<toml::Value> = raw_values
+                .map(|value| toml_value(&value.to_string_lossy()))
+                .collect();
+            let value = match (arg.get_action(), values.as_slice()) {
+                (ArgAction::Append, _) => toml::Value::Array(values),
+                (_, [value]) => value.clone(),
+                _ => toml::Value::Array(values),
+            };
+            table.insert(long.to_string(), value);
+        }
+        Ok(table)
+    }
+
+    /// Returns the storage capacity (in megabytes) of the provided subnetwork.
+    pub fn storage_capacity_mb(&self, subnetwork: &str) -> u64 {
+        match &self.storage_allocation {
+            Some(storage_allocation) => {
+                storage_allocation.capacity_mb(subnetwork, self.mb.into(), &self.portal_subnetworks)
+            }
+            None => self.mb.into(),
+        }
+    }
 }
 
 pub fn check_private_key_length(private_key: &str) -> Result<B256, String> {
@@ -302,13 +798,30 @@ pub fn check_private_key_length(private_key: &str) -> Result<B256, String> {
     ))
 }
 
+/// Parses the network, either a built-in network or the path of a network spec file.
 pub fn network_parser(network_string: &str) -> Result<Arc<NetworkSpec>, String> {
     match network_string {
         "mainnet" => Ok(MAINNET.clone()),
         "testnet" => Ok(TESTNET.clone()),
-        _ => Err(format!(
-            "Not a valid network: {network_string}, must be 'testnet' or 'mainnet'"
-        )),
+        _ => NetworkSpec::from_file(Path::new(network_string))
+            .map(Arc::new)
+            .map_err(|err| {
+                format!(
+                    "Not a valid network: {network_string}, must be 'testnet', 'mainnet' or the path of a network spec file: {err}"
+                )
+            }),
+    }
+}
+
+/// Parses the radius from its base-2 logarithm, such that `N` represents radius `2^N-1`.
+pub fn radius_log2_parser(log2: &str) -> Result<Distance, String> {
+    let log2 = log2
+        .parse::<usize>()
+        .map_err(|err| format!("Invalid radius: {err}"))?;
+    match log2 {
+        0 => Ok(Distance::ZERO),
+        1..=256 => Ok(Distance::from(U256::MAX >> (256 - log2))),
+        _ => Err(format!("Invalid radius: {log2}, must be in range 0-256")),
     }
 }
 
@@ -346,6 +859,136 @@ impl fmt::Display for TrinConfig {
 #[allow(clippy::enum_variant_names)]
 pub enum TrinConfigCommands {
     CreateDashboard(DashboardConfig),
+    /// Inspect and maintain the node's database
+    Db(DbConfig),
+    /// Manage the node private key in the encrypted keystore
+    Key(KeyConfig),
+    /// Inspect the configuration
+    Config(ConfigCommandConfig),
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct ConfigCommandConfig {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum ConfigCommands {
+    /// Print the effective configuration, from the CLI flags, env vars, config file and defaults,
+    /// in the format of the config file
+    Dump,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct KeyConfig {
+    #[command(subcommand)]
+    pub command: KeyCommands,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum KeyCommands {
+    /// Generate a new private key, and store it in the keystore
+    Generate(KeyGenerateConfig),
+    /// Import a private key from a file, and store it in the keystore
+    Import(KeyImportConfig),
+    /// Export the private key, decrypted from the keystore, into a file
+    Export(KeyExportConfig),
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct KeyGenerateConfig {
+    #[arg(
+        long,
+        help = "Replace the existing private key, which changes the node id of the node"
+    )]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct KeyImportConfig {
+    #[arg(
+        long,
+        help = "The file containing the hex encoded 32 byte private key (with 0x prefix)"
+    )]
+    pub input: PathBuf,
+
+    #[arg(
+        long,
+        help = "Replace the existing private key, which changes the node id of the node"
+    )]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct KeyExportConfig {
+    #[arg(
+        long,
+        help = "The file into which the hex encoded private key is exported, readable only by its owner"
+    )]
+    pub output: PathBuf,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct DbConfig {
+    #[command(subcommand)]
+    pub command: DbCommands,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum DbCommands {
+    /// Check the integrity of the stored history and state content
+    Check(DbCheckConfig),
+    /// Export the stored content of a subnetwork into a file
+    Export(DbExportConfig),
+    /// Import the content from a file created with `trin db export`
+    Import(DbImportConfig),
+}
+
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+pub struct DbCheckConfig {
+    #[arg(long, help = "Delete corrupt entries and fix the usage stats")]
+    pub repair: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct DbExportConfig {
+    #[arg(
+        long,
+        default_value = HISTORY_NETWORK,
+        value_parser = [HISTORY_NETWORK, STATE_NETWORK],
+        help = "The subnetwork whose content is exported"
+    )]
+    pub subnetwork: String,
+
+    #[arg(long, help = "The file into which content is exported")]
+    pub output: PathBuf,
+
+    #[arg(
+        long,
+        help = "Export only content whose content key starts with the given bytes (0x-prefixed hex string)"
+    )]
+    pub content_key_prefix: Option<Bytes>,
+
+    #[arg(
+        long,
+        value_parser = radius_log2_parser,
+        help = "Export only content at distance of at least 2^N-1 (N in range 0-256) from the node id"
+    )]
+    pub min_distance: Option<Distance>,
+
+    #[arg(
+        long,
+        value_parser = radius_log2_parser,
+        help = "Export only content at distance of at most 2^N-1 (N in range 0-256) from the node id"
+    )]
+    pub max_distance: Option<Distance>,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct DbImportConfig {
+    #[arg(long, help = "The file created with `trin db export`")]
+    pub input: PathBuf,
 }
 
 #[derive(Args, Debug, Default, Clone, PartialEq)]
@@ -392,7 +1035,9 @@ pub fn create_dashboard(
 #[allow(clippy::unwrap_used)]
 mod test {
     use super::*;
+    use crate::types::query_tuning::QueryTuning;
     use std::net::{IpAddr, Ipv4Addr};
+    use tempfile::TempDir;
     use test_log::test;
 
     #[test]
@@ -528,6 +1173,63 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_ip_mode() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.ip_mode, IpMode::Ipv4);
+
+        let config = TrinConfig::new_from(["trin", "--ip-mode", "ipv6"].iter()).unwrap();
+        assert_eq!(config.ip_mode, IpMode::Ipv6);
+
+        let config = TrinConfig::new_from(["trin", "--ip-mode", "dual-stack"].iter()).unwrap();
+        assert_eq!(config.ip_mode, IpMode::DualStack);
+        assert_eq!(config.ip_mode.to_string(), "dual-stack");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid ip-mode arg")]
+    fn test_ip_mode_invalid() {
+        TrinConfig::new_from(["trin", "--ip-mode", "ipv5"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_key_commands() {
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--keystore-password-file",
+                "/tmp/password",
+                "key",
+                "generate",
+                "--force",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            config.keystore_password_file,
+            Some(PathBuf::from("/tmp/password"))
+        );
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Key(KeyConfig {
+                command: KeyCommands::Generate(KeyGenerateConfig { force: true })
+            }))
+        );
+
+        let config =
+            TrinConfig::new_from(["trin", "key", "export", "--output", "/tmp/key.hex"].iter())
+                .unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Key(KeyConfig {
+                command: KeyCommands::Export(KeyExportConfig {
+                    output: PathBuf::from("/tmp/key.hex")
+                })
+            }))
+        );
+    }
+
     #[test]
     fn test_custom_private_key() {
         let expected_config = TrinConfig {
@@ -663,9 +1365,561 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_db_check() {
+        let config = TrinConfig::new_from(["trin", "db", "check"].iter()).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Check(DbCheckConfig { repair: false }),
+            }))
+        );
+
+        let config = TrinConfig::new_from(["trin", "db", "check", "--repair"].iter()).unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Check(DbCheckConfig { repair: true }),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_db_export() {
+        let config =
+            TrinConfig::new_from(["trin", "db", "export", "--output", "content.e2s"].iter())
+                .unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Export(DbExportConfig {
+                    subnetwork: HISTORY_NETWORK.to_string(),
+                    output: PathBuf::from("content.e2s"),
+                    content_key_prefix: None,
+                    min_distance: None,
+                    max_distance: None,
+                }),
+            }))
+        );
+
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "db",
+                "export",
+                "--subnetwork",
+                "state",
+                "--output",
+                "content.e2s",
+                "--content-key-prefix",
+                "0x20",
+                "--max-distance",
+                "254",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Export(DbExportConfig {
+                    subnetwork: STATE_NETWORK.to_string(),
+                    output: PathBuf::from("content.e2s"),
+                    content_key_prefix: Some(Bytes::from(vec![0x20])),
+                    min_distance: None,
+                    max_distance: Some(Distance::from(U256::MAX >> 2)),
+                }),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_db_import() {
+        let config =
+            TrinConfig::new_from(["trin", "db", "import", "--input", "content.e2s"].iter())
+                .unwrap();
+        assert_eq!(
+            config.command,
+            Some(TrinConfigCommands::Db(DbConfig {
+                command: DbCommands::Import(DbImportConfig {
+                    input: PathBuf::from("content.e2s"),
+                }),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_storage_backend() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.storage_backend, "sqlite");
+
+        let config = TrinConfig::new_from(["trin", "--storage-backend", "segment"].iter()).unwrap();
+        assert_eq!(config.storage_backend, "segment");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid value 'rocksdb' for '--storage-backend")]
+    fn test_storage_backend_invalid() {
+        TrinConfig::new_from(["trin", "--storage-backend", "rocksdb"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_storage_allocation() {
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--mb",
+                "1000",
+                "--portal-subnetworks",
+                "history,state,beacon",
+                "--storage-allocation",
+                "history:80%,state:100",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.storage_capacity_mb(HISTORY_NETWORK), 800);
+        assert_eq!(config.storage_capacity_mb(STATE_NETWORK), 100);
+        assert_eq!(config.storage_capacity_mb(BEACON_NETWORK), 100);
+
+        let config = TrinConfig::new_from(["trin", "--mb", "1000"].iter()).unwrap();
+        assert_eq!(config.storage_capacity_mb(HISTORY_NETWORK), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds total capacity")]
+    fn test_storage_allocation_above_capacity() {
+        TrinConfig::new_from(
+            [
+                "trin",
+                "--mb",
+                "1000",
+                "--storage-allocation",
+                "history:1500",
+            ]
+            .iter(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage is allocated to state subnetwork, which is not enabled")]
+    fn test_storage_allocation_to_disabled_subnetwork() {
+        TrinConfig::new_from(["trin", "--storage-allocation", "state:10%"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_radius_args() {
+        let config = TrinConfig::new_from(["trin", "--fixed-radius", "255"].iter()).unwrap();
+        assert_eq!(config.fixed_radius, Some(Distance::from(U256::MAX >> 1)));
+        assert_eq!(config.min_radius, None);
+
+        let config = TrinConfig::new_from(["trin", "--min-radius", "0"].iter()).unwrap();
+        assert_eq!(config.fixed_radius, None);
+        assert_eq!(config.min_radius, Some(Distance::ZERO));
+
+        let config = TrinConfig::new_from(["trin", "--min-radius", "256"].iter()).unwrap();
+        assert_eq!(config.min_radius, Some(Distance::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be in range 0-256")]
+    fn test_radius_out_of_range() {
+        TrinConfig::new_from(["trin", "--fixed-radius", "257"].iter()).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be used with")]
+    fn test_fixed_radius_conflicts_with_min_radius() {
+        TrinConfig::new_from(["trin", "--fixed-radius", "100", "--min-radius", "50"].iter())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_gossip_strategy() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.gossip_strategy, GossipStrategy::Random);
+
+        let config =
+            TrinConfig::new_from(["trin", "--gossip-strategy", "closest:16"].iter()).unwrap();
+        assert_eq!(config.gossip_strategy, GossipStrategy::Closest(16));
+
+        let config =
+            TrinConfig::new_from(["trin", "--gossip-strategy", "hybrid:8"].iter()).unwrap();
+        assert_eq!(config.gossip_strategy, GossipStrategy::Hybrid(8));
+        assert_eq!(config.gossip_strategy.to_string(), "hybrid:8");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid gossip-strategy arg")]
+    fn test_gossip_strategy_invalid() {
+        TrinConfig::new_from(["trin", "--gossip-strategy", "closest:0"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_accept_queue_timeout() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(
+            config.accept_queue_timeout,
+            DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS
+        );
+
+        let config = TrinConfig::new_from(["trin", "--accept-queue-timeout", "30"].iter()).unwrap();
+        assert_eq!(config.accept_queue_timeout, 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid value '0' for '--accept-queue-timeout")]
+    fn test_accept_queue_timeout_zero() {
+        TrinConfig::new_from(["trin", "--accept-queue-timeout", "0"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_utp_stall_args() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.utp_min_throughput, DEFAULT_UTP_MIN_THROUGHPUT);
+        assert_eq!(config.utp_stall_timeout, DEFAULT_UTP_STALL_TIMEOUT_SECS);
+        assert_eq!(config.utp_inbound_timeout, DEFAULT_UTP_INBOUND_TIMEOUT_SECS);
+        assert_eq!(
+            config.utp_max_streams_per_peer,
+            DEFAULT_UTP_MAX_STREAMS_PER_PEER
+        );
+
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--utp-min-throughput",
+                "4096",
+                "--utp-stall-timeout",
+                "10",
+                "--utp-inbound-timeout",
+                "600",
+                "--utp-max-streams-per-peer",
+                "2",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.utp_min_throughput, 4096);
+        assert_eq!(config.utp_stall_timeout, 10);
+        assert_eq!(config.utp_inbound_timeout, 600);
+        assert_eq!(config.utp_max_streams_per_peer, 2);
+    }
+
+    #[test]
+    fn test_utp_adaptive_transfer_limit() {
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--utp-adaptive-transfer-limit",
+                "--utp-transfer-limit-min",
+                "5",
+                "--utp-transfer-limit-max",
+                "500",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert!(config.utp_adaptive_transfer_limit);
+        assert_eq!(config.utp_transfer_limit, DEFAULT_UTP_TRANSFER_LIMIT);
+        assert_eq!(config.utp_transfer_limit_min, 5);
+        assert_eq!(config.utp_transfer_limit_max, 500);
+    }
+
+    #[test]
+    fn test_utp_transfer_limit_bounds_require_adaptive() {
+        assert!(TrinConfig::new_from(["trin", "--utp-transfer-limit-min", "5"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_utp_transfer_limit_min_greater_than_max() {
+        let args = [
+            "trin",
+            "--utp-adaptive-transfer-limit",
+            "--utp-transfer-limit-min",
+            "100",
+            "--utp-transfer-limit-max",
+            "50",
+        ];
+        assert!(TrinConfig::new_from(args.iter()).is_err());
+    }
+
+    #[test]
+    fn test_utp_conn_tuning() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert_eq!(config.utp_conn_tuning, UtpConnectionTuning::default());
+
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--utp-max-packet-size",
+                "512",
+                "--utp-initial-timeout-ms",
+                "3000",
+                "--utp-idle-timeout-ms",
+                "30000",
+                "--utp-max-conn-attempts",
+                "5",
+                "--utp-window-size",
+                "65536",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            config.utp_conn_tuning,
+            UtpConnectionTuning {
+                max_packet_size: Some(512),
+                initial_timeout_ms: Some(3000),
+                idle_timeout_ms: Some(30000),
+                max_conn_attempts: Some(5),
+                window_size: Some(65536),
+            }
+        );
+    }
+
+    #[test]
+    fn test_utp_max_packet_size_too_small() {
+        for max_packet_size in ["0", "20", "149"] {
+            let args = ["trin", "--utp-max-packet-size", max_packet_size];
+            assert!(TrinConfig::new_from(args.iter()).is_err());
+        }
+        let args = ["trin", "--utp-max-packet-size", "150"];
+        assert!(TrinConfig::new_from(args.iter()).is_ok());
+    }
+
+    #[test]
+    fn test_utp_conn_tuning_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(
+            &temp_dir,
+            "utp-conn-tuning",
+            "utp-max-packet-size = 512\nutp-idle-timeout-ms = 30000\n",
+        );
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--config",
+                path.to_str().unwrap(),
+                "--utp-max-packet-size",
+                "1000",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.utp_conn_tuning.max_packet_size, Some(1000));
+        assert_eq!(config.utp_conn_tuning.idle_timeout_ms, Some(30000));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid value '0' for '--utp-max-streams-per-peer")]
+    fn test_utp_max_streams_per_peer_zero() {
+        TrinConfig::new_from(["trin", "--utp-max-streams-per-peer", "0"].iter()).unwrap();
+    }
+
     #[test]
     #[should_panic(expected = "Invalid web3-transport arg. Expected either 'http' or 'ipc'")]
     fn test_invalid_web3_transport_argument() {
         TrinConfig::new_from(["trin", "--web3-transport", "invalid"].iter()).unwrap();
     }
+
+    #[test]
+    fn test_network_spec_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(
+            &temp_dir,
+            "network-spec",
+            r#"
+            name = "devnet"
+            chain-id = 1337
+
+            [protocol-ids]
+            state = "0x5F0A"
+            history = "0x5F0B"
+            beacon = "0x5F0C"
+            "#,
+        );
+        let network_arg = path.display().to_string();
+        let config = TrinConfig::new_from(["trin", "--network", &network_arg].iter()).unwrap();
+        assert_eq!(config.network.get_network_name(), "devnet");
+        assert_eq!(config.network.chain_id(), 1337);
+        assert_eq!(config.network.cli_value(), network_arg);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not a valid network: devnet")]
+    fn test_invalid_network() {
+        TrinConfig::new_from(["trin", "--network", "devnet"].iter()).unwrap();
+    }
+
+    fn write_config_file(temp_dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = temp_dir.path().join(format!("{name}.toml"));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(
+            &temp_dir,
+            "config-file",
+            r#"
+            web3-transport = "http"
+            ws = true
+            mb = 500
+            portal-subnetworks = ["history", "state"]
+            accept_queue_timeout = 30
+
+            [overlay]
+            query-parallelism = 5
+
+            [pruning]
+            target-capacity-fraction = 0.9
+            "#,
+        );
+        let config_arg = path.display().to_string();
+
+        let config = TrinConfig::new_from(["trin", "--config", &config_arg].iter()).unwrap();
+        assert_eq!(config.web3_transport, Web3TransportType::HTTP);
+        assert!(config.ws);
+        assert_eq!(config.mb, 500);
+        assert_eq!(
+            config.portal_subnetworks,
+            vec![HISTORY_NETWORK.to_string(), STATE_NETWORK.to_string()]
+        );
+        assert_eq!(config.accept_queue_timeout, 30);
+        assert_eq!(config.overlay.query_parallelism, Some(5));
+        assert_eq!(config.pruning.target_capacity_fraction, Some(0.9));
+
+        // Flags set on the command line take precedence over the file.
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--config",
+                &config_arg,
+                "--mb",
+                "200",
+                "--portal-subnetworks",
+                "beacon",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.mb, 200);
+        assert_eq!(config.portal_subnetworks, vec![BEACON_NETWORK.to_string()]);
+        assert_eq!(config.accept_queue_timeout, 30);
+    }
+
+    #[test]
+    fn test_query_tuning() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(
+            &temp_dir,
+            "query-tuning",
+            r#"
+            [overlay]
+            query-parallelism = 5
+
+            [overlay.history]
+            query-parallelism = 6
+            query-timeout-ms = 30000
+            "#,
+        );
+        let config_arg = path.display().to_string();
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--config",
+                &config_arg,
+                "--query-tuning",
+                "history:query-parallelism=8,state:query-num-results=32",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            config.overlay.query_tuning(HISTORY_NETWORK),
+            QueryTuning {
+                query_parallelism: Some(8),
+                query_timeout_ms: Some(30000),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            config.overlay.query_tuning(STATE_NETWORK),
+            QueryTuning {
+                query_parallelism: Some(5),
+                query_num_results: Some(32),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_env_var_overrides_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(&temp_dir, "env-var", "disable-poke = false");
+        let config_arg = path.display().to_string();
+        let config = TrinConfig::new_from_env(["trin", "--config", &config_arg].iter(), |name| {
+            (name == "TRIN_DISABLE_POKE").then(|| "true".to_string())
+        });
+        assert!(config.unwrap().disable_poke);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown flag web3-transprot in config file")]
+    fn test_config_file_unknown_flag() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(&temp_dir, "unknown-flag", r#"web3-transprot = "http""#);
+        let config_arg = path.display().to_string();
+        TrinConfig::new_from(["trin", "--config", &config_arg].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_effective_args_round_trip() {
+        let args = [
+            "trin",
+            "--web3-transport",
+            "http",
+            "--mb",
+            "500",
+            "--portal-subnetworks",
+            "history,state",
+            "--ephemeral",
+        ];
+        let table = TrinConfig::effective_args(args.iter()).unwrap();
+        assert_eq!(table["web3-transport"].as_str(), Some("http"));
+        assert_eq!(table["mb"].as_integer(), Some(500));
+        assert_eq!(table["ephemeral"].as_bool(), Some(true));
+        assert!(!table.contains_key("config"));
+
+        // The dumped config, loaded as a config file, results in the same config.
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config_file(&temp_dir, "round-trip", &toml::to_string(&table).unwrap());
+        let config_arg = path.display().to_string();
+        let loaded_config = TrinConfig::new_from(["trin", "--config", &config_arg].iter()).unwrap();
+        let expected_config = TrinConfig::new_from(args.iter()).unwrap();
+        assert_eq!(
+            TrinConfig {
+                config: None,
+                ..loaded_config
+            },
+            expected_config
+        );
+    }
+
+    #[test]
+    fn test_effective_args_omit_private_key() {
+        let table = TrinConfig::effective_args(
+            [
+                "trin",
+                "--unsafe-private-key",
+                "0x1234567890123456789012345678901234567890123456789012345678901234",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert!(!table.contains_key("unsafe-private-key"));
+    }
 }
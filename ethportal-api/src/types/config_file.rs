@@ -0,0 +1,310 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, ensure};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+    query_tuning::QueryTuning,
+};
+
+/// Name of the table with the overlay network tuning.
+pub const OVERLAY_TABLE: &str = "overlay";
+/// Name of the table with the storage pruning tuning.
+pub const PRUNING_TABLE: &str = "pruning";
+
+/// The TOML file loaded with `--config`.
+///
+/// Top-level keys are the long names of the `trin` CLI flags (e.g. `web3-transport`), with values
+/// in the same format as on the command line. Flags are set with booleans, and flags that take
+/// multiple values with arrays. The `[overlay]` and `[pruning]` tables set the tuning that can't
+/// be set on the command line.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConfigFile {
+    /// The values of the CLI flags, by long name.
+    pub args: toml::Table,
+    pub overlay: OverlayTuning,
+    pub pruning: PruningTuning,
+}
+
+impl ConfigFile {
+    /// Loads the config file from the path.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        content.parse()
+    }
+
+    /// Returns the value of the CLI flag with the long name.
+    pub fn arg(&self, long: &str) -> Option<&toml::Value> {
+        self.args.get(long)
+    }
+}
+
+impl std::str::FromStr for ConfigFile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut args: toml::Table = s.parse()?;
+        let overlay: OverlayTuning = match args.remove(OVERLAY_TABLE) {
+            Some(overlay) => overlay.try_into()?,
+            None => OverlayTuning::default(),
+        };
+        let pruning: PruningTuning = match args.remove(PRUNING_TABLE) {
+            Some(pruning) => pruning.try_into()?,
+            None => PruningTuning::default(),
+        };
+        overlay.validate()?;
+        pruning.validate()?;
+        // Accept both `web3-transport` and `web3_transport`.
+        let args = args
+            .into_iter()
+            .map(|(key, value)| (key.replace('_', "-"), value))
+            .collect();
+        Ok(Self {
+            args,
+            overlay,
+            pruning,
+        })
+    }
+}
+
+/// Converts the value of a CLI flag from the config file into the string values that are passed
+/// on the command line. Booleans are returned as "true" or "false".
+pub fn arg_values(long: &str, value: &toml::Value) -> anyhow::Result<Vec<String>> {
+    match value {
+        toml::Value::String(value) => Ok(vec![value.clone()]),
+        toml::Value::Integer(value) => Ok(vec![value.to_string()]),
+        toml::Value::Float(value) => Ok(vec![value.to_string()]),
+        toml::Value::Boolean(value) => Ok(vec![value.to_string()]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                toml::Value::Array(_) | toml::Value::Table(_) => Err(anyhow!(
+                    "Invalid value of {long}: nested arrays are not supported"
+                )),
+                value => Ok(arg_values(long, value)?.remove(0)),
+            })
+            .collect(),
+        toml::Value::Datetime(_) | toml::Value::Table(_) => Err(anyhow!(
+            "Invalid value of {long}: expected a string, number, boolean or array"
+        )),
+    }
+}
+
+/// Converts the string value of a CLI flag into the TOML value written by `trin config dump`.
+pub fn toml_value(value: &str) -> toml::Value {
+    match value.parse::<i64>() {
+        Ok(value) => toml::Value::Integer(value),
+        Err(_) => toml::Value::String(value.to_string()),
+    }
+}
+
+/// The overlay network tuning, applied to all subnetworks. The `[overlay.<subnetwork>]` tables
+/// override the query tuning of individual subnetworks. Unset values use the defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct OverlayTuning {
+    /// The time a pending node waits for a slot in a full k-bucket, in milliseconds.
+    pub bucket_pending_timeout_ms: Option<u64>,
+    /// The maximum number of incoming nodes per k-bucket.
+    pub max_incoming_per_bucket: Option<usize>,
+    /// The interval at which the nodes of the routing table are pinged, in milliseconds.
+    pub ping_queue_interval_ms: Option<u64>,
+    /// The number of peers that a query requests in parallel.
+    pub query_parallelism: Option<usize>,
+    /// The timeout of a query, in milliseconds.
+    pub query_timeout_ms: Option<u64>,
+    /// The timeout of a request to a single peer of a query, in milliseconds.
+    pub query_peer_timeout_ms: Option<u64>,
+    /// The number of results of a FINDNODES query.
+    pub query_num_results: Option<usize>,
+    /// The number of distances requested from each peer in a FINDNODES query.
+    pub findnodes_query_distances_per_peer: Option<usize>,
+    /// The maximum number of nodes of the routing table in the same subnet (/24 for IPv4, /64
+    /// for IPv6).
+    pub table_subnet_limit: Option<usize>,
+    /// The maximum number of nodes of a k-bucket in the same subnet (/24 for IPv4, /64 for IPv6).
+    pub bucket_subnet_limit: Option<usize>,
+    /// The query tuning of the history subnetwork.
+    pub history: QueryTuning,
+    /// The query tuning of the state subnetwork.
+    pub state: QueryTuning,
+    /// The query tuning of the beacon subnetwork.
+    pub beacon: QueryTuning,
+}
+
+impl OverlayTuning {
+    /// Returns the query tuning of the `[overlay]` table, which applies to all subnetworks.
+    pub fn common_query_tuning(&self) -> QueryTuning {
+        QueryTuning {
+            query_parallelism: self.query_parallelism,
+            query_timeout_ms: self.query_timeout_ms,
+            query_peer_timeout_ms: self.query_peer_timeout_ms,
+            query_num_results: self.query_num_results,
+            findnodes_query_distances_per_peer: self.findnodes_query_distances_per_peer,
+        }
+    }
+
+    /// Returns the query tuning of the subnetwork, with the values of its `[overlay.<subnetwork>]`
+    /// table taking precedence over the values of the `[overlay]` table.
+    pub fn query_tuning(&self, subnetwork: &str) -> QueryTuning {
+        let query_tuning = self.common_query_tuning();
+        match subnetwork {
+            HISTORY_NETWORK => query_tuning.merge(&self.history),
+            STATE_NETWORK => query_tuning.merge(&self.state),
+            BEACON_NETWORK => query_tuning.merge(&self.beacon),
+            _ => query_tuning,
+        }
+    }
+
+    /// Returns the tuning of the subnetwork, with the query tuning of [Self::query_tuning].
+    pub fn for_subnetwork(&self, subnetwork: &str) -> OverlayTuning {
+        let query_tuning = self.query_tuning(subnetwork);
+        OverlayTuning {
+            query_parallelism: query_tuning.query_parallelism,
+            query_timeout_ms: query_tuning.query_timeout_ms,
+            query_peer_timeout_ms: query_tuning.query_peer_timeout_ms,
+            query_num_results: query_tuning.query_num_results,
+            findnodes_query_distances_per_peer: query_tuning.findnodes_query_distances_per_peer,
+            ..self.clone()
+        }
+    }
+
+    /// Checks that the query tuning of all subnetworks is valid.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for subnetwork in [HISTORY_NETWORK, STATE_NETWORK, BEACON_NETWORK] {
+            self.query_tuning(subnetwork).validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// The storage pruning tuning. Unset values use the defaults.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PruningTuning {
+    /// The fraction of storage capacity that pruning aims for.
+    pub target_capacity_fraction: Option<f64>,
+    /// The fraction by which the number of pruned entries changes, when pruning duration is
+    /// outside the optimal range.
+    pub max_pruning_count_change_fraction: Option<f64>,
+    /// The lower end of the optimal pruning duration, in milliseconds.
+    pub min_optimal_pruning_duration_ms: Option<u64>,
+    /// The upper end of the optimal pruning duration, in milliseconds.
+    pub max_optimal_pruning_duration_ms: Option<u64>,
+}
+
+impl PruningTuning {
+    /// Checks that the values are within the ranges accepted by the storage.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(fraction) = self.target_capacity_fraction {
+            ensure!(
+                (0.0..=1.0).contains(&fraction),
+                "Invalid target-capacity-fraction {fraction}: must be in range 0-1"
+            );
+        }
+        if let Some(fraction) = self.max_pruning_count_change_fraction {
+            ensure!(
+                (0.0..1.0).contains(&fraction),
+                "Invalid max-pruning-count-change-fraction {fraction}: must be in range 0-1 (exclusive)"
+            );
+        }
+        if let (Some(min), Some(max)) = (
+            self.min_optimal_pruning_duration_ms,
+            self.max_optimal_pruning_duration_ms,
+        ) {
+            ensure!(
+                min < max,
+                "Invalid optimal pruning duration: min {min}ms must be lower than max {max}ms"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_file() {
+        let config_file: ConfigFile = r#"
+            web3_transport = "http"
+            mb = 500
+            portal-subnetworks = ["history", "state"]
+            ephemeral = true
+
+            [overlay]
+            query-parallelism = 5
+            query-timeout-ms = 30000
+
+            [overlay.history]
+            query-parallelism = 8
+
+            [pruning]
+            target-capacity-fraction = 0.9
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            arg_values("web3-transport", config_file.arg("web3-transport").unwrap()).unwrap(),
+            vec!["http"]
+        );
+        assert_eq!(
+            arg_values("mb", config_file.arg("mb").unwrap()).unwrap(),
+            vec!["500"]
+        );
+        assert_eq!(
+            arg_values(
+                "portal-subnetworks",
+                config_file.arg("portal-subnetworks").unwrap()
+            )
+            .unwrap(),
+            vec!["history", "state"]
+        );
+        assert_eq!(
+            arg_values("ephemeral", config_file.arg("ephemeral").unwrap()).unwrap(),
+            vec!["true"]
+        );
+        assert_eq!(
+            config_file.overlay.query_tuning(HISTORY_NETWORK),
+            QueryTuning {
+                query_parallelism: Some(8),
+                query_timeout_ms: Some(30000),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            config_file.overlay.query_tuning(STATE_NETWORK),
+            QueryTuning {
+                query_parallelism: Some(5),
+                query_timeout_ms: Some(30000),
+                ..Default::default()
+            }
+        );
+        assert_eq!(config_file.pruning.target_capacity_fraction, Some(0.9));
+    }
+
+    #[test]
+    fn unknown_overlay_key() {
+        let result = "[overlay]\nquery-paralelism = 5".parse::<ConfigFile>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_overlay_tuning() {
+        let result = "[overlay.state]\nquery-parallelism = 0".parse::<ConfigFile>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_pruning_tuning() {
+        let result = "[pruning]\ntarget-capacity-fraction = 1.5".parse::<ConfigFile>();
+        assert!(result.is_err());
+        let result = "[pruning]\nmin-optimal-pruning-duration-ms = 300\nmax-optimal-pruning-duration-ms = 100"
+            .parse::<ConfigFile>();
+        assert!(result.is_err());
+    }
+}
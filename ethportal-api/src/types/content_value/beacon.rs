@@ -33,6 +33,10 @@ use ssz::{Decode, DecodeError, Encode};
 use ssz_types::{typenum::U128, VariableList};
 use std::ops::Deref;
 
+/// The number of slots in the sync committee period (`SLOTS_PER_EPOCH` *
+/// `EPOCHS_PER_SYNC_COMMITTEE_PERIOD`).
+pub const SLOTS_PER_SYNC_COMMITTEE_PERIOD: u64 = 32 * 256;
+
 /// A wrapper type including a `ForkName` and `LightClientBootstrap`
 #[derive(Clone, Debug, PartialEq)]
 pub struct ForkVersionedLightClientBootstrap {
@@ -59,6 +63,20 @@ impl From<LightClientBootstrapDeneb> for ForkVersionedLightClientBootstrap {
 }
 
 impl ForkVersionedLightClientBootstrap {
+    /// Returns the slot of the bootstrap's header.
+    pub fn slot(&self) -> u64 {
+        match &self.bootstrap {
+            LightClientBootstrap::Bellatrix(bootstrap) => bootstrap.header.beacon.slot,
+            LightClientBootstrap::Capella(bootstrap) => bootstrap.header.beacon.slot,
+            LightClientBootstrap::Deneb(bootstrap) => bootstrap.header.beacon.slot,
+        }
+    }
+
+    /// Returns the sync committee period of the bootstrap's header.
+    pub fn sync_committee_period(&self) -> u64 {
+        self.slot() / SLOTS_PER_SYNC_COMMITTEE_PERIOD
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let fork_digest = self.fork_name.as_fork_digest();
 
@@ -68,6 +86,12 @@ impl ForkVersionedLightClientBootstrap {
     }
 
     pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < 4 {
+            return Err(DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: 4,
+            });
+        }
         let fork_digest = ForkDigest::try_from(&bytes[0..4]).map_err(|err| {
             DecodeError::BytesInvalid(format!("Unable to decode fork digest: {err:?}"))
         })?;
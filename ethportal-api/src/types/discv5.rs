@@ -1,4 +1,6 @@
-use super::enr::Enr;
+use std::collections::BTreeMap;
+
+use super::{enr::Enr, ping_payload::PingExtensions, portal::DataRadius};
 use crate::utils::bytes::hex_encode;
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +36,79 @@ pub struct NodeInfo {
 pub struct RoutingTableInfo {
     pub local_node_id: String,
     pub buckets: KBucketsTable,
+    /// The ping extensions received from the nodes in the routing table, keyed by node id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ping_extensions: BTreeMap<String, PingExtensions>,
+}
+
+/// Detailed information about the nodes of an overlay network's routing table.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingTableDetail {
+    pub local_node_id: String,
+    pub nodes: Vec<RoutingTableNodeDetail>,
+}
+
+/// Detailed information about a node in an overlay network's routing table.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingTableNodeDetail {
+    pub node_id: String,
+    pub enr: Enr,
+    pub bucket_index: usize,
+    pub data_radius: DataRadius,
+    pub connection_state: NodeConnectionState,
+    pub connection_direction: NodeConnectionDirection,
+    /// The client string advertised in the node's ENR.
+    pub client: Option<String>,
+    /// Unix timestamp (in seconds) of the last request or response received from the node.
+    pub last_seen: Option<u64>,
+    /// Round trip time (in milliseconds) of the last successful ping of the node.
+    pub last_ping_rtt_ms: Option<u64>,
+    /// The number of requests sent to the node.
+    pub requests_sent: u64,
+    /// The number of responses received from the node.
+    pub responses_received: u64,
+    /// The number of requests to the node that failed.
+    pub requests_failed: u64,
+    /// The number of requests received from the node.
+    pub requests_received: u64,
+    /// The number of uTP streams with the node that were aborted because they stalled.
+    pub utp_stalls: u64,
+}
+
+/// The connection state of a node in the routing table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeConnectionState {
+    Connected,
+    Disconnected,
+}
+
+/// The direction of the connection with a node in the routing table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeConnectionDirection {
+    Incoming,
+    Outgoing,
+}
+
+impl From<discv5::kbucket::ConnectionState> for NodeConnectionState {
+    fn from(state: discv5::kbucket::ConnectionState) -> Self {
+        match state {
+            discv5::kbucket::ConnectionState::Connected => Self::Connected,
+            discv5::kbucket::ConnectionState::Disconnected => Self::Disconnected,
+        }
+    }
+}
+
+impl From<discv5::kbucket::ConnectionDirection> for NodeConnectionDirection {
+    fn from(direction: discv5::kbucket::ConnectionDirection) -> Self {
+        match direction {
+            discv5::kbucket::ConnectionDirection::Incoming => Self::Incoming,
+            discv5::kbucket::ConnectionDirection::Outgoing => Self::Outgoing,
+        }
+    }
 }
 
 impl<TVal: Eq> From<discv5::kbucket::KBucketsTable<NodeId, TVal>> for KBucketsTable {
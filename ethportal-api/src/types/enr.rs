@@ -111,7 +111,8 @@ mod test {
     fn test_generate_random_node_id_1() {
         let target_bucket_idx: u8 = 5;
         let local_node_id = NodeId::random();
-        let random_node_id = generate_random_node_id(target_bucket_idx, local_node_id);
+        let random_node_id =
+            generate_random_node_id(&mut rand::thread_rng(), target_bucket_idx, local_node_id);
         let distance = XorMetric::distance(&random_node_id.raw(), &local_node_id.raw());
         let distance = distance.big_endian();
 
@@ -123,7 +124,8 @@ mod test {
     fn test_generate_random_node_id_2() {
         let target_bucket_idx: u8 = 0;
         let local_node_id = NodeId::random();
-        let random_node_id = generate_random_node_id(target_bucket_idx, local_node_id);
+        let random_node_id =
+            generate_random_node_id(&mut rand::thread_rng(), target_bucket_idx, local_node_id);
         let distance = XorMetric::distance(&random_node_id.raw(), &local_node_id.raw());
         let distance = distance.big_endian();
 
@@ -135,7 +137,8 @@ mod test {
     fn test_generate_random_node_id_3() {
         let target_bucket_idx: u8 = 255;
         let local_node_id = NodeId::random();
-        let random_node_id = generate_random_node_id(target_bucket_idx, local_node_id);
+        let random_node_id =
+            generate_random_node_id(&mut rand::thread_rng(), target_bucket_idx, local_node_id);
         let distance = XorMetric::distance(&random_node_id.raw(), &local_node_id.raw());
         let distance = distance.big_endian();
 
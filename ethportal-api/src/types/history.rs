@@ -1,5 +1,6 @@
 use super::query_trace::QueryTrace;
-use crate::{types::enr::Enr, HistoryContentKey, HistoryContentValue};
+use crate::{types::enr::Enr, BlockReceiptsKey, HistoryContentKey, HistoryContentValue};
+use alloy_primitives::B256;
 use serde::{Deserialize, Serialize};
 
 /// Response for FindContent & RecursiveFindContent endpoints
@@ -36,3 +37,62 @@ pub struct PaginateLocalContentInfo {
     pub content_keys: Vec<HistoryContentKey>,
     pub total_entries: u64,
 }
+
+/// The type of the content fetched for each block by the GetRange endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryContentType {
+    Header,
+    Body,
+    Receipts,
+}
+
+impl HistoryContentType {
+    /// Returns the content key of this type for the given block hash.
+    pub fn content_key(&self, block_hash: B256) -> HistoryContentKey {
+        match self {
+            Self::Header => HistoryContentKey::BlockHeaderWithProof(block_hash.into()),
+            Self::Body => HistoryContentKey::BlockBody(block_hash.into()),
+            Self::Receipts => HistoryContentKey::BlockReceipts(BlockReceiptsKey {
+                block_hash: block_hash.0,
+            }),
+        }
+    }
+}
+
+/// Status of a single item of the GetRange endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GetRangeItemStatus {
+    /// The content was found locally or on the network.
+    Found,
+    /// The content couldn't be found on the network.
+    NotFound,
+    /// The lookup of the content failed for another reason.
+    Error,
+}
+
+/// A single item of the GetRange endpoint, for one block and content type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRangeItem {
+    pub block_number: u64,
+    pub content_type: HistoryContentType,
+    /// The content key, `None` if the block hash couldn't be resolved.
+    pub content_key: Option<HistoryContentKey>,
+    pub status: GetRangeItemStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<HistoryContentValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Response for GetRange endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRangeInfo {
+    /// Items ordered by block number and then by the order of the requested content types.
+    pub items: Vec<GetRangeItem>,
+    /// The block number to continue from, if the range didn't fit in a single response.
+    pub next_block: Option<u64>,
+}
@@ -1,6 +1,7 @@
 use crate::{
-    types::enr::Enr, BeaconContentKey, BeaconContentValue, HistoryContentKey, HistoryContentValue,
-    StateContentKey, StateContentValue,
+    types::{enr::Enr, query_tuning::QueryTuning},
+    BeaconContentKey, BeaconContentValue, HistoryContentKey, HistoryContentValue, StateContentKey,
+    StateContentValue,
 };
 use discv5::enr::NodeId;
 
@@ -16,6 +17,8 @@ pub enum Discv5Endpoint {
 pub enum StateEndpoint {
     /// params: None
     RoutingTableInfo,
+    /// params: None
+    RoutingTableDetail,
     /// params: [enr]
     Ping(Enr),
     /// params: [enr]
@@ -44,12 +47,18 @@ pub enum StateEndpoint {
     Store(StateContentKey, StateContentValue),
     /// params: [enr, content_key]
     Offer(Enr, StateContentKey, Option<StateContentValue>),
+    /// params: [enr, content_key, content_value]
+    TraceOffer(Enr, StateContentKey, StateContentValue),
     /// params: [content_key, content_value]
     Gossip(StateContentKey, StateContentValue),
     /// params: [content_key, content_value]
     TraceGossip(StateContentKey, StateContentValue),
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
+    /// params: [capacity_mb]
+    SetStorageCapacity(u64),
+    /// params: [tuning]
+    SetQueryTuning(QueryTuning),
 }
 
 /// History network JSON-RPC endpoints. Start with "portal_history" prefix
@@ -77,6 +86,8 @@ pub enum HistoryEndpoint {
     TraceGossip(HistoryContentKey, HistoryContentValue),
     /// params: [enr, content_key]
     Offer(Enr, HistoryContentKey, Option<HistoryContentValue>),
+    /// params: [enr, content_key, content_value]
+    TraceOffer(Enr, HistoryContentKey, HistoryContentValue),
     /// params: [enr]
     Ping(Enr),
     /// params: content_key
@@ -87,11 +98,17 @@ pub enum HistoryEndpoint {
     Store(HistoryContentKey, HistoryContentValue),
     /// params: None
     RoutingTableInfo,
+    /// params: None
+    RoutingTableDetail,
     // This endpoint is not History network specific
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
     /// params: [node_id]
     RecursiveFindNodes(NodeId),
+    /// params: [capacity_mb]
+    SetStorageCapacity(u64),
+    /// params: [tuning]
+    SetQueryTuning(QueryTuning),
 }
 
 /// Beacon network JSON-RPC endpoints. Start with "portal_beacon" prefix
@@ -129,8 +146,14 @@ pub enum BeaconEndpoint {
     Store(BeaconContentKey, BeaconContentValue),
     /// params: None
     RoutingTableInfo,
+    /// params: None
+    RoutingTableDetail,
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
     /// params: [node_id]
     RecursiveFindNodes(NodeId),
+    /// params: [capacity_mb]
+    SetStorageCapacity(u64),
+    /// params: [tuning]
+    SetQueryTuning(QueryTuning),
 }
@@ -2,6 +2,7 @@ pub mod beacon;
 pub mod bootnodes;
 pub mod bytes;
 pub mod cli;
+pub mod config_file;
 pub mod consensus;
 pub mod content_key;
 pub mod content_value;
@@ -11,9 +12,15 @@ pub mod enr;
 pub mod execution;
 pub mod history;
 pub mod jsonrpc;
+pub mod network_spec;
 pub mod node_id;
+pub mod ping_payload;
 pub mod portal;
 pub mod portal_wire;
 pub mod query_trace;
+pub mod query_tuning;
 pub mod state;
 pub mod state_trie;
+pub mod storage_allocation;
+pub mod subscription;
+pub mod utp_tuning;
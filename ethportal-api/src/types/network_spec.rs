@@ -0,0 +1,319 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use alloy_primitives::B256;
+use anyhow::{anyhow, ensure};
+use bimap::BiHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::{
+        enr::Enr,
+        portal_wire::{NetworkSpec, ProtocolId},
+    },
+    utils::bytes::{hex_decode, hex_encode_upper},
+};
+
+/// The protocol id of uTP, which is shared by the networks unless their spec sets another one.
+const DEFAULT_UTP_PROTOCOL_ID: &str = "0x757470";
+
+/// The names of the built-in networks, which can't be used by a network spec file.
+const BUILT_IN_NETWORKS: [&str; 2] = ["mainnet", "testnet"];
+
+/// The forks that the validation of the chain history depends on. The defaults are the mainnet
+/// forks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ForkParams {
+    /// The number of the merge block.
+    pub merge_block: u64,
+    /// The number of the first Shanghai block.
+    pub shanghai_block: u64,
+    /// The epoch of the Capella fork of the beacon chain.
+    pub capella_epoch: u64,
+}
+
+impl Default for ForkParams {
+    fn default() -> Self {
+        Self {
+            merge_block: 15_537_394,
+            shanghai_block: 17_034_870,
+            capella_epoch: 194_048,
+        }
+    }
+}
+
+/// The TOML file with the spec of a private network, loaded with `--network <path>`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct NetworkSpecFile {
+    /// The name of the network, which is also the name of its data directory.
+    name: String,
+    chain_id: u64,
+    protocol_ids: ProtocolIds,
+    /// The ENRs of the bootnodes, used unless `--bootnodes` is set.
+    #[serde(default)]
+    bootnodes: Vec<String>,
+    pre_merge_accumulator_path: Option<PathBuf>,
+    pre_merge_accumulator_hash: Option<B256>,
+    genesis_hash: Option<B256>,
+    #[serde(default)]
+    forks: ForkParams,
+}
+
+/// The hex encoded protocol ids of the subnetworks.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ProtocolIds {
+    state: String,
+    history: String,
+    beacon: String,
+    canonical_indices: Option<String>,
+    verkle_state: Option<String>,
+    transaction_gossip: Option<String>,
+    utp: Option<String>,
+}
+
+impl NetworkSpec {
+    /// Loads the spec of a private network from a TOML file.
+    ///
+    /// A relative accumulator path is resolved against the directory of the file.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Unable to read network spec {}: {err}", path.display()))?;
+        let mut spec: Self = content.parse()?;
+        if let (Some(acc_path), Some(dir)) = (&mut spec.pre_merge_acc_path, path.parent()) {
+            if acc_path.is_relative() {
+                *acc_path = dir.join(&*acc_path);
+            }
+        }
+        spec.spec_file = Some(path.to_path_buf());
+        Ok(spec)
+    }
+}
+
+impl FromStr for NetworkSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let file: NetworkSpecFile = toml::from_str(s)?;
+        ensure!(
+            !file.name.is_empty()
+                && file
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Invalid network name: {:?}, must only contain letters, digits, '-' and '_'",
+            file.name
+        );
+        ensure!(
+            !BUILT_IN_NETWORKS.contains(&file.name.as_str()),
+            "Network name {} is reserved for the built-in network",
+            file.name
+        );
+
+        let ids = file.protocol_ids;
+        let protocol_ids = [
+            (ProtocolId::State, Some(ids.state)),
+            (ProtocolId::History, Some(ids.history)),
+            (ProtocolId::Beacon, Some(ids.beacon)),
+            (ProtocolId::CanonicalIndices, ids.canonical_indices),
+            (ProtocolId::VerkleState, ids.verkle_state),
+            (ProtocolId::TransactionGossip, ids.transaction_gossip),
+            (
+                ProtocolId::Utp,
+                Some(
+                    ids.utp
+                        .unwrap_or_else(|| DEFAULT_UTP_PROTOCOL_ID.to_string()),
+                ),
+            ),
+        ];
+        let mut portal_networks = BiHashMap::new();
+        for (protocol_id, hex) in protocol_ids {
+            let Some(hex) = hex else {
+                continue;
+            };
+            let bytes = hex_decode(&hex)
+                .map_err(|err| anyhow!("Invalid {protocol_id} protocol id {hex}: {err}"))?;
+            ensure!(!bytes.is_empty(), "Empty {protocol_id} protocol id");
+            // Protocol ids are looked up by their upper case hex encoding.
+            portal_networks
+                .insert_no_overwrite(protocol_id, hex_encode_upper(bytes))
+                .map_err(|_| anyhow!("Protocol id {hex} is used by more than one subnetwork"))?;
+        }
+
+        let bootnodes = file
+            .bootnodes
+            .iter()
+            .map(|enr| Enr::from_str(enr).map_err(|err| anyhow!("Invalid bootnode {enr}: {err}")))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            network_name: file.name,
+            portal_networks,
+            chain_id: file.chain_id,
+            bootnodes: (!bootnodes.is_empty()).then_some(bootnodes),
+            pre_merge_acc_path: file.pre_merge_accumulator_path,
+            pre_merge_acc_hash: file.pre_merge_accumulator_hash,
+            genesis_hash: file.genesis_hash,
+            forks: file.forks,
+            spec_file: None,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use tempfile::TempDir;
+    use test_log::test;
+
+    use super::*;
+
+    const BOOTNODE: &str = "enr:-Jy4QIs2pCyiKna9YWnAF0zgf7bT0GzlAGoF8MEKFJOExmtofBIqzm71zDvmzRiiLkxaEJcs_Amr7XIhLI74k1rtlXICY5Z0IDAuMS4xLWFscGhhLjEtMTEwZjUwgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQLSC_nhF1iRwsCw0n3J4jRjqoaRxtKgsEe5a-Dz7y0JloN1ZHCCIyg";
+
+    #[test]
+    fn parse_network_spec() {
+        let spec: NetworkSpec = format!(
+            r#"
+            name = "devnet-1"
+            chain-id = 1337
+            bootnodes = ["{BOOTNODE}"]
+            pre-merge-accumulator-path = "/tmp/accumulator.bin"
+            pre-merge-accumulator-hash = "0x8eac399e24480dce3cfe06f4bdecba51c6e5d0c46200e3e8611a0b44a3a69ff9"
+            genesis-hash = "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+
+            [protocol-ids]
+            state = "0x5f0a"
+            history = "0x5F0B"
+            beacon = "0x5F0C"
+
+            [forks]
+            merge-block = 0
+            shanghai-block = 100
+            "#
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(spec.get_network_name(), "devnet-1");
+        assert_eq!(spec.chain_id(), 1337);
+        assert_eq!(
+            spec.get_protocol_id_from_hex("0x5F0A").unwrap(),
+            ProtocolId::State
+        );
+        assert_eq!(
+            spec.get_protocol_hex_from_id(&ProtocolId::Utp).unwrap(),
+            "0x757470"
+        );
+        assert!(spec
+            .get_protocol_hex_from_id(&ProtocolId::VerkleState)
+            .is_err());
+        assert_eq!(
+            spec.bootnodes().unwrap(),
+            &[Enr::from_str(BOOTNODE).unwrap()]
+        );
+        assert_eq!(
+            spec.pre_merge_acc_path(),
+            Some(Path::new("/tmp/accumulator.bin"))
+        );
+        assert!(spec.pre_merge_acc_hash().is_some());
+        assert!(spec.genesis_hash().is_some());
+        assert_eq!(
+            spec.forks(),
+            &ForkParams {
+                merge_block: 0,
+                shanghai_block: 100,
+                capella_epoch: ForkParams::default().capella_epoch,
+            }
+        );
+        assert_eq!(spec.cli_value(), "devnet-1");
+    }
+
+    #[test]
+    fn parse_minimal_network_spec() {
+        let spec: NetworkSpec = r#"
+            name = "devnet"
+            chain-id = 7
+
+            [protocol-ids]
+            state = "0x5F0A"
+            history = "0x5F0B"
+            beacon = "0x5F0C"
+            "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(spec.bootnodes(), None);
+        assert_eq!(spec.pre_merge_acc_path(), None);
+        assert_eq!(spec.forks(), &ForkParams::default());
+    }
+
+    #[test]
+    fn reject_invalid_network_specs() {
+        let spec = |name: &str, history: &str| {
+            format!(
+                r#"
+                name = "{name}"
+                chain-id = 7
+
+                [protocol-ids]
+                state = "0x5F0A"
+                history = "{history}"
+                beacon = "0x5F0C"
+                "#
+            )
+            .parse::<NetworkSpec>()
+        };
+
+        assert!(spec("devnet", "0x5F0B").is_ok());
+        assert!(spec("mainnet", "0x5F0B").is_err());
+        assert!(spec("../devnet", "0x5F0B").is_err());
+        assert!(spec("", "0x5F0B").is_err());
+        assert!(spec("devnet", "0x5f0a").is_err());
+        assert!(spec("devnet", "5F0B").is_err());
+        assert!(spec("devnet", "0x").is_err());
+        assert!(r#"
+            name = "devnet"
+            chain-id = 7
+
+            [protocol-ids]
+            state = "0x5F0A"
+            history = "0x5F0B"
+            "#
+        .parse::<NetworkSpec>()
+        .is_err());
+    }
+
+    #[test]
+    fn network_spec_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let path = dir.join("devnet.toml");
+        fs::write(
+            &path,
+            r#"
+            name = "devnet"
+            chain-id = 7
+            pre-merge-accumulator-path = "accumulator.bin"
+
+            [protocol-ids]
+            state = "0x5F0A"
+            history = "0x5F0B"
+            beacon = "0x5F0C"
+            "#,
+        )
+        .unwrap();
+
+        let spec = NetworkSpec::from_file(&path).unwrap();
+        assert_eq!(
+            spec.pre_merge_acc_path(),
+            Some(dir.join("accumulator.bin").as_path())
+        );
+        assert_eq!(spec.spec_file(), Some(path.as_path()));
+        assert_eq!(spec.cli_value(), path.display().to_string());
+    }
+}
@@ -0,0 +1,248 @@
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use ssz_types::{typenum, VariableList};
+
+use crate::types::{bytes::ByteList32, distance::Distance, portal_wire::CustomPayload};
+
+/// The ENR key used to advertise support for the custom payload extensions of Ping and Pong
+/// messages. The value is the highest supported `PING_EXTENSIONS_VERSION`.
+///
+/// Nodes that don't advertise it only understand the legacy custom payload (the data radius), so
+/// the extensions are sent only to nodes that advertise it.
+pub const ENR_PING_EXTENSIONS_KEY: &str = "pe";
+
+/// The current version of the custom payload extensions.
+pub const PING_EXTENSIONS_VERSION: u8 = 1;
+
+/// The size of the SSZ encoded data radius, which is the legacy custom payload.
+const DATA_RADIUS_SIZE: usize = 32;
+
+/// The typed custom payload of Ping and Pong messages.
+///
+/// The payload is encoded as:
+///
+/// ```text
+/// legacy:   ssz(data_radius)
+/// extended: ssz(data_radius) || version (1 byte) || ssz(extensions)
+/// ```
+///
+/// The payload with unknown extensions version is decoded as legacy payload, so newer versions
+/// stay compatible with older clients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PingPongPayload {
+    pub data_radius: Distance,
+    pub extensions: Option<PingExtensions>,
+}
+
+/// The optional node information that is exchanged in Ping and Pong messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingExtensions {
+    /// The client name and version (e.g. "t 0.1.0").
+    pub client_version: Option<String>,
+    /// The capabilities enabled on the node (e.g. enabled subnetworks).
+    pub capabilities: Option<Vec<String>>,
+    /// The utilisation of the node's storage.
+    pub storage_utilization: Option<StorageUtilization>,
+}
+
+/// The utilisation of the node's storage for a subnetwork.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUtilization {
+    pub used_bytes: u64,
+    pub capacity_bytes: u64,
+}
+
+type ByteList256 = VariableList<u8, typenum::U256>;
+
+/// The SSZ representation of the `PingExtensions` (version 1).
+#[derive(Encode, Decode)]
+struct SszPingExtensionsV1 {
+    client_version: Option<ByteList256>,
+    capabilities: Option<VariableList<ByteList32, typenum::U32>>,
+    storage_utilization: Option<StorageUtilization>,
+}
+
+impl PingPongPayload {
+    pub fn new(data_radius: Distance) -> Self {
+        Self {
+            data_radius,
+            extensions: None,
+        }
+    }
+
+    pub fn with_extensions(mut self, extensions: PingExtensions) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.data_radius.as_ssz_bytes();
+        if let Some(extensions) = &self.extensions {
+            bytes.push(PING_EXTENSIONS_VERSION);
+            bytes.extend(extensions.to_ssz().as_ssz_bytes());
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < DATA_RADIUS_SIZE {
+            return Err(DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: DATA_RADIUS_SIZE,
+            });
+        }
+        let (data_radius, extensions) = bytes.split_at(DATA_RADIUS_SIZE);
+        let data_radius = Distance::from(U256::from_le_slice(data_radius));
+
+        let extensions = match extensions.split_first() {
+            Some((&PING_EXTENSIONS_VERSION, extensions)) => Some(PingExtensions::from_ssz(
+                SszPingExtensionsV1::from_ssz_bytes(extensions)?,
+            )?),
+            // Legacy payload, or extensions of unknown version.
+            _ => None,
+        };
+        Ok(Self {
+            data_radius,
+            extensions,
+        })
+    }
+}
+
+impl From<PingPongPayload> for CustomPayload {
+    fn from(payload: PingPongPayload) -> Self {
+        CustomPayload::from(payload.encode())
+    }
+}
+
+impl TryFrom<&CustomPayload> for PingPongPayload {
+    type Error = DecodeError;
+
+    fn try_from(custom_payload: &CustomPayload) -> Result<Self, Self::Error> {
+        Self::decode(custom_payload.as_bytes())
+    }
+}
+
+impl PingExtensions {
+    fn to_ssz(&self) -> SszPingExtensionsV1 {
+        // Values that don't fit into the SSZ limits are truncated.
+        let to_byte_list = |value: &str, max_len: usize| {
+            let mut len = value.len().min(max_len);
+            while !value.is_char_boundary(len) {
+                len -= 1;
+            }
+            VariableList::from(value.as_bytes()[..len].to_vec())
+        };
+        SszPingExtensionsV1 {
+            client_version: self
+                .client_version
+                .as_ref()
+                .map(|client_version| to_byte_list(client_version, 256)),
+            capabilities: self.capabilities.as_ref().map(|capabilities| {
+                VariableList::from(
+                    capabilities
+                        .iter()
+                        .take(32)
+                        .map(|capability| to_byte_list(capability, 32))
+                        .collect::<Vec<_>>(),
+                )
+            }),
+            storage_utilization: self.storage_utilization,
+        }
+    }
+
+    fn from_ssz(extensions: SszPingExtensionsV1) -> Result<Self, DecodeError> {
+        let to_string = |value: &[u8]| {
+            String::from_utf8(value.to_vec())
+                .map_err(|err| DecodeError::BytesInvalid(format!("Invalid utf8 string: {err}")))
+        };
+        Ok(Self {
+            client_version: extensions
+                .client_version
+                .map(|client_version| to_string(&client_version))
+                .transpose()?,
+            capabilities: extensions
+                .capabilities
+                .map(|capabilities| {
+                    capabilities
+                        .iter()
+                        .map(|capability| to_string(capability))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?,
+            storage_utilization: extensions.storage_utilization,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn extensions() -> PingExtensions {
+        PingExtensions {
+            client_version: Some("t 0.1.0".to_string()),
+            capabilities: Some(vec!["history".to_string(), "state".to_string()]),
+            storage_utilization: Some(StorageUtilization {
+                used_bytes: 1_000,
+                capacity_bytes: 10_000,
+            }),
+        }
+    }
+
+    #[test]
+    fn legacy_payload() {
+        let data_radius = Distance::from(U256::MAX / U256::from(2));
+        let payload = PingPongPayload::new(data_radius);
+
+        // Legacy payload is just the SSZ encoded radius
+        let encoded = payload.encode();
+        assert_eq!(encoded, data_radius.as_ssz_bytes());
+        assert_eq!(PingPongPayload::decode(&encoded).unwrap(), payload);
+    }
+
+    #[test]
+    fn extended_payload() {
+        let payload = PingPongPayload::new(Distance::MAX).with_extensions(extensions());
+
+        let encoded = payload.encode();
+        assert_eq!(encoded[..32], Distance::MAX.as_ssz_bytes());
+        assert_eq!(encoded[32], PING_EXTENSIONS_VERSION);
+        assert_eq!(PingPongPayload::decode(&encoded).unwrap(), payload);
+    }
+
+    #[test]
+    fn extended_payload_without_optional_fields() {
+        let payload = PingPongPayload::new(Distance::ZERO).with_extensions(PingExtensions {
+            client_version: Some("t 0.1.0".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(PingPongPayload::decode(&payload.encode()).unwrap(), payload);
+    }
+
+    #[test]
+    fn unknown_extensions_version() {
+        let mut encoded = Distance::MAX.as_ssz_bytes();
+        encoded.extend([PING_EXTENSIONS_VERSION + 1, 1, 2, 3]);
+
+        assert_eq!(
+            PingPongPayload::decode(&encoded).unwrap(),
+            PingPongPayload::new(Distance::MAX)
+        );
+    }
+
+    #[test]
+    fn invalid_payload() {
+        // Too short for radius
+        assert!(PingPongPayload::decode(&[0; 31]).is_err());
+
+        // Invalid extensions
+        let mut encoded = Distance::MAX.as_ssz_bytes();
+        encoded.extend([PING_EXTENSIONS_VERSION, 1, 2, 3]);
+        assert!(PingPongPayload::decode(&encoded).is_err());
+    }
+}
@@ -33,7 +33,7 @@ pub struct AcceptInfo {
 }
 
 /// Response for TraceGossip endpoint
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TraceGossipInfo {
     // List of all ENRs that were offered the content
@@ -2,10 +2,11 @@ use std::{
     convert::{TryFrom, TryInto},
     fmt,
     ops::Deref,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
-use alloy_primitives::U256;
+use alloy_primitives::{B256, U256};
 use bimap::BiHashMap;
 use once_cell::sync::Lazy;
 use rlp::Encodable;
@@ -22,6 +23,7 @@ use crate::{
         bytes::ByteList2048,
         distance::Distance,
         enr::{Enr, SszEnr},
+        network_spec::ForkParams,
     },
     utils::bytes::{hex_decode, hex_encode, ByteUtilsError},
     RawContentKey,
@@ -102,10 +104,19 @@ impl From<Vec<u8>> for CustomPayload {
     }
 }
 
+impl CustomPayload {
+    /// Returns the raw bytes of the payload.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.payload.deref()
+    }
+}
+
 impl From<CustomPayload> for Distance {
     fn from(val: CustomPayload) -> Self {
-        let bytes = val.payload;
-        U256::from_le_slice(bytes.deref()).into()
+        // The radius is encoded in the first 32 bytes, the rest are the optional extensions
+        // (see `PingPongPayload`).
+        let bytes = val.as_bytes();
+        U256::from_le_slice(&bytes[..bytes.len().min(32)]).into()
     }
 }
 
@@ -177,10 +188,26 @@ pub enum ProtocolId {
     Utp,
 }
 
+/// The parameters of a portal network: its protocol ids, and the chain it serves.
+///
+/// Besides the built-in `MAINNET` and `TESTNET`, specs of private networks are loaded from a file
+/// with `NetworkSpec::from_file`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct NetworkSpec {
-    network_name: String,
-    portal_networks: BiHashMap<ProtocolId, String>,
+    pub(crate) network_name: String,
+    pub(crate) portal_networks: BiHashMap<ProtocolId, String>,
+    pub(crate) chain_id: u64,
+    /// The bootnodes of the network, or `None` to use the default bootnodes.
+    pub(crate) bootnodes: Option<Vec<Enr>>,
+    /// The path of the pre-merge accumulator, or `None` to use the mainnet accumulator.
+    pub(crate) pre_merge_acc_path: Option<PathBuf>,
+    /// The expected tree hash root of the pre-merge accumulator.
+    pub(crate) pre_merge_acc_hash: Option<B256>,
+    /// The expected hash of the genesis header.
+    pub(crate) genesis_hash: Option<B256>,
+    pub(crate) forks: ForkParams,
+    /// The file the spec was loaded from.
+    pub(crate) spec_file: Option<PathBuf>,
 }
 
 impl NetworkSpec {
@@ -204,6 +231,63 @@ impl NetworkSpec {
             .cloned()
             .ok_or(ProtocolIdError::Invalid)
     }
+
+    /// Returns the id of the chain served by the network, as returned by `eth_chainId`.
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Returns the bootnodes of the network, if it doesn't use the default bootnodes.
+    pub fn bootnodes(&self) -> Option<&[Enr]> {
+        self.bootnodes.as_deref()
+    }
+
+    /// Returns the path of the pre-merge accumulator, if it isn't the mainnet accumulator.
+    pub fn pre_merge_acc_path(&self) -> Option<&Path> {
+        self.pre_merge_acc_path.as_deref()
+    }
+
+    /// Returns the expected tree hash root of the pre-merge accumulator, if any.
+    pub fn pre_merge_acc_hash(&self) -> Option<B256> {
+        self.pre_merge_acc_hash
+    }
+
+    /// Returns the expected hash of the genesis header, if any.
+    pub fn genesis_hash(&self) -> Option<B256> {
+        self.genesis_hash
+    }
+
+    pub fn forks(&self) -> &ForkParams {
+        &self.forks
+    }
+
+    /// Returns the file the spec was loaded from, or `None` for the built-in networks.
+    pub fn spec_file(&self) -> Option<&Path> {
+        self.spec_file.as_deref()
+    }
+
+    /// Returns the value of the `--network` flag that selects this network.
+    pub fn cli_value(&self) -> String {
+        match &self.spec_file {
+            Some(path) => path.display().to_string(),
+            None => self.network_name.clone(),
+        }
+    }
+
+    /// Returns the spec of a built-in network, which serves the mainnet chain.
+    fn built_in(network_name: &str, portal_networks: BiHashMap<ProtocolId, String>) -> Self {
+        Self {
+            network_name: network_name.to_string(),
+            portal_networks,
+            chain_id: 1,
+            bootnodes: None,
+            pre_merge_acc_path: None,
+            pre_merge_acc_hash: None,
+            genesis_hash: None,
+            forks: ForkParams::default(),
+            spec_file: None,
+        }
+    }
 }
 
 pub static MAINNET: Lazy<Arc<NetworkSpec>> = Lazy::new(|| {
@@ -215,11 +299,7 @@ pub static MAINNET: Lazy<Arc<NetworkSpec>> = Lazy::new(|| {
     portal_networks.insert(ProtocolId::VerkleState, "0x500E".to_string());
     portal_networks.insert(ProtocolId::TransactionGossip, "0x500F".to_string());
     portal_networks.insert(ProtocolId::Utp, "0x757470".to_string());
-    NetworkSpec {
-        portal_networks,
-        network_name: "mainnet".to_string(),
-    }
-    .into()
+    NetworkSpec::built_in("mainnet", portal_networks).into()
 });
 
 pub static TESTNET: Lazy<Arc<NetworkSpec>> = Lazy::new(|| {
@@ -231,11 +311,7 @@ pub static TESTNET: Lazy<Arc<NetworkSpec>> = Lazy::new(|| {
     portal_networks.insert(ProtocolId::VerkleState, "0x504E".to_string());
     portal_networks.insert(ProtocolId::TransactionGossip, "0x504F".to_string());
     portal_networks.insert(ProtocolId::Utp, "0x757470".to_string());
-    NetworkSpec {
-        portal_networks,
-        network_name: "testnet".to_string(),
-    }
-    .into()
+    NetworkSpec::built_in("testnet", portal_networks).into()
 });
 
 impl fmt::Display for ProtocolId {
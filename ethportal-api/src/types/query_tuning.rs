@@ -0,0 +1,206 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure};
+use serde::{Deserialize, Serialize};
+
+use crate::types::cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK};
+
+/// The tuning of the overlay network queries (FINDNODES and FINDCONTENT). Unset values are left
+/// unchanged.
+///
+/// The keys are the same in the config file and in the `portal_setQueryTuning` admin RPC.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct QueryTuning {
+    /// The number of peers that a query requests in parallel.
+    pub query_parallelism: Option<usize>,
+    /// The timeout of a query, in milliseconds.
+    pub query_timeout_ms: Option<u64>,
+    /// The timeout of a request to a single peer of a query, in milliseconds.
+    pub query_peer_timeout_ms: Option<u64>,
+    /// The number of results of a FINDNODES query.
+    pub query_num_results: Option<usize>,
+    /// The number of distances requested from each peer in a FINDNODES query.
+    pub findnodes_query_distances_per_peer: Option<usize>,
+}
+
+impl QueryTuning {
+    /// Returns the tuning, with the values that are set in `other` taking precedence.
+    pub fn merge(self, other: &QueryTuning) -> Self {
+        Self {
+            query_parallelism: other.query_parallelism.or(self.query_parallelism),
+            query_timeout_ms: other.query_timeout_ms.or(self.query_timeout_ms),
+            query_peer_timeout_ms: other.query_peer_timeout_ms.or(self.query_peer_timeout_ms),
+            query_num_results: other.query_num_results.or(self.query_num_results),
+            findnodes_query_distances_per_peer: other
+                .findnodes_query_distances_per_peer
+                .or(self.findnodes_query_distances_per_peer),
+        }
+    }
+
+    /// Checks that the values are greater than 0.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (key, value) in [
+            (
+                "query-parallelism",
+                self.query_parallelism.map(|v| v as u64),
+            ),
+            ("query-timeout-ms", self.query_timeout_ms),
+            ("query-peer-timeout-ms", self.query_peer_timeout_ms),
+            (
+                "query-num-results",
+                self.query_num_results.map(|v| v as u64),
+            ),
+            (
+                "findnodes-query-distances-per-peer",
+                self.findnodes_query_distances_per_peer.map(|v| v as u64),
+            ),
+        ] {
+            ensure!(value != Some(0), "Invalid {key}: must be greater than 0");
+        }
+        Ok(())
+    }
+
+    /// Sets the value of the key, e.g. `query-parallelism`.
+    fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let invalid_value = |err| anyhow!("Invalid value of {key}: {err}");
+        match key {
+            "query-parallelism" => {
+                self.query_parallelism = Some(value.parse().map_err(invalid_value)?)
+            }
+            "query-timeout-ms" => {
+                self.query_timeout_ms = Some(value.parse().map_err(invalid_value)?)
+            }
+            "query-peer-timeout-ms" => {
+                self.query_peer_timeout_ms = Some(value.parse().map_err(invalid_value)?)
+            }
+            "query-num-results" => {
+                self.query_num_results = Some(value.parse().map_err(invalid_value)?)
+            }
+            "findnodes-query-distances-per-peer" => {
+                self.findnodes_query_distances_per_peer =
+                    Some(value.parse().map_err(invalid_value)?)
+            }
+            _ => return Err(anyhow!("Invalid query tuning key: {key}")),
+        }
+        Ok(())
+    }
+}
+
+/// The query tuning of individual subnetworks, set with `--query-tuning`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SubnetworkQueryTuning(Vec<(String, QueryTuning)>);
+
+impl SubnetworkQueryTuning {
+    /// Returns the query tuning of the subnetwork.
+    pub fn get(&self, subnetwork: &str) -> QueryTuning {
+        self.0
+            .iter()
+            .find(|(name, _)| name == subnetwork)
+            .map(|(_, tuning)| *tuning)
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for SubnetworkQueryTuning {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut subnetwork_tuning: Vec<(String, QueryTuning)> = vec![];
+        for entry in s.split(',') {
+            let Some((subnetwork, key, value)) =
+                entry.split_once(':').and_then(|(subnetwork, setting)| {
+                    let (key, value) = setting.split_once('=')?;
+                    Some((subnetwork, key, value))
+                })
+            else {
+                return Err(anyhow!(
+                    "Invalid query tuning {entry}, expected <subnetwork>:<key>=<value>"
+                ));
+            };
+            if ![BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK].contains(&subnetwork) {
+                return Err(anyhow!("Invalid query tuning subnetwork: {subnetwork}"));
+            }
+            let index = match subnetwork_tuning
+                .iter()
+                .position(|(name, _)| name == subnetwork)
+            {
+                Some(index) => index,
+                None => {
+                    subnetwork_tuning.push((subnetwork.to_string(), QueryTuning::default()));
+                    subnetwork_tuning.len() - 1
+                }
+            };
+            let tuning = &mut subnetwork_tuning[index].1;
+            tuning.set(key, value)?;
+            tuning.validate()?;
+        }
+        Ok(Self(subnetwork_tuning))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    #[test]
+    fn parse_subnetwork_query_tuning() {
+        let tuning = SubnetworkQueryTuning::from_str(
+            "history:query-parallelism=8,history:query-timeout-ms=30000,state:query-num-results=32",
+        )
+        .unwrap();
+        assert_eq!(
+            tuning.get(HISTORY_NETWORK),
+            QueryTuning {
+                query_parallelism: Some(8),
+                query_timeout_ms: Some(30000),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            tuning.get(STATE_NETWORK),
+            QueryTuning {
+                query_num_results: Some(32),
+                ..Default::default()
+            }
+        );
+        assert_eq!(tuning.get(BEACON_NETWORK), QueryTuning::default());
+    }
+
+    #[rstest]
+    #[case("history")]
+    #[case("history:query-parallelism")]
+    #[case("foo:query-parallelism=8")]
+    #[case("history:query-paralelism=8")]
+    #[case("history:query-parallelism=-1")]
+    #[case("history:query-parallelism=0")]
+    fn parse_invalid_subnetwork_query_tuning(#[case] s: &str) {
+        assert!(SubnetworkQueryTuning::from_str(s).is_err());
+    }
+
+    #[test]
+    fn merge() {
+        let tuning = QueryTuning {
+            query_parallelism: Some(3),
+            query_timeout_ms: Some(60000),
+            ..Default::default()
+        }
+        .merge(&QueryTuning {
+            query_parallelism: Some(8),
+            query_num_results: Some(32),
+            ..Default::default()
+        });
+        assert_eq!(
+            tuning,
+            QueryTuning {
+                query_parallelism: Some(8),
+                query_timeout_ms: Some(60000),
+                query_num_results: Some(32),
+                ..Default::default()
+            }
+        );
+    }
+}
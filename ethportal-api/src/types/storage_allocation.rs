@@ -0,0 +1,210 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::types::cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK};
+
+/// The storage capacity allocated to a single subnetwork.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SubnetworkCapacity {
+    /// The storage capacity in megabytes.
+    Megabytes(u64),
+    /// The percentage of the total storage capacity.
+    Percentage(u64),
+}
+
+impl SubnetworkCapacity {
+    /// Returns the storage capacity in megabytes.
+    pub fn capacity_mb(&self, total_capacity_mb: u64) -> u64 {
+        match self {
+            Self::Megabytes(capacity_mb) => *capacity_mb,
+            Self::Percentage(percentage) => total_capacity_mb * percentage / 100,
+        }
+    }
+}
+
+impl FromStr for SubnetworkCapacity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percentage) => {
+                let percentage = u64::from_str(percentage)
+                    .map_err(|err| anyhow!("Invalid percentage {s}: {err}"))?;
+                if percentage > 100 {
+                    return Err(anyhow!("Invalid percentage {s}: must not exceed 100%"));
+                }
+                Ok(Self::Percentage(percentage))
+            }
+            None => Ok(Self::Megabytes(
+                u64::from_str(s).map_err(|err| anyhow!("Invalid capacity {s}: {err}"))?,
+            )),
+        }
+    }
+}
+
+/// The allocation of the total storage capacity between subnetworks.
+///
+/// Subnetworks that are not explicitly allocated share the unallocated storage capacity evenly.
+/// If no subnetwork is allocated, each subnetwork uses the total storage capacity.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StorageAllocation(Vec<(String, SubnetworkCapacity)>);
+
+impl StorageAllocation {
+    /// Returns the subnetworks that are explicitly allocated.
+    pub fn subnetworks(&self) -> impl Iterator<Item = &String> {
+        self.0.iter().map(|(subnetwork, _)| subnetwork)
+    }
+
+    /// Checks that the allocated storage capacity doesn't exceed the total storage capacity.
+    pub fn validate(&self, total_capacity_mb: u64) -> anyhow::Result<()> {
+        let allocated_mb: u64 = self
+            .0
+            .iter()
+            .map(|(_, capacity)| capacity.capacity_mb(total_capacity_mb))
+            .sum();
+        if allocated_mb > total_capacity_mb {
+            return Err(anyhow!(
+                "Invalid storage allocation, allocated capacity ({allocated_mb} MB) exceeds total capacity ({total_capacity_mb} MB)"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the storage capacity (in megabytes) allocated to the subnetwork.
+    pub fn capacity_mb(
+        &self,
+        subnetwork: &str,
+        total_capacity_mb: u64,
+        enabled_subnetworks: &[String],
+    ) -> u64 {
+        if self.0.is_empty() {
+            return total_capacity_mb;
+        }
+        if let Some((_, capacity)) = self.0.iter().find(|(name, _)| name == subnetwork) {
+            return capacity.capacity_mb(total_capacity_mb);
+        }
+
+        let allocated_mb: u64 = self
+            .0
+            .iter()
+            .filter(|(name, _)| enabled_subnetworks.contains(name))
+            .map(|(_, capacity)| capacity.capacity_mb(total_capacity_mb))
+            .sum();
+        let unallocated_count = enabled_subnetworks
+            .iter()
+            .filter(|name| self.subnetworks().all(|allocated| allocated != *name))
+            .count() as u64;
+        if unallocated_count == 0 {
+            return 0;
+        }
+        total_capacity_mb.saturating_sub(allocated_mb) / unallocated_count
+    }
+}
+
+impl FromStr for StorageAllocation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut allocation: Vec<(String, SubnetworkCapacity)> = vec![];
+        for entry in s.split(',') {
+            let Some((subnetwork, capacity)) = entry.split_once(':') else {
+                return Err(anyhow!(
+                    "Invalid storage allocation {entry}, expected <subnetwork>:<capacity>"
+                ));
+            };
+            if ![BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK].contains(&subnetwork) {
+                return Err(anyhow!(
+                    "Invalid storage allocation subnetwork: {subnetwork}"
+                ));
+            }
+            if allocation.iter().any(|(name, _)| name == subnetwork) {
+                return Err(anyhow!("Duplicate storage allocation for {subnetwork}"));
+            }
+            allocation.push((
+                subnetwork.to_string(),
+                SubnetworkCapacity::from_str(capacity)?,
+            ));
+        }
+
+        let total_percentage: u64 = allocation
+            .iter()
+            .filter_map(|(_, capacity)| match capacity {
+                SubnetworkCapacity::Percentage(percentage) => Some(percentage),
+                SubnetworkCapacity::Megabytes(_) => None,
+            })
+            .sum();
+        if total_percentage > 100 {
+            return Err(anyhow!(
+                "Invalid storage allocation, total percentage ({total_percentage}%) exceeds 100%"
+            ));
+        }
+        Ok(Self(allocation))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn subnetworks(subnetworks: &[&str]) -> Vec<String> {
+        subnetworks.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn no_allocation() {
+        let allocation = StorageAllocation::default();
+        let enabled = subnetworks(&["history", "state"]);
+        assert_eq!(allocation.capacity_mb("history", 1000, &enabled), 1000);
+        assert_eq!(allocation.capacity_mb("state", 1000, &enabled), 1000);
+    }
+
+    #[rstest]
+    #[case("history:80%,state:20%", 800, 200, 0)]
+    #[case("history:80%", 800, 100, 100)]
+    #[case("history:500,state:10%", 500, 100, 400)]
+    #[case("history:1000", 1000, 0, 0)]
+    #[case("beacon:0", 500, 500, 0)]
+    fn allocation(
+        #[case] allocation: &str,
+        #[case] expected_history_mb: u64,
+        #[case] expected_state_mb: u64,
+        #[case] expected_beacon_mb: u64,
+    ) {
+        let allocation = StorageAllocation::from_str(allocation).unwrap();
+        let enabled = subnetworks(&["history", "state", "beacon"]);
+        assert_eq!(
+            allocation.capacity_mb("history", 1000, &enabled),
+            expected_history_mb
+        );
+        assert_eq!(
+            allocation.capacity_mb("state", 1000, &enabled),
+            expected_state_mb
+        );
+        assert_eq!(
+            allocation.capacity_mb("beacon", 1000, &enabled),
+            expected_beacon_mb
+        );
+    }
+
+    #[rstest]
+    #[case::megabytes("history:1500")]
+    #[case::megabytes_and_percentage("history:500,state:60%")]
+    fn allocation_exceeding_capacity(#[case] allocation: &str) {
+        let allocation = StorageAllocation::from_str(allocation).unwrap();
+        assert!(allocation.validate(1000).is_err());
+    }
+
+    #[rstest]
+    #[case::missing_capacity("history")]
+    #[case::invalid_subnetwork("execution:10%")]
+    #[case::invalid_capacity("history:ten")]
+    #[case::percentage_above_100("history:101%")]
+    #[case::total_percentage_above_100("history:80%,state:30%")]
+    #[case::duplicate("history:10%,history:20%")]
+    fn invalid_allocation(#[case] allocation: &str) {
+        assert!(StorageAllocation::from_str(allocation).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+    enr::Enr,
+};
+
+/// The kinds of events that JSON-RPC clients can subscribe to with `portal_subscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+    HistoryContentStored,
+    HistoryOfferReceived,
+    StateContentStored,
+    StateOfferReceived,
+    BeaconContentStored,
+    BeaconOfferReceived,
+    /// A peer is connected in the routing table of any subnetwork.
+    PeerConnected,
+    /// A new light client optimistic update is stored by the beacon subnetwork.
+    LightClientHead,
+}
+
+/// An event that is sent to the JSON-RPC clients subscribed to its kind.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SubscriptionEvent {
+    /// The content received from the network is stored in the local store.
+    #[serde(rename_all = "camelCase")]
+    ContentStored {
+        subnetwork: String,
+        content_key: String,
+    },
+    /// An offer is received from a peer.
+    #[serde(rename_all = "camelCase")]
+    OfferReceived {
+        subnetwork: String,
+        peer: String,
+        content_keys: Vec<String>,
+        /// Whether each of the offered content keys is accepted.
+        accepted: Vec<bool>,
+    },
+    /// A peer is connected in the routing table of the subnetwork.
+    #[serde(rename_all = "camelCase")]
+    PeerConnected { subnetwork: String, enr: Enr },
+    /// A new light client optimistic update is stored.
+    #[serde(rename_all = "camelCase")]
+    LightClientHead { signature_slot: u64 },
+}
+
+impl SubscriptionEvent {
+    /// Returns the kind of the event, or `None` if the event belongs to an unknown subnetwork.
+    pub fn kind(&self) -> Option<SubscriptionKind> {
+        match self {
+            SubscriptionEvent::ContentStored { subnetwork, .. } => match subnetwork.as_str() {
+                HISTORY_NETWORK => Some(SubscriptionKind::HistoryContentStored),
+                STATE_NETWORK => Some(SubscriptionKind::StateContentStored),
+                BEACON_NETWORK => Some(SubscriptionKind::BeaconContentStored),
+                _ => None,
+            },
+            SubscriptionEvent::OfferReceived { subnetwork, .. } => match subnetwork.as_str() {
+                HISTORY_NETWORK => Some(SubscriptionKind::HistoryOfferReceived),
+                STATE_NETWORK => Some(SubscriptionKind::StateOfferReceived),
+                BEACON_NETWORK => Some(SubscriptionKind::BeaconOfferReceived),
+                _ => None,
+            },
+            SubscriptionEvent::PeerConnected { .. } => Some(SubscriptionKind::PeerConnected),
+            SubscriptionEvent::LightClientHead { .. } => Some(SubscriptionKind::LightClientHead),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn kind_from_json() {
+        let kind: SubscriptionKind = serde_json::from_value(json!("historyContentStored")).unwrap();
+        assert_eq!(kind, SubscriptionKind::HistoryContentStored);
+        let kind: SubscriptionKind = serde_json::from_value(json!("lightClientHead")).unwrap();
+        assert_eq!(kind, SubscriptionKind::LightClientHead);
+    }
+
+    #[test]
+    fn event_to_json() {
+        let event = SubscriptionEvent::ContentStored {
+            subnetwork: HISTORY_NETWORK.to_string(),
+            content_key: "0x00".to_string(),
+        };
+        assert_eq!(event.kind(), Some(SubscriptionKind::HistoryContentStored));
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "type": "contentStored",
+                "subnetwork": "history",
+                "contentKey": "0x00",
+            })
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+/// The default maximum size of a uTP packet, in bytes.
+pub const DEFAULT_UTP_MAX_PACKET_SIZE: u16 = 1024;
+
+/// The lowest maximum size of a uTP packet, in bytes. Packets must have room for the 20 byte uTP
+/// header and a useful payload.
+pub const MIN_UTP_MAX_PACKET_SIZE: u16 = 150;
+
+/// The tuning of uTP connections. Unset values use the defaults of the uTP implementation, except
+/// for the max packet size, which defaults to [`DEFAULT_UTP_MAX_PACKET_SIZE`].
+///
+/// The keys are the same in the config file and in the uTP test app.
+#[derive(Args, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UtpConnectionTuning {
+    #[arg(
+        long = "utp-max-packet-size",
+        help = "The maximum size of a uTP packet, in bytes [default: 1024]",
+        value_parser = clap::value_parser!(u16).range(MIN_UTP_MAX_PACKET_SIZE as i64..),
+    )]
+    pub max_packet_size: Option<u16>,
+
+    #[arg(
+        long = "utp-initial-timeout-ms",
+        help = "The initial retransmission timeout of a uTP connection, in milliseconds",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub initial_timeout_ms: Option<u64>,
+
+    #[arg(
+        long = "utp-idle-timeout-ms",
+        help = "The time after which an idle uTP connection is closed, in milliseconds",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub idle_timeout_ms: Option<u64>,
+
+    #[arg(
+        long = "utp-max-conn-attempts",
+        help = "The maximum number of attempts to establish a uTP connection",
+        value_parser = clap::value_parser!(u64).range(1..).map(|attempts| attempts as usize),
+    )]
+    pub max_conn_attempts: Option<usize>,
+
+    #[arg(
+        long = "utp-window-size",
+        help = "The size of the send and receive windows of a uTP connection, in bytes",
+        value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    pub window_size: Option<u32>,
+}
+
+impl UtpConnectionTuning {
+    /// Returns the tuning, with the values that are set in `other` taking precedence.
+    pub fn merge(self, other: &UtpConnectionTuning) -> Self {
+        Self {
+            max_packet_size: other.max_packet_size.or(self.max_packet_size),
+            initial_timeout_ms: other.initial_timeout_ms.or(self.initial_timeout_ms),
+            idle_timeout_ms: other.idle_timeout_ms.or(self.idle_timeout_ms),
+            max_conn_attempts: other.max_conn_attempts.or(self.max_conn_attempts),
+            window_size: other.window_size.or(self.window_size),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let tuning = UtpConnectionTuning {
+            max_packet_size: Some(1024),
+            initial_timeout_ms: Some(1000),
+            ..Default::default()
+        }
+        .merge(&UtpConnectionTuning {
+            initial_timeout_ms: Some(3000),
+            window_size: Some(65536),
+            ..Default::default()
+        });
+        assert_eq!(
+            tuning,
+            UtpConnectionTuning {
+                max_packet_size: Some(1024),
+                initial_timeout_ms: Some(3000),
+                window_size: Some(65536),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let tuning: UtpConnectionTuning =
+            serde_json::from_str(r#"{"max-packet-size": 512, "idle-timeout-ms": 32000}"#).unwrap();
+        assert_eq!(
+            tuning,
+            UtpConnectionTuning {
+                max_packet_size: Some(512),
+                idle_timeout_ms: Some(32000),
+                ..Default::default()
+            }
+        );
+        assert!(serde_json::from_str::<UtpConnectionTuning>(r#"{"packet-size": 512}"#).is_err());
+    }
+}
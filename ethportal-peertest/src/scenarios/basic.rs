@@ -2,6 +2,7 @@ use crate::{utils::fixture_header_with_proof, Peertest};
 use alloy_primitives::{B256, U256};
 use ethportal_api::{
     types::{distance::Distance, portal_wire::ProtocolId},
+    utils::bytes::hex_encode,
     BeaconNetworkApiClient, BlockHeaderKey, Discv5ApiClient, HistoryContentKey,
     HistoryNetworkApiClient, StateNetworkApiClient, Web3ApiClient,
 };
@@ -42,6 +43,32 @@ pub async fn test_routing_table_info(protocol: ProtocolId, target: &Client) {
     assert!(result.local_node_id.starts_with("0x"));
 }
 
+pub async fn test_routing_table_detail(protocol: ProtocolId, target: &Client, peertest: &Peertest) {
+    info!("Testing routing_table_detail for {protocol}");
+    let result = match protocol {
+        ProtocolId::Beacon => BeaconNetworkApiClient::routing_table_detail(target),
+        ProtocolId::History => HistoryNetworkApiClient::routing_table_detail(target),
+        ProtocolId::State => StateNetworkApiClient::routing_table_detail(target),
+        _ => panic!("Unexpected protocol: {protocol}"),
+    }
+    .await
+    .unwrap();
+    assert!(result.local_node_id.starts_with("0x"));
+
+    // The bootnode was pinged by the target, so its activity should be recorded.
+    let bootnode_id = hex_encode(peertest.bootnode.enr.node_id().raw());
+    let bootnode = result
+        .nodes
+        .iter()
+        .find(|node| node.node_id == bootnode_id)
+        .expect("Bootnode should be in the routing table");
+    assert!(bootnode.requests_sent > 0);
+    assert!(bootnode.responses_received > 0);
+    assert!(bootnode.last_seen.is_some());
+    assert!(bootnode.last_ping_rtt_ms.is_some());
+    assert_eq!(bootnode.client, Some(format!("t {}", get_trin_version())));
+}
+
 pub async fn test_radius(protocol: ProtocolId, target: &Client) {
     info!("Testing radius for {protocol}");
     let result = match protocol {
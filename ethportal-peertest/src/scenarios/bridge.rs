@@ -6,10 +6,13 @@ use ethportal_api::{jsonrpsee::http_client::HttpClient, BeaconContentKey, Beacon
 use portal_bridge::{
     api::{consensus::ConsensusApi, execution::ExecutionApi},
     bridge::{beacon::BeaconBridge, history::HistoryBridge},
-    constants::DEFAULT_GOSSIP_LIMIT,
+    constants::{DEFAULT_GOSSIP_LIMIT, DEFAULT_REPLICATION_TARGET},
+    gossip::ReplicationConfig,
+    retry_list::RetryList,
     types::mode::BridgeMode,
 };
 use serde_json::Value;
+use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 use trin_validation::oracle::HeaderOracle;
 use url::Url;
@@ -23,6 +26,11 @@ pub async fn test_history_bridge(peertest: &Peertest, portal_client: &HttpClient
     let execution_api = ExecutionApi::new(client_url.clone(), client_url)
         .await
         .unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let replication = ReplicationConfig {
+        target: DEFAULT_REPLICATION_TARGET,
+        retry_list: Arc::new(RetryList::open(&temp_dir.path().join("retry-list.jsonl")).unwrap()),
+    };
     // Wait for bootnode to start
     sleep(Duration::from_secs(1)).await;
     let bridge = HistoryBridge::new(
@@ -32,6 +40,7 @@ pub async fn test_history_bridge(peertest: &Peertest, portal_client: &HttpClient
         header_oracle,
         epoch_acc_path,
         DEFAULT_GOSSIP_LIMIT,
+        replication,
     );
     bridge.launch().await;
     let (content_key, content_value) = fixture_header_with_proof_1000010();
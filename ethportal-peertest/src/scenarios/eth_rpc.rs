@@ -10,6 +10,6 @@ pub async fn test_eth_chain_id(peertest: &Peertest) {
     info!("Testing eth_chainId");
     let target = &peertest.bootnode.ipc_client;
     let chain_id = target.chain_id().await.unwrap();
-    // For now, the chain ID is always 1 -- portal only supports mainnet Ethereum
+    // The peertest nodes are part of the mainnet portal network
     assert_eq!(chain_id, U256::from(CHAIN_ID));
 }
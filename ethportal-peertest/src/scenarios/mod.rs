@@ -5,6 +5,7 @@ pub mod find;
 pub mod gossip;
 pub mod offer_accept;
 pub mod paginate;
+pub mod range;
 pub mod state;
 pub mod utp;
 pub mod validation;
@@ -0,0 +1,66 @@
+use std::fs;
+
+use ethportal_api::{
+    types::{
+        execution::accumulator::EpochAccumulator,
+        history::{GetRangeItemStatus, HistoryContentType},
+    },
+    EpochAccumulatorKey, HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient,
+};
+use jsonrpsee::async_client::Client;
+use ssz::Decode;
+use tracing::info;
+use tree_hash::TreeHash;
+
+/// Epoch accumulator of the last pre-merge epoch, containing blocks 15_532_032 to 15_537_393.
+const EPOCH_ACC_PATH: &str = "./trin-validation/src/assets/epoch_accs/0xe6ebe562c89bc8ecb94dc9b2889a27a816ec05d3d6bd1625acad72227071e721.bin";
+const EPOCH_START: u64 = 15_532_032;
+
+pub async fn test_get_range(target: &Client) {
+    info!("Testing portal_historyGetRange");
+    let epoch_acc = fs::read(EPOCH_ACC_PATH).unwrap();
+    let epoch_acc = EpochAccumulator::from_ssz_bytes(&epoch_acc).unwrap();
+    let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+        epoch_hash: epoch_acc.tree_hash_root(),
+    });
+    let content_value = HistoryContentValue::EpochAccumulator(epoch_acc.clone());
+    assert!(target.store(content_key, content_value).await.unwrap());
+
+    let start_block = 15_537_392;
+    let end_block = 15_537_393;
+    let result = target
+        .get_range(
+            start_block,
+            end_block,
+            vec![HistoryContentType::Header, HistoryContentType::Body],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.next_block, None);
+    assert_eq!(result.items.len(), 4);
+
+    // The block hashes are resolved through the stored epoch accumulator, while the content
+    // itself isn't available in the network.
+    for (item, (block_number, content_type)) in result.items.iter().zip([
+        (start_block, HistoryContentType::Header),
+        (start_block, HistoryContentType::Body),
+        (end_block, HistoryContentType::Header),
+        (end_block, HistoryContentType::Body),
+    ]) {
+        let block_hash = epoch_acc[(block_number - EPOCH_START) as usize].block_hash;
+        assert_eq!(item.block_number, block_number);
+        assert_eq!(item.content_type, content_type);
+        assert_eq!(item.content_key, Some(content_type.content_key(block_hash)));
+        assert_eq!(item.status, GetRangeItemStatus::NotFound);
+        assert_eq!(item.content, None);
+    }
+
+    // The merge block isn't in the pre-merge accumulator, so it's rejected.
+    let result = target
+        .get_range(end_block, end_block + 1, vec![HistoryContentType::Header])
+        .await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Post-merge blocks are not supported"));
+}
@@ -24,7 +24,7 @@ use crate::{
         history::{HEADER_SATURATION_DELAY, SERVE_BLOCK_TIMEOUT},
         utils::lookup_epoch_acc,
     },
-    gossip::gossip_history_content,
+    gossip::{gossip_history_content, ReplicationConfig},
     stats::{HistoryBlockStats, StatsReporter},
     types::{
         era1::{BlockTuple, Era1},
@@ -54,6 +54,7 @@ pub struct Era1Bridge {
     pub metrics: BridgeMetricsReporter,
     pub gossip_limit: usize,
     pub execution_api: ExecutionApi,
+    pub replication: ReplicationConfig,
 }
 
 // todo: validate via checksum, so we don't have to validate content on a per-value basis
@@ -65,6 +66,7 @@ impl Era1Bridge {
         epoch_acc_path: PathBuf,
         gossip_limit: usize,
         execution_api: ExecutionApi,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<Self> {
         let http_client: Client = Config::new()
             .add_header("Content-Type", "application/xml")
@@ -82,6 +84,7 @@ impl Era1Bridge {
             metrics,
             gossip_limit,
             execution_api,
+            replication,
         })
     }
 
@@ -264,6 +267,7 @@ impl Era1Bridge {
                 permit,
                 self.metrics.clone(),
                 hunt,
+                self.replication.clone(),
             );
             serve_block_tuple_handles.push(serve_block_tuple_handle);
         }
@@ -287,9 +291,16 @@ impl Era1Bridge {
         debug!("Built EpochAccumulator for Epoch #{epoch_index:?}: now gossiping.");
         // spawn gossip in new thread to avoid blocking
         let portal_client = self.portal_client.clone();
+        let replication = self.replication.clone();
         tokio::spawn(async move {
-            if let Err(msg) =
-                gossip_history_content(portal_client, content_key, content_value, block_stats).await
+            if let Err(msg) = gossip_history_content(
+                portal_client,
+                content_key,
+                content_value,
+                block_stats,
+                replication,
+            )
+            .await
             {
                 warn!("Failed to gossip epoch accumulator: {msg}");
             }
@@ -305,6 +316,7 @@ impl Era1Bridge {
         permit: OwnedSemaphorePermit,
         metrics: BridgeMetricsReporter,
         hunt: bool,
+        replication: ReplicationConfig,
     ) -> JoinHandle<()> {
         let number = block_tuple.header.header.number;
         info!("Spawning serve_block_tuple for block at height: {number}");
@@ -321,6 +333,7 @@ impl Era1Bridge {
                     block_stats.clone(),
                     metrics.clone(),
                     hunt,
+                    replication,
                 )).await
                 {
                 Ok(result) => match result {
@@ -347,6 +360,7 @@ impl Era1Bridge {
         block_stats: Arc<Mutex<HistoryBlockStats>>,
         metrics: BridgeMetricsReporter,
         hunt: bool,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         info!(
             "Serving block tuple at height: {}",
@@ -378,6 +392,7 @@ impl Era1Bridge {
                 epoch_acc,
                 header_validator,
                 block_stats.clone(),
+                replication.clone(),
             )
             .await
             {
@@ -432,6 +447,7 @@ impl Era1Bridge {
                 portal_client.clone(),
                 block_tuple.clone(),
                 block_stats.clone(),
+                replication.clone(),
             )
             .await
             {
@@ -476,6 +492,7 @@ impl Era1Bridge {
                 portal_client.clone(),
                 block_tuple.clone(),
                 block_stats.clone(),
+                replication.clone(),
             )
             .await
             {
@@ -505,6 +522,7 @@ impl Era1Bridge {
         epoch_acc: Arc<EpochAccumulator>,
         header_validator: Arc<HeaderValidator>,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         debug!(
             "Serving block header at height: {}",
@@ -536,13 +554,21 @@ impl Era1Bridge {
         // Construct HistoryContentValue
         let content_value = HistoryContentValue::BlockHeaderWithProof(header_with_proof);
 
-        gossip_history_content(portal_client, content_key, content_value, block_stats).await
+        gossip_history_content(
+            portal_client,
+            content_key,
+            content_value,
+            block_stats,
+            replication,
+        )
+        .await
     }
 
     async fn construct_and_gossip_block_body(
         portal_client: HttpClient,
         block_tuple: BlockTuple,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         debug!(
             "Serving block body at height: {}",
@@ -555,13 +581,21 @@ impl Era1Bridge {
         });
         // Construct HistoryContentValue
         let content_value = HistoryContentValue::BlockBody(block_tuple.body.body);
-        gossip_history_content(portal_client, content_key, content_value, block_stats).await
+        gossip_history_content(
+            portal_client,
+            content_key,
+            content_value,
+            block_stats,
+            replication,
+        )
+        .await
     }
 
     async fn construct_and_gossip_receipts(
         portal_client: HttpClient,
         block_tuple: BlockTuple,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         debug!(
             "Serving block receipts at height: {}",
@@ -574,7 +608,14 @@ impl Era1Bridge {
         });
         // Construct HistoryContentValue
         let content_value = HistoryContentValue::Receipts(block_tuple.receipts.receipts);
-        gossip_history_content(portal_client, content_key, content_value, block_stats).await
+        gossip_history_content(
+            portal_client,
+            content_key,
+            content_value,
+            block_stats,
+            replication,
+        )
+        .await
     }
 }
 
@@ -15,7 +15,7 @@ use trin_metrics::bridge::BridgeMetricsReporter;
 use crate::{
     api::execution::ExecutionApi,
     bridge::utils::lookup_epoch_acc,
-    gossip::gossip_history_content,
+    gossip::{gossip_history_content, ReplicationConfig},
     stats::{HistoryBlockStats, StatsReporter},
     types::{full_header::FullHeader, mode::BridgeMode},
     utils::{read_test_assets_from_file, TestAssets},
@@ -43,6 +43,7 @@ pub struct HistoryBridge {
     pub epoch_acc_path: PathBuf,
     pub metrics: BridgeMetricsReporter,
     pub gossip_limit: usize,
+    pub replication: ReplicationConfig,
 }
 
 impl HistoryBridge {
@@ -53,6 +54,7 @@ impl HistoryBridge {
         header_oracle: HeaderOracle,
         epoch_acc_path: PathBuf,
         gossip_limit: usize,
+        replication: ReplicationConfig,
     ) -> Self {
         let metrics = BridgeMetricsReporter::new("history".to_string(), &format!("{mode:?}"));
         Self {
@@ -63,6 +65,7 @@ impl HistoryBridge {
             epoch_acc_path,
             metrics,
             gossip_limit,
+            replication,
         }
     }
 }
@@ -93,6 +96,7 @@ impl HistoryBridge {
                 asset.content_key.clone(),
                 asset.content_value,
                 block_stats.clone(),
+                self.replication.clone(),
             )
             .await;
             if let HistoryContentKey::BlockHeaderWithProof(_) = asset.content_key {
@@ -154,6 +158,7 @@ impl HistoryBridge {
                     self.execution_api.clone(),
                     None,
                     self.metrics.clone(),
+                    self.replication.clone(),
                 );
             }
             block_index = latest_block + 1;
@@ -214,6 +219,7 @@ impl HistoryBridge {
                 self.execution_api.clone(),
                 Some(permit),
                 self.metrics.clone(),
+                self.replication.clone(),
             ));
         }
 
@@ -229,13 +235,21 @@ impl HistoryBridge {
         execution_api: ExecutionApi,
         permit: Option<OwnedSemaphorePermit>,
         metrics: BridgeMetricsReporter,
+        replication: ReplicationConfig,
     ) -> JoinHandle<()> {
         tokio::spawn(async move {
             let timer = metrics.start_process_timer("spawn_serve_full_block");
             match timeout(
                 SERVE_BLOCK_TIMEOUT,
-                Self::serve_full_block(height, epoch_acc, portal_client, execution_api, metrics.clone())
-                    .in_current_span(),
+                Self::serve_full_block(
+                    height,
+                    epoch_acc,
+                    portal_client,
+                    execution_api,
+                    metrics.clone(),
+                    replication,
+                )
+                .in_current_span(),
             )
             .await {
                 Ok(result) => match result {
@@ -257,6 +271,7 @@ impl HistoryBridge {
         portal_client: HttpClient,
         execution_api: ExecutionApi,
         metrics: BridgeMetricsReporter,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         info!("Serving block: {height}");
         let timer = metrics.start_process_timer("construct_and_gossip_header");
@@ -272,6 +287,7 @@ impl HistoryBridge {
             header_content_key,
             header_content_value,
             block_stats.clone(),
+            replication.clone(),
         )
         .await
         {
@@ -288,6 +304,7 @@ impl HistoryBridge {
         let block_body_execution_api = execution_api.clone();
         let block_body_block_stats = block_stats.clone();
         let block_body_metrics = metrics.clone();
+        let block_body_replication = replication.clone();
         let serve_block_body_task = tokio::spawn(async move {
             match timeout(
                 SERVE_CONTENT_TIMEOUT,
@@ -297,6 +314,7 @@ impl HistoryBridge {
                     &block_body_execution_api,
                     block_body_block_stats,
                     block_body_metrics,
+                    block_body_replication,
                 ).in_current_span(),
             )
             .await {
@@ -317,6 +335,7 @@ impl HistoryBridge {
                     &execution_api,
                     receipt_block_stats,
                     metrics,
+                    replication,
                 ).in_current_span(),
             )
             .await {
@@ -361,6 +380,7 @@ impl HistoryBridge {
             content_key,
             content_value,
             block_stats,
+            self.replication.clone(),
         )
         .await;
         Ok(Arc::new(epoch_acc))
@@ -372,6 +392,7 @@ impl HistoryBridge {
         execution_api: &ExecutionApi,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
         metrics: BridgeMetricsReporter,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         let timer = metrics.start_process_timer("construct_and_gossip_receipt");
         let (content_key, content_value) = execution_api.get_receipts(full_header).await?;
@@ -384,6 +405,7 @@ impl HistoryBridge {
             content_key,
             content_value,
             block_stats,
+            replication,
         )
         .await;
         metrics.stop_process_timer(timer);
@@ -396,6 +418,7 @@ impl HistoryBridge {
         execution_api: &ExecutionApi,
         block_stats: Arc<Mutex<HistoryBlockStats>>,
         metrics: BridgeMetricsReporter,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         let timer = metrics.start_process_timer("construct_and_gossip_block_body");
         let (content_key, content_value) = execution_api.get_block_body(full_header).await?;
@@ -403,8 +426,14 @@ impl HistoryBridge {
             "Built and validated BlockBody for Block #{:?}: now gossiping.",
             full_header.header.number
         );
-        let result =
-            gossip_history_content(portal_client, content_key, content_value, block_stats).await;
+        let result = gossip_history_content(
+            portal_client,
+            content_key,
+            content_value,
+            block_stats,
+            replication,
+        )
+        .await;
         metrics.stop_process_timer(timer);
         result
     }
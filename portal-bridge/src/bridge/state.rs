@@ -16,7 +16,7 @@ use trin_validation::{constants::EPOCH_SIZE, oracle::HeaderOracle};
 
 use crate::{
     bridge::history::SERVE_BLOCK_TIMEOUT,
-    gossip::gossip_state_content,
+    gossip::{gossip_state_content, ReplicationConfig},
     types::{
         era1::Era1,
         mode::{BridgeMode, ModeType},
@@ -37,6 +37,7 @@ pub struct StateBridge {
     pub http_client: Client,
     pub metrics: BridgeMetricsReporter,
     pub gossip_limit: usize,
+    pub replication: ReplicationConfig,
 }
 
 impl StateBridge {
@@ -46,6 +47,7 @@ impl StateBridge {
         header_oracle: HeaderOracle,
         epoch_acc_path: PathBuf,
         gossip_limit: usize,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<Self> {
         let http_client: Client = Config::new()
             .add_header("Content-Type", "application/xml")
@@ -62,6 +64,7 @@ impl StateBridge {
             http_client,
             metrics,
             gossip_limit,
+            replication,
         })
     }
 
@@ -136,6 +139,7 @@ impl StateBridge {
                     content_value,
                     permit,
                     self.metrics.clone(),
+                    self.replication.clone(),
                 );
             }
         }
@@ -148,6 +152,7 @@ impl StateBridge {
         content_value: StateContentValue,
         permit: OwnedSemaphorePermit,
         metrics: BridgeMetricsReporter,
+        replication: ReplicationConfig,
     ) -> JoinHandle<()> {
         info!("Spawning serve_state_proof for content key: {content_key}");
         tokio::spawn(async move {
@@ -158,7 +163,8 @@ impl StateBridge {
                     portal_client,
                     content_key.clone(),
                     content_value,
-                    metrics.clone()
+                    metrics.clone(),
+                    replication,
                 )).await
                 {
                 Ok(result) => match result {
@@ -179,9 +185,17 @@ impl StateBridge {
         content_key: StateContentKey,
         content_value: StateContentValue,
         metrics: BridgeMetricsReporter,
+        replication: ReplicationConfig,
     ) -> anyhow::Result<()> {
         let timer = metrics.start_process_timer("gossip_state_content");
-        match gossip_state_content(portal_client, content_key.clone(), content_value).await {
+        match gossip_state_content(
+            portal_client,
+            content_key.clone(),
+            content_value,
+            replication,
+        )
+        .await
+        {
             Ok(_) => {
                 debug!("Successfully gossiped state proof: {content_key}")
             }
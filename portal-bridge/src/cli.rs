@@ -2,7 +2,7 @@ use std::{env, net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
 
 use alloy_primitives::B256;
 use anyhow::anyhow;
-use clap::{Parser, Subcommand};
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand};
 use surf::{
     middleware::{Middleware, Next},
     Body, Client, Config, Request, Response,
@@ -16,22 +16,37 @@ use url::Url;
 
 use crate::{
     client_handles::{fluffy_handle, trin_handle},
-    constants::{DEFAULT_GOSSIP_LIMIT, HTTP_REQUEST_TIMEOUT},
+    constants::{DEFAULT_GOSSIP_LIMIT, DEFAULT_REPLICATION_TARGET, HTTP_REQUEST_TIMEOUT},
     types::{mode::BridgeMode, network::NetworkKind},
     DEFAULT_BASE_CL_ENDPOINT, DEFAULT_BASE_EL_ENDPOINT, FALLBACK_BASE_CL_ENDPOINT,
     FALLBACK_BASE_EL_ENDPOINT,
 };
 use ethportal_api::types::{
     cli::{
-        check_private_key_length, network_parser, DEFAULT_DISCOVERY_PORT, DEFAULT_NETWORK,
+        check_private_key_length, GossipStrategy, DEFAULT_DISCOVERY_PORT, DEFAULT_NETWORK,
         DEFAULT_WEB3_HTTP_PORT,
     },
-    portal_wire::NetworkSpec,
+    portal_wire::{NetworkSpec, MAINNET, TESTNET},
 };
 
 const DEFAULT_SUBNETWORK: &str = "history";
 const DEFAULT_EXECUTABLE_PATH: &str = "./target/debug/trin";
 const DEFAULT_EPOCH_ACC_PATH: &str = "./portal-accumulators";
+const DEFAULT_GOSSIP_RETRY_LIST_PATH: &str = "./gossip-retry-list.jsonl";
+
+/// Parses the network of the bridge.
+///
+/// Only the built-in networks are supported, as the bridge validates headers and reads blocks
+/// from its data sources according to the mainnet fork schedule and pre-merge accumulator.
+fn bridge_network_parser(network_string: &str) -> Result<Arc<NetworkSpec>, String> {
+    match network_string {
+        "mainnet" => Ok(MAINNET.clone()),
+        "testnet" => Ok(TESTNET.clone()),
+        _ => Err(format!(
+            "Not a valid network: {network_string}, the bridge only supports 'testnet' or 'mainnet'"
+        )),
+    }
+}
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "Trin Bridge", about = "Feed the network")]
@@ -65,7 +80,7 @@ pub struct BridgeConfig {
             long = "network",
                 help = "Choose mainnet or testnet",
                 default_value = DEFAULT_NETWORK,
-                value_parser = network_parser
+                value_parser = bridge_network_parser
             )]
     pub network: Arc<NetworkSpec>,
 
@@ -153,6 +168,28 @@ pub struct BridgeConfig {
         help = "The maximum number of active blocks being gossiped."
     )]
     pub gossip_limit: usize,
+
+    #[arg(
+        default_value_t = GossipStrategy::Random,
+        long = "gossip-strategy",
+        help = "The selection of peers that the trin client gossips content to: 'random', 'closest:N' or 'hybrid:N' (as 'closest:N', with a FINDNODES query if fewer than N interested peers are known)"
+    )]
+    pub gossip_strategy: GossipStrategy,
+
+    #[arg(
+        default_value_t = DEFAULT_REPLICATION_TARGET,
+        long = "replication-target",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+        help = "The number of peers that history and state content must be transferred to. If gossip falls short, the content is offered to the peers closest to it."
+    )]
+    pub replication_target: usize,
+
+    #[arg(
+        default_value = DEFAULT_GOSSIP_RETRY_LIST_PATH,
+        long = "gossip-retry-list",
+        help = "The file in which content that failed to reach the replication target is recorded. The content is gossiped again when the bridge starts, and periodically while it runs."
+    )]
+    pub gossip_retry_list: PathBuf,
 }
 
 pub fn url_to_client(url: Url) -> Result<Client, String> {
@@ -320,6 +357,46 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_bridge_config_with_gossip_strategy() {
+        let bridge_config = BridgeConfig::parse_from(["bridge", "trin"]);
+        assert_eq!(bridge_config.gossip_strategy, GossipStrategy::Random);
+
+        let bridge_config =
+            BridgeConfig::parse_from(["bridge", "--gossip-strategy", "hybrid:16", "trin"]);
+        assert_eq!(bridge_config.gossip_strategy, GossipStrategy::Hybrid(16));
+    }
+
+    #[test]
+    fn test_bridge_config_with_replication_target() {
+        let bridge_config = BridgeConfig::parse_from(["bridge", "trin"]);
+        assert_eq!(bridge_config.replication_target, DEFAULT_REPLICATION_TARGET);
+        assert_eq!(
+            bridge_config.gossip_retry_list,
+            PathBuf::from(DEFAULT_GOSSIP_RETRY_LIST_PATH)
+        );
+
+        let bridge_config = BridgeConfig::parse_from([
+            "bridge",
+            "--replication-target",
+            "4",
+            "--gossip-retry-list",
+            "path/to/retry-list.jsonl",
+            "trin",
+        ]);
+        assert_eq!(bridge_config.replication_target, 4);
+        assert_eq!(
+            bridge_config.gossip_retry_list,
+            PathBuf::from("path/to/retry-list.jsonl")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ValueValidation")]
+    fn test_bridge_config_with_zero_replication_target() {
+        BridgeConfig::try_parse_from(["bridge", "--replication-target", "0", "trin"]).unwrap();
+    }
+
     #[test]
     fn test_bridge_config_with_epoch() {
         const EXECUTABLE_PATH: &str = "path/to/executable";
@@ -356,6 +433,13 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "the bridge only supports 'testnet' or 'mainnet'")]
+    fn test_custom_network_spec_is_rejected() {
+        BridgeConfig::try_parse_from(["bridge", "--network", "./devnet.toml", "trin"].iter())
+            .unwrap();
+    }
+
     #[test]
     #[should_panic(expected = "MissingSubcommand")]
     fn test_config_requires_client_type_subcommand() {
@@ -57,7 +57,7 @@ pub fn trin_handle(bridge_config: &BridgeConfig) -> anyhow::Result<Child> {
         .args(["--ephemeral"])
         .args(["--mb", "0"])
         .args(["--web3-transport", "http"])
-        .args(["--network", bridge_config.network.get_network_name()])
+        .args(["--network", &bridge_config.network.cli_value()])
         .args(["--portal-subnetworks", &portal_subnetworks])
         .args(["--unsafe-private-key", &private_key])
         .args([
@@ -65,7 +65,11 @@ pub fn trin_handle(bridge_config: &BridgeConfig) -> anyhow::Result<Child> {
             &format!("http://127.0.0.1:{rpc_port}"),
         ])
         .args(["--discovery-port", &format!("{udp_port}")])
-        .args(["--bootnodes", &bridge_config.bootnodes]);
+        .args(["--bootnodes", &bridge_config.bootnodes])
+        .args([
+            "--gossip-strategy",
+            &bridge_config.gossip_strategy.to_string(),
+        ]);
     if let Some(ip) = bridge_config.external_ip.clone() {
         command.args(["--external-address", &format!("{ip}:{udp_port}")]);
     }
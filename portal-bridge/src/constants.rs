@@ -21,5 +21,8 @@ pub const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
 // This is the maximum number of active blocks being gossiped
 pub const DEFAULT_GOSSIP_LIMIT: usize = 32;
 
+// This is the number of peers that history and state content must be transferred to
+pub const DEFAULT_REPLICATION_TARGET: usize = 1;
+
 // Number of seconds to wait before retrying a provider request
 pub const FALLBACK_RETRY_AFTER: Duration = Duration::from_secs(5);
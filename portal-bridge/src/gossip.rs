@@ -1,18 +1,69 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
 
+use async_trait::async_trait;
+use discv5::enr::NodeId;
 use jsonrpsee::http_client::HttpClient;
-use tokio::time::{sleep, Duration};
-use tracing::{debug, warn, Instrument};
+use tokio::time::{interval, sleep, Duration};
+use tracing::{debug, info, warn, Instrument};
 
-use crate::stats::{BeaconSlotStats, HistoryBlockStats, StatsReporter};
+use crate::{
+    retry_list::{RetryEntry, RetryList},
+    stats::{BeaconSlotStats, HistoryBlockStats, StatsReporter},
+    types::network::NetworkKind,
+};
 use ethportal_api::{
-    jsonrpsee::core::Error, types::portal::TraceGossipInfo, BeaconContentKey, BeaconContentValue,
-    BeaconNetworkApiClient, HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient,
-    OverlayContentKey, StateContentKey, StateContentValue, StateNetworkApiClient,
+    jsonrpsee::core::Error,
+    types::{enr::Enr, portal::TraceGossipInfo},
+    BeaconContentKey, BeaconContentValue, BeaconNetworkApiClient, ContentValue, HistoryContentKey,
+    HistoryContentValue, HistoryNetworkApiClient, OverlayContentKey, StateContentKey,
+    StateContentValue, StateNetworkApiClient,
 };
 
 const GOSSIP_RETRY_COUNT: u64 = 3;
 const RETRY_AFTER: Duration = Duration::from_secs(15);
+/// The interval at which the content of the retry list is gossiped again.
+const RETRY_LIST_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// The number of times content is gossiped again from the retry list, before it's removed.
+const MAX_RETRY_ATTEMPTS: u32 = 5;
+
+/// The replication requirements of gossiped history and state content.
+#[derive(Clone, Debug)]
+pub struct ReplicationConfig {
+    /// The number of peers the content must be transferred to, for the gossip to succeed.
+    pub target: usize,
+    /// The list that content is recorded in, if it failed to reach the replication target.
+    pub retry_list: Arc<RetryList>,
+}
+
+impl ReplicationConfig {
+    /// Records the content in the retry list.
+    fn record_failure(
+        &self,
+        subnetwork: NetworkKind,
+        content_key: &impl OverlayContentKey,
+        content_value: &impl ContentValue,
+        replicated: usize,
+    ) {
+        let entry = RetryEntry::new(subnetwork, content_key, content_value, replicated);
+        if let Err(err) = self.retry_list.record(&entry) {
+            warn!(
+                "Error recording content in retry list: content key={:?} error={err}",
+                content_key.to_hex()
+            );
+        }
+    }
+}
+
+/// Returns the ENRs that were offered the content, in any of the traces.
+fn offered_enrs(traces: &[TraceGossipInfo]) -> HashSet<String> {
+    traces
+        .iter()
+        .flat_map(|trace| trace.offered.iter().cloned())
+        .collect()
+}
 
 /// Gossip any given content key / value to the history network.
 pub async fn gossip_beacon_content(
@@ -87,15 +138,126 @@ async fn beacon_trace_gossip(
     })
 }
 
+/// The JSON-RPC API of a subnetwork whose content is gossiped until it's replicated to the
+/// replication target.
+#[async_trait]
+trait ReplicatedNetworkApi {
+    type ContentKey: OverlayContentKey + Send + Sync + 'static;
+    type ContentValue: ContentValue + Clone + Send + Sync + 'static;
+
+    const NETWORK: NetworkKind;
+
+    async fn trace_gossip(
+        client: &HttpClient,
+        content_key: Self::ContentKey,
+        content_value: Self::ContentValue,
+    ) -> Result<TraceGossipInfo, Error>;
+
+    async fn trace_offer(
+        client: &HttpClient,
+        enr: Enr,
+        content_key: Self::ContentKey,
+        content_value: Self::ContentValue,
+    ) -> Result<TraceGossipInfo, Error>;
+
+    async fn recursive_find_nodes(client: &HttpClient, node_id: NodeId) -> Result<Vec<Enr>, Error>;
+
+    /// Returns whether the content was found on the network.
+    async fn find_content(client: &HttpClient, content_key: Self::ContentKey) -> bool;
+}
+
+struct HistoryApi;
+
+#[async_trait]
+impl ReplicatedNetworkApi for HistoryApi {
+    type ContentKey = HistoryContentKey;
+    type ContentValue = HistoryContentValue;
+
+    const NETWORK: NetworkKind = NetworkKind::History;
+
+    async fn trace_gossip(
+        client: &HttpClient,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+    ) -> Result<TraceGossipInfo, Error> {
+        HistoryNetworkApiClient::trace_gossip(client, content_key, content_value).await
+    }
+
+    async fn trace_offer(
+        client: &HttpClient,
+        enr: Enr,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+    ) -> Result<TraceGossipInfo, Error> {
+        HistoryNetworkApiClient::trace_offer(client, enr, content_key, content_value).await
+    }
+
+    async fn recursive_find_nodes(client: &HttpClient, node_id: NodeId) -> Result<Vec<Enr>, Error> {
+        HistoryNetworkApiClient::recursive_find_nodes(client, node_id).await
+    }
+
+    async fn find_content(client: &HttpClient, content_key: HistoryContentKey) -> bool {
+        matches!(
+            HistoryNetworkApiClient::recursive_find_content(client, content_key).await,
+            Ok(ethportal_api::types::history::ContentInfo::Content { .. })
+        )
+    }
+}
+
+struct StateApi;
+
+#[async_trait]
+impl ReplicatedNetworkApi for StateApi {
+    type ContentKey = StateContentKey;
+    type ContentValue = StateContentValue;
+
+    const NETWORK: NetworkKind = NetworkKind::State;
+
+    async fn trace_gossip(
+        client: &HttpClient,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> Result<TraceGossipInfo, Error> {
+        StateNetworkApiClient::trace_gossip(client, content_key, content_value).await
+    }
+
+    async fn trace_offer(
+        client: &HttpClient,
+        enr: Enr,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> Result<TraceGossipInfo, Error> {
+        StateNetworkApiClient::trace_offer(client, enr, content_key, content_value).await
+    }
+
+    async fn recursive_find_nodes(client: &HttpClient, node_id: NodeId) -> Result<Vec<Enr>, Error> {
+        StateNetworkApiClient::recursive_find_nodes(client, node_id).await
+    }
+
+    async fn find_content(client: &HttpClient, content_key: StateContentKey) -> bool {
+        matches!(
+            StateNetworkApiClient::recursive_find_content(client, content_key).await,
+            Ok(ethportal_api::types::state::ContentInfo::Content { .. })
+        )
+    }
+}
+
 /// Gossip any given content key / value to the history network.
 pub async fn gossip_history_content(
     portal_client: HttpClient,
     content_key: HistoryContentKey,
     content_value: HistoryContentValue,
     block_stats: Arc<Mutex<HistoryBlockStats>>,
+    replication: ReplicationConfig,
 ) -> anyhow::Result<()> {
     let result = tokio::spawn(
-        history_trace_gossip(portal_client, content_key.clone(), content_value).in_current_span(),
+        replicated_trace_gossip::<HistoryApi>(
+            portal_client,
+            content_key.clone(),
+            content_value,
+            replication,
+        )
+        .in_current_span(),
     )
     .await?;
     if let Ok(mut data) = block_stats.lock() {
@@ -106,126 +268,255 @@ pub async fn gossip_history_content(
     Ok(())
 }
 
-async fn history_trace_gossip(
-    client: HttpClient,
-    content_key: HistoryContentKey,
-    content_value: HistoryContentValue,
-) -> Result<GossipReport, Error> {
-    let mut retries = 0;
-    let mut traces = vec![];
-    let mut found = false;
-    while retries < GOSSIP_RETRY_COUNT {
-        let result = HistoryNetworkApiClient::trace_gossip(
-            &client,
-            content_key.clone(),
-            content_value.clone(),
-        )
-        .await;
-        // check if content was successfully transferred to at least one peer on network
-        if let Ok(trace) = result {
-            traces.push(trace.clone());
-            if !trace.transferred.is_empty() {
-                return Ok(GossipReport {
-                    traces,
-                    retries,
-                    found,
-                });
-            }
-        }
-        // if not, make rfc request to see if data is available on network
-        let result =
-            HistoryNetworkApiClient::recursive_find_content(&client, content_key.clone()).await;
-        if let Ok(ethportal_api::types::history::ContentInfo::Content { .. }) = result {
-            debug!("Found content on network, after failing to gossip, aborting gossip. content key={:?}", content_key.to_hex());
-            found = true;
-            return Ok(GossipReport {
-                traces,
-                retries,
-                found,
-            });
-        }
-        retries += 1;
-        debug!("Unable to locate content on network, after failing to gossip, retrying in {:?} seconds. content key={:?}", RETRY_AFTER, content_key.to_hex());
-        sleep(RETRY_AFTER).await;
-    }
-    warn!(
-        "Failed to gossip history content, without successfully locating data on network, after {} attempts: content key={:?}",
-        GOSSIP_RETRY_COUNT,
-        content_key.to_hex(),
-    );
-    Ok(GossipReport {
-        traces,
-        retries,
-        found,
-    })
-}
-
 /// Gossip any given content key / value to the state network.
 pub async fn gossip_state_content(
     portal_client: HttpClient,
     content_key: StateContentKey,
     content_value: StateContentValue,
+    replication: ReplicationConfig,
 ) -> anyhow::Result<()> {
     // stats are not currently being reported for state content
     let _result = tokio::spawn(
-        state_trace_gossip(portal_client, content_key, content_value).in_current_span(),
+        replicated_trace_gossip::<StateApi>(portal_client, content_key, content_value, replication)
+            .in_current_span(),
     )
     .await?;
     Ok(())
 }
 
-async fn state_trace_gossip(
+/// Gossips the content of the retry list again, for the given subnetworks, every
+/// `RETRY_LIST_INTERVAL`. Content that still fails to reach the replication target is kept in the
+/// list, until it failed `MAX_RETRY_ATTEMPTS` times.
+pub async fn gossip_retry_list(
+    portal_client: HttpClient,
+    replication: ReplicationConfig,
+    subnetworks: Vec<NetworkKind>,
+) {
+    let mut retry_interval = interval(RETRY_LIST_INTERVAL);
+    loop {
+        retry_interval.tick().await;
+        if let Err(err) =
+            gossip_retry_list_entries(&portal_client, &replication, &subnetworks).await
+        {
+            warn!("Error gossiping retry list: {err}");
+        }
+    }
+}
+
+/// Gossips the entries of the retry list once.
+async fn gossip_retry_list_entries(
+    portal_client: &HttpClient,
+    replication: &ReplicationConfig,
+    subnetworks: &[NetworkKind],
+) -> anyhow::Result<()> {
+    let (entries, position) = replication.retry_list.read_entries()?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    info!("Gossiping {} entries of the retry list", entries.len());
+    let mut kept_entries = vec![];
+    for entry in entries {
+        let result = match entry.subnetwork.parse::<NetworkKind>() {
+            Ok(NetworkKind::History) if subnetworks.contains(&NetworkKind::History) => {
+                retry_gossip::<HistoryApi>(portal_client, &entry, replication.target).await
+            }
+            Ok(NetworkKind::State) if subnetworks.contains(&NetworkKind::State) => {
+                retry_gossip::<StateApi>(portal_client, &entry, replication.target).await
+            }
+            // Keep the content for a bridge that runs the subnetwork.
+            _ => {
+                kept_entries.push(entry);
+                continue;
+            }
+        };
+        match result {
+            Ok(None) => {}
+            Ok(Some(replicated)) if entry.attempts + 1 < MAX_RETRY_ATTEMPTS => {
+                kept_entries.push(RetryEntry {
+                    replicated,
+                    attempts: entry.attempts + 1,
+                    ..entry
+                });
+            }
+            Ok(Some(_)) => warn!(
+                "Removing content from retry list after {MAX_RETRY_ATTEMPTS} failed attempts: content key={:?}",
+                entry.content_key
+            ),
+            Err(err) => warn!(
+                "Removing invalid retry list entry: content key={:?} error={err}",
+                entry.content_key
+            ),
+        }
+    }
+    replication
+        .retry_list
+        .replace_entries(position, &kept_entries)
+}
+
+/// Gossips the content of the retry list entry again. Returns the number of peers the content
+/// was replicated to, if it failed to reach the replication target.
+async fn retry_gossip<N: ReplicatedNetworkApi>(
+    client: &HttpClient,
+    entry: &RetryEntry,
+    target: usize,
+) -> anyhow::Result<Option<usize>> {
+    let content_key = N::ContentKey::from_hex(&entry.content_key)?;
+    let content_value = N::ContentValue::from_hex(&entry.content_value)?;
+    let (_report, failed_replication) =
+        replicate_content::<N>(client.clone(), content_key, content_value, target).await;
+    Ok(failed_replication)
+}
+
+/// Gossips the content until it's replicated to the replication target, and records it in the
+/// retry list if it fails to reach the target.
+async fn replicated_trace_gossip<N: ReplicatedNetworkApi>(
     client: HttpClient,
-    content_key: StateContentKey,
-    content_value: StateContentValue,
+    content_key: N::ContentKey,
+    content_value: N::ContentValue,
+    replication: ReplicationConfig,
 ) -> Result<GossipReport, Error> {
+    let (report, failed_replication) = replicate_content::<N>(
+        client,
+        content_key.clone(),
+        content_value.clone(),
+        replication.target,
+    )
+    .await;
+    if let Some(replicated) = failed_replication {
+        replication.record_failure(N::NETWORK, &content_key, &content_value, replicated);
+    }
+    Ok(report)
+}
+
+/// Gossips the content until it's replicated to the replication target. Returns the gossip
+/// report, and the number of peers the content was replicated to if it failed to reach the
+/// target without being found on the network.
+async fn replicate_content<N: ReplicatedNetworkApi>(
+    client: HttpClient,
+    content_key: N::ContentKey,
+    content_value: N::ContentValue,
+    target: usize,
+) -> (GossipReport, Option<usize>) {
     let mut retries = 0;
     let mut traces = vec![];
     let mut found = false;
+    // ENRs of all peers that the content was transferred to
+    let mut replicated = HashSet::new();
     while retries < GOSSIP_RETRY_COUNT {
-        let result = StateNetworkApiClient::trace_gossip(
-            &client,
-            content_key.clone(),
-            content_value.clone(),
-        )
-        .await;
-        // check if content was successfully transferred to at least one peer on network
+        let result = N::trace_gossip(&client, content_key.clone(), content_value.clone()).await;
+        // check if content was successfully transferred to enough peers on network
         if let Ok(trace) = result {
-            traces.push(trace.clone());
-            if !trace.transferred.is_empty() {
-                return Ok(GossipReport {
-                    traces,
-                    retries,
-                    found,
-                });
+            replicated.extend(trace.transferred.iter().cloned());
+            traces.push(trace);
+            if replicated.len() >= target {
+                return (
+                    GossipReport {
+                        traces,
+                        retries,
+                        found,
+                    },
+                    None,
+                );
+            }
+            // if not, offer the content to the peers closest to it, that weren't offered yet
+            let trace = offer_to_closest_peers::<N>(
+                &client,
+                &content_key,
+                &content_value,
+                &offered_enrs(&traces),
+                target - replicated.len(),
+            )
+            .await;
+            replicated.extend(trace.transferred.iter().cloned());
+            traces.push(trace);
+            if replicated.len() >= target {
+                return (
+                    GossipReport {
+                        traces,
+                        retries,
+                        found,
+                    },
+                    None,
+                );
             }
         }
         // if not, make rfc request to see if data is available on network
-        let result =
-            StateNetworkApiClient::recursive_find_content(&client, content_key.clone()).await;
-        if let Ok(ethportal_api::types::state::ContentInfo::Content { .. }) = result {
+        if N::find_content(&client, content_key.clone()).await {
             debug!("Found content on network, after failing to gossip, aborting gossip. content key={:?}", content_key.to_hex());
             found = true;
-            return Ok(GossipReport {
-                traces,
-                retries,
-                found,
-            });
+            return (
+                GossipReport {
+                    traces,
+                    retries,
+                    found,
+                },
+                None,
+            );
         }
         retries += 1;
         debug!("Unable to locate content on network, after failing to gossip, retrying in {:?} seconds. content key={:?}", RETRY_AFTER, content_key.to_hex());
         sleep(RETRY_AFTER).await;
     }
     warn!(
-        "Failed to gossip state content, without successfully locating data on network, after {} attempts: content key={:?}",
+        "Failed to gossip {} content, without successfully locating data on network, after {} attempts: content key={:?}",
+        N::NETWORK,
         GOSSIP_RETRY_COUNT,
         content_key.to_hex(),
     );
-    Ok(GossipReport {
-        traces,
-        retries,
-        found,
-    })
+    (
+        GossipReport {
+            traces,
+            retries,
+            found,
+        },
+        Some(replicated.len()),
+    )
+}
+
+/// Offers the content to the peers closest to it, found via a RecursiveFindNodes lookup, until
+/// it was transferred to `count` peers. Peers that were offered the content before are skipped.
+/// Returns a trace of the offers.
+async fn offer_to_closest_peers<N: ReplicatedNetworkApi>(
+    client: &HttpClient,
+    content_key: &N::ContentKey,
+    content_value: &N::ContentValue,
+    offered: &HashSet<String>,
+    count: usize,
+) -> TraceGossipInfo {
+    let mut trace = TraceGossipInfo::default();
+    let target = NodeId::new(&content_key.content_id());
+    let enrs = match N::recursive_find_nodes(client, target).await {
+        Ok(enrs) => enrs,
+        Err(err) => {
+            warn!(
+                "Error looking up peers close to {} content: content key={:?} error={err}",
+                N::NETWORK,
+                content_key.to_hex()
+            );
+            return trace;
+        }
+    };
+    for enr in enrs {
+        if trace.transferred.len() >= count {
+            break;
+        }
+        if offered.contains(&enr.to_string()) {
+            continue;
+        }
+        match N::trace_offer(client, enr, content_key.clone(), content_value.clone()).await {
+            Ok(offer_trace) => {
+                trace.offered.extend(offer_trace.offered);
+                trace.accepted.extend(offer_trace.accepted);
+                trace.transferred.extend(offer_trace.transferred);
+            }
+            Err(err) => debug!(
+                "Error offering {} content to peer: content key={:?} error={err}",
+                N::NETWORK,
+                content_key.to_hex()
+            ),
+        }
+    }
+    trace
 }
 
 pub struct GossipReport {
@@ -7,6 +7,7 @@ pub mod cli;
 pub mod client_handles;
 pub mod constants;
 pub mod gossip;
+pub mod retry_list;
 pub mod stats;
 pub mod types;
 pub mod utils;
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use clap::Parser;
 use tokio::time::{sleep, Duration};
 use tracing::Instrument;
@@ -7,6 +9,8 @@ use portal_bridge::{
     api::{consensus::ConsensusApi, execution::ExecutionApi},
     bridge::{beacon::BeaconBridge, era1::Era1Bridge, history::HistoryBridge, state::StateBridge},
     cli::BridgeConfig,
+    gossip::{gossip_retry_list, ReplicationConfig},
+    retry_list::RetryList,
     types::{mode::BridgeMode, network::NetworkKind},
 };
 use trin_utils::log::init_tracing_logger;
@@ -39,6 +43,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build(web3_http_address.clone())
         .map_err(|e| e.to_string())?;
 
+    let replication = ReplicationConfig {
+        target: bridge_config.replication_target,
+        retry_list: Arc::new(
+            RetryList::open(&bridge_config.gossip_retry_list).map_err(|e| e.to_string())?,
+        ),
+    };
+
+    // Periodically gossip the content that failed to reach the replication target. The task runs
+    // until the bridge stops, so the bridge doesn't wait for it.
+    tokio::spawn(
+        gossip_retry_list(
+            portal_client.clone(),
+            replication.clone(),
+            bridge_config.portal_subnetworks.clone(),
+        )
+        .instrument(tracing::trace_span!("retry_list")),
+    );
+
     let mut bridge_tasks = Vec::new();
 
     // Launch Beacon Network portal bridge
@@ -80,6 +102,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             header_oracle,
             epoch_acc_path,
             bridge_config.gossip_limit,
+            replication.clone(),
         )
         .await?;
         let bridge_handle = tokio::spawn(async move {
@@ -111,6 +134,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     bridge_config.epoch_acc_path,
                     bridge_config.gossip_limit,
                     execution_api,
+                    replication,
                 )
                 .await?;
                 let bridge_handle = tokio::spawn(async move {
@@ -132,6 +156,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         header_oracle,
                         bridge_config.epoch_acc_path,
                         bridge_config.gossip_limit,
+                        replication,
                     );
 
                     bridge
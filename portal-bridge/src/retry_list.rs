@@ -0,0 +1,231 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::types::network::NetworkKind;
+use ethportal_api::{ContentValue, OverlayContentKey};
+
+/// Content that failed to be gossiped to the replication target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryEntry {
+    pub subnetwork: String,
+    pub content_key: String,
+    pub content_value: String,
+    /// The number of peers the content was replicated to.
+    pub replicated: usize,
+    /// Unix timestamp (in seconds) of the failed gossip.
+    pub timestamp: u64,
+    /// The number of times the content failed to be gossiped again from the retry list.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl RetryEntry {
+    pub fn new(
+        subnetwork: NetworkKind,
+        content_key: &impl OverlayContentKey,
+        content_value: &impl ContentValue,
+        replicated: usize,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            subnetwork: subnetwork.to_string(),
+            content_key: content_key.to_hex(),
+            content_value: content_value.to_hex(),
+            replicated,
+            timestamp,
+            attempts: 0,
+        }
+    }
+}
+
+/// A persistent list of content that failed to be gossiped to the replication target, stored as
+/// one JSON entry per line, so that the content can be gossiped again later.
+#[derive(Debug)]
+pub struct RetryList {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl RetryList {
+    /// Opens the retry list at the given path, creating it if it doesn't exist.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends the entry to the retry list.
+    pub fn record(&self, entry: &RetryEntry) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|err| anyhow!("Unable to acquire retry list lock: {err}"))?;
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reads the entries of the retry list, skipping the lines that can't be parsed.
+    ///
+    /// Returns the entries together with the position up to which the list was read, which is
+    /// passed to `replace_entries` once the entries are processed.
+    pub fn read_entries(&self) -> anyhow::Result<(Vec<RetryEntry>, u64)> {
+        let _file = self
+            .file
+            .lock()
+            .map_err(|err| anyhow!("Unable to acquire retry list lock: {err}"))?;
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut entries = vec![];
+        let mut position = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            // A line without a newline is an entry that was only partially written.
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            position += read as u64;
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => warn!("Skipping invalid retry list entry: line={line:?} error={err}"),
+            }
+        }
+        Ok((entries, position))
+    }
+
+    /// Replaces the entries read up to the given position with the given entries. The entries
+    /// recorded after the position are kept.
+    ///
+    /// The list is written into a temporary file first, which then replaces the list, so the
+    /// entries aren't lost if the bridge stops in the meantime.
+    pub fn replace_entries(&self, position: u64, entries: &[RetryEntry]) -> anyhow::Result<()> {
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|err| anyhow!("Unable to acquire retry list lock: {err}"))?;
+        let mut recorded_since = vec![];
+        let mut reader = File::open(&self.path)?;
+        reader.seek(SeekFrom::Start(position))?;
+        reader.read_to_end(&mut recorded_since)?;
+
+        let temp_path = self.path.with_extension("tmp");
+        let mut temp_file = File::create(&temp_path)?;
+        for entry in entries {
+            let mut line = serde_json::to_string(entry)?;
+            line.push('\n');
+            temp_file.write_all(line.as_bytes())?;
+        }
+        temp_file.write_all(&recorded_since)?;
+        temp_file.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+        *file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use ethportal_api::{HistoryContentKey, HistoryContentValue};
+    use portalnet::utils::db::setup_temp_dir;
+
+    use crate::constants::{HEADER_WITH_PROOF_CONTENT_KEY, HEADER_WITH_PROOF_CONTENT_VALUE};
+
+    #[test]
+    fn record_and_replace_entries() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir
+            .path()
+            .join("retry")
+            .join("gossip-retry-list.jsonl");
+        let retry_list = RetryList::open(&path).unwrap();
+        assert_eq!(retry_list.read_entries().unwrap(), (vec![], 0));
+
+        let entry = test_entry();
+        retry_list.record(&entry).unwrap();
+        retry_list.record(&entry).unwrap();
+
+        // Entries are kept when the list is opened again, until they are replaced.
+        drop(retry_list);
+        let retry_list = RetryList::open(&path).unwrap();
+        let (entries, position) = retry_list.read_entries().unwrap();
+        assert_eq!(entries, vec![entry.clone(), entry.clone()]);
+        assert_eq!(retry_list.read_entries().unwrap().0, entries);
+        assert_eq!(entries[0].content_key, HEADER_WITH_PROOF_CONTENT_KEY);
+
+        // Entries recorded after the list was read are kept.
+        let retried_entry = RetryEntry {
+            attempts: 1,
+            ..entry.clone()
+        };
+        retry_list.record(&entry).unwrap();
+        retry_list
+            .replace_entries(position, &[retried_entry.clone()])
+            .unwrap();
+        assert_eq!(
+            retry_list.read_entries().unwrap().0,
+            vec![retried_entry.clone(), entry.clone()]
+        );
+
+        // Entries are appended to the replaced list.
+        retry_list.record(&entry).unwrap();
+        assert_eq!(
+            retry_list.read_entries().unwrap().0,
+            vec![retried_entry, entry.clone(), entry]
+        );
+    }
+
+    #[test]
+    fn skip_invalid_entries() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let path = temp_dir.path().join("gossip-retry-list.jsonl");
+        let entry = test_entry();
+        let line = serde_json::to_string(&entry).unwrap();
+        // An invalid line, a valid line, and a partially written line.
+        fs::write(&path, format!("{{invalid\n{line}\n{}", &line[..10])).unwrap();
+
+        let retry_list = RetryList::open(&path).unwrap();
+        let (entries, position) = retry_list.read_entries().unwrap();
+        assert_eq!(entries, vec![entry.clone()]);
+
+        // The partially written line isn't replaced.
+        retry_list.replace_entries(position, &[]).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), &line[..10]);
+    }
+
+    #[test]
+    fn read_entry_without_attempts() {
+        let mut entry = serde_json::to_value(test_entry()).unwrap();
+        entry.as_object_mut().unwrap().remove("attempts");
+        let entry: RetryEntry = serde_json::from_value(entry).unwrap();
+        assert_eq!(entry.attempts, 0);
+    }
+
+    fn test_entry() -> RetryEntry {
+        let content_key = HistoryContentKey::from_hex(HEADER_WITH_PROOF_CONTENT_KEY).unwrap();
+        let content_value = HistoryContentValue::from_hex(HEADER_WITH_PROOF_CONTENT_VALUE).unwrap();
+        RetryEntry::new(NetworkKind::History, &content_key, &content_value, 1)
+    }
+}
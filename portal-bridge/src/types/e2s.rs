@@ -1,3 +1,5 @@
+use std::io::{ErrorKind, Read};
+
 use anyhow::anyhow;
 use ssz_derive::{Decode, Encode};
 
@@ -93,7 +95,7 @@ impl Entry {
     }
 
     /// Serialize to a byte vector.
-    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+    pub fn serialize(&self) -> anyhow::Result<Vec<u8>> {
         let length = self.length();
         let mut buf = vec![0; length];
         self.write(&mut buf)?;
@@ -136,6 +138,27 @@ impl Entry {
             value: bytes[8..].to_vec(),
         })
     }
+
+    /// Read the next entry from the reader. Returns `None` if the reader is already at the end.
+    pub fn read(reader: &mut impl Read) -> anyhow::Result<Option<Self>> {
+        let mut header = [0; HEADER_SIZE as usize];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let header = Header::deserialize(&header)?;
+        if header.length as usize > VALUE_SIZE_LIMIT {
+            return Err(anyhow!(
+                "entry value size limit exceeded: {} - {}",
+                header.length,
+                VALUE_SIZE_LIMIT
+            ));
+        }
+        let mut value = vec![0; header.length as usize];
+        reader.read_exact(&mut value)?;
+        Ok(Some(Self { header, value }))
+    }
 }
 
 /// Represents the header of an e2store `Entry`
@@ -221,6 +244,25 @@ mod test {
         assert_eq!(hex_encode(actual), expected);
     }
 
+    #[test]
+    fn test_entry_read() {
+        let buf = hex_decode("0x2a00020000000000beef0900040000000000abcdabcd").unwrap();
+        let mut reader = buf.as_slice();
+        let entry = Entry::read(&mut reader).unwrap().unwrap();
+        assert_eq!(entry.header.type_, 0x2a);
+        assert_eq!(entry.value, vec![0xbe, 0xef]);
+        let entry = Entry::read(&mut reader).unwrap().unwrap();
+        assert_eq!(entry.header.type_, 0x09);
+        assert_eq!(entry.value, vec![0xab, 0xcd, 0xab, 0xcd]);
+        assert_eq!(Entry::read(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_entry_read_truncated() {
+        let buf = hex_decode("0x2a00020000000000be").unwrap();
+        assert!(Entry::read(&mut buf.as_slice()).is_err());
+    }
+
     #[rstest::rstest]
     #[case("0xffff000000000001")] // reserved bytes are non-zero
     #[case("0xbeef010000000000")] // length exceeds buffer
@@ -1,11 +1,17 @@
 use delay_map::HashMapDelay;
-use ethportal_api::{types::enr::Enr, OverlayContentKey};
+use ethportal_api::{
+    types::{cli::DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS, enr::Enr},
+    OverlayContentKey,
+};
 use futures::prelude::*;
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{rngs::StdRng, seq::SliceRandom};
 use tokio::time::Duration;
 use tracing::{debug, warn};
+use trin_metrics::{labels::AcceptQueueOutcomeLabel, overlay::OverlayMetricsReporter};
 
-const OFFER_QUEUE_TIMEOUT: Duration = Duration::from_secs(120);
+/// The default time a content key is kept in the accept queue before it expires.
+pub const DEFAULT_ACCEPT_QUEUE_TIMEOUT: Duration =
+    Duration::from_secs(DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS);
 
 /// A record of peers that have offered a content key.
 struct SeenPeers {
@@ -32,30 +38,32 @@ where
     // a map of content keys actively being transferred / processed
     // pointing to seen peers that have offered them the content
     content_key_map: HashMapDelay<TContentKey, SeenPeers>,
+    metrics: OverlayMetricsReporter,
+    // shuffles the fallback peers of the failed content keys
+    rng: StdRng,
 }
 
-impl<TContentKey> Default for AcceptQueue<TContentKey>
+impl<TContentKey> AcceptQueue<TContentKey>
 where
     TContentKey: OverlayContentKey,
 {
-    fn default() -> Self {
+    /// Creates a queue whose content keys expire after `timeout`.
+    pub fn new(timeout: Duration, metrics: OverlayMetricsReporter, rng: StdRng) -> Self {
         Self {
-            content_key_map: HashMapDelay::new(OFFER_QUEUE_TIMEOUT),
+            content_key_map: HashMapDelay::new(timeout),
+            metrics,
+            rng,
         }
     }
-}
 
-impl<TContentKey> AcceptQueue<TContentKey>
-where
-    TContentKey: OverlayContentKey,
-{
     /// Tries to add a content key to the queue.
     /// If the key is not in the queue, it is added to the queue and returns true.
     /// If the key is in the queue, the seen peer is stored as a fallback, and it returns false.
     /// Also polls for expired items, which will remove them from the queue.
+    /// The expired items are also removed periodically by the overlay service, so that they are
+    /// reported while no content is offered.
     pub fn add_key_to_queue(&mut self, content_key: &TContentKey, peer: &Enr) -> bool {
-        // poll for expired items, which will remove them from the queue
-        let _ = future::poll_fn(|cx| self.content_key_map.poll_expired(cx)).now_or_never();
+        self.remove_expired_keys();
         if let Some(mut seen_peers) = self.content_key_map.remove(content_key) {
             if seen_peers.origin == *peer || seen_peers.fallback.contains(peer) {
                 debug!(
@@ -85,35 +93,68 @@ where
         self.content_key_map.remove(content_key);
     }
 
-    /// Removes a failed content key, and returns a randomly selected
-    /// fallback peer to send a fallback FINDCONTENT request.
-    /// If no fallback peer is found, it returns None.
-    pub fn process_failed_key(&mut self, content_key: &TContentKey) -> Option<Enr> {
+    /// Removes a failed content key, and returns its fallback peers in random order,
+    /// to send fallback FINDCONTENT requests to.
+    /// If no fallback peer is found, it returns an empty list.
+    pub fn process_failed_key(&mut self, content_key: &TContentKey) -> Vec<Enr> {
         if let Some(mut seen_peers) = self.content_key_map.remove(content_key) {
             if seen_peers.fallback.is_empty() {
                 debug!("Failed to process content key: {content_key}, no fallback peers found.");
-                return None;
+                self.metrics
+                    .report_accept_queue_outcome(AcceptQueueOutcomeLabel::Lost, 1);
+                return vec![];
             }
-            // randomly choose a seen peer as the fallback peer
-            seen_peers.fallback.shuffle(&mut thread_rng());
-            Some(seen_peers.fallback.remove(0))
+            seen_peers.fallback.shuffle(&mut self.rng);
+            seen_peers.fallback
         } else {
             warn!(
                 "Failed to process content key: {content_key}, but a corresponding AcceptQueue record was not found"
             );
-            None
+            vec![]
         }
     }
+
+    /// Removes all expired content keys from the queue, and returns how many were removed.
+    ///
+    /// Expired content keys were never stored, so they are reported as lost offers.
+    pub fn remove_expired_keys(&mut self) -> usize {
+        let mut expired = 0;
+        while let Some(Some(Ok((content_key, seen_peers)))) =
+            future::poll_fn(|cx| self.content_key_map.poll_expired(cx)).now_or_never()
+        {
+            debug!(
+                origin = %seen_peers.origin.node_id(),
+                fallback = seen_peers.fallback.len(),
+                "Content key: {content_key} expired from accept queue without being stored"
+            );
+            expired += 1;
+        }
+        if expired > 0 {
+            self.metrics
+                .report_accept_queue_outcome(AcceptQueueOutcomeLabel::Expired, expired as u64);
+        }
+        expired
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use ethportal_api::{types::enr::generate_random_remote_enr, IdentityContentKey};
+    use rand::SeedableRng;
+    use trin_metrics::portalnet::PORTALNET_METRICS;
+
+    fn new_accept_queue(timeout: Duration) -> AcceptQueue<IdentityContentKey> {
+        let metrics = OverlayMetricsReporter {
+            overlay_metrics: PORTALNET_METRICS.overlay(),
+            protocol: "test".to_string(),
+        };
+        AcceptQueue::new(timeout, metrics, StdRng::from_entropy())
+    }
 
     #[tokio::test]
     async fn test_remove_key() {
-        let mut accept_queue = AcceptQueue::default();
+        let mut accept_queue = new_accept_queue(DEFAULT_ACCEPT_QUEUE_TIMEOUT);
         let content_key = IdentityContentKey::random();
         let (_, peer) = generate_random_remote_enr();
         assert!(accept_queue.add_key_to_queue(&content_key, &peer));
@@ -124,7 +165,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_multiple_peers() {
-        let mut accept_queue = AcceptQueue::default();
+        let mut accept_queue = new_accept_queue(DEFAULT_ACCEPT_QUEUE_TIMEOUT);
         let content_key = IdentityContentKey::random();
         let (_, peer1) = generate_random_remote_enr();
         let (_, peer2) = generate_random_remote_enr();
@@ -137,7 +178,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_queue_keeps_record_after_duplicate_offers_from_same_peer() {
-        let mut accept_queue = AcceptQueue::default();
+        let mut accept_queue = new_accept_queue(DEFAULT_ACCEPT_QUEUE_TIMEOUT);
         let content_key = IdentityContentKey::random();
         let (_, peer1) = generate_random_remote_enr();
         let (_, peer2) = generate_random_remote_enr();
@@ -146,24 +187,24 @@ mod tests {
         // peer1 offers the same content key again
         assert!(!accept_queue.add_key_to_queue(&content_key, &peer1));
         let actual_fallback = accept_queue.process_failed_key(&content_key);
-        assert_eq!(actual_fallback, Some(peer2));
+        assert_eq!(actual_fallback, vec![peer2]);
     }
 
     #[tokio::test]
     async fn test_process_failed_key() {
-        let mut accept_queue = AcceptQueue::default();
+        let mut accept_queue = new_accept_queue(DEFAULT_ACCEPT_QUEUE_TIMEOUT);
         let content_key = IdentityContentKey::random();
         let (_, original_peer) = generate_random_remote_enr();
         let (_, fallback_peer) = generate_random_remote_enr();
         assert!(accept_queue.add_key_to_queue(&content_key, &original_peer));
         assert!(!accept_queue.add_key_to_queue(&content_key, &fallback_peer));
         let actual_fallback = accept_queue.process_failed_key(&content_key);
-        assert_eq!(actual_fallback, Some(fallback_peer));
+        assert_eq!(actual_fallback, vec![fallback_peer]);
     }
 
     #[tokio::test]
     async fn test_process_failed_key_with_multiple_records() {
-        let mut accept_queue = AcceptQueue::default();
+        let mut accept_queue = new_accept_queue(DEFAULT_ACCEPT_QUEUE_TIMEOUT);
         let content_key1 = IdentityContentKey::random();
         let content_key2 = IdentityContentKey::random();
         let content_key3 = IdentityContentKey::random();
@@ -178,13 +219,13 @@ mod tests {
         assert!(!accept_queue.add_key_to_queue(&content_key2, &peer3));
         assert!(accept_queue.add_key_to_queue(&content_key3, &peer2));
         let actual_fallback1 = accept_queue.process_failed_key(&content_key1);
-        assert_eq!(actual_fallback1, Some(peer2));
+        assert_eq!(actual_fallback1, vec![peer2]);
         // test that content_key3 is still in the queue
         assert!(!accept_queue.add_key_to_queue(&content_key3, &peer1));
         // test that content_key1 is no longer in the queue
         assert!(accept_queue.add_key_to_queue(&content_key1, &peer1));
         let actual_fallback2 = accept_queue.process_failed_key(&content_key2);
-        assert_eq!(actual_fallback2, Some(peer3));
+        assert_eq!(actual_fallback2, vec![peer3]);
         // test that content_key3 is still in the queue
         assert!(!accept_queue.add_key_to_queue(&content_key3, &peer1));
         // test that content_key2 is no longer in the queue
@@ -193,9 +234,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_queue_timeout() {
-        let mut accept_queue = AcceptQueue::<IdentityContentKey> {
-            content_key_map: HashMapDelay::new(Duration::from_secs(1)),
-        };
+        let mut accept_queue = new_accept_queue(Duration::from_secs(1));
         let content_key = IdentityContentKey::random();
         let (_, peer) = generate_random_remote_enr();
         assert!(accept_queue.add_key_to_queue(&content_key, &peer));
@@ -203,4 +242,35 @@ mod tests {
         // validate that the content key has been removed from the queue
         assert!(accept_queue.add_key_to_queue(&content_key, &peer));
     }
+
+    #[tokio::test]
+    async fn test_process_failed_key_returns_all_fallback_peers() {
+        let mut accept_queue = new_accept_queue(DEFAULT_ACCEPT_QUEUE_TIMEOUT);
+        let content_key = IdentityContentKey::random();
+        let (_, original_peer) = generate_random_remote_enr();
+        let (_, peer1) = generate_random_remote_enr();
+        let (_, peer2) = generate_random_remote_enr();
+        assert!(accept_queue.add_key_to_queue(&content_key, &original_peer));
+        assert!(!accept_queue.add_key_to_queue(&content_key, &peer1));
+        assert!(!accept_queue.add_key_to_queue(&content_key, &peer2));
+        let mut actual_fallback = accept_queue.process_failed_key(&content_key);
+        actual_fallback.sort_by_key(|enr| enr.node_id());
+        let mut expected_fallback = vec![peer1, peer2];
+        expected_fallback.sort_by_key(|enr| enr.node_id());
+        assert_eq!(actual_fallback, expected_fallback);
+        // the content key is no longer in the queue
+        assert!(accept_queue.process_failed_key(&content_key).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remove_expired_keys() {
+        let mut accept_queue = new_accept_queue(Duration::from_secs(1));
+        let (_, peer) = generate_random_remote_enr();
+        assert!(accept_queue.add_key_to_queue(&IdentityContentKey::random(), &peer));
+        assert!(accept_queue.add_key_to_queue(&IdentityContentKey::random(), &peer));
+        assert_eq!(accept_queue.remove_expired_keys(), 0);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(accept_queue.remove_expired_keys(), 2);
+        assert_eq!(accept_queue.remove_expired_keys(), 0);
+    }
 }
@@ -1,11 +1,18 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use alloy_primitives::B256;
 use ethportal_api::types::{
-    bootnodes::Bootnodes,
-    cli::{TrinConfig, DEFAULT_UTP_TRANSFER_LIMIT},
+    bootnodes::{Bootnode, Bootnodes},
+    cli::{
+        GossipStrategy, IpMode, TrinConfig, DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS,
+        DEFAULT_UTP_TRANSFER_LIMIT,
+    },
+    config_file::OverlayTuning,
+    utp_tuning::UtpConnectionTuning,
 };
 
+use crate::{utp_controller::UtpStallConfig, utp_transfer_limit::AdaptiveTransferLimitConfig};
+
 /// Capacity of the cache for observed `NodeAddress` values.
 /// Provides capacity for 32 full k-buckets. This capacity will be shared among all active portal
 /// subnetworks.
@@ -14,6 +21,7 @@ const NODE_ADDR_CACHE_CAPACITY: usize = discv5::kbucket::MAX_NODES_PER_BUCKET *
 #[derive(Clone)]
 pub struct PortalnetConfig {
     pub external_addr: Option<SocketAddr>,
+    pub ip_mode: IpMode,
     pub private_key: B256,
     pub listen_port: u16,
     pub bootnodes: Bootnodes,
@@ -25,12 +33,27 @@ pub struct PortalnetConfig {
     pub trusted_block_root: Option<String>,
     // the max number of concurrent utp transfers
     pub utp_transfer_limit: usize,
+    // the bounds of the max number of concurrent utp transfers, if it's adaptive
+    pub utp_adaptive_transfer_limit: Option<AdaptiveTransferLimitConfig>,
+    // the detection of stalled utp streams and the max number of utp streams per peer
+    pub utp_stall_config: UtpStallConfig,
+    // the tuning of the utp connections
+    pub utp_conn_tuning: UtpConnectionTuning,
+    // the enabled subnetworks, advertised as capabilities in ping extensions
+    pub enabled_subnetworks: Vec<String>,
+    // the strategy used to select the peers that content is gossiped to
+    pub gossip_strategy: GossipStrategy,
+    // the time an accepted content key is kept in the accept queue
+    pub accept_queue_timeout: Duration,
+    // the overlay tuning from the config file, applied to all subnetworks
+    pub overlay: OverlayTuning,
 }
 
 impl Default for PortalnetConfig {
     fn default() -> Self {
         Self {
             external_addr: None,
+            ip_mode: IpMode::default(),
             private_key: B256::random(),
             listen_port: 4242,
             bootnodes: Bootnodes::default(),
@@ -41,6 +64,13 @@ impl Default for PortalnetConfig {
             disable_poke: false,
             trusted_block_root: None,
             utp_transfer_limit: DEFAULT_UTP_TRANSFER_LIMIT,
+            utp_adaptive_transfer_limit: None,
+            utp_stall_config: UtpStallConfig::default(),
+            utp_conn_tuning: UtpConnectionTuning::default(),
+            enabled_subnetworks: vec![],
+            gossip_strategy: GossipStrategy::default(),
+            accept_queue_timeout: Duration::from_secs(DEFAULT_ACCEPT_QUEUE_TIMEOUT_SECS),
+            overlay: OverlayTuning::default(),
         }
     }
 }
@@ -49,14 +79,38 @@ impl PortalnetConfig {
     pub fn new(trin_config: &TrinConfig, private_key: B256) -> Self {
         Self {
             external_addr: trin_config.external_addr,
+            ip_mode: trin_config.ip_mode,
             private_key,
             listen_port: trin_config.discovery_port,
             no_stun: trin_config.no_stun,
             no_upnp: trin_config.no_upnp,
-            bootnodes: trin_config.bootnodes.clone(),
+            // The bootnodes of a network spec file replace the default bootnodes.
+            bootnodes: match (&trin_config.bootnodes, trin_config.network.bootnodes()) {
+                (Bootnodes::Default, Some(enrs)) => {
+                    Bootnodes::Custom(enrs.iter().cloned().map(Bootnode::from).collect())
+                }
+                (bootnodes, _) => bootnodes.clone(),
+            },
             disable_poke: trin_config.disable_poke,
             trusted_block_root: trin_config.trusted_block_root.clone(),
             utp_transfer_limit: trin_config.utp_transfer_limit,
+            utp_adaptive_transfer_limit: trin_config.utp_adaptive_transfer_limit.then_some(
+                AdaptiveTransferLimitConfig {
+                    min: trin_config.utp_transfer_limit_min,
+                    max: trin_config.utp_transfer_limit_max,
+                },
+            ),
+            utp_stall_config: UtpStallConfig {
+                min_throughput: trin_config.utp_min_throughput,
+                stall_timeout: Duration::from_secs(trin_config.utp_stall_timeout),
+                inbound_timeout: Duration::from_secs(trin_config.utp_inbound_timeout),
+                max_streams_per_peer: trin_config.utp_max_streams_per_peer,
+            },
+            utp_conn_tuning: trin_config.utp_conn_tuning,
+            enabled_subnetworks: trin_config.portal_subnetworks.clone(),
+            gossip_strategy: trin_config.gossip_strategy,
+            accept_queue_timeout: Duration::from_secs(trin_config.accept_queue_timeout),
+            overlay: trin_config.overlay.clone(),
             ..Default::default()
         }
     }
@@ -1,8 +1,9 @@
 use std::{
+    collections::BTreeMap,
     fmt, fs,
     hash::{Hash, Hasher},
     io,
-    net::{Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::PathBuf,
     str::FromStr,
     sync::Arc,
@@ -17,16 +18,18 @@ use discv5::{
 };
 use lru::LruCache;
 use parking_lot::RwLock;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, info, warn};
 use utp_rs::{cid::ConnectionPeer, udp::AsyncUdpSocket};
 
 use super::config::PortalnetConfig;
-use crate::socket;
+use crate::{socket, transport::TalkReqTransport};
 use ethportal_api::{
     types::{
+        cli::IpMode,
         discv5::RoutingTableInfo,
         enr::Enr,
+        ping_payload::{ENR_PING_EXTENSIONS_KEY, PING_EXTENSIONS_VERSION},
         portal_wire::{NetworkSpec, ProtocolId},
     },
     utils::bytes::{hex_decode, hex_encode},
@@ -40,6 +43,9 @@ const TALKREQ_CHANNEL_BUFFER: usize = 100;
 /// ENR key for portal network client version.
 const ENR_PORTAL_CLIENT_KEY: &str = "c";
 
+/// Capacity of the channel of the bootnodes that are learned after startup.
+const BOOTNODE_UPDATES_CHANNEL_CAPACITY: usize = 16;
+
 /// ENR file name saving enr history to disk.
 const ENR_FILE_NAME: &str = "trin.enr";
 
@@ -62,10 +68,15 @@ pub struct Discovery {
     node_addr_cache: Arc<RwLock<LruCache<NodeId, NodeAddress>>>,
     /// Indicates if the Discv5 service has been started.
     pub started: bool,
-    /// The socket address that the Discv5 service listens on.
+    /// The socket address that the Discv5 service listens on. In dual-stack mode, this is the
+    /// IPv4 socket address, and the IPv6 socket address uses the same port.
     pub listen_socket: SocketAddr,
+    /// The IP address families that the Discv5 service listens on.
+    pub ip_mode: IpMode,
     /// The Portal Network to Protocal Id Map etc MAINNET, TESTNET
     network_spec: Arc<NetworkSpec>,
+    /// The bootnodes that are learned after startup, e.g. from a refreshed ENR tree.
+    bootnode_updates: broadcast::Sender<Vec<Enr>>,
 }
 
 impl fmt::Debug for Discovery {
@@ -86,45 +97,24 @@ impl Discovery {
         node_data_dir: PathBuf,
         network_spec: Arc<NetworkSpec>,
     ) -> Result<Self, String> {
-        let listen_all_ips = SocketAddr::new(
-            "0.0.0.0"
-                .parse()
-                .expect("Parsing static socket address to work"),
-            portal_config.listen_port,
-        );
+        let ip_mode = portal_config.ip_mode;
+        if let Some(external_addr) = portal_config.external_addr {
+            if !ip_mode.supports(&external_addr.ip()) {
+                return Err(format!(
+                    "External address {external_addr} is not supported by ip mode {ip_mode}, consider changing --ip-mode"
+                ));
+            }
+        }
 
-        let (mut enr_address, mut enr_port) = if portal_config.no_stun {
-            (None, portal_config.listen_port)
+        let (enr_ipv4, enr_ipv4_port) = if ip_mode.has_ipv4() {
+            Self::external_ipv4(&portal_config)?
         } else {
-            let known_external = portal_config
-                .external_addr
-                .or_else(|| socket::stun_for_external(&listen_all_ips));
-
-            match known_external {
-                Some(socket) => (Some(socket.ip()), socket.port()),
-                None => (None, portal_config.listen_port),
-            }
+            (None, portal_config.listen_port)
         };
-
-        if !portal_config.no_upnp && !socket::is_local_addr(enr_address) {
-            if let Some(socket) = socket::upnp_for_external(listen_all_ips) {
-                if let Some(known_external) = enr_address {
-                    if known_external != socket.ip() {
-                        if portal_config.external_addr.is_some() {
-                            return Err(format!(
-                                "Mismatched known external address {} vs UPnP found address {}, consider disable --external-addr or --no-upnp",
-                                known_external,
-                                socket.ip()
-                            ));
-                        } else {
-                            // STUN address is different from UPnP address. Use UPnP
-                            warn!("overriding STUN address with known UPnP external address");
-                        }
-                    }
-                };
-                enr_address = Some(socket.ip());
-                enr_port = socket.port();
-            }
+        let (enr_ipv6, enr_ipv6_port) = if ip_mode.has_ipv6() {
+            Self::external_ipv6(&portal_config)
+        } else {
+            (None, portal_config.listen_port)
         };
 
         let enr_key =
@@ -133,16 +123,26 @@ impl Discovery {
 
         let mut enr = {
             let mut builder = Discv5Enr::builder();
-            if let Some(ip_address) = enr_address {
-                builder.ip(ip_address);
+            if ip_mode.has_ipv4() {
+                if let Some(ip_address) = enr_ipv4 {
+                    builder.ip4(ip_address);
+                }
+                builder.udp4(enr_ipv4_port);
+            }
+            if ip_mode.has_ipv6() {
+                if let Some(ip_address) = enr_ipv6 {
+                    builder.ip6(ip_address);
+                }
+                builder.udp6(enr_ipv6_port);
             }
-            builder.udp4(enr_port);
 
             let trin_version = get_trin_version();
             // Use "t" as short-hand for "Trin" to save bytes in ENR.
             let client_info = format!("t {trin_version}");
             // Use "c" as short-hand for "client".
             builder.add_value(ENR_PORTAL_CLIENT_KEY, &client_info.as_bytes());
+            // Advertise support for the custom payload extensions of Ping and Pong.
+            builder.add_value(ENR_PING_EXTENSIONS_KEY, &PING_EXTENSIONS_VERSION);
             builder
                 .build(&enr_key)
                 .map_err(|e| format!("When adding key to servers ENR: {e:?}"))?
@@ -173,9 +173,28 @@ impl Discovery {
             fs::write(trin_enr_path, enr.to_base64()).expect("Unable to write Trin Enr to file");
         }
 
-        let listen_config = ListenConfig::Ipv4 {
-            ip: Ipv4Addr::UNSPECIFIED,
-            port: portal_config.listen_port,
+        let listen_port = portal_config.listen_port;
+        let listen_config = match ip_mode {
+            IpMode::Ipv4 => ListenConfig::Ipv4 {
+                ip: Ipv4Addr::UNSPECIFIED,
+                port: listen_port,
+            },
+            IpMode::Ipv6 => ListenConfig::Ipv6 {
+                ip: Ipv6Addr::UNSPECIFIED,
+                port: listen_port,
+            },
+            IpMode::DualStack => ListenConfig::DualStack {
+                ipv4: Ipv4Addr::UNSPECIFIED,
+                ipv4_port: listen_port,
+                ipv6: Ipv6Addr::UNSPECIFIED,
+                ipv6_port: listen_port,
+            },
+        };
+        let listen_socket = match ip_mode {
+            IpMode::Ipv6 => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), listen_port),
+            IpMode::Ipv4 | IpMode::DualStack => {
+                SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), listen_port)
+            }
         };
 
         let discv5_config = ConfigBuilder::new(listen_config)
@@ -202,11 +221,102 @@ impl Discovery {
             discv5,
             node_addr_cache,
             started: false,
-            listen_socket: listen_all_ips,
+            listen_socket,
+            ip_mode,
             network_spec,
+            bootnode_updates: broadcast::channel(BOOTNODE_UPDATES_CHANNEL_CAPACITY).0,
         })
     }
 
+    /// Returns the external IPv4 address and port to advertise in the ENR, found with the
+    /// configured external address, STUN or UPnP.
+    fn external_ipv4(portal_config: &PortalnetConfig) -> Result<(Option<Ipv4Addr>, u16), String> {
+        let listen_all_ips =
+            SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), portal_config.listen_port);
+        let external_addr = portal_config
+            .external_addr
+            .filter(|external_addr| external_addr.is_ipv4());
+
+        let (mut enr_address, mut enr_port) = if portal_config.no_stun {
+            (None, portal_config.listen_port)
+        } else {
+            let known_external =
+                external_addr.or_else(|| socket::stun_for_external(&listen_all_ips));
+
+            match known_external {
+                Some(socket) => (Some(socket.ip()), socket.port()),
+                None => (None, portal_config.listen_port),
+            }
+        };
+
+        if !portal_config.no_upnp && !socket::is_local_addr(enr_address) {
+            if let Some(socket) = socket::upnp_for_external(listen_all_ips) {
+                if let Some(known_external) = enr_address {
+                    if known_external != socket.ip() {
+                        if external_addr.is_some() {
+                            return Err(format!(
+                                "Mismatched known external address {} vs UPnP found address {}, consider disable --external-addr or --no-upnp",
+                                known_external,
+                                socket.ip()
+                            ));
+                        } else {
+                            // STUN address is different from UPnP address. Use UPnP
+                            warn!("overriding STUN address with known UPnP external address");
+                        }
+                    }
+                };
+                enr_address = Some(socket.ip());
+                enr_port = socket.port();
+            }
+        };
+
+        let enr_address = enr_address.and_then(|ip| match ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(ip) => {
+                warn!(%ip, "Ignoring IPv6 address found for the IPv4 ENR entry");
+                None
+            }
+        });
+        Ok((enr_address, enr_port))
+    }
+
+    /// Returns the external IPv6 address and port to advertise in the ENR, found with the
+    /// configured external address or the global address of a local network interface.
+    fn external_ipv6(portal_config: &PortalnetConfig) -> (Option<Ipv6Addr>, u16) {
+        match portal_config.external_addr {
+            Some(SocketAddr::V6(external_addr)) => {
+                (Some(*external_addr.ip()), external_addr.port())
+            }
+            _ if portal_config.no_stun => (None, portal_config.listen_port),
+            _ => (socket::ipv6_for_external(), portal_config.listen_port),
+        }
+    }
+
+    /// Returns whether the ENR has a UDP socket of an address family that discovery listens on.
+    pub fn is_routable(&self, enr: &Enr) -> bool {
+        (self.ip_mode.has_ipv4() && enr.udp4_socket().is_some())
+            || (self.ip_mode.has_ipv6() && enr.udp6_socket().is_some())
+    }
+
+    /// Returns an ENR of the node that can be reached with the address families that discovery
+    /// listens on. If the given ENR can't be reached, e.g. it's outdated or only advertises the
+    /// other address family, the ENR in the routing table or the node address cache is used
+    /// instead, if it can be reached.
+    fn routable_enr(&self, enr: Enr) -> Enr {
+        if self.is_routable(&enr) {
+            return enr;
+        }
+        let node_id = enr.node_id();
+        self.find_enr(&node_id)
+            .into_iter()
+            .chain(
+                self.cached_node_addr(&node_id)
+                    .map(|node_addr| node_addr.enr),
+            )
+            .find(|known_enr| self.is_routable(known_enr))
+            .unwrap_or(enr)
+    }
+
     pub async fn start(&mut self) -> Result<mpsc::Receiver<TalkRequest>, String> {
         info!(enr = %self.local_enr(), "Starting discv5 with");
         debug!(enr = ?self.local_enr(), "Discv5 enr details");
@@ -235,6 +345,8 @@ impl Discovery {
                         let _ = talk_req_tx.send(talk_req).await;
                     }
                     Event::SessionEstablished(enr, socket_addr) => {
+                        // Dual-stack sockets observe IPv4 peers with IPv4-mapped IPv6 addresses.
+                        let socket_addr = socket::canonical_socket_addr(socket_addr);
                         if let Some(old) = node_addr_cache.write().put(
                             enr.node_id(),
                             NodeAddress {
@@ -279,7 +391,9 @@ impl Discovery {
                 .discv5
                 .local_enr()
                 .ip4()
-                .map_or(Some("None".to_owned()), |ip| Some(ip.to_string())),
+                .map(|ip| ip.to_string())
+                .or_else(|| self.discv5.local_enr().ip6().map(|ip| ip.to_string()))
+                .or_else(|| Some("None".to_owned())),
         })
     }
 
@@ -289,6 +403,7 @@ impl Discovery {
         RoutingTableInfo {
             local_node_id: hex_encode(self.discv5.local_enr().node_id().raw()),
             buckets: self.discv5.kbuckets().into(),
+            ping_extensions: BTreeMap::new(),
         }
     }
 
@@ -297,6 +412,11 @@ impl Discovery {
         self.discv5.table_entries_id()
     }
 
+    /// Returns the spec of the portal network that the node is part of.
+    pub fn network_spec(&self) -> &Arc<NetworkSpec> {
+        &self.network_spec
+    }
+
     /// Returns the ENR of the local node.
     pub fn local_enr(&self) -> Enr {
         self.discv5.local_enr()
@@ -312,6 +432,23 @@ impl Discovery {
         self.discv5.add_enr(enr)
     }
 
+    /// Adds bootnodes that are learned after startup to the routing table, and to the routing
+    /// tables of the overlay networks.
+    pub fn add_bootnodes(&self, enrs: Vec<Enr>) {
+        let local_node_id = self.local_enr().node_id();
+        let enrs: Vec<Enr> = enrs
+            .into_iter()
+            .filter(|enr| enr.node_id() != local_node_id)
+            .collect();
+        for enr in &enrs {
+            if let Err(err) = self.discv5.add_enr(enr.clone()) {
+                debug!(node_id = %enr.node_id(), %err, "Failed to add bootnode ENR");
+            }
+        }
+        // There are no receivers when no overlay network is running.
+        let _ = self.bootnode_updates.send(enrs);
+    }
+
     /// Returns the cached `NodeAddress` or `None` if not cached.
     pub fn cached_node_addr(&self, node_id: &NodeId) -> Option<NodeAddress> {
         self.node_addr_cache.write().get(node_id).cloned()
@@ -340,11 +477,48 @@ impl Discovery {
             }
         };
 
+        let enr = self.routable_enr(enr);
         let response = self.discv5.talk_req(enr, protocol, request).await?;
         Ok(response)
     }
 }
 
+#[async_trait]
+impl TalkReqTransport for Discovery {
+    fn local_enr(&self) -> Enr {
+        Discovery::local_enr(self)
+    }
+
+    fn find_enr(&self, node_id: &NodeId) -> Option<Enr> {
+        Discovery::find_enr(self, node_id)
+    }
+
+    fn table_entries_enr(&self) -> Vec<Enr> {
+        Discovery::table_entries_enr(self)
+    }
+
+    fn cached_node_addr(&self, node_id: &NodeId) -> Option<NodeAddress> {
+        Discovery::cached_node_addr(self, node_id)
+    }
+
+    fn put_cached_node_addr(&self, node_addr: NodeAddress) -> Option<NodeAddress> {
+        Discovery::put_cached_node_addr(self, node_addr)
+    }
+
+    fn bootnode_updates(&self) -> Option<broadcast::Receiver<Vec<Enr>>> {
+        Some(self.bootnode_updates.subscribe())
+    }
+
+    async fn send_talk_req(
+        &self,
+        enr: Enr,
+        protocol: ProtocolId,
+        request: ProtocolRequest,
+    ) -> Result<Vec<u8>, RequestError> {
+        Discovery::send_talk_req(self, enr, protocol, request).await
+    }
+}
+
 pub struct Discv5UdpSocket {
     talk_reqs: mpsc::UnboundedReceiver<TalkRequest>,
     discv5: Arc<Discovery>,
@@ -370,6 +544,14 @@ impl UtpEnr {
             .get(ENR_PORTAL_CLIENT_KEY)
             .and_then(|v| String::from_utf8(v.to_vec()).ok())
     }
+
+    /// Returns whether the node advertises support for the ping extensions.
+    pub fn supports_ping_extensions(&self) -> bool {
+        self.0
+            .get(ENR_PING_EXTENSIONS_KEY)
+            .and_then(|v| v.first().copied())
+            .map_or(false, |version| version >= PING_EXTENSIONS_VERSION)
+    }
 }
 
 // Why are we implementing Hash, PartialEq, Eq for UtpEnr?
@@ -457,7 +639,9 @@ impl AsyncUdpSocket<UtpEnr> for Discv5UdpSocket {
 mod tests {
     use super::*;
     use crate::utils::db::{configure_node_data_dir, configure_trin_data_dir};
-    use ethportal_api::types::{bootnodes::Bootnodes, portal_wire::MAINNET};
+    use ethportal_api::types::{
+        bootnodes::Bootnodes, enr::generate_random_remote_enr, portal_wire::MAINNET,
+    };
 
     #[test]
     fn test_enr_file() {
@@ -466,7 +650,7 @@ mod tests {
 
         // Configure node data dir based on the provided private key
         let (node_data_dir, private_key) =
-            configure_node_data_dir(trin_data_dir, None, "test".to_string()).unwrap();
+            configure_node_data_dir(trin_data_dir, None, "test".to_string(), None).unwrap();
 
         let mut portalnet_config = PortalnetConfig {
             private_key,
@@ -514,4 +698,104 @@ mod tests {
         assert_eq!(old_enr.seq(), 2);
         assert_eq!(discovery.local_enr(), old_enr);
     }
+
+    #[test]
+    fn test_ipv6_enr() {
+        let trin_data_dir = configure_trin_data_dir(true).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, "test".to_string(), None).unwrap();
+        let external_addr: SocketAddr = "[2a01:4f8::1]:9009".parse().unwrap();
+        let portalnet_config = PortalnetConfig {
+            private_key,
+            external_addr: Some(external_addr),
+            ip_mode: IpMode::Ipv6,
+            bootnodes: Bootnodes::None,
+            ..Default::default()
+        };
+
+        let discovery = Discovery::new(portalnet_config, node_data_dir, MAINNET.clone()).unwrap();
+        let enr = discovery.local_enr();
+        assert_eq!(
+            enr.udp6_socket(),
+            Some("[2a01:4f8::1]:9009".parse().unwrap())
+        );
+        assert_eq!(enr.udp4(), None);
+        assert_eq!(enr.ip4(), None);
+        assert!(discovery.listen_socket.is_ipv6());
+        assert_eq!(
+            discovery.node_info().unwrap().ip,
+            Some("2a01:4f8::1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dual_stack_enr() {
+        let trin_data_dir = configure_trin_data_dir(true).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, "test".to_string(), None).unwrap();
+        let portalnet_config = PortalnetConfig {
+            private_key,
+            listen_port: 9009,
+            ip_mode: IpMode::DualStack,
+            no_stun: true,
+            no_upnp: true,
+            bootnodes: Bootnodes::None,
+            ..Default::default()
+        };
+
+        let discovery = Discovery::new(portalnet_config, node_data_dir, MAINNET.clone()).unwrap();
+        let enr = discovery.local_enr();
+        assert_eq!(enr.udp4(), Some(9009));
+        assert_eq!(enr.udp6(), Some(9009));
+
+        // Peers are routable with either address family.
+        let (_, ipv4_peer) = generate_random_remote_enr();
+        assert!(discovery.is_routable(&ipv4_peer));
+        let ipv6_peer = build_ipv6_enr();
+        assert!(discovery.is_routable(&ipv6_peer));
+    }
+
+    #[test]
+    fn test_ipv4_only_discovery_does_not_route_to_ipv6_peer() {
+        let trin_data_dir = configure_trin_data_dir(true).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, "test".to_string(), None).unwrap();
+        let portalnet_config = PortalnetConfig {
+            private_key,
+            no_stun: true,
+            no_upnp: true,
+            bootnodes: Bootnodes::None,
+            ..Default::default()
+        };
+
+        let discovery = Discovery::new(portalnet_config, node_data_dir, MAINNET.clone()).unwrap();
+        let (_, ipv4_peer) = generate_random_remote_enr();
+        assert!(discovery.is_routable(&ipv4_peer));
+        assert!(!discovery.is_routable(&build_ipv6_enr()));
+    }
+
+    #[test]
+    fn test_external_addr_must_match_ip_mode() {
+        let trin_data_dir = configure_trin_data_dir(true).unwrap();
+        let (node_data_dir, private_key) =
+            configure_node_data_dir(trin_data_dir, None, "test".to_string(), None).unwrap();
+        let portalnet_config = PortalnetConfig {
+            private_key,
+            external_addr: Some("[2a01:4f8::1]:9009".parse().unwrap()),
+            bootnodes: Bootnodes::None,
+            ..Default::default()
+        };
+
+        let err = Discovery::new(portalnet_config, node_data_dir, MAINNET.clone()).unwrap_err();
+        assert!(err.contains("not supported by ip mode ipv4"));
+    }
+
+    fn build_ipv6_enr() -> Enr {
+        let key = CombinedKey::generate_secp256k1();
+        Discv5Enr::builder()
+            .ip6("2a01:4f8::2".parse().unwrap())
+            .udp6(9009)
+            .build(&key)
+            .unwrap()
+    }
 }
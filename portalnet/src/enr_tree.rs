@@ -0,0 +1,495 @@
+//! Resolution of EIP-1459 ENR trees, which publish a list of nodes in DNS TXT records.
+//!
+//! The root of a tree is signed by the key in the tree link, and each entry of the tree is
+//! published at a subdomain named after the hash of the entry, so all the resolved nodes are
+//! authenticated by the link.
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy_primitives::keccak256;
+use anyhow::{anyhow, bail, ensure};
+use async_trait::async_trait;
+use data_encoding::BASE32_NOPAD;
+use discv5::enr::{k256::ecdsa::VerifyingKey, EnrPublicKey};
+use hickory_resolver::{error::ResolveErrorKind, TokioAsyncResolver};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::discovery::Discovery;
+use ethportal_api::types::{
+    bootnodes::{EnrTreeLink, ENR_TREE_PREFIX},
+    enr::Enr,
+};
+
+/// The interval at which an ENR tree is resolved again, to learn about new nodes.
+pub const ENR_TREE_REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// The delay before resolving an ENR tree again, while it was never resolved. The delay is doubled
+/// after each failed attempt, up to the refresh interval.
+const ENR_TREE_RETRY_DELAY: Duration = Duration::from_secs(15);
+
+/// The maximum number of entries that are resolved in an ENR tree, including the linked trees.
+const MAX_TREE_ENTRIES: usize = 10_000;
+
+const ROOT_PREFIX: &str = "enrtree-root:v1";
+const BRANCH_PREFIX: &str = "enrtree-branch:";
+const ENR_PREFIX: &str = "enr:";
+
+/// A resolver of DNS TXT records.
+#[async_trait]
+pub trait TxtResolver: Send + Sync {
+    /// Returns the content of the TXT record of `name`, or `None` if there is no such record.
+    async fn lookup_txt(&self, name: &str) -> anyhow::Result<Option<String>>;
+}
+
+/// A `TxtResolver` that uses the DNS servers of the system.
+pub struct DnsTxtResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl DnsTxtResolver {
+    pub fn from_system_conf() -> anyhow::Result<Self> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|err| anyhow!("Unable to read the DNS configuration: {err}"))?;
+        Ok(Self { resolver })
+    }
+}
+
+#[async_trait]
+impl TxtResolver for DnsTxtResolver {
+    async fn lookup_txt(&self, name: &str) -> anyhow::Result<Option<String>> {
+        match self.resolver.txt_lookup(name).await {
+            // A long record is split into multiple strings.
+            Ok(lookup) => Ok(lookup.iter().next().map(|txt| {
+                txt.txt_data()
+                    .iter()
+                    .map(|data| String::from_utf8_lossy(data))
+                    .collect()
+            })),
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(None),
+            Err(err) => Err(anyhow!("Unable to resolve TXT record of {name}: {err}")),
+        }
+    }
+}
+
+/// The root of an ENR tree.
+struct TreeRoot {
+    /// The hash of the root of the subtree of ENRs.
+    enr_root: String,
+    /// The hash of the root of the subtree of links to other trees.
+    link_root: String,
+    seq: u64,
+}
+
+impl TreeRoot {
+    /// Parses the root record, and verifies its signature.
+    fn parse(record: &str, public_key: &[u8; 33]) -> anyhow::Result<Self> {
+        let (signed, signature) = record
+            .rsplit_once(" sig=")
+            .ok_or_else(|| anyhow!("ENR tree root is not signed"))?;
+        let mut fields = signed.split_whitespace();
+        ensure!(
+            fields.next() == Some(ROOT_PREFIX),
+            "ENR tree root must start with {ROOT_PREFIX}"
+        );
+        let (mut enr_root, mut link_root, mut seq) = (None, None, None);
+        for field in fields {
+            match field.split_once('=') {
+                Some(("e", hash)) => enr_root = Some(hash.to_string()),
+                Some(("l", hash)) => link_root = Some(hash.to_string()),
+                Some(("seq", value)) => seq = Some(value.parse()?),
+                _ => bail!("Unexpected field in ENR tree root: {field}"),
+            }
+        }
+
+        let signature = base64::decode_config(
+            signature.trim().trim_end_matches('='),
+            base64::URL_SAFE_NO_PAD,
+        )?;
+        // The signature is followed by the recovery id, which isn't needed to verify it.
+        ensure!(
+            signature.len() == 65,
+            "ENR tree root signature must be 65 bytes"
+        );
+        let key = VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|err| anyhow!("Invalid ENR tree public key: {err}"))?;
+        ensure!(
+            key.verify_v4(signed.as_bytes(), &signature[..64]),
+            "Invalid ENR tree root signature"
+        );
+
+        Ok(Self {
+            enr_root: enr_root.ok_or_else(|| anyhow!("ENR tree root has no ENR subtree"))?,
+            link_root: link_root.ok_or_else(|| anyhow!("ENR tree root has no link subtree"))?,
+            seq: seq.ok_or_else(|| anyhow!("ENR tree root has no sequence number"))?,
+        })
+    }
+}
+
+/// The resolved entries of a tree.
+struct ResolvedTree {
+    seq: u64,
+    enrs: Vec<Enr>,
+    links: Vec<EnrTreeLink>,
+}
+
+/// An EIP-1459 ENR tree, together with the trees that it links to.
+pub struct EnrTree {
+    link: EnrTreeLink,
+    resolver: Arc<dyn TxtResolver>,
+    /// The resolved trees by domain, which are only traversed again when their sequence number
+    /// changes.
+    resolved: HashMap<String, ResolvedTree>,
+}
+
+impl EnrTree {
+    pub fn new(link: EnrTreeLink, resolver: Arc<dyn TxtResolver>) -> Self {
+        Self {
+            link,
+            resolver,
+            resolved: HashMap::new(),
+        }
+    }
+
+    pub fn link(&self) -> &EnrTreeLink {
+        &self.link
+    }
+
+    /// Resolves the tree and the trees that it links to, and returns their ENRs, with the latest
+    /// ENR of each node.
+    pub async fn resolve(&mut self) -> anyhow::Result<Vec<Enr>> {
+        let mut pending = vec![self.link.clone()];
+        let mut visited = HashSet::new();
+        let mut resolved = HashMap::new();
+        let mut entries = 0;
+        while let Some(link) = pending.pop() {
+            if !visited.insert(link.domain.clone()) {
+                continue;
+            }
+            match self.resolve_tree(&link, &mut entries).await {
+                Ok(tree) => {
+                    pending.extend(tree.links.iter().cloned());
+                    resolved.insert(link.domain, tree);
+                }
+                Err(err) if link == self.link => return Err(err),
+                // A linked tree that can't be resolved doesn't prevent the use of the other trees.
+                Err(err) => warn!(%err, tree = %link, "Failed to resolve linked ENR tree"),
+            }
+        }
+        self.resolved = resolved;
+
+        let mut enrs: HashMap<_, Enr> = HashMap::new();
+        for enr in self.resolved.values().flat_map(|tree| tree.enrs.iter()) {
+            let is_latest = enrs
+                .get(&enr.node_id())
+                .map_or(true, |known| known.seq() < enr.seq());
+            if is_latest {
+                enrs.insert(enr.node_id(), enr.clone());
+            }
+        }
+        Ok(enrs.into_values().collect())
+    }
+
+    async fn resolve_tree(
+        &mut self,
+        link: &EnrTreeLink,
+        entries: &mut usize,
+    ) -> anyhow::Result<ResolvedTree> {
+        let record = self
+            .resolver
+            .lookup_txt(&link.domain)
+            .await?
+            .ok_or_else(|| anyhow!("ENR tree root not found at {}", link.domain))?;
+        let root = TreeRoot::parse(&record, &link.public_key)?;
+        if let Some(tree) = self.resolved.remove(&link.domain) {
+            if tree.seq == root.seq {
+                return Ok(tree);
+            }
+        }
+
+        let mut enrs = vec![];
+        let mut links = vec![];
+        for (subtree_root, is_link_subtree) in [(&root.enr_root, false), (&root.link_root, true)] {
+            let mut pending = vec![subtree_root.clone()];
+            while let Some(hash) = pending.pop() {
+                *entries += 1;
+                ensure!(
+                    *entries <= MAX_TREE_ENTRIES,
+                    "ENR tree has more than {MAX_TREE_ENTRIES} entries"
+                );
+                let name = format!("{hash}.{}", link.domain);
+                let record = self
+                    .resolver
+                    .lookup_txt(&name)
+                    .await?
+                    .ok_or_else(|| anyhow!("ENR tree entry not found at {name}"))?;
+                ensure!(
+                    entry_hash(&record).eq_ignore_ascii_case(&hash),
+                    "ENR tree entry at {name} doesn't match its hash"
+                );
+
+                if let Some(children) = record.strip_prefix(BRANCH_PREFIX) {
+                    pending.extend(
+                        children
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|child| !child.is_empty())
+                            .map(String::from),
+                    );
+                } else if !is_link_subtree && record.starts_with(ENR_PREFIX) {
+                    match Enr::from_str(&record) {
+                        Ok(enr) => enrs.push(enr),
+                        Err(err) => warn!(%err, entry = %name, "Invalid ENR in ENR tree"),
+                    }
+                } else if is_link_subtree && record.starts_with(ENR_TREE_PREFIX) {
+                    links.push(EnrTreeLink::from_str(&record)?);
+                } else {
+                    bail!("Unexpected ENR tree entry at {name}");
+                }
+            }
+        }
+        Ok(ResolvedTree {
+            seq: root.seq,
+            enrs,
+            links,
+        })
+    }
+}
+
+/// Returns the hash of a tree entry, which is the subdomain of the entry.
+fn entry_hash(record: &str) -> String {
+    BASE32_NOPAD.encode(&keccak256(record.as_bytes())[..16])
+}
+
+/// Returns the delay before the next resolution of an ENR tree: the refresh interval once the tree
+/// was resolved, or the retry delay with an exponential backoff while it was never resolved.
+fn resolve_delay(resolved: bool, failed_attempts: u32, refresh_interval: Duration) -> Duration {
+    if resolved {
+        return refresh_interval;
+    }
+    ENR_TREE_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(failed_attempts))
+        .min(refresh_interval)
+}
+
+/// Resolves the ENR tree periodically, and adds its new nodes as bootnodes, which refreshes the
+/// routing tables. `known_enrs` are the ENRs resolved at startup, or `None` if the resolution
+/// failed, in which case the tree is resolved again sooner.
+pub fn spawn_enr_tree_refresh(
+    mut tree: EnrTree,
+    known_enrs: Option<Vec<Enr>>,
+    discovery: Arc<Discovery>,
+    refresh_interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut resolved = known_enrs.is_some();
+        let mut known: HashMap<_, u64> = known_enrs
+            .unwrap_or_default()
+            .iter()
+            .map(|enr| (enr.node_id(), enr.seq()))
+            .collect();
+        let mut failed_attempts = 0;
+        loop {
+            tokio::time::sleep(resolve_delay(resolved, failed_attempts, refresh_interval)).await;
+            let enrs = match tree.resolve().await {
+                Ok(enrs) => {
+                    resolved = true;
+                    enrs
+                }
+                Err(err) => {
+                    warn!(%err, tree = %tree.link(), "Failed to refresh ENR tree");
+                    failed_attempts = failed_attempts.saturating_add(1);
+                    continue;
+                }
+            };
+            let new_enrs: Vec<Enr> = enrs
+                .into_iter()
+                .filter(|enr| {
+                    known
+                        .get(&enr.node_id())
+                        .map_or(true, |seq| *seq < enr.seq())
+                })
+                .collect();
+            if new_enrs.is_empty() {
+                continue;
+            }
+            info!(
+                tree = %tree.link(),
+                count = new_enrs.len(),
+                "Adding new bootnodes from ENR tree"
+            );
+            known.extend(new_enrs.iter().map(|enr| (enr.node_id(), enr.seq())));
+            discovery.add_bootnodes(new_enrs);
+        }
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use discv5::enr::{k256::ecdsa::SigningKey, EnrKey};
+    use parking_lot::Mutex;
+
+    use ethportal_api::types::enr::generate_random_remote_enr;
+
+    /// A stand-in for DNS, with the TXT records in memory.
+    #[derive(Default)]
+    struct MemoryTxtResolver {
+        records: Mutex<HashMap<String, String>>,
+    }
+
+    #[async_trait]
+    impl TxtResolver for MemoryTxtResolver {
+        async fn lookup_txt(&self, name: &str) -> anyhow::Result<Option<String>> {
+            Ok(self.records.lock().get(name).cloned())
+        }
+    }
+
+    /// Publishes a tree with the ENRs and the links, in a branch each, and returns its link.
+    fn publish_tree(
+        resolver: &MemoryTxtResolver,
+        key: &SigningKey,
+        domain: &str,
+        seq: u64,
+        enrs: &[Enr],
+        links: &[EnrTreeLink],
+    ) -> EnrTreeLink {
+        let mut records = resolver.records.lock();
+        let mut publish = |record: String| {
+            let hash = entry_hash(&record);
+            records.insert(format!("{hash}.{domain}"), record);
+            hash
+        };
+        let enr_hashes: Vec<_> = enrs.iter().map(|enr| publish(enr.to_base64())).collect();
+        let enr_root = publish(format!("{BRANCH_PREFIX}{}", enr_hashes.join(",")));
+        let link_hashes: Vec<_> = links.iter().map(|link| publish(link.to_string())).collect();
+        let link_root = publish(format!("{BRANCH_PREFIX}{}", link_hashes.join(",")));
+
+        let signed = format!("{ROOT_PREFIX} e={enr_root} l={link_root} seq={seq}");
+        let mut signature = key.sign_v4(signed.as_bytes()).unwrap();
+        signature.push(0);
+        let signature = base64::encode_config(signature, base64::URL_SAFE_NO_PAD);
+        records.insert(domain.to_string(), format!("{signed} sig={signature}"));
+
+        EnrTreeLink {
+            public_key: key.public().encode().as_slice().try_into().unwrap(),
+            domain: domain.to_string(),
+        }
+    }
+
+    fn sorted_node_ids(enrs: &[Enr]) -> Vec<String> {
+        let mut node_ids: Vec<_> = enrs.iter().map(|enr| enr.node_id().to_string()).collect();
+        node_ids.sort();
+        node_ids
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn resolve_tree_with_link() {
+        let resolver = Arc::new(MemoryTxtResolver::default());
+        let linked_enrs: Vec<_> = (0..2).map(|_| generate_random_remote_enr().1).collect();
+        let linked = publish_tree(
+            &resolver,
+            &SigningKey::random(&mut rand::thread_rng()),
+            "linked.example.org",
+            1,
+            &linked_enrs,
+            &[],
+        );
+        let enrs: Vec<_> = (0..3).map(|_| generate_random_remote_enr().1).collect();
+        let link = publish_tree(
+            &resolver,
+            &SigningKey::random(&mut rand::thread_rng()),
+            "nodes.example.org",
+            1,
+            &enrs,
+            &[linked],
+        );
+
+        let mut tree = EnrTree::new(link, resolver);
+        let resolved = tree.resolve().await.unwrap();
+        let expected: Vec<_> = enrs.iter().chain(linked_enrs.iter()).cloned().collect();
+        assert_eq!(sorted_node_ids(&resolved), sorted_node_ids(&expected));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn resolve_updated_tree() {
+        let resolver = Arc::new(MemoryTxtResolver::default());
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let enrs: Vec<_> = (0..3).map(|_| generate_random_remote_enr().1).collect();
+        let link = publish_tree(&resolver, &key, "nodes.example.org", 1, &enrs[..2], &[]);
+        let mut tree = EnrTree::new(link, Arc::clone(&resolver) as Arc<dyn TxtResolver>);
+        assert_eq!(tree.resolve().await.unwrap().len(), 2);
+
+        publish_tree(&resolver, &key, "nodes.example.org", 2, &enrs, &[]);
+        let resolved = tree.resolve().await.unwrap();
+        assert_eq!(sorted_node_ids(&resolved), sorted_node_ids(&enrs));
+    }
+
+    #[test]
+    fn resolve_delay_backs_off_until_resolved() {
+        let delays: Vec<Duration> = (0..8)
+            .map(|failed_attempts| resolve_delay(false, failed_attempts, ENR_TREE_REFRESH_INTERVAL))
+            .collect();
+        assert_eq!(delays[0], ENR_TREE_RETRY_DELAY);
+        assert_eq!(delays[1], ENR_TREE_RETRY_DELAY * 2);
+        assert!(delays.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(
+            resolve_delay(false, u32::MAX, ENR_TREE_REFRESH_INTERVAL),
+            ENR_TREE_REFRESH_INTERVAL
+        );
+        assert_eq!(
+            resolve_delay(true, 3, ENR_TREE_REFRESH_INTERVAL),
+            ENR_TREE_REFRESH_INTERVAL
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn reject_tree_signed_by_other_key() {
+        let resolver = Arc::new(MemoryTxtResolver::default());
+        let enrs = vec![generate_random_remote_enr().1];
+        let mut link = publish_tree(
+            &resolver,
+            &SigningKey::random(&mut rand::thread_rng()),
+            "nodes.example.org",
+            1,
+            &enrs,
+            &[],
+        );
+        let other_key = SigningKey::random(&mut rand::thread_rng());
+        link.public_key = other_key.public().encode().as_slice().try_into().unwrap();
+
+        let mut tree = EnrTree::new(link, resolver);
+        let err = tree.resolve().await.unwrap_err();
+        assert_eq!(err.to_string(), "Invalid ENR tree root signature");
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn reject_tampered_entry() {
+        let resolver = Arc::new(MemoryTxtResolver::default());
+        let enrs = vec![generate_random_remote_enr().1];
+        let link = publish_tree(
+            &resolver,
+            &SigningKey::random(&mut rand::thread_rng()),
+            "nodes.example.org",
+            1,
+            &enrs,
+            &[],
+        );
+        let name = format!("{}.nodes.example.org", entry_hash(&enrs[0].to_base64()));
+        resolver
+            .records
+            .lock()
+            .insert(name, generate_random_remote_enr().1.to_base64());
+
+        let mut tree = EnrTree::new(link, resolver);
+        let err = tree.resolve().await.unwrap_err();
+        assert!(err.to_string().contains("doesn't match its hash"));
+    }
+}
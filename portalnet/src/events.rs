@@ -3,15 +3,21 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use discv5::TalkRequest;
+use discv5::{enr::NodeId, TalkRequest};
 use futures::stream::{select_all, StreamExt};
 use tokio::sync::{broadcast, mpsc};
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::{debug, error, trace, warn};
 
 use ethportal_api::{
-    types::portal_wire::{NetworkSpec, ProtocolId},
+    types::{
+        cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+        enr::Enr,
+        portal_wire::{NetworkSpec, ProtocolId},
+        subscription::SubscriptionEvent,
+    },
     utils::bytes::{hex_encode, hex_encode_upper},
+    BeaconContentKey, RawContentKey,
 };
 
 /// Handles for communication between the main event handler and an overlay.
@@ -22,6 +28,9 @@ pub struct OverlayHandle {
     pub rx: Option<broadcast::Receiver<EventEnvelope>>,
 }
 
+/// The capacity of the channel publishing overlay events to the JSON-RPC subscriptions.
+pub const SUBSCRIPTION_CHANNEL_CAPACITY: usize = 1024;
+
 type OverlayChannels = (
     Option<mpsc::UnboundedSender<OverlayRequest>>,
     Option<broadcast::Receiver<EventEnvelope>>,
@@ -65,6 +74,8 @@ pub struct PortalnetEvents {
     pub utp_talk_reqs: mpsc::UnboundedSender<TalkRequest>,
     /// The Portal Network to Protocal Id Map etc MAINNET, TESTNET
     network_spec: Arc<NetworkSpec>,
+    /// Publishes the overlay events to the JSON-RPC subscriptions.
+    subscription_tx: broadcast::Sender<SubscriptionEvent>,
 }
 
 impl PortalnetEvents {
@@ -75,6 +86,7 @@ impl PortalnetEvents {
         beacon_channels: OverlayChannels,
         utp_talk_reqs: mpsc::UnboundedSender<TalkRequest>,
         network_spec: Arc<NetworkSpec>,
+        subscription_tx: broadcast::Sender<SubscriptionEvent>,
     ) -> Self {
         Self {
             talk_req_receiver,
@@ -83,6 +95,7 @@ impl PortalnetEvents {
             beacon_handle: beacon_channels.into(),
             utp_talk_reqs,
             network_spec,
+            subscription_tx,
         }
     }
 
@@ -169,6 +182,15 @@ impl PortalnetEvents {
     fn dispatch_overlay_event(&self, event: EventEnvelope) {
         use OverlayRequest::Event;
 
+        // Sending fails only if there are no subscriptions, which is fine.
+        for subscription_event in event.subscription_events() {
+            let _ = self.subscription_tx.send(subscription_event);
+        }
+        if event.destination.as_ref().is_some_and(Vec::is_empty) {
+            // The event is only meant for the subscriptions.
+            return;
+        }
+
         let all_protocols = vec![ProtocolId::History, ProtocolId::Beacon, ProtocolId::State];
         let mut recipients = event
             .destination
@@ -223,6 +245,16 @@ impl PortalnetEvents {
 pub enum OverlayEvent {
     LightClientOptimisticUpdate,
     LightClientFinalityUpdate,
+    /// Content received from the network was stored in the local store.
+    ContentStored(RawContentKey),
+    /// An offer was received from a peer.
+    OfferReceived {
+        peer: NodeId,
+        content_keys: Vec<RawContentKey>,
+        accepted: Vec<bool>,
+    },
+    /// A peer was connected in the routing table.
+    PeerConnected(Enr),
 }
 
 /// Timestamp of an overlay event.
@@ -272,7 +304,8 @@ pub struct EventEnvelope {
     pub payload: OverlayEvent,
     /// Specifies the protocols to which this event should be sent.
     ///
-    /// A value of `None` is taken to indicate `all protocols`.
+    /// A value of `None` is taken to indicate `all protocols`, while an empty list indicates that
+    /// the event is only sent to the JSON-RPC subscriptions.
     pub destination: Option<Vec<ProtocolId>>,
 }
 
@@ -292,6 +325,54 @@ impl EventEnvelope {
     }
 }
 
+impl EventEnvelope {
+    /// Returns the events that should be sent to the JSON-RPC subscriptions.
+    pub fn subscription_events(&self) -> Vec<SubscriptionEvent> {
+        let subnetwork = match self.from {
+            ProtocolId::History => HISTORY_NETWORK,
+            ProtocolId::State => STATE_NETWORK,
+            ProtocolId::Beacon => BEACON_NETWORK,
+            _ => return vec![],
+        }
+        .to_string();
+        match &self.payload {
+            OverlayEvent::LightClientOptimisticUpdate | OverlayEvent::LightClientFinalityUpdate => {
+                vec![]
+            }
+            OverlayEvent::ContentStored(content_key) => {
+                let mut events = vec![SubscriptionEvent::ContentStored {
+                    subnetwork,
+                    content_key: hex_encode(content_key),
+                }];
+                if self.from == ProtocolId::Beacon {
+                    if let Ok(BeaconContentKey::LightClientOptimisticUpdate(key)) =
+                        BeaconContentKey::try_from(content_key.clone())
+                    {
+                        events.push(SubscriptionEvent::LightClientHead {
+                            signature_slot: key.signature_slot,
+                        });
+                    }
+                }
+                events
+            }
+            OverlayEvent::OfferReceived {
+                peer,
+                content_keys,
+                accepted,
+            } => vec![SubscriptionEvent::OfferReceived {
+                subnetwork,
+                peer: hex_encode(peer.raw()),
+                content_keys: content_keys.iter().map(hex_encode).collect(),
+                accepted: accepted.clone(),
+            }],
+            OverlayEvent::PeerConnected(enr) => vec![SubscriptionEvent::PeerConnected {
+                subnetwork,
+                enr: enr.clone(),
+            }],
+        }
+    }
+}
+
 /// Converts the given time to the number of milliseconds since the Unix epoch.
 pub fn millis_to_epoch(time: SystemTime) -> i64 {
     time.duration_since(UNIX_EPOCH)
@@ -302,6 +383,7 @@ pub fn millis_to_epoch(time: SystemTime) -> i64 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use ethportal_api::OverlayContentKey;
     use std::time::SystemTime;
 
     #[test]
@@ -323,4 +405,38 @@ mod test {
         let t: Timestamp = 100.into();
         assert_eq!(t, Timestamp::CreateTime(100));
     }
+
+    #[test]
+    fn test_subscription_events_light_client_head() {
+        use ethportal_api::types::content_key::beacon::LightClientOptimisticUpdateKey;
+
+        let content_key =
+            BeaconContentKey::LightClientOptimisticUpdate(LightClientOptimisticUpdateKey::new(42))
+                .to_bytes();
+        let event = EventEnvelope::new(
+            OverlayEvent::ContentStored(content_key.clone()),
+            ProtocolId::Beacon,
+            Some(vec![]),
+        );
+        assert_eq!(
+            event.subscription_events(),
+            vec![
+                SubscriptionEvent::ContentStored {
+                    subnetwork: BEACON_NETWORK.to_string(),
+                    content_key: hex_encode(content_key),
+                },
+                SubscriptionEvent::LightClientHead { signature_slot: 42 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subscription_events_ignore_light_client_updates() {
+        let event = EventEnvelope::new(
+            OverlayEvent::LightClientOptimisticUpdate,
+            ProtocolId::Beacon,
+            None,
+        );
+        assert!(event.subscription_events().is_empty());
+    }
 }
@@ -21,12 +21,10 @@
 // This basis of this file has been taken from the rust-libp2p codebase:
 // https://github.com/libp2p/rust-libp2p
 
-use std::{
-    collections::btree_map::{BTreeMap, Entry},
-    time::Instant,
-};
+use std::collections::btree_map::{BTreeMap, Entry};
 
 use discv5::kbucket::{Distance, Key};
+use tokio::time::Instant;
 
 use super::{
     super::query_pool::QueryState,
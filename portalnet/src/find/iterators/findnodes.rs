@@ -27,10 +27,8 @@ use super::{
 };
 
 use discv5::kbucket::{Distance, Key};
-use std::{
-    collections::btree_map::{BTreeMap, Entry},
-    time::Instant,
-};
+use std::collections::btree_map::{BTreeMap, Entry};
+use tokio::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct FindNodeQuery<TNodeId> {
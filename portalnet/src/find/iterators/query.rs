@@ -23,9 +23,10 @@
 
 use super::super::query_pool::QueryState;
 
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use discv5::kbucket::Key;
+use tokio::time::Instant;
 
 // Configuration for a `Query`.
 #[derive(Debug, Clone)]
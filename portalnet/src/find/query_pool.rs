@@ -26,10 +26,8 @@ use ethportal_api::OverlayContentKey;
 
 use discv5::kbucket::Key;
 use fnv::FnvHashMap;
-use std::{
-    marker::PhantomData,
-    time::{Duration, Instant},
-};
+use std::{marker::PhantomData, time::Duration};
+use tokio::time::Instant;
 
 pub trait TargetKey<TNodeId> {
     fn key(&self) -> Key<TNodeId>;
@@ -84,6 +82,17 @@ where
         }
     }
 
+    /// Returns the timeout of the queries.
+    pub fn query_timeout(&self) -> Duration {
+        self.query_timeout
+    }
+
+    /// Sets the timeout of the queries. The timeout is checked against the start of each query
+    /// when the pool is polled, so it also applies to the queries in the pool.
+    pub fn set_query_timeout(&mut self, query_timeout: Duration) {
+        self.query_timeout = query_timeout;
+    }
+
     /// Returns an iterator over the queries in the pool.
     pub fn iter(&self) -> impl Iterator<Item = &(QueryInfo<TContentKey>, TQuery)> {
         self.queries.values()
@@ -1,15 +1,31 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 use discv5::{
     enr::NodeId,
     kbucket::{self, KBucketsTable},
 };
-use futures::channel::oneshot;
-use parking_lot::RwLock;
-use rand::seq::IteratorRandom;
+use futures::{
+    channel::oneshot,
+    future::BoxFuture,
+    stream::{FuturesUnordered, StreamExt},
+    FutureExt,
+};
+use parking_lot::{Mutex, RwLock};
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::{
+    sync::{mpsc, OwnedSemaphorePermit},
+    time::{interval, Instant},
+};
 use tracing::{debug, error, warn};
+use trin_metrics::{
+    labels::{GossipOutcomeLabel, GossipQueueLabel},
+    overlay::OverlayMetricsReporter,
+};
 
 use crate::{
     overlay::{
@@ -21,7 +37,8 @@ use crate::{
 };
 use ethportal_api::{
     types::{
-        distance::{Metric, XorMetric},
+        cli::GossipStrategy,
+        distance::{Distance, Metric, XorMetric},
         enr::Enr,
         portal_wire::{PopulatedOffer, PopulatedOfferWithResult, Request, Response},
     },
@@ -40,178 +57,885 @@ pub struct GossipResult {
     pub transferred: Vec<Enr>,
 }
 
-/// Propagate gossip in a way that can be used across threads, without &self.
-/// Doesn't trace gossip results
-pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
-    content: Vec<(TContentKey, Vec<u8>)>,
+/// The maximum number of content keys offered in a single OFFER message, bound by the capacity of
+/// the ACCEPT bitlist.
+pub const MAX_CONTENT_KEYS_PER_OFFER: usize = 8;
+
+/// The interval at which the gossip scheduler checks for peers that are ready for an offer.
+const DISPATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The maximum number of FINDNODES queries run at once for content gossiped with
+/// [`GossipStrategy::Hybrid`].
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+/// Configuration of the gossip scheduler.
+#[derive(Clone, Debug)]
+pub struct GossipConfig {
+    /// The minimum interval between two offers to the same peer.
+    pub peer_offer_interval: Duration,
+    /// The maximum number of offers sent per second, to all peers.
+    pub max_offers_per_sec: u32,
+    /// The maximum number of attempts to offer a content key to a peer.
+    pub max_attempts: u32,
+    /// The backoff after a failed offer to a peer, doubled with every further attempt.
+    pub retry_backoff: Duration,
+    /// The maximum number of content keys queued for a single peer. When exceeded, the content
+    /// farthest from the peer is dropped.
+    pub max_queued_keys_per_peer: usize,
+    /// The strategy used to select the peers that content is gossiped to.
+    pub strategy: GossipStrategy,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            peer_offer_interval: Duration::from_millis(200),
+            max_offers_per_sec: 50,
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(2),
+            max_queued_keys_per_peer: 1024,
+            strategy: GossipStrategy::default(),
+        }
+    }
+}
+
+/// Content scheduled to be offered to a peer.
+type GossipItem<TContentKey> = (Enr, TContentKey, Arc<Vec<u8>>);
+
+/// Content for which fewer interested peers than required by [`GossipStrategy::Hybrid`] are in
+/// the routing table.
+pub struct GossipLookup<TContentKey> {
+    content_key: TContentKey,
+    content_value: Arc<Vec<u8>>,
+    /// The interested peers from the routing table.
+    known: Vec<Enr>,
+    /// The number of peers to offer the content to.
+    num_peers: usize,
+}
+
+/// Content whose gossip is traced. The content is offered on its own to each of the peers, so
+/// that its transfer can be traced.
+pub struct GossipTrace<TContentKey> {
+    content_key: TContentKey,
+    content_value: Vec<u8>,
+    /// The peers to offer the content to.
+    enrs: Vec<Enr>,
+    /// The sender of the trace, once all the offers are completed.
+    result_tx: oneshot::Sender<GossipResult>,
+}
+
+/// Content scheduled by the [`GossipHandle`].
+pub enum GossipMessage<TContentKey> {
+    /// Content to offer to the selected peers.
+    Offer(Vec<GossipItem<TContentKey>>),
+    /// Content to offer once more peers close to it are found.
+    Lookup(GossipLookup<TContentKey>),
+    /// Content to offer to the selected peers, tracing the outcome of the offers.
+    Trace(GossipTrace<TContentKey>),
+}
+
+/// Handle used to schedule content for gossip. The content is offered to the interested peers by
+/// the [`GossipScheduler`].
+#[derive(Clone)]
+pub struct GossipHandle<TContentKey> {
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
-    command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
-    utp_controller: Option<Arc<UtpController>>,
-) -> usize {
-    // Get all connected nodes from overlay routing table
-    let kbuckets = kbuckets.read();
-    let all_nodes: Vec<&kbucket::Node<NodeId, Node>> = kbuckets
-        .buckets_iter()
-        .flat_map(|kbucket| {
-            kbucket
-                .iter()
-                .filter(|node| node.status.is_connected())
-                .collect::<Vec<&kbucket::Node<NodeId, Node>>>()
-        })
-        .collect();
+    strategy: GossipStrategy,
+    gossip_tx: mpsc::UnboundedSender<GossipMessage<TContentKey>>,
+    /// Selects the random recipients of [`GossipStrategy::Random`].
+    rng: Arc<Mutex<StdRng>>,
+}
 
-    if all_nodes.is_empty() {
-        // If there are no nodes whatsoever in the routing table the gossip cannot proceed.
-        warn!("No nodes in routing table, gossip cannot proceed.");
-        return 0;
+impl<TContentKey: OverlayContentKey> GossipHandle<TContentKey> {
+    /// Creates a handle, and the receiver to pass to [`GossipScheduler::spawn`].
+    pub fn new(
+        kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+        strategy: GossipStrategy,
+        rng: StdRng,
+    ) -> (Self, mpsc::UnboundedReceiver<GossipMessage<TContentKey>>) {
+        let (gossip_tx, gossip_rx) = mpsc::unbounded_channel();
+        (
+            Self {
+                kbuckets,
+                strategy,
+                gossip_tx,
+                rng: Arc::new(Mutex::new(rng)),
+            },
+            gossip_rx,
+        )
     }
 
-    // HashMap to temporarily store all interested ENRs and the content.
-    // Key is base64 string of node's ENR.
-    let mut enrs_and_content: HashMap<String, Vec<(RawContentKey, Vec<u8>)>> = HashMap::new();
+    /// Schedules the content to be offered to the interested peers. Returns the number of peers
+    /// from the routing table the content was scheduled for. With [`GossipStrategy::Hybrid`],
+    /// the content may be offered to further peers once they are found.
+    pub fn gossip(&self, content: Vec<(TContentKey, Vec<u8>)>) -> usize {
+        let mut items = vec![];
+        let mut lookups = vec![];
+        let mut peers = HashSet::new();
+        {
+            let kbuckets = self.kbuckets.read();
+            let all_nodes = connected_nodes(&kbuckets);
+            if all_nodes.is_empty() {
+                // If there are no nodes whatsoever in the routing table the gossip cannot proceed.
+                warn!("No nodes in routing table, gossip cannot proceed.");
+                return 0;
+            }
 
-    for (content_key, content_value) in content {
-        let interested_enrs = calculate_interested_enrs(&content_key, &all_nodes);
+            for (content_key, content_value) in content {
+                let content_value = Arc::new(content_value);
+                let enrs = calculate_interested_enrs(
+                    &content_key,
+                    &all_nodes,
+                    self.strategy,
+                    &mut *self.rng.lock(),
+                );
+                peers.extend(enrs.iter().map(|enr| enr.node_id()));
+                match self.strategy {
+                    GossipStrategy::Hybrid(num_peers) if enrs.len() < num_peers => {
+                        lookups.push(GossipLookup {
+                            content_key,
+                            content_value,
+                            known: enrs,
+                            num_peers,
+                        });
+                    }
+                    _ => items.extend(
+                        enrs.into_iter()
+                            .map(|enr| (enr, content_key.clone(), Arc::clone(&content_value))),
+                    ),
+                }
+            }
+        }
+
+        let mut messages: Vec<GossipMessage<TContentKey>> =
+            lookups.into_iter().map(GossipMessage::Lookup).collect();
+        if !items.is_empty() {
+            messages.push(GossipMessage::Offer(items));
+        }
+        for message in messages {
+            if let Err(err) = self.gossip_tx.send(message) {
+                error!(error = %err, "Error scheduling content for gossip");
+                return 0;
+            }
+        }
+        peers.len()
+    }
+
+    /// Offers the content to the interested peers, and returns the trace of the offers once they
+    /// are completed. The offers are subject to the same rate limit and uTP transfer permits as
+    /// the scheduled gossip. With [`GossipStrategy::Hybrid`], the content is only offered to the
+    /// peers from the routing table.
+    pub async fn trace_gossip(
+        &self,
+        content_key: TContentKey,
+        content_value: Vec<u8>,
+    ) -> GossipResult {
+        let enrs = {
+            let kbuckets = self.kbuckets.read();
+            let all_nodes = connected_nodes(&kbuckets);
+            if all_nodes.is_empty() {
+                // If there are no nodes whatsoever in the routing table the gossip cannot proceed.
+                warn!("No nodes in routing table, gossip cannot proceed.");
+                return GossipResult::default();
+            }
+            calculate_interested_enrs(
+                &content_key,
+                &all_nodes,
+                self.strategy,
+                &mut *self.rng.lock(),
+            )
+        };
+        self.trace(content_key, content_value, enrs).await
+    }
+
+    /// Offers the content to the peer, and returns the trace of the offer once it is completed.
+    /// The offer is subject to the same rate limit and uTP transfer permits as the scheduled
+    /// gossip.
+    pub async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_key: TContentKey,
+        content_value: Vec<u8>,
+    ) -> GossipResult {
+        self.trace(content_key, content_value, vec![enr]).await
+    }
+
+    /// Schedules the traced offers of the content to the peers.
+    async fn trace(
+        &self,
+        content_key: TContentKey,
+        content_value: Vec<u8>,
+        enrs: Vec<Enr>,
+    ) -> GossipResult {
+        if enrs.is_empty() {
+            return GossipResult::default();
+        }
 
-        // Temporarily store all randomly selected nodes with the content of interest.
-        // We want this so we can offer all the content to an interested node in one request.
-        let raw_item = (content_key.into(), content_value);
-        for enr in interested_enrs {
-            enrs_and_content
-                .entry(enr.to_base64())
-                .or_default()
-                .push(raw_item.clone());
+        let (result_tx, result_rx) = oneshot::channel();
+        let trace = GossipTrace {
+            content_key,
+            content_value,
+            enrs,
+            result_tx,
+        };
+        if let Err(err) = self.gossip_tx.send(GossipMessage::Trace(trace)) {
+            error!(error = %err, "Error scheduling content for traced gossip");
+            return GossipResult::default();
         }
+        result_rx.await.unwrap_or_default()
     }
+}
+
+/// Returns the connected nodes of the routing table.
+fn connected_nodes(kbuckets: &KBucketsTable<NodeId, Node>) -> Vec<&kbucket::Node<NodeId, Node>> {
+    kbuckets
+        .buckets_iter()
+        .flat_map(|kbucket| kbucket.iter().filter(|node| node.status.is_connected()))
+        .collect()
+}
+
+/// A content key queued for a peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct QueuedKey {
+    content_id: [u8; 32],
+    /// The number of failed offers of the content key to the peer.
+    attempts: u32,
+}
+
+/// The content keys queued for a single peer.
+struct PeerQueue {
+    enr: Enr,
+    /// The queued content keys, ordered by the distance of the content to the peer.
+    keys: BTreeMap<Distance, QueuedKey>,
+    /// The earliest time at which the next offer can be sent to the peer.
+    next_offer_at: Instant,
+    /// Whether an offer to the peer is waiting for a response.
+    in_flight: bool,
+}
+
+/// Content that is queued for at least one peer.
+struct QueuedContent<TContentKey> {
+    content_key: TContentKey,
+    content_value: Arc<Vec<u8>>,
+    /// The number of peers the content is queued or in flight for.
+    peers: usize,
+}
+
+/// A batch of content keys to offer to a peer.
+struct OfferBatch {
+    enr: Enr,
+    keys: Vec<QueuedKey>,
+    content_items: Vec<(RawContentKey, Vec<u8>)>,
+}
 
-    let num_propagated_peers = enrs_and_content.len();
-    // Create and send OFFER overlay request to the interested nodes
-    for (enr_string, interested_content) in enrs_and_content.into_iter() {
-        let permit = match utp_controller {
-            Some(ref utp_controller) => match utp_controller.get_outbound_semaphore() {
-                Some(permit) => Some(permit),
-                None => continue,
+/// The outcome of an offer sent by the gossip scheduler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OfferOutcome {
+    /// The peer answered the offer, accepting the given number of the offered content keys.
+    Answered { accepted: usize },
+    /// The offer failed (e.g. it timed out), and may be retried.
+    Failed,
+}
+
+/// The per-peer queues of content keys waiting to be offered. Each content value is held only
+/// once, no matter the number of peers it is queued for.
+struct GossipQueue<TContentKey> {
+    config: GossipConfig,
+    /// Ordered by node id, so that the peers that are ready at the same time are offered in a
+    /// fixed order.
+    peers: BTreeMap<NodeId, PeerQueue>,
+    content: HashMap<[u8; 32], QueuedContent<TContentKey>>,
+    metrics: OverlayMetricsReporter,
+}
+
+impl<TContentKey: OverlayContentKey> GossipQueue<TContentKey> {
+    fn new(config: GossipConfig, metrics: OverlayMetricsReporter) -> Self {
+        Self {
+            config,
+            peers: BTreeMap::new(),
+            content: HashMap::new(),
+            metrics,
+        }
+    }
+
+    /// Queues the content for the peer, unless it is already queued.
+    fn push(&mut self, enr: Enr, content_key: TContentKey, content_value: Arc<Vec<u8>>) {
+        let content_id = content_key.content_id();
+        let node_id = enr.node_id();
+        let distance = XorMetric::distance(&content_id, &node_id.raw());
+        let peer = self.peers.entry(node_id).or_insert_with(|| PeerQueue {
+            enr,
+            keys: BTreeMap::new(),
+            next_offer_at: Instant::now(),
+            in_flight: false,
+        });
+        if peer.keys.contains_key(&distance) {
+            return;
+        }
+        peer.keys.insert(
+            distance,
+            QueuedKey {
+                content_id,
+                attempts: 0,
             },
-            None => None,
+        );
+        let dropped = if peer.keys.len() > self.config.max_queued_keys_per_peer {
+            peer.keys.pop_last().map(|(_, key)| key.content_id)
+        } else {
+            None
         };
+        self.content
+            .entry(content_id)
+            .or_insert_with(|| QueuedContent {
+                content_key,
+                content_value,
+                peers: 0,
+            })
+            .peers += 1;
+        if let Some(content_id) = dropped {
+            self.metrics
+                .report_gossip_outcome(GossipOutcomeLabel::Dropped, 1);
+            self.release(&content_id);
+        }
+    }
 
-        let enr = match Enr::from_str(&enr_string) {
-            Ok(enr) => enr,
-            Err(err) => {
-                error!(error = %err, enr.base64 = %enr_string, "Error decoding ENR from base-64");
-                continue;
+    /// Takes the content keys closest to the next peer that is ready for an offer.
+    fn next_batch(&mut self, now: Instant) -> Option<OfferBatch> {
+        let (node_id, _) = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| {
+                !peer.in_flight && !peer.keys.is_empty() && peer.next_offer_at <= now
+            })
+            .min_by_key(|(_, peer)| peer.next_offer_at)?;
+        let node_id = *node_id;
+        let peer = self.peers.get_mut(&node_id)?;
+
+        let mut keys = vec![];
+        while keys.len() < MAX_CONTENT_KEYS_PER_OFFER {
+            match peer.keys.pop_first() {
+                Some((_, key)) => keys.push(key),
+                None => break,
             }
+        }
+        peer.in_flight = true;
+        let content_items = keys
+            .iter()
+            .filter_map(|key| self.content.get(&key.content_id))
+            .map(|content| {
+                (
+                    content.content_key.clone().into(),
+                    content.content_value.as_ref().clone(),
+                )
+            })
+            .collect();
+        Some(OfferBatch {
+            enr: peer.enr.clone(),
+            keys,
+            content_items,
+        })
+    }
+
+    /// Completes an offer to the peer, queuing the content keys again if the offer failed.
+    fn complete(
+        &mut self,
+        node_id: NodeId,
+        keys: Vec<QueuedKey>,
+        outcome: OfferOutcome,
+        now: Instant,
+    ) {
+        let Some(peer) = self.peers.get_mut(&node_id) else {
+            return;
         };
+        peer.in_flight = false;
+        peer.next_offer_at = now + self.config.peer_offer_interval;
 
-        let offer_request = Request::PopulatedOffer(PopulatedOffer {
-            content_items: interested_content,
-        });
+        let mut released = vec![];
+        match outcome {
+            OfferOutcome::Answered { accepted } => {
+                let accepted = accepted.min(keys.len());
+                self.metrics
+                    .report_gossip_outcome(GossipOutcomeLabel::Accepted, accepted as u64);
+                self.metrics.report_gossip_outcome(
+                    GossipOutcomeLabel::Declined,
+                    (keys.len() - accepted) as u64,
+                );
+                released.extend(keys.into_iter().map(|key| key.content_id));
+            }
+            OfferOutcome::Failed => {
+                let mut max_attempts = 0;
+                let mut retried = 0;
+                for mut key in keys {
+                    key.attempts += 1;
+                    if key.attempts >= self.config.max_attempts {
+                        released.push(key.content_id);
+                        continue;
+                    }
+                    max_attempts = max_attempts.max(key.attempts);
+                    retried += 1;
+                    let distance = XorMetric::distance(&key.content_id, &node_id.raw());
+                    peer.keys.insert(distance, key);
+                }
+                if max_attempts > 0 {
+                    let backoff = self.config.retry_backoff * 2u32.pow(max_attempts - 1);
+                    peer.next_offer_at = now + backoff;
+                }
+                self.metrics
+                    .report_gossip_outcome(GossipOutcomeLabel::Retried, retried);
+                self.metrics
+                    .report_gossip_outcome(GossipOutcomeLabel::Dropped, released.len() as u64);
+            }
+        }
+        // A drained peer is kept until its next offer is due, so that content queued for it in
+        // the meantime still waits for the interval.
+        for content_id in released {
+            self.release(&content_id);
+        }
+    }
 
-        let overlay_request = OverlayRequest::new(
-            offer_request,
-            RequestDirection::Outgoing { destination: enr },
-            None,
-            None,
-            permit,
-        );
+    /// Removes the peers without queued content keys whose next offer is due, as they would be
+    /// queued again with no wait.
+    fn remove_idle_peers(&mut self, now: Instant) {
+        self.peers
+            .retain(|_, peer| peer.in_flight || !peer.keys.is_empty() || peer.next_offer_at > now);
+    }
 
-        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
-            error!(error = %err, "Error sending OFFER message to service")
+    /// Releases the content for one peer, dropping it once it isn't queued for any peer.
+    fn release(&mut self, content_id: &[u8; 32]) {
+        if let Some(content) = self.content.get_mut(content_id) {
+            content.peers = content.peers.saturating_sub(1);
+            if content.peers == 0 {
+                self.content.remove(content_id);
+            }
         }
     }
 
-    num_propagated_peers
+    fn report_metrics(&self) {
+        let keys = self.peers.values().map(|peer| peer.keys.len()).sum();
+        let in_flight = self.peers.values().filter(|peer| peer.in_flight).count();
+        let peers = self
+            .peers
+            .values()
+            .filter(|peer| peer.in_flight || !peer.keys.is_empty())
+            .count();
+        self.metrics
+            .report_gossip_queue(GossipQueueLabel::Keys, keys);
+        self.metrics
+            .report_gossip_queue(GossipQueueLabel::Peers, peers);
+        self.metrics
+            .report_gossip_queue(GossipQueueLabel::InFlight, in_flight);
+    }
+}
+
+/// The neighborhood of content, which is the first byte of its content id. The lookups of content
+/// from the same neighborhood share a single FINDNODES query.
+type Neighborhood = u8;
+
+fn neighborhood(content_id: &[u8; 32]) -> Neighborhood {
+    content_id[0]
+}
+
+/// The lookups of content waiting for the peers close to it, grouped by neighborhood. At most
+/// [`MAX_CONCURRENT_LOOKUPS`] queries are running at once, and content from a neighborhood whose
+/// query is running or pending is added to that query.
+struct LookupQueue<TContentKey> {
+    /// The lookups of the neighborhoods whose query is running.
+    running: HashMap<Neighborhood, Vec<GossipLookup<TContentKey>>>,
+    /// The lookups of the neighborhoods waiting for a query, in the order they were scheduled.
+    pending: VecDeque<(Neighborhood, Vec<GossipLookup<TContentKey>>)>,
 }
 
-/// Propagate gossip in a way that can be used across threads, without &self.
-/// This function is designed to be used via the JSON-RPC API. Since it is blocking, it should not
-/// be used internally in the offer/accept flow.
-/// Returns a trace detailing the outcome of the gossip.
-pub async fn trace_propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
+impl<TContentKey: OverlayContentKey> LookupQueue<TContentKey> {
+    fn new() -> Self {
+        Self {
+            running: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Adds the lookup to the query of its neighborhood.
+    fn push(&mut self, lookup: GossipLookup<TContentKey>) {
+        let neighborhood = neighborhood(&lookup.content_key.content_id());
+        if let Some(lookups) = self.running.get_mut(&neighborhood) {
+            lookups.push(lookup);
+        } else if let Some((_, lookups)) = self
+            .pending
+            .iter_mut()
+            .find(|(pending, _)| *pending == neighborhood)
+        {
+            lookups.push(lookup);
+        } else {
+            self.pending.push_back((neighborhood, vec![lookup]));
+        }
+    }
+
+    /// Returns the neighborhood and the target of the next query to run, unless the maximum
+    /// number of queries is already running.
+    fn next_query(&mut self) -> Option<(Neighborhood, NodeId)> {
+        if self.running.len() >= MAX_CONCURRENT_LOOKUPS {
+            return None;
+        }
+        let (neighborhood, lookups) = self.pending.pop_front()?;
+        let target = NodeId::new(&lookups.first()?.content_key.content_id());
+        self.running.insert(neighborhood, lookups);
+        Some((neighborhood, target))
+    }
+
+    /// Completes the query of the neighborhood, returning its lookups.
+    fn complete(&mut self, neighborhood: Neighborhood) -> Vec<GossipLookup<TContentKey>> {
+        self.running.remove(&neighborhood).unwrap_or_default()
+    }
+}
+
+/// A single offer of traced content to a peer.
+struct TracedOffer<TContentKey> {
+    trace_id: u64,
+    enr: Enr,
     content_key: TContentKey,
-    data: Vec<u8>,
+    content_value: Arc<Vec<u8>>,
+}
+
+/// The trace of gossiped content, which is sent once all of its offers are completed.
+struct PendingTrace {
+    result: GossipResult,
+    /// The number of offers that aren't completed yet.
+    pending: usize,
+    result_tx: oneshot::Sender<GossipResult>,
+}
+
+/// Schedules the gossip of content via OFFER messages. The content keys are batched per peer up to
+/// the limit of a single OFFER, with the content closest to the peer offered first. Offers are
+/// rate limited per peer and globally, and failed offers are retried with an exponential backoff.
+///
+/// Traced content is offered on its own, ahead of the queued content, within the same global
+/// rate limit and uTP transfer permits.
+pub struct GossipScheduler<TContentKey> {
+    queue: GossipQueue<TContentKey>,
+    lookups: LookupQueue<TContentKey>,
+    /// The offers of traced content waiting to be sent.
+    traced_offers: VecDeque<TracedOffer<TContentKey>>,
+    /// The traces of the gossiped content, by id.
+    traces: HashMap<u64, PendingTrace>,
+    next_trace_id: u64,
+    gossip_rx: mpsc::UnboundedReceiver<GossipMessage<TContentKey>>,
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
     command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
-) -> GossipResult {
-    let mut gossip_result = GossipResult::default();
-    // Get all connected nodes from overlay routing table
-    let interested_enrs = {
-        let kbuckets = kbuckets.read();
-        let all_nodes: Vec<&kbucket::Node<NodeId, Node>> = kbuckets
-            .buckets_iter()
-            .flat_map(|kbucket| {
-                kbucket
-                    .iter()
-                    .filter(|node| node.status.is_connected())
-                    .collect::<Vec<&kbucket::Node<NodeId, Node>>>()
-            })
-            .collect();
+    utp_controller: Arc<UtpController>,
+    /// The tokens of the global rate limit. Each offer consumes one token.
+    tokens: f64,
+    last_refill: Instant,
+}
 
-        if all_nodes.is_empty() {
-            // If there are no nodes whatsoever in the routing table the gossip cannot proceed.
-            warn!("No nodes in routing table, gossip cannot proceed.");
-            return gossip_result;
+impl<TContentKey: 'static + OverlayContentKey + Send + Sync> GossipScheduler<TContentKey> {
+    /// Spawns the gossip scheduler, processing the content scheduled by the [`GossipHandle`].
+    pub fn spawn(
+        config: GossipConfig,
+        gossip_rx: mpsc::UnboundedReceiver<GossipMessage<TContentKey>>,
+        kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+        command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+        utp_controller: Arc<UtpController>,
+        metrics: OverlayMetricsReporter,
+    ) {
+        let tokens = f64::from(config.max_offers_per_sec);
+        let scheduler = Self {
+            queue: GossipQueue::new(config, metrics),
+            lookups: LookupQueue::new(),
+            traced_offers: VecDeque::new(),
+            traces: HashMap::new(),
+            next_trace_id: 0,
+            gossip_rx,
+            kbuckets,
+            command_tx,
+            utp_controller,
+            tokens,
+            last_refill: Instant::now(),
+        };
+        tokio::spawn(scheduler.run());
+    }
+
+    async fn run(mut self) {
+        let mut offers = FuturesUnordered::new();
+        let mut traced = FuturesUnordered::new();
+        let mut lookups = FuturesUnordered::new();
+        let mut dispatch_interval = interval(DISPATCH_INTERVAL);
+        loop {
+            tokio::select! {
+                // The branches are polled in order, rather than randomly, so that the same
+                // sequence of events is always handled in the same way.
+                biased;
+                message = self.gossip_rx.recv() => match message {
+                    Some(GossipMessage::Offer(items)) => {
+                        for (enr, content_key, content_value) in items {
+                            self.queue.push(enr, content_key, content_value);
+                        }
+                    }
+                    Some(GossipMessage::Lookup(lookup)) => {
+                        self.lookups.push(lookup);
+                        self.start_lookups(&mut lookups);
+                    }
+                    Some(GossipMessage::Trace(trace)) => self.trace(trace),
+                    // All handles are dropped, the overlay is shutting down.
+                    None => break,
+                },
+                Some((neighborhood, found)) = lookups.next() => {
+                    for lookup in self.lookups.complete(neighborhood) {
+                        self.complete_lookup(lookup, found.clone());
+                    }
+                    self.start_lookups(&mut lookups);
+                }
+                Some((node_id, keys, outcome)) = offers.next() => {
+                    self.queue.complete(node_id, keys, outcome, Instant::now());
+                }
+                Some((trace_id, result)) = traced.next() => {
+                    self.complete_traced_offer(trace_id, result);
+                }
+                _ = dispatch_interval.tick() => {
+                    self.dispatch(&mut offers, &mut traced);
+                }
+            }
+            self.queue.report_metrics();
         }
-        calculate_interested_enrs(&content_key, &all_nodes)
-    };
-    if interested_enrs.is_empty() {
-        return gossip_result;
-    };
-
-    // Create and send OFFER overlay request to the interested nodes
-    for enr in interested_enrs.into_iter() {
-        let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel();
-        let offer_request = Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
-            content_item: (content_key.clone().into(), data.clone()),
-            result_tx,
-        });
+    }
+
+    /// Starts the FINDNODES queries for the peers closest to the pending neighborhoods, up to the
+    /// maximum number of concurrent queries.
+    fn start_lookups(
+        &mut self,
+        lookups: &mut FuturesUnordered<BoxFuture<'static, (Neighborhood, Vec<Enr>)>>,
+    ) {
+        while let Some((neighborhood, target)) = self.lookups.next_query() {
+            let (tx, rx) = oneshot::channel();
+            if let Err(err) = self.command_tx.send(OverlayCommand::FindNodeQuery {
+                target,
+                callback: tx,
+            }) {
+                error!(error = %err, "Error submitting FindNode query for gossip");
+                for lookup in self.lookups.complete(neighborhood) {
+                    self.complete_lookup(lookup, vec![]);
+                }
+                continue;
+            }
+            lookups.push(
+                async move {
+                    let found = rx.await.unwrap_or_default();
+                    (neighborhood, found)
+                }
+                .boxed(),
+            );
+        }
+    }
+
+    /// Queues the content for the peers closest to it, among the interested peers from the
+    /// routing table and the peers found by the FINDNODES query.
+    ///
+    /// Found peers whose radius is known to not cover the content are skipped. Found peers that
+    /// aren't in the routing table have an unknown radius and may decline the content, so they
+    /// are selected separately, in addition to the peers known to be interested.
+    fn complete_lookup(&mut self, lookup: GossipLookup<TContentKey>, found: Vec<Enr>) {
+        let content_id = lookup.content_key.content_id();
+        let mut interested = vec![];
+        let mut unknown_radius = vec![];
+        {
+            let mut kbuckets = self.kbuckets.write();
+            for enr in found {
+                let key = kbucket::Key::from(enr.node_id());
+                match kbuckets.entry(&key) {
+                    kbucket::Entry::Present(entry, _) => {
+                        if XorMetric::distance(&content_id, &enr.node_id().raw())
+                            < entry.value().data_radius()
+                        {
+                            interested.push(enr);
+                        }
+                    }
+                    kbucket::Entry::SelfEntry => {}
+                    _ => unknown_radius.push(enr),
+                }
+            }
+        }
+        let mut enrs = select_closest_enrs(&content_id, lookup.known, interested, lookup.num_peers);
+        enrs.extend(select_closest_enrs(
+            &content_id,
+            vec![],
+            unknown_radius,
+            lookup.num_peers,
+        ));
+        if enrs.is_empty() {
+            debug!(
+                content.id = %hex_encode(content_id),
+                "No peers found for neighborhood gossip"
+            );
+        }
+        for enr in enrs {
+            self.queue.push(
+                enr,
+                lookup.content_key.clone(),
+                Arc::clone(&lookup.content_value),
+            );
+        }
+    }
 
+    /// Queues the offers of the traced content to each of its peers.
+    fn trace(&mut self, trace: GossipTrace<TContentKey>) {
+        if trace.enrs.is_empty() {
+            let _ = trace.result_tx.send(GossipResult::default());
+            return;
+        }
+        let trace_id = self.next_trace_id;
+        self.next_trace_id += 1;
+        let content_value = Arc::new(trace.content_value);
+        for enr in trace.enrs.iter() {
+            self.traced_offers.push_back(TracedOffer {
+                trace_id,
+                enr: enr.clone(),
+                content_key: trace.content_key.clone(),
+                content_value: Arc::clone(&content_value),
+            });
+        }
+        self.traces.insert(
+            trace_id,
+            PendingTrace {
+                result: GossipResult::default(),
+                pending: trace.enrs.len(),
+                result_tx: trace.result_tx,
+            },
+        );
+    }
+
+    /// Adds the outcome of a traced offer to its trace, and sends the trace once all of its offers
+    /// are completed.
+    fn complete_traced_offer(&mut self, trace_id: u64, result: GossipResult) {
+        let Some(trace) = self.traces.get_mut(&trace_id) else {
+            return;
+        };
+        trace.result.offered.extend(result.offered);
+        trace.result.accepted.extend(result.accepted);
+        trace.result.transferred.extend(result.transferred);
+        trace.pending -= 1;
+        if trace.pending == 0 {
+            if let Some(trace) = self.traces.remove(&trace_id) {
+                let _ = trace.result_tx.send(trace.result);
+            }
+        }
+    }
+
+    /// Sends the offer of traced content, and returns the future of its outcome.
+    fn offer_traced(
+        &self,
+        offer: TracedOffer<TContentKey>,
+        permit: OwnedSemaphorePermit,
+    ) -> BoxFuture<'static, (u64, GossipResult)> {
+        let TracedOffer {
+            trace_id,
+            enr,
+            content_key,
+            content_value,
+        } = offer;
+        let (result_tx, mut result_rx) = mpsc::unbounded_channel();
         let (tx, rx) = oneshot::channel();
-        let responder = Some(tx);
-        let overlay_request = OverlayRequest::new(
-            offer_request,
+        let request = OverlayRequest::new(
+            Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
+                content_item: (content_key.into(), content_value.as_ref().clone()),
+                result_tx,
+            }),
             RequestDirection::Outgoing {
                 destination: enr.clone(),
             },
-            responder,
-            None,
+            Some(tx),
             None,
+            Some(permit),
         );
-        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
+        if let Err(err) = self.command_tx.send(OverlayCommand::Request(request)) {
             error!(error = %err, "Error sending OFFER message to service");
-            continue;
-        }
-        // update gossip result with peer marked as being offered the content
-        gossip_result.offered.push(enr.clone());
-        match rx.await {
-            Ok(res) => {
-                if let Ok(Response::Accept(accept)) = res {
-                    if !accept.content_keys.is_zero() {
-                        // update gossip result with peer marked as accepting the content
-                        gossip_result.accepted.push(enr.clone());
+            return async move { (trace_id, GossipResult::default()) }.boxed();
+        }
+        async move {
+            let mut result = GossipResult {
+                offered: vec![enr.clone()],
+                ..Default::default()
+            };
+            if let Ok(Ok(Response::Accept(accept))) = rx.await {
+                if !accept.content_keys.is_zero() {
+                    result.accepted.push(enr.clone());
+                    if let Some(true) = result_rx.recv().await {
+                        result.transferred.push(enr);
                     }
-                } else {
-                    // continue to next peer if no content was accepted
-                    continue;
                 }
             }
-            // continue to next peer if err while waiting for response
-            Err(_) => continue,
+            (trace_id, result)
         }
-        if let Some(result) = result_rx.recv().await {
-            if result {
-                // update gossip result with peer marked as successfully transferring the content
-                gossip_result.transferred.push(enr);
+        .boxed()
+    }
+
+    /// Sends offers to the peers that are ready, within the global rate limit and the available
+    /// uTP transfer permits. The offers of traced content are sent first.
+    fn dispatch(
+        &mut self,
+        offers: &mut FuturesUnordered<BoxFuture<'static, (NodeId, Vec<QueuedKey>, OfferOutcome)>>,
+        traced: &mut FuturesUnordered<BoxFuture<'static, (u64, GossipResult)>>,
+    ) {
+        let now = Instant::now();
+        let rate = f64::from(self.queue.config.max_offers_per_sec);
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+        self.queue.remove_idle_peers(now);
+
+        while self.tokens >= 1.0 {
+            let Some(permit) = self.utp_controller.get_outbound_semaphore() else {
+                break;
+            };
+            if let Some(offer) = self.traced_offers.pop_front() {
+                self.tokens -= 1.0;
+                traced.push(self.offer_traced(offer, permit));
+                continue;
+            }
+            let Some(batch) = self.queue.next_batch(now) else {
+                break;
+            };
+            self.tokens -= 1.0;
+
+            let node_id = batch.enr.node_id();
+            let (tx, rx) = oneshot::channel();
+            let request = OverlayRequest::new(
+                Request::PopulatedOffer(PopulatedOffer {
+                    content_items: batch.content_items,
+                }),
+                RequestDirection::Outgoing {
+                    destination: batch.enr,
+                },
+                Some(tx),
+                None,
+                Some(permit),
+            );
+            if let Err(err) = self.command_tx.send(OverlayCommand::Request(request)) {
+                error!(error = %err, "Error sending OFFER message to service");
+                self.queue
+                    .complete(node_id, batch.keys, OfferOutcome::Failed, now);
+                continue;
             }
+            let keys = batch.keys;
+            offers.push(
+                async move {
+                    let outcome = match rx.await {
+                        Ok(Ok(Response::Accept(accept))) => OfferOutcome::Answered {
+                            accepted: accept.content_keys.num_set_bits(),
+                        },
+                        _ => OfferOutcome::Failed,
+                    };
+                    (node_id, keys, outcome)
+                }
+                .boxed(),
+            );
         }
     }
-    gossip_result
 }
 
 /// Filter all nodes from overlay routing table where XOR_distance(content_id, nodeId) < node radius
+/// and select the gossip recipients among them according to the strategy.
 fn calculate_interested_enrs<TContentKey: OverlayContentKey>(
     content_key: &TContentKey,
     all_nodes: &[&kbucket::Node<NodeId, Node>],
+    strategy: GossipStrategy,
+    rng: &mut impl Rng,
 ) -> Vec<Enr> {
     // HashMap to temporarily store all interested ENRs and the content.
     // Key is base64 string of node's ENR.
@@ -247,25 +971,36 @@ fn calculate_interested_enrs<TContentKey: OverlayContentKey>(
         })
     });
 
-    select_gossip_recipients(interested_enrs)
+    select_gossip_recipients(interested_enrs, strategy, rng)
 }
 
 /// Randomly select `num_enrs` nodes from `enrs`.
-fn select_random_enrs(num_enrs: usize, enrs: Vec<Enr>) -> Vec<Enr> {
-    let random_enrs: Vec<Enr> = enrs
-        .into_iter()
-        .choose_multiple(&mut rand::thread_rng(), num_enrs);
+fn select_random_enrs(num_enrs: usize, enrs: Vec<Enr>, rng: &mut impl Rng) -> Vec<Enr> {
+    let random_enrs: Vec<Enr> = enrs.into_iter().choose_multiple(rng, num_enrs);
     random_enrs
 }
 
 const NUM_CLOSEST_NODES: usize = 4;
 const NUM_FARTHER_NODES: usize = 4;
 /// Selects gossip recipients from a vec of sorted interested ENRs.
-/// Returned vec is a concatenation of, at most:
+/// With [`GossipStrategy::Random`], the returned vec is a concatenation of, at most:
 /// 1. First `NUM_CLOSEST_NODES` elements of `interested_sorted_enrs`.
 /// 2. `NUM_FARTHER_NODES` elements randomly selected from
 ///    `interested_sorted_enrs[NUM_CLOSEST_NODES..]`
-fn select_gossip_recipients(interested_sorted_enrs: Vec<Enr>) -> Vec<Enr> {
+///
+/// Otherwise, it's the first `N` elements of `interested_sorted_enrs`.
+fn select_gossip_recipients(
+    mut interested_sorted_enrs: Vec<Enr>,
+    strategy: GossipStrategy,
+    rng: &mut impl Rng,
+) -> Vec<Enr> {
+    match strategy {
+        GossipStrategy::Random => {}
+        GossipStrategy::Closest(num_peers) | GossipStrategy::Hybrid(num_peers) => {
+            interested_sorted_enrs.truncate(num_peers);
+            return interested_sorted_enrs;
+        }
+    }
     let mut gossip_recipients: Vec<Enr> = vec![];
 
     // Get first n closest nodes
@@ -278,20 +1013,205 @@ fn select_gossip_recipients(interested_sorted_enrs: Vec<Enr>) -> Vec<Enr> {
     if interested_sorted_enrs.len() > NUM_CLOSEST_NODES {
         let farther_enrs = interested_sorted_enrs[NUM_CLOSEST_NODES..].to_vec();
         // Get random non-close ENRs to gossip to.
-        let random_farther_enrs = select_random_enrs(NUM_FARTHER_NODES, farther_enrs);
+        let random_farther_enrs = select_random_enrs(NUM_FARTHER_NODES, farther_enrs, rng);
         gossip_recipients.extend(random_farther_enrs);
     }
     gossip_recipients
 }
 
+/// Selects the `num_peers` ENRs closest to the content from the known and found ENRs.
+fn select_closest_enrs(
+    content_id: &[u8; 32],
+    known: Vec<Enr>,
+    found: Vec<Enr>,
+    num_peers: usize,
+) -> Vec<Enr> {
+    let mut enrs = known;
+    for enr in found {
+        if !enrs.iter().any(|known| known.node_id() == enr.node_id()) {
+            enrs.push(enr);
+        }
+    }
+    enrs.sort_by_key(|enr| XorMetric::distance(content_id, &enr.node_id().raw()));
+    enrs.truncate(num_peers);
+    enrs
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    use discv5::kbucket::{ConnectionDirection, ConnectionState, NodeStatus};
+    use rand::{thread_rng, SeedableRng};
     use rstest::rstest;
 
-    use ethportal_api::types::enr::generate_random_remote_enr;
+    use crate::overlay::config::OverlayConfig;
+    use ethportal_api::{types::enr::generate_random_remote_enr, IdentityContentKey};
+    use trin_metrics::portalnet::PORTALNET_METRICS;
+
+    fn build_queue(config: GossipConfig) -> GossipQueue<IdentityContentKey> {
+        let metrics = OverlayMetricsReporter {
+            overlay_metrics: PORTALNET_METRICS.overlay(),
+            protocol: "test".to_string(),
+        };
+        GossipQueue::new(config, metrics)
+    }
+
+    fn push_random_content(
+        queue: &mut GossipQueue<IdentityContentKey>,
+        enr: &Enr,
+        count: usize,
+    ) -> Vec<[u8; 32]> {
+        (0..count)
+            .map(|_| {
+                let content_key = IdentityContentKey::random();
+                let content_id = content_key.content_id();
+                queue.push(enr.clone(), content_key, Arc::new(vec![0xef]));
+                content_id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gossip_queue_batches_closest_content_first() {
+        let config = GossipConfig::default();
+        let mut queue = build_queue(config.clone());
+        let (_, enr) = generate_random_remote_enr();
+        let mut content_ids = push_random_content(&mut queue, &enr, MAX_CONTENT_KEYS_PER_OFFER + 2);
+        content_ids.sort_by_key(|content_id| XorMetric::distance(content_id, &enr.node_id().raw()));
+
+        let now = Instant::now();
+        let batch = queue.next_batch(now).unwrap();
+        assert_eq!(batch.enr, enr);
+        assert_eq!(batch.content_items.len(), MAX_CONTENT_KEYS_PER_OFFER);
+        let batch_ids: Vec<[u8; 32]> = batch.keys.iter().map(|key| key.content_id).collect();
+        assert_eq!(batch_ids, content_ids[..MAX_CONTENT_KEYS_PER_OFFER]);
+
+        // Only one offer at a time is in flight for a peer.
+        assert!(queue.next_batch(now).is_none());
+
+        // The next offer respects the per-peer interval.
+        queue.complete(
+            enr.node_id(),
+            batch.keys,
+            OfferOutcome::Answered {
+                accepted: MAX_CONTENT_KEYS_PER_OFFER,
+            },
+            now,
+        );
+        assert!(queue.next_batch(now).is_none());
+        let batch = queue.next_batch(now + config.peer_offer_interval).unwrap();
+        let batch_ids: Vec<[u8; 32]> = batch.keys.iter().map(|key| key.content_id).collect();
+        assert_eq!(batch_ids, content_ids[MAX_CONTENT_KEYS_PER_OFFER..]);
+    }
+
+    #[test]
+    fn gossip_queue_retries_failed_offers_with_backoff() {
+        let config = GossipConfig::default();
+        let mut queue = build_queue(config.clone());
+        let (_, enr) = generate_random_remote_enr();
+        let content_ids = push_random_content(&mut queue, &enr, 1);
+
+        let mut now = Instant::now();
+        for attempt in 1..config.max_attempts {
+            let batch = queue.next_batch(now).unwrap();
+            queue.complete(enr.node_id(), batch.keys, OfferOutcome::Failed, now);
+
+            let backoff = config.retry_backoff * 2u32.pow(attempt - 1);
+            assert!(queue
+                .next_batch(now + backoff - Duration::from_millis(1))
+                .is_none());
+            now += backoff;
+        }
+
+        // The last attempt fails as well, so the content is dropped.
+        let batch = queue.next_batch(now).unwrap();
+        assert_eq!(batch.keys[0].content_id, content_ids[0]);
+        assert_eq!(batch.keys[0].attempts, config.max_attempts - 1);
+        queue.complete(enr.node_id(), batch.keys, OfferOutcome::Failed, now);
+        assert!(queue.content.is_empty());
+        queue.remove_idle_peers(now + config.peer_offer_interval);
+        assert!(queue.peers.is_empty());
+    }
+
+    #[test]
+    fn gossip_queue_paces_content_queued_after_drained_offer() {
+        let config = GossipConfig::default();
+        let mut queue = build_queue(config.clone());
+        let (_, enr) = generate_random_remote_enr();
+        push_random_content(&mut queue, &enr, 1);
+
+        let now = Instant::now();
+        let batch = queue.next_batch(now).unwrap();
+        queue.complete(
+            enr.node_id(),
+            batch.keys,
+            OfferOutcome::Answered { accepted: 1 },
+            now,
+        );
+
+        // The peer's queue is drained, but content queued right after still waits for the
+        // per-peer interval.
+        queue.remove_idle_peers(now);
+        push_random_content(&mut queue, &enr, 1);
+        assert!(queue
+            .next_batch(now + config.peer_offer_interval - Duration::from_millis(1))
+            .is_none());
+        assert!(queue.next_batch(now + config.peer_offer_interval).is_some());
+    }
+
+    #[test]
+    fn gossip_queue_holds_content_once() {
+        let mut queue = build_queue(GossipConfig::default());
+        let (_, enr1) = generate_random_remote_enr();
+        let (_, enr2) = generate_random_remote_enr();
+        let content_key = IdentityContentKey::random();
+        let content_value = Arc::new(vec![0xef]);
+        queue.push(
+            enr1.clone(),
+            content_key.clone(),
+            Arc::clone(&content_value),
+        );
+        queue.push(
+            enr2.clone(),
+            content_key.clone(),
+            Arc::clone(&content_value),
+        );
+        // Content that is already queued for a peer isn't queued again.
+        queue.push(enr2.clone(), content_key, content_value);
+        assert_eq!(queue.content.len(), 1);
+
+        let now = Instant::now();
+        for _ in 0..2 {
+            let batch = queue.next_batch(now).unwrap();
+            assert_eq!(batch.keys.len(), 1);
+            queue.complete(
+                batch.enr.node_id(),
+                batch.keys,
+                OfferOutcome::Answered { accepted: 0 },
+                now,
+            );
+        }
+        assert!(queue.next_batch(now).is_none());
+        assert!(queue.content.is_empty());
+    }
+
+    #[test]
+    fn gossip_queue_drops_farthest_content_when_full() {
+        let mut queue = build_queue(GossipConfig {
+            max_queued_keys_per_peer: 2,
+            ..Default::default()
+        });
+        let (_, enr) = generate_random_remote_enr();
+        let mut content_ids = push_random_content(&mut queue, &enr, 3);
+        content_ids.sort_by_key(|content_id| XorMetric::distance(content_id, &enr.node_id().raw()));
+
+        let batch = queue.next_batch(Instant::now()).unwrap();
+        let batch_ids: Vec<[u8; 32]> = batch.keys.iter().map(|key| key.content_id).collect();
+        assert_eq!(batch_ids, content_ids[..2]);
+        assert_eq!(queue.content.len(), 2);
+    }
 
     #[rstest]
     #[case(vec![generate_random_remote_enr().1; 0], 0)]
@@ -304,7 +1224,143 @@ mod tests {
         #[case] all_nodes: Vec<Enr>,
         #[case] expected_size: usize,
     ) {
-        let gossip_recipients = select_gossip_recipients(all_nodes);
+        let gossip_recipients =
+            select_gossip_recipients(all_nodes, GossipStrategy::Random, &mut thread_rng());
         assert_eq!(gossip_recipients.len(), expected_size);
     }
+
+    #[rstest]
+    #[case(GossipStrategy::Closest(1), 10, 1)]
+    #[case(GossipStrategy::Closest(16), 10, 10)]
+    #[case(GossipStrategy::Hybrid(8), 10, 8)]
+    #[case(GossipStrategy::Hybrid(8), 0, 0)]
+    fn test_select_gossip_recipients_closest(
+        #[case] strategy: GossipStrategy,
+        #[case] num_nodes: usize,
+        #[case] expected_size: usize,
+    ) {
+        let all_nodes: Vec<Enr> = (0..num_nodes)
+            .map(|_| generate_random_remote_enr().1)
+            .collect();
+        let gossip_recipients =
+            select_gossip_recipients(all_nodes.clone(), strategy, &mut thread_rng());
+        assert_eq!(gossip_recipients, all_nodes[..expected_size]);
+    }
+
+    #[test]
+    fn test_select_closest_enrs() {
+        let content_id = IdentityContentKey::random().content_id();
+        let mut enrs: Vec<Enr> = (0..6).map(|_| generate_random_remote_enr().1).collect();
+        enrs.sort_by_key(|enr| XorMetric::distance(&content_id, &enr.node_id().raw()));
+
+        let known = vec![enrs[1].clone(), enrs[4].clone()];
+        // Found peers overlap with the known peers.
+        let found = vec![
+            enrs[5].clone(),
+            enrs[4].clone(),
+            enrs[0].clone(),
+            enrs[2].clone(),
+        ];
+        let selected = select_closest_enrs(&content_id, known, found, 4);
+        assert_eq!(
+            selected,
+            enrs[..3]
+                .iter()
+                .chain([&enrs[4]])
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn lookup_queue_merges_neighborhoods() {
+        let lookup = |first_byte: u8| {
+            let mut content_id = [0; 32];
+            content_id[0] = first_byte;
+            content_id[31] = rand::random();
+            GossipLookup {
+                content_key: IdentityContentKey::new(content_id),
+                content_value: Arc::new(vec![]),
+                known: vec![],
+                num_peers: 4,
+            }
+        };
+        let mut lookups = LookupQueue::new();
+        for first_byte in 0..MAX_CONCURRENT_LOOKUPS as u8 + 2 {
+            lookups.push(lookup(first_byte));
+            lookups.push(lookup(first_byte));
+        }
+
+        // Only the maximum number of queries run at once, one per neighborhood.
+        let started: Vec<Neighborhood> =
+            std::iter::from_fn(|| lookups.next_query().map(|(neighborhood, _)| neighborhood))
+                .collect();
+        assert_eq!(
+            started,
+            (0..MAX_CONCURRENT_LOOKUPS as u8).collect::<Vec<_>>()
+        );
+
+        // Content from a neighborhood with a running query is added to it.
+        lookups.push(lookup(0));
+        assert_eq!(lookups.complete(0).len(), 3);
+
+        let (neighborhood, _) = lookups.next_query().unwrap();
+        assert_eq!(neighborhood, MAX_CONCURRENT_LOOKUPS as u8);
+        assert!(lookups.next_query().is_none());
+        assert_eq!(lookups.complete(neighborhood).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn trace_gossip_honors_closest_strategy() {
+        let overlay_config = OverlayConfig::default();
+        let mut kbuckets = KBucketsTable::new(
+            NodeId::random().into(),
+            overlay_config.bucket_pending_timeout,
+            overlay_config.max_incoming_per_bucket,
+            overlay_config.table_filter,
+            overlay_config.bucket_filter,
+        );
+        let mut enrs: Vec<Enr> = (0..10).map(|_| generate_random_remote_enr().1).collect();
+        for enr in enrs.iter() {
+            let status = NodeStatus {
+                state: ConnectionState::Connected,
+                direction: ConnectionDirection::Outgoing,
+            };
+            let _ = kbuckets.insert_or_update(
+                &kbucket::Key::from(enr.node_id()),
+                Node::new(enr.clone(), Distance::MAX),
+                status,
+            );
+        }
+        let kbuckets = Arc::new(RwLock::new(kbuckets));
+        let connected: Vec<NodeId> = connected_nodes(&kbuckets.read())
+            .iter()
+            .map(|node| *node.key.preimage())
+            .collect();
+        enrs.retain(|enr| connected.contains(&enr.node_id()));
+
+        let (gossip_handle, mut gossip_rx) = GossipHandle::new(
+            Arc::clone(&kbuckets),
+            GossipStrategy::Closest(2),
+            StdRng::from_entropy(),
+        );
+        let content_key = IdentityContentKey::random();
+        let content_id = content_key.content_id();
+        let trace =
+            tokio::spawn(async move { gossip_handle.trace_gossip(content_key, vec![0xef]).await });
+
+        let Some(GossipMessage::Trace(trace_message)) = gossip_rx.recv().await else {
+            panic!("Expected traced gossip");
+        };
+        enrs.sort_by_key(|enr| XorMetric::distance(&content_id, &enr.node_id().raw()));
+        assert_eq!(trace_message.enrs, enrs[..2]);
+
+        let result = GossipResult {
+            offered: trace_message.enrs.clone(),
+            accepted: trace_message.enrs.clone(),
+            transferred: trace_message.enrs[..1].to_vec(),
+        };
+        trace_message.result_tx.send(result.clone()).unwrap();
+        assert_eq!(trace.await.unwrap(), result);
+    }
 }
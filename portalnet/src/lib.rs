@@ -4,11 +4,17 @@
 pub mod accept_queue;
 pub mod config;
 pub mod discovery;
+pub mod enr_tree;
 pub mod events;
 pub mod find;
 pub mod gossip;
 pub mod overlay;
+pub mod peer_stats;
+#[cfg(feature = "test-utils")]
+pub mod simulation;
 pub mod socket;
+pub mod transport;
 pub mod types;
 pub mod utils;
 pub mod utp_controller;
+pub mod utp_transfer_limit;
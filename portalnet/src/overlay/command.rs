@@ -4,7 +4,7 @@ use tokio::sync::broadcast;
 
 use super::request::OverlayRequest;
 use crate::{events::EventEnvelope, find::query_info::RecursiveFindContentResult};
-use ethportal_api::types::enr::Enr;
+use ethportal_api::types::{enr::Enr, query_tuning::QueryTuning};
 
 /// A network-based action that the overlay may perform.
 ///
@@ -39,4 +39,13 @@ pub enum OverlayCommand<TContentKey> {
     RequestEventStream(oneshot::Sender<broadcast::Receiver<EventEnvelope>>),
     /// Handle an event sent from another overlay.
     Event(EventEnvelope),
+    /// Updates the query parameters that are set in `tuning`.
+    SetQueryTuning {
+        tuning: QueryTuning,
+        /// A callback channel to transmit the resulting query parameters.
+        callback: oneshot::Sender<QueryTuning>,
+    },
+    /// Adds nodes that were learned after startup (e.g. from a refreshed ENR tree) to the routing
+    /// table as bootnodes.
+    AddBootnodes(Vec<Enr>),
 }
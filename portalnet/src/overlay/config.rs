@@ -4,8 +4,17 @@ use std::time::Duration;
 
 use discv5::kbucket::{Filter, MAX_NODES_PER_BUCKET};
 
-use crate::types::node::Node;
-use ethportal_api::types::{cli::DEFAULT_UTP_TRANSFER_LIMIT, enr::Enr};
+use crate::{
+    accept_queue::DEFAULT_ACCEPT_QUEUE_TIMEOUT,
+    gossip::GossipConfig,
+    types::{filter::SubnetLimitFilter, node::Node},
+    utp_controller::UtpStallConfig,
+    utp_transfer_limit::AdaptiveTransferLimitConfig,
+};
+use ethportal_api::types::{
+    cli::DEFAULT_UTP_TRANSFER_LIMIT, config_file::OverlayTuning, enr::Enr,
+    query_tuning::QueryTuning, utp_tuning::UtpConnectionTuning,
+};
 
 /// Configuration parameters for the overlay network.
 #[derive(Clone)]
@@ -23,6 +32,22 @@ pub struct OverlayConfig {
     pub findnodes_query_distances_per_peer: usize,
     pub disable_poke: bool,
     pub utp_transfer_limit: usize,
+    /// The bounds of the uTP transfer limit, if it adapts to the health of the transfers.
+    pub utp_adaptive_transfer_limit: Option<AdaptiveTransferLimitConfig>,
+    /// The detection of stalled uTP streams and the limit of concurrent uTP streams per peer.
+    pub utp_stall_config: UtpStallConfig,
+    /// The tuning of the uTP connections.
+    pub utp_conn_tuning: UtpConnectionTuning,
+    /// The capabilities advertised in the ping extensions (e.g. the enabled subnetworks).
+    pub ping_capabilities: Vec<String>,
+    /// The rate limits and retries of the gossip scheduler.
+    pub gossip: GossipConfig,
+    /// The time an accepted content key is kept in the accept queue, to collect fallback peers,
+    /// before it expires.
+    pub accept_queue_timeout: Duration,
+    /// The seed of the random choices of the overlay (e.g. the gossip peers, the refreshed
+    /// buckets and the request ids). If `None`, the overlay is seeded from the OS entropy.
+    pub rng_seed: Option<u64>,
 }
 
 impl Default for OverlayConfig {
@@ -41,6 +66,99 @@ impl Default for OverlayConfig {
             findnodes_query_distances_per_peer: 3,
             disable_poke: false,
             utp_transfer_limit: DEFAULT_UTP_TRANSFER_LIMIT,
+            utp_adaptive_transfer_limit: None,
+            utp_stall_config: UtpStallConfig::default(),
+            utp_conn_tuning: UtpConnectionTuning::default(),
+            ping_capabilities: vec![],
+            gossip: GossipConfig::default(),
+            accept_queue_timeout: DEFAULT_ACCEPT_QUEUE_TIMEOUT,
+            rng_seed: None,
         }
     }
 }
+
+impl OverlayConfig {
+    /// Overrides the defaults with the values set in the `[overlay]` table of the config file.
+    pub fn with_tuning(mut self, tuning: &OverlayTuning) -> Self {
+        if let Some(timeout) = tuning.bucket_pending_timeout_ms {
+            self.bucket_pending_timeout = Duration::from_millis(timeout);
+        }
+        if let Some(max_incoming) = tuning.max_incoming_per_bucket {
+            self.max_incoming_per_bucket = max_incoming;
+        }
+        if let Some(interval) = tuning.ping_queue_interval_ms {
+            self.ping_queue_interval = Some(Duration::from_millis(interval));
+        }
+        if let Some(limit) = tuning.table_subnet_limit {
+            self.table_filter = Some(Box::new(SubnetLimitFilter::new(limit)));
+        }
+        if let Some(limit) = tuning.bucket_subnet_limit {
+            self.bucket_filter = Some(Box::new(SubnetLimitFilter::new(limit)));
+        }
+        self.with_query_tuning(&tuning.common_query_tuning())
+    }
+
+    /// Overrides the query parameters with the values set in the query tuning.
+    pub fn with_query_tuning(mut self, tuning: &QueryTuning) -> Self {
+        if let Some(parallelism) = tuning.query_parallelism {
+            self.query_parallelism = parallelism;
+        }
+        if let Some(timeout) = tuning.query_timeout_ms {
+            self.query_timeout = Duration::from_millis(timeout);
+        }
+        if let Some(timeout) = tuning.query_peer_timeout_ms {
+            self.query_peer_timeout = Duration::from_millis(timeout);
+        }
+        if let Some(num_results) = tuning.query_num_results {
+            self.query_num_results = num_results;
+        }
+        if let Some(distances) = tuning.findnodes_query_distances_per_peer {
+            self.findnodes_query_distances_per_peer = distances;
+        }
+        self
+    }
+
+    /// Returns the tuning, in the format of the `[overlay]` table of the config file. The subnet
+    /// limits are omitted, since they can't be read from the filters.
+    pub fn tuning(&self) -> OverlayTuning {
+        OverlayTuning {
+            bucket_pending_timeout_ms: Some(self.bucket_pending_timeout.as_millis() as u64),
+            max_incoming_per_bucket: Some(self.max_incoming_per_bucket),
+            ping_queue_interval_ms: self
+                .ping_queue_interval
+                .map(|interval| interval.as_millis() as u64),
+            query_parallelism: Some(self.query_parallelism),
+            query_timeout_ms: Some(self.query_timeout.as_millis() as u64),
+            query_peer_timeout_ms: Some(self.query_peer_timeout.as_millis() as u64),
+            query_num_results: Some(self.query_num_results),
+            findnodes_query_distances_per_peer: Some(self.findnodes_query_distances_per_peer),
+            table_subnet_limit: None,
+            bucket_subnet_limit: None,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_tuning() {
+        let config = OverlayConfig::default().with_tuning(&OverlayTuning {
+            query_parallelism: Some(5),
+            query_timeout_ms: Some(30_000),
+            bucket_subnet_limit: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(config.query_parallelism, 5);
+        assert_eq!(config.query_timeout, Duration::from_secs(30));
+        assert!(config.bucket_filter.is_some());
+
+        // Values that are not set keep the defaults.
+        let default_config = OverlayConfig::default();
+        assert_eq!(config.query_peer_timeout, default_config.query_peer_timeout);
+        assert_eq!(config.query_num_results, default_config.query_num_results);
+        assert!(config.table_filter.is_none());
+    }
+}
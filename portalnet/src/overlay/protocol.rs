@@ -6,45 +6,50 @@ use std::{
     future::Future,
     marker::{PhantomData, Sync},
     sync::Arc,
+    time::UNIX_EPOCH,
 };
 
 use anyhow::anyhow;
 use discv5::{
     enr::NodeId,
     kbucket::{Entry, FailureReason, InsertResult, KBucketsTable, Key, NodeStatus},
+    rpc::RequestId,
     ConnectionDirection, ConnectionState, TalkRequest,
 };
 use futures::channel::oneshot;
 use parking_lot::RwLock;
-use ssz::Encode;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use tokio::sync::{broadcast, mpsc::UnboundedSender};
 use tracing::{debug, error, info, warn};
 use utp_rs::socket::UtpSocket;
 
 use crate::{
-    discovery::{Discovery, UtpEnr},
+    discovery::UtpEnr,
     find::query_info::{FindContentResult, RecursiveFindContentResult},
-    gossip::{propagate_gossip_cross_thread, trace_propagate_gossip_cross_thread, GossipResult},
+    gossip::{GossipHandle, GossipResult, GossipScheduler},
     overlay::{
         command::OverlayCommand,
         config::OverlayConfig,
         errors::OverlayRequestError,
         request::{OverlayRequest, RequestDirection},
-        service::OverlayService,
+        service::{build_ping_payload, OverlayService},
     },
+    peer_stats::PeerStatsTable,
+    transport::TalkReqTransport,
     types::node::Node,
     utp_controller::UtpController,
 };
 use ethportal_api::{
     types::{
         bootnodes::Bootnode,
-        discv5::RoutingTableInfo,
+        discv5::{RoutingTableDetail, RoutingTableInfo, RoutingTableNodeDetail},
         distance::{Distance, Metric},
         enr::Enr,
         portal_wire::{
-            Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, Offer, Ping,
-            Pong, PopulatedOffer, ProtocolId, Request, Response,
+            Accept, Content, FindContent, FindNodes, Message, Nodes, Offer, Ping, Pong,
+            PopulatedOffer, ProtocolId, Request, Response,
         },
+        query_tuning::QueryTuning,
     },
     utils::bytes::hex_encode,
     OverlayContentKey, RawContentKey,
@@ -63,8 +68,8 @@ type BucketEntry = (NodeId, Enr, NodeStatus, Distance, Option<String>);
 /// for handling common network requests/responses.
 #[derive(Clone)]
 pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
-    /// Reference to the underlying discv5 protocol
-    pub discovery: Arc<Discovery>,
+    /// Reference to the underlying discv5 protocol, or another talk request transport
+    pub discovery: Arc<dyn TalkReqTransport>,
     /// The data store.
     pub store: Arc<RwLock<TStore>>,
     /// The overlay routing table of the local node.
@@ -85,6 +90,12 @@ pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     validator: Arc<TValidator>,
     /// Runtime telemetry metrics for the overlay network.
     metrics: OverlayMetricsReporter,
+    /// The capabilities advertised in the ping extensions.
+    ping_capabilities: Vec<String>,
+    /// The activity observed for the peers of the overlay.
+    peer_stats: Arc<RwLock<PeerStatsTable>>,
+    /// Schedules the content to gossip.
+    gossip_handle: GossipHandle<TContentKey>,
 }
 
 impl<
@@ -98,7 +109,7 @@ where
 {
     pub async fn new(
         config: OverlayConfig,
-        discovery: Arc<Discovery>,
+        discovery: Arc<dyn TalkReqTransport>,
         utp_socket: Arc<UtpSocket<UtpEnr>>,
         store: Arc<RwLock<TStore>>,
         protocol: ProtocolId,
@@ -111,6 +122,7 @@ where
             config.table_filter,
             config.bucket_filter,
         )));
+        let peer_stats = Arc::new(RwLock::new(PeerStatsTable::default()));
         // Initialize metrics, keep a reference in order to build metrics summaries for logging
         let metrics = OverlayMetricsReporter {
             overlay_metrics: PORTALNET_METRICS.overlay(),
@@ -118,13 +130,28 @@ where
         };
         let utp_controller = Arc::new(UtpController::new(
             config.utp_transfer_limit,
+            config.utp_adaptive_transfer_limit,
+            config.utp_stall_config,
+            config.utp_conn_tuning,
             utp_socket,
+            Arc::clone(&peer_stats),
             metrics.clone(),
         ));
+        // The random choices of the overlay tasks are all derived from a single seed.
+        let mut rng = match config.rng_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let (gossip_handle, gossip_rx) = GossipHandle::new(
+            Arc::clone(&kbuckets),
+            config.gossip.strategy,
+            StdRng::seed_from_u64(rng.gen()),
+        );
         let command_tx = OverlayService::<TContentKey, TMetric, TValidator, TStore>::spawn(
             Arc::clone(&discovery),
             Arc::clone(&store),
             Arc::clone(&kbuckets),
+            Arc::clone(&peer_stats),
             config.bootnode_enrs,
             config.ping_queue_interval,
             protocol,
@@ -137,8 +164,23 @@ where
             config.query_num_results,
             config.findnodes_query_distances_per_peer,
             config.disable_poke,
+            config.ping_capabilities.clone(),
+            gossip_handle.clone(),
+            config.accept_queue_timeout,
+            rng,
         )
         .await;
+        if let Some(bootnode_updates) = discovery.bootnode_updates() {
+            Self::forward_bootnode_updates(bootnode_updates, command_tx.clone());
+        }
+        GossipScheduler::spawn(
+            config.gossip,
+            gossip_rx,
+            Arc::clone(&kbuckets),
+            command_tx.clone(),
+            Arc::clone(&utp_controller),
+            metrics.clone(),
+        );
 
         Self {
             discovery,
@@ -151,9 +193,35 @@ where
             phantom_metric: PhantomData,
             validator,
             metrics,
+            ping_capabilities: config.ping_capabilities,
+            peer_stats,
+            gossip_handle,
         }
     }
 
+    /// Forwards the bootnodes that are learned after startup to the overlay service, which adds
+    /// them to the routing table.
+    fn forward_bootnode_updates(
+        mut bootnode_updates: broadcast::Receiver<Vec<Enr>>,
+        command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                match bootnode_updates.recv().await {
+                    Ok(enrs) => {
+                        if command_tx.send(OverlayCommand::AddBootnodes(enrs)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Skipped bootnode updates");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
     /// Returns the subnetwork protocol of the overlay protocol.
     pub fn protocol(&self) -> &ProtocolId {
         &self.protocol
@@ -174,17 +242,30 @@ where
         &self,
         talk_request: &TalkRequest,
     ) -> Result<Response, OverlayRequestError> {
-        let request = match Message::try_from(Vec::<u8>::from(talk_request.body())) {
+        self.process_one_raw_request(
+            *talk_request.node_id(),
+            talk_request.id().clone(),
+            talk_request.body(),
+        )
+        .await
+    }
+
+    /// Processes the body of a single TALKREQ message from `source`, received over any
+    /// [`TalkReqTransport`].
+    pub async fn process_one_raw_request(
+        &self,
+        source: NodeId,
+        id: RequestId,
+        body: &[u8],
+    ) -> Result<Response, OverlayRequestError> {
+        let request = match Message::try_from(body.to_vec()) {
             Ok(message) => match Request::try_from(message) {
                 Ok(request) => request,
                 Err(err) => return Err(OverlayRequestError::InvalidRequest(err.to_string())),
             },
             Err(_) => return Err(OverlayRequestError::DecodeError),
         };
-        let direction = RequestDirection::Incoming {
-            id: talk_request.id().clone(),
-            source: *talk_request.node_id(),
-        };
+        let direction = RequestDirection::Incoming { id, source };
 
         // Send the request and wait on the response.
         self.send_overlay_request(request, direction).await
@@ -203,10 +284,10 @@ where
         Ok(())
     }
 
-    /// Propagate gossip accepted content via OFFER/ACCEPT, return number of peers propagated
+    /// Schedule gossip of accepted content via OFFER/ACCEPT, return number of peers the content
+    /// was scheduled for
     pub fn propagate_gossip(&self, content: Vec<(TContentKey, Vec<u8>)>) -> usize {
-        let kbuckets = Arc::clone(&self.kbuckets);
-        propagate_gossip_cross_thread(content, kbuckets, self.command_tx.clone(), None)
+        self.gossip_handle.gossip(content)
     }
 
     /// Propagate gossip accepted content via OFFER/ACCEPT, returns trace detailing outcome of
@@ -216,9 +297,18 @@ where
         content_key: TContentKey,
         data: Vec<u8>,
     ) -> GossipResult {
-        let kbuckets = Arc::clone(&self.kbuckets);
-        trace_propagate_gossip_cross_thread(content_key, data, kbuckets, self.command_tx.clone())
-            .await
+        self.gossip_handle.trace_gossip(content_key, data).await
+    }
+
+    /// Offer the content to the peer via OFFER/ACCEPT, returns trace detailing outcome of the
+    /// offer
+    pub async fn send_offer_trace(
+        &self,
+        enr: Enr,
+        content_key: TContentKey,
+        data: Vec<u8>,
+    ) -> GossipResult {
+        self.gossip_handle.trace_offer(enr, content_key, data).await
     }
 
     /// Returns a vector of all ENR node IDs of nodes currently contained in the routing table.
@@ -241,9 +331,66 @@ where
 
     /// Returns the node-id and a nested array of node-ids to represent this node's k-buckets table.
     pub fn routing_table_info(&self) -> RoutingTableInfo {
+        let kbuckets = self.kbuckets.read().clone();
+        let ping_extensions = kbuckets
+            .buckets_iter()
+            .flat_map(|bucket| bucket.iter())
+            .filter_map(|node| {
+                node.value.ping_extensions().map(|ping_extensions| {
+                    (hex_encode(*node.key.preimage()), ping_extensions.clone())
+                })
+            })
+            .collect();
         RoutingTableInfo {
             local_node_id: hex_encode(self.local_enr().node_id().raw()),
-            buckets: self.kbuckets.read().clone().into(),
+            buckets: kbuckets.into(),
+            ping_extensions,
+        }
+    }
+
+    /// Returns the details of each node in the routing table, together with the activity observed
+    /// for the node.
+    pub fn routing_table_detail(&self) -> RoutingTableDetail {
+        let peer_stats = self.peer_stats.read();
+        let nodes = self
+            .kbuckets
+            .read()
+            .buckets_iter()
+            .enumerate()
+            .flat_map(|(bucket_index, bucket)| {
+                bucket
+                    .iter()
+                    .map(|node| {
+                        let node_id = *node.key.preimage();
+                        let stats = peer_stats.get(&node_id).unwrap_or_default();
+                        RoutingTableNodeDetail {
+                            node_id: hex_encode(node_id),
+                            enr: node.value.enr(),
+                            bucket_index,
+                            data_radius: *node.value.data_radius(),
+                            connection_state: node.status.state.into(),
+                            connection_direction: node.status.direction.into(),
+                            client: UtpEnr(node.value.enr()).client(),
+                            last_seen: stats.last_seen.and_then(|last_seen| {
+                                last_seen
+                                    .duration_since(UNIX_EPOCH)
+                                    .ok()
+                                    .map(|last_seen| last_seen.as_secs())
+                            }),
+                            last_ping_rtt_ms: stats.last_ping_rtt.map(|rtt| rtt.as_millis() as u64),
+                            requests_sent: stats.requests_sent,
+                            responses_received: stats.responses_received,
+                            requests_failed: stats.requests_failed,
+                            requests_received: stats.requests_received,
+                            utp_stalls: stats.utp_stalls,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        RoutingTableDetail {
+            local_node_id: hex_encode(self.local_enr().node_id().raw()),
+            nodes,
         }
     }
 
@@ -294,10 +441,7 @@ where
         let key = Key::from(enr.node_id());
         match self.kbuckets.write().insert_or_update(
             &key,
-            Node {
-                enr,
-                data_radius: Distance::MAX,
-            },
+            Node::new(enr, Distance::MAX),
             NodeStatus {
                 state: ConnectionState::Connected,
                 direction: ConnectionDirection::Incoming,
@@ -385,8 +529,12 @@ where
     pub async fn send_ping(&self, enr: Enr) -> Result<Pong, OverlayRequestError> {
         // Construct the request.
         let enr_seq = self.discovery.local_enr().seq();
-        let data_radius = self.data_radius();
-        let custom_payload = CustomPayload::from(data_radius.as_ssz_bytes());
+        let custom_payload = build_ping_payload(
+            &self.store,
+            &self.local_enr(),
+            &self.ping_capabilities,
+            UtpEnr(enr.clone()).supports_ping_extensions(),
+        );
         let request = Ping {
             enr_seq,
             custom_payload,
@@ -611,6 +759,21 @@ where
         })
     }
 
+    /// Updates the query parameters that are set in `tuning`, and returns the resulting query
+    /// parameters.
+    pub async fn set_query_tuning(&self, tuning: QueryTuning) -> anyhow::Result<QueryTuning> {
+        tuning.validate()?;
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send(OverlayCommand::SetQueryTuning {
+                tuning,
+                callback: tx,
+            })
+            .map_err(|err| anyhow!("Error submitting query tuning to service: {err}"))?;
+        rx.await
+            .map_err(|err| anyhow!("Error receiving query tuning from service: {err}"))
+    }
+
     /// Performs a content lookup for `target`.
     /// Returns the target content along with the peers traversed during content lookup.
     pub async fn lookup_content(
@@ -728,6 +891,14 @@ where
         self.metrics.get_utp_summary()
     }
 
+    pub fn get_gossip_summary(&self) -> String {
+        self.metrics.get_gossip_summary()
+    }
+
+    pub fn get_accept_queue_summary(&self) -> String {
+        self.metrics.get_accept_queue_summary()
+    }
+
     /// Creates an event stream channel which can be polled to receive overlay events.
     pub fn event_stream(
         &self,
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Instant};
 
 use discv5::{enr::NodeId, rpc::RequestId};
 use futures::channel::oneshot;
@@ -22,7 +22,8 @@ pub enum RequestDirection {
 
 /// An identifier for an overlay network request. The ID is used to track active outgoing requests.
 // We only have visibility on the request IDs for incoming Discovery v5 talk requests. Here we use
-// a separate identifier to track outgoing talk requests.
+// a separate identifier to track outgoing talk requests. The overlay service assigns it when the
+// request is sent.
 pub type OverlayRequestId = u128;
 
 /// An overlay request response channel.
@@ -31,8 +32,6 @@ type OverlayResponder = oneshot::Sender<Result<Response, OverlayRequestError>>;
 /// A request to pass through the overlay.
 #[derive(Debug)]
 pub struct OverlayRequest {
-    /// The request identifier.
-    pub id: OverlayRequestId,
     /// The inner request.
     pub request: Request,
     /// The direction of the request.
@@ -57,7 +56,6 @@ impl OverlayRequest {
         request_permit: Option<OwnedSemaphorePermit>,
     ) -> Self {
         OverlayRequest {
-            id: rand::random(),
             request,
             direction,
             responder,
@@ -78,6 +76,8 @@ pub struct ActiveOutgoingRequest {
     pub query_id: Option<QueryId>,
     /// An optional permit to allow for transfer caps
     pub request_permit: Option<OwnedSemaphorePermit>,
+    /// The time when the request was sent.
+    pub sent_at: Instant,
 }
 
 /// A response for a particular overlay request.
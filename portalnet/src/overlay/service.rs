@@ -4,7 +4,7 @@ use std::{
     marker::{PhantomData, Sync},
     sync::Arc,
     task::Poll,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
@@ -21,7 +21,7 @@ use discv5::{
 use futures::{channel::oneshot, future::join_all, prelude::*};
 use itertools::Itertools;
 use parking_lot::RwLock;
-use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use smallvec::SmallVec;
 use ssz::Encode;
 use ssz_types::BitList;
@@ -38,7 +38,7 @@ use utp_rs::cid::ConnectionId;
 
 use crate::{
     accept_queue::AcceptQueue,
-    discovery::{Discovery, UtpEnr},
+    discovery::UtpEnr,
     events::{EventEnvelope, OverlayEvent},
     find::{
         iterators::{
@@ -49,7 +49,7 @@ use crate::{
         query_info::{QueryInfo, QueryType, RecursiveFindContentResult},
         query_pool::{QueryId, QueryPool, QueryPoolState, TargetKey},
     },
-    gossip::propagate_gossip_cross_thread,
+    gossip::GossipHandle,
     overlay::{
         command::OverlayCommand,
         errors::OverlayRequestError,
@@ -58,6 +58,8 @@ use crate::{
             RequestDirection,
         },
     },
+    peer_stats::PeerStatsTable,
+    transport::TalkReqTransport,
     types::node::Node,
     utils::portal_wire,
     utp_controller::UtpController,
@@ -67,17 +69,19 @@ use ethportal_api::{
     types::{
         distance::{Distance, Metric},
         enr::{Enr, SszEnr},
+        ping_payload::{PingExtensions, PingPongPayload},
         portal_wire::{
             Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, Offer, Ping,
             Pong, PopulatedOffer, ProtocolId, Request, Response, MAX_PORTAL_CONTENT_PAYLOAD_SIZE,
             MAX_PORTAL_NODES_ENRS_SIZE,
         },
         query_trace::QueryTrace,
+        query_tuning::QueryTuning,
     },
     utils::bytes::hex_encode_compact,
     OverlayContentKey, RawContentKey,
 };
-use trin_metrics::overlay::OverlayMetricsReporter;
+use trin_metrics::{labels::AcceptQueueOutcomeLabel, overlay::OverlayMetricsReporter};
 use trin_storage::{ContentStore, ShouldWeStoreContent};
 use trin_validation::validator::{ValidationResult, Validator};
 
@@ -94,16 +98,23 @@ const EXPECTED_NON_EMPTY_BUCKETS: usize = 17;
 /// Bucket refresh lookup interval in seconds
 const BUCKET_REFRESH_INTERVAL_SECS: u64 = 60;
 
+/// The interval in seconds at which expired content keys are removed from the accept queue.
+const ACCEPT_QUEUE_EXPIRY_INTERVAL_SECS: u64 = 5;
+
 /// The capacity of the event-stream's broadcast channel.
-const EVENT_STREAM_CHANNEL_CAPACITY: usize = 10;
+const EVENT_STREAM_CHANNEL_CAPACITY: usize = 1024;
+
+/// The time after an outbound uTP stream with a peer stalled, during which no content is offered
+/// to the peer.
+const STALLED_PEER_OFFER_PENALTY: Duration = Duration::from_secs(10 * 60);
 
 /// The overlay service.
 pub struct OverlayService<TContentKey, TMetric, TValidator, TStore>
 where
     TContentKey: OverlayContentKey,
 {
-    /// The underlying Discovery v5 protocol.
-    discovery: Arc<Discovery>,
+    /// The underlying Discovery v5 protocol, or another talk request transport.
+    discovery: Arc<dyn TalkReqTransport>,
     /// The content database of the local node.
     store: Arc<RwLock<TStore>>,
     /// The routing table of the local node.
@@ -155,6 +166,15 @@ where
     disable_poke: bool,
     /// Accept Queue for inbound content keys
     accept_queue: Arc<RwLock<AcceptQueue<TContentKey>>>,
+    /// The capabilities advertised in the ping extensions.
+    ping_capabilities: Vec<String>,
+    /// The activity observed for the peers of the overlay.
+    peer_stats: Arc<RwLock<PeerStatsTable>>,
+    /// Schedules the content to gossip.
+    gossip_handle: GossipHandle<TContentKey>,
+    /// The source of the random choices of the service (e.g. the refreshed buckets and the
+    /// request ids).
+    rng: StdRng,
 }
 
 impl<
@@ -173,9 +193,10 @@ where
     /// processes.
     #[allow(clippy::too_many_arguments)]
     pub async fn spawn(
-        discovery: Arc<Discovery>,
+        discovery: Arc<dyn TalkReqTransport>,
         store: Arc<RwLock<TStore>>,
         kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+        peer_stats: Arc<RwLock<PeerStatsTable>>,
         bootnode_enrs: Vec<Enr>,
         ping_queue_interval: Option<Duration>,
         protocol: ProtocolId,
@@ -188,6 +209,10 @@ where
         query_num_results: usize,
         findnodes_query_distances_per_peer: usize,
         disable_poke: bool,
+        ping_capabilities: Vec<String>,
+        gossip_handle: GossipHandle<TContentKey>,
+        accept_queue_timeout: Duration,
+        mut rng: StdRng,
     ) -> UnboundedSender<OverlayCommand<TContentKey>>
     where
         <TContentKey as TryFrom<Vec<u8>>>::Error: Send,
@@ -204,6 +229,12 @@ where
         let (response_tx, response_rx) = mpsc::unbounded_channel();
         let (event_stream, _) = broadcast::channel(EVENT_STREAM_CHANNEL_CAPACITY);
 
+        let accept_queue = AcceptQueue::new(
+            accept_queue_timeout,
+            metrics.clone(),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+
         tokio::spawn(async move {
             let mut service = Self {
                 discovery,
@@ -229,7 +260,11 @@ where
                 validator,
                 event_stream,
                 disable_poke,
-                accept_queue: Arc::new(RwLock::new(AcceptQueue::default())),
+                accept_queue: Arc::new(RwLock::new(accept_queue)),
+                ping_capabilities,
+                peer_stats,
+                gossip_handle,
+                rng,
             };
 
             info!(protocol = %protocol, "Starting overlay service");
@@ -302,7 +337,8 @@ where
         self.init_find_nodes_query(&local_node_id, None);
 
         for bucket_index in (255 - EXPECTED_NON_EMPTY_BUCKETS as u8)..255 {
-            let target_node_id = generate_random_node_id(bucket_index, self.local_enr().into());
+            let target_node_id =
+                generate_random_node_id(&mut self.rng, bucket_index, local_node_id);
             self.init_find_nodes_query(&target_node_id, None);
         }
     }
@@ -320,13 +356,21 @@ where
     /// information relevant to the overlay network.
     ///
     /// Bucket maintenance: Maintain the routing table (more info documented above function).
+    ///
+    /// Accept queue expiry: Remove the content keys that expired from the accept queue, so that
+    /// they can be accepted again.
     async fn start(&mut self) {
         // Construct bucket refresh interval
         let mut bucket_refresh_interval =
             tokio::time::interval(Duration::from_secs(BUCKET_REFRESH_INTERVAL_SECS));
+        let mut accept_queue_expiry_interval =
+            tokio::time::interval(Duration::from_secs(ACCEPT_QUEUE_EXPIRY_INTERVAL_SECS));
 
         loop {
             tokio::select! {
+                // The branches are polled in order, rather than randomly, so that the same
+                // sequence of events is always handled in the same way.
+                biased;
                 Some(command) = self.command_rx.recv() => {
                     match command {
                         OverlayCommand::Request(request) => self.process_request(request),
@@ -355,6 +399,25 @@ where
                                 error!("Failed to return the event stream channel");
                             }
                         }
+                        OverlayCommand::SetQueryTuning { tuning, callback } => {
+                            if callback.send(self.set_query_tuning(tuning)).is_err() {
+                                error!("Failed to return the query tuning");
+                            }
+                        }
+                        OverlayCommand::AddBootnodes(enrs) => {
+                            // The nodes that are already in the routing table keep their status.
+                            let enrs = enrs
+                                .into_iter()
+                                .filter(|enr| {
+                                    let key = kbucket::Key::from(enr.node_id());
+                                    matches!(
+                                        self.kbuckets.write().entry(&key),
+                                        kbucket::Entry::Absent(_)
+                                    )
+                                })
+                                .collect();
+                            self.add_bootnodes(enrs, false);
+                        }
                     }
                 }
                 Some(response) = self.response_rx.recv() => {
@@ -368,12 +431,18 @@ where
                         }
 
                         // Perform background processing.
+                        let node_id = request.destination.node_id();
                         match response.response {
                             Ok(response) => {
                                 self.metrics.report_inbound_response(&response);
+                                let ping_rtt = matches!(response, Response::Pong(_)).then(|| request.sent_at.elapsed());
+                                self.peer_stats.write().record_response_received(node_id, ping_rtt);
                                 self.process_response(response, request.destination, request.request, request.query_id, request.request_permit)
                             }
-                            Err(error) => self.process_request_failure(response.request_id, request.destination, error),
+                            Err(error) => {
+                                self.peer_stats.write().record_request_failed(node_id);
+                                self.process_request_failure(response.request_id, request.destination, error)
+                            }
                         }
 
                     } else {
@@ -400,6 +469,9 @@ where
                     trace!(protocol = %self.protocol, "Routing table bucket refresh");
                     self.bucket_refresh_lookup();
                 }
+                _ = accept_queue_expiry_interval.tick() => {
+                    self.accept_queue.write().remove_expired_keys();
+                }
             }
         }
     }
@@ -409,18 +481,19 @@ where
         // Look at local routing table and select the largest 17 buckets.
         // We only need the 17 bits furthest from our own node ID, because the closest 239 bits of
         // buckets are going to be empty-ish.
+        let local_node_id = self.local_enr().node_id();
         let target_node_id = {
             let buckets = self.kbuckets.read();
             let buckets = buckets.buckets_iter().enumerate().collect::<Vec<_>>();
             let buckets = &buckets[256 - EXPECTED_NON_EMPTY_BUCKETS..];
 
             // Randomly pick one of these buckets.
-            let target_bucket = buckets.choose(&mut rand::thread_rng());
+            let target_bucket = buckets.choose(&mut self.rng);
             match target_bucket {
                 Some(bucket) => {
                     trace!(protocol = %self.protocol, bucket = %bucket.0, "Refreshing routing table bucket");
                     match u8::try_from(bucket.0) {
-                        Ok(idx) => generate_random_node_id(idx, self.local_enr().into()),
+                        Ok(idx) => generate_random_node_id(&mut self.rng, idx, local_node_id),
                         Err(err) => {
                             error!(error = %err, "Error downcasting bucket index");
                             return;
@@ -447,6 +520,18 @@ where
         self.store.read().radius()
     }
 
+    /// Returns the custom payload for Ping and Pong messages.
+    ///
+    /// The extensions should only be included if the remote node supports them.
+    fn ping_payload(&self, include_extensions: bool) -> CustomPayload {
+        build_ping_payload(
+            &self.store,
+            &self.local_enr(),
+            &self.ping_capabilities,
+            include_extensions,
+        )
+    }
+
     /// Maintains the routing table.
     ///
     /// Consumes previously applied pending entries from the `KBucketsTable`. An `AppliedPending`
@@ -795,6 +880,51 @@ where
         }
     }
 
+    /// Updates the query parameters that are set in the tuning, and returns the resulting query
+    /// tuning. A new query timeout also applies to the queries in progress.
+    fn set_query_tuning(&mut self, tuning: QueryTuning) -> QueryTuning {
+        if let Some(parallelism) = tuning.query_parallelism {
+            self.query_parallelism = parallelism;
+        }
+        if let Some(timeout) = tuning.query_timeout_ms {
+            let timeout = Duration::from_millis(timeout);
+            self.find_node_query_pool.write().set_query_timeout(timeout);
+            self.find_content_query_pool
+                .write()
+                .set_query_timeout(timeout);
+        }
+        if let Some(timeout) = tuning.query_peer_timeout_ms {
+            self.query_peer_timeout = Duration::from_millis(timeout);
+        }
+        if let Some(num_results) = tuning.query_num_results {
+            self.query_num_results = num_results;
+        }
+        if let Some(distances) = tuning.findnodes_query_distances_per_peer {
+            self.findnodes_query_distances_per_peer = distances;
+        }
+        info!(
+            protocol = %self.protocol,
+            parallelism = self.query_parallelism,
+            peer_timeout = ?self.query_peer_timeout,
+            num_results = self.query_num_results,
+            "Updated query tuning"
+        );
+        self.query_tuning()
+    }
+
+    /// Returns the current query parameters.
+    fn query_tuning(&self) -> QueryTuning {
+        QueryTuning {
+            query_parallelism: Some(self.query_parallelism),
+            query_timeout_ms: Some(
+                self.find_node_query_pool.read().query_timeout().as_millis() as u64
+            ),
+            query_peer_timeout_ms: Some(self.query_peer_timeout.as_millis() as u64),
+            query_num_results: Some(self.query_num_results),
+            findnodes_query_distances_per_peer: Some(self.findnodes_query_distances_per_peer),
+        }
+    }
+
     /// Processes an overlay request.
     fn process_request(&mut self, request: OverlayRequest) {
         // For incoming requests, handle the request, possibly send the response over the channel,
@@ -805,6 +935,7 @@ where
         // channel if the request was initiated internally (e.g. for maintenance).
         match request.direction {
             RequestDirection::Incoming { id, source } => {
+                self.peer_stats.write().record_request_received(source);
                 self.register_node_activity(source);
 
                 let response = self.handle_request(request.request.clone(), id.clone(), &source);
@@ -819,18 +950,49 @@ where
                 self.process_incoming_request(request.request, id, source);
             }
             RequestDirection::Outgoing { destination } => {
+                // Don't offer content to peers whose uTP streams recently stalled, since the
+                // transfer of the content is likely to stall again and take up a permit in the
+                // meantime.
+                if matches!(
+                    request.request,
+                    Request::PopulatedOffer(_) | Request::PopulatedOfferWithResult(_)
+                ) && self
+                    .peer_stats
+                    .read()
+                    .has_recent_utp_stall(&destination.node_id(), STALLED_PEER_OFFER_PENALTY)
+                {
+                    debug!(
+                        protocol = %self.protocol,
+                        request.dest = %destination.node_id(),
+                        "Not offering content to peer with recently stalled uTP stream",
+                    );
+                    if let Request::PopulatedOfferWithResult(offer) = &request.request {
+                        let _ = offer.result_tx.send(false);
+                    }
+                    if let Some(responder) = request.responder {
+                        let _ = responder.send(Err(OverlayRequestError::OfferError(
+                            "uTP stream with peer recently stalled".to_owned(),
+                        )));
+                    }
+                    return;
+                }
+                let request_id: OverlayRequestId = self.rng.gen();
                 self.active_outgoing_requests.write().insert(
-                    request.id,
+                    request_id,
                     ActiveOutgoingRequest {
                         destination: destination.clone(),
                         responder: request.responder,
                         request: request.request.clone(),
                         query_id: request.query_id,
                         request_permit: request.request_permit,
+                        sent_at: Instant::now(),
                     },
                 );
                 self.metrics.report_outbound_request(&request.request);
-                self.send_talk_req(request.request, request.id, destination);
+                self.peer_stats
+                    .write()
+                    .record_request_sent(destination.node_id());
+                self.send_talk_req(request.request, request_id, destination);
             }
         }
     }
@@ -876,9 +1038,17 @@ where
             request
         );
 
+        // Only reply with the extensions to nodes that sent them, as other nodes might not be
+        // able to parse them.
+        let include_extensions = matches!(
+            PingPongPayload::try_from(&request.custom_payload),
+            Ok(PingPongPayload {
+                extensions: Some(_),
+                ..
+            })
+        );
         let enr_seq = self.local_enr().seq();
-        let data_radius = self.data_radius();
-        let custom_payload = CustomPayload::from(data_radius.as_ssz_bytes());
+        let custom_payload = self.ping_payload(include_extensions);
         Pong {
             enr_seq,
             custom_payload,
@@ -1036,6 +1206,7 @@ where
             })?;
 
         let mut accepted_keys: Vec<TContentKey> = Vec::default();
+        let mut accepted = Vec::with_capacity(content_keys.len());
 
         // if we're unable to find the ENR for the source node we throw an error
         // since the enr is required for the accept queue, and it is expected to be present
@@ -1071,8 +1242,18 @@ where
                     "Unable to set requested keys bits: {err:?}"
                 ))
             })?;
+            accepted.push(accept);
         }
 
+        self.send_event(
+            OverlayEvent::OfferReceived {
+                peer: *source,
+                content_keys: content_keys.iter().map(|key| key.to_bytes()).collect(),
+                accepted,
+            },
+            Some(vec![]),
+        );
+
         // If no content keys were accepted, then return an Accept with a connection ID value of
         // zero.
         if requested_keys.is_zero() {
@@ -1132,10 +1313,8 @@ where
                         .map(|content_key| {
                             let utp_processing = utp_processing.clone();
                             tokio::spawn(async move {
-                                // We don't really care about the result from these fallbacks.
-                                // If the fallback FINDCONTENT task fails, that's fine for now.
-                                // In the future, we might want to cycle through all available
-                                // fallback peers on an error.
+                                // The fallback FINDCONTENT task cycles through all available
+                                // fallback peers, and reports the outcome in the metrics.
                                 let _ = Self::fallback_find_content(
                                     content_key.clone(),
                                     utp_processing,
@@ -1300,10 +1479,7 @@ where
             // peer.
             if let Some(node_addr) = self.discovery.cached_node_addr(&source) {
                 // TODO: Decide default data radius, and define a constant.
-                let node = Node {
-                    enr: node_addr.enr,
-                    data_radius: Distance::MAX,
-                };
+                let node = Node::new(node_addr.enr, Distance::MAX);
                 self.connect_node(node, ConnectionDirection::Incoming);
             }
         }
@@ -1321,20 +1497,13 @@ where
 
         // If the node is in the routing table, then check if we need to update the node.
         if let Some(node) = optional_node {
-            // TODO: How do we handle data in the custom payload? This is unique to each overlay
-            // network, so there may need to be some way to parameterize the update for a
-            // ping/pong.
-
             // If the ENR sequence number in pong is less than the ENR sequence number for the
             // routing table entry, then request the node.
             if node.enr().seq() < ping.enr_seq {
                 self.request_node(&node.enr());
             }
 
-            let data_radius: Distance = ping.custom_payload.into();
-            if node.data_radius != data_radius {
-                self.update_node_radius(node.enr(), data_radius);
-            }
+            self.update_node_from_ping_payload(node, &ping.custom_payload);
         }
     }
 
@@ -1555,12 +1724,7 @@ where
             .map(|(k, _)| hex_encode_compact(k.content_id()))
             .collect();
         debug!(ids = ?ids_to_propagate, "propagating validated content");
-        propagate_gossip_cross_thread(
-            content_to_propagate,
-            utp_processing.kbuckets,
-            utp_processing.command_tx.clone(),
-            Some(utp_processing.utp_controller),
-        );
+        utp_processing.gossip_handle.gossip(content_to_propagate);
         Ok(())
     }
 
@@ -1623,6 +1787,8 @@ where
                         content.key = %key.to_hex(),
                         "Error storing accepted content"
                     );
+                } else {
+                    utp_processing.notify_content_stored(&key);
                 }
             }
             Ok(ShouldWeStoreContent::NotWithinRadius) => {
@@ -1648,23 +1814,74 @@ where
         Some((key, content_value, validation_result))
     }
 
-    /// Attempts to send a single FINDCONTENT request to a fallback peer,
-    /// if found in the accept queue. Then validate, store & propagate the content.
+    /// Sends FINDCONTENT requests to the fallback peers found in the accept queue, one at a time,
+    /// until one of them provides valid content. Then store & propagate the content.
     async fn fallback_find_content(
         content_key: TContentKey,
         utp_processing: UtpProcessing<TValidator, TStore, TContentKey>,
     ) -> anyhow::Result<()> {
-        let fallback_peer = match utp_processing
+        let fallback_peers = utp_processing
             .accept_queue
             .write()
-            .process_failed_key(&content_key)
-        {
-            Some(peer) => peer,
-            None => {
-                debug!("No fallback peer found for content key");
-                return Ok(());
+            .process_failed_key(&content_key);
+        if fallback_peers.is_empty() {
+            debug!("No fallback peer found for content key");
+            return Ok(());
+        }
+        for fallback_peer in fallback_peers {
+            let data = match Self::fallback_find_content_from_peer(
+                &content_key,
+                &fallback_peer,
+                &utp_processing,
+            )
+            .await
+            {
+                Ok(data) => data,
+                Err(err) => {
+                    debug!(
+                        %err,
+                        content.key = %content_key.to_hex(),
+                        "Fallback FINDCONTENT request to peer {fallback_peer} failed"
+                    );
+                    continue;
+                }
+            };
+            match Self::validate_and_store_content(
+                content_key.clone(),
+                data,
+                utp_processing.clone(),
+            )
+            .await
+            {
+                Some(validated_content) => {
+                    utp_processing
+                        .metrics
+                        .report_accept_queue_outcome(AcceptQueueOutcomeLabel::FallbackStored, 1);
+                    let _ =
+                        Self::propagate_validated_content(vec![validated_content], utp_processing)
+                            .await;
+                    return Ok(());
+                }
+                None => {
+                    debug!("Fallback FINDCONTENT request to peer {fallback_peer} did not yield valid content");
+                }
             }
-        };
+        }
+        utp_processing
+            .metrics
+            .report_accept_queue_outcome(AcceptQueueOutcomeLabel::Lost, 1);
+        Err(anyhow!(
+            "No fallback peer provided valid content for content key: {}",
+            content_key.to_hex()
+        ))
+    }
+
+    /// Sends a FINDCONTENT request to a single fallback peer, and returns the received content.
+    async fn fallback_find_content_from_peer(
+        content_key: &TContentKey,
+        fallback_peer: &Enr,
+        utp_processing: &UtpProcessing<TValidator, TStore, TContentKey>,
+    ) -> anyhow::Result<Vec<u8>> {
         let request = Request::FindContent(FindContent {
             content_key: content_key.clone().into(),
         });
@@ -1681,11 +1898,11 @@ where
                 None,
                 None,
             )))?;
-        let data: Vec<u8> = match rx.await? {
+        match rx.await? {
             Ok(Response::Content(found_content)) => {
                 match found_content {
-                    Content::Content(content) => content,
-                    Content::Enrs(_) => return Err(anyhow!("expected content, got ENRs")),
+                    Content::Content(content) => Ok(content),
+                    Content::Enrs(_) => Err(anyhow!("expected content, got ENRs")),
                     // Init uTP stream if `connection_id` is received
                     Content::ConnectionId(conn_id) => {
                         let conn_id = u16::from_be(conn_id);
@@ -1697,28 +1914,12 @@ where
                         utp_processing
                             .utp_controller
                             .connect_inbound_stream(cid)
-                            .await?
+                            .await
                     }
                 }
             }
-            _ => return Err(anyhow!("invalid response")),
-        };
-        let validated_content = match Self::validate_and_store_content(
-            content_key,
-            data,
-            utp_processing.clone(),
-        )
-        .await
-        {
-            Some(validated_content) => validated_content,
-            None => {
-                debug!("Fallback FINDCONTENT request to peer {fallback_peer} did not yield valid content");
-                return Ok(());
-            }
-        };
-
-        let _ = Self::propagate_validated_content(vec![validated_content], utp_processing).await;
-        Ok(())
+            _ => Err(anyhow!("invalid response")),
+        }
     }
 
     /// Processes a Pong response.
@@ -1734,35 +1935,43 @@ where
 
         // If the ENR sequence number in pong is less than the ENR sequence number for the routing
         // table entry, then request the node.
-        //
-        // TODO: Perform update on non-ENR node entry state. See note in `process_ping`.
         let key = kbucket::Key::from(node_id);
         let optional_node = match self.kbuckets.write().entry(&key) {
             kbucket::Entry::Present(ref mut entry, _) => Some(entry.value().clone()),
             kbucket::Entry::Pending(ref mut entry, _) => Some(entry.value().clone()),
             _ => None,
         };
-        if let Some(node) = optional_node {
+        if let Some(mut node) = optional_node {
             if node.enr().seq() < pong.enr_seq {
                 self.request_node(&node.enr());
             }
 
-            let data_radius: Distance = pong.custom_payload.into();
-            if node.data_radius != data_radius {
-                self.update_node_radius(source, data_radius);
-            }
+            node.set_enr(source);
+            self.update_node_from_ping_payload(node, &pong.custom_payload);
         }
     }
 
-    /// Update the recorded radius of a node in our routing table.
-    fn update_node_radius(&self, enr: Enr, data_radius: Distance) {
-        let node_id = enr.node_id();
-        let key = kbucket::Key::from(node_id);
-
-        let updated_node = Node { enr, data_radius };
+    /// Updates the radius and ping extensions of a node in our routing table with the custom
+    /// payload of a Ping or Pong message.
+    fn update_node_from_ping_payload(&self, mut node: Node, custom_payload: &CustomPayload) {
+        let node_id = node.enr.node_id();
+        let payload = match PingPongPayload::try_from(custom_payload) {
+            Ok(payload) => payload,
+            Err(err) => {
+                debug!(
+                    protocol = %self.protocol,
+                    peer = %node_id,
+                    "Ignoring invalid ping custom payload: {err:?}"
+                );
+                return;
+            }
+        };
+        if !node.apply_ping_payload(payload) {
+            return;
+        }
 
-        if let UpdateResult::Failed(_) = self.kbuckets.write().update_node(&key, updated_node, None)
-        {
+        let key = kbucket::Key::from(node_id);
+        if let UpdateResult::Failed(_) = self.kbuckets.write().update_node(&key, node, None) {
             error!(
                 "Failed to update radius of node {}",
                 hex_encode_compact(node_id.raw())
@@ -1895,6 +2104,8 @@ where
                         content.key = %content_key,
                         "Error storing content"
                     );
+                } else {
+                    utp_processing.notify_content_stored(&content_key);
                 }
             }
         }
@@ -1944,10 +2155,8 @@ where
             // succeeds, then add the node to the ping queue. Ignore insertion failures.
             if let Some(node) = optional_node {
                 if node.enr().seq() < enr.seq() {
-                    let updated_node = Node {
-                        enr,
-                        data_radius: node.data_radius(),
-                    };
+                    let mut updated_node = node.clone();
+                    updated_node.set_enr(enr);
 
                     // The update removed the node because it would violate the incoming peers
                     // condition or a bucket/table filter. Remove the node from
@@ -2154,8 +2363,8 @@ where
         );
 
         let enr_seq = self.local_enr().seq();
-        let data_radius = self.data_radius();
-        let custom_payload = CustomPayload::from(data_radius.as_ssz_bytes());
+        let custom_payload =
+            self.ping_payload(UtpEnr(destination.clone()).supports_ping_extensions());
         let ping = Request::Ping(Ping {
             enr_seq,
             custom_payload,
@@ -2189,7 +2398,8 @@ where
 
     /// Attempts to insert a newly connected node or update an existing node to connected.
     fn connect_node(&mut self, node: Node, connection_direction: ConnectionDirection) {
-        let node_id = node.enr().node_id();
+        let enr = node.enr();
+        let node_id = enr.node_id();
         let key = kbucket::Key::from(node_id);
         let status = NodeStatus {
             state: ConnectionState::Connected,
@@ -2197,6 +2407,7 @@ where
         };
 
         let mut node_to_ping = None;
+        let mut connected = false;
         match self.kbuckets.write().insert_or_update(&key, node, status) {
             InsertResult::Inserted => {
                 // The node was inserted into the routing table. Add the node to the ping queue.
@@ -2207,6 +2418,7 @@ where
                 );
 
                 self.peers_to_ping.insert(node_id);
+                connected = true;
             }
             InsertResult::Pending { disconnected } => {
                 // The disconnected node is the least-recently connected entry that is
@@ -2228,6 +2440,7 @@ where
                         "Node promoted to connected",
                     );
                     self.peers_to_ping.insert(node_id);
+                    connected = true;
                 }
             }
             InsertResult::ValueUpdated | InsertResult::UpdatedPending => {}
@@ -2242,6 +2455,10 @@ where
             }
         }
 
+        if connected {
+            self.send_event(OverlayEvent::PeerConnected(enr), Some(vec![]));
+        }
+
         // Ping node to check for connectivity. See comment above for reasoning.
         if let Some(key) = node_to_ping {
             if let kbucket::Entry::Present(ref mut entry, _) = self.kbuckets.write().entry(&key) {
@@ -2533,7 +2750,6 @@ where
     }
 
     /// Send `OverlayEvent` to the event stream.
-    #[allow(dead_code)] // TODO: remove when used
     fn send_event(&self, event: OverlayEvent, to: Option<Vec<ProtocolId>>) {
         trace!(
             "Sending event={:?} to event-stream from protocol {}",
@@ -2584,6 +2800,27 @@ where
     utp_controller: Arc<UtpController>,
     accept_queue: Arc<RwLock<AcceptQueue<TContentKey>>>,
     disable_poke: bool,
+    protocol: ProtocolId,
+    event_stream: broadcast::Sender<EventEnvelope>,
+    gossip_handle: GossipHandle<TContentKey>,
+}
+
+impl<TValidator, TStore, TContentKey> UtpProcessing<TValidator, TStore, TContentKey>
+where
+    TContentKey: OverlayContentKey + Send + Sync,
+    TValidator: Validator<TContentKey>,
+    TStore: ContentStore,
+{
+    /// Notifies the JSON-RPC subscriptions that the content was stored.
+    fn notify_content_stored(&self, content_key: &TContentKey) {
+        let event = EventEnvelope::new(
+            OverlayEvent::ContentStored(content_key.to_bytes()),
+            self.protocol,
+            Some(vec![]),
+        );
+        // Sending fails only if there are no receivers, e.g. in tests.
+        let _ = self.event_stream.send(event);
+    }
 }
 
 impl<TContentKey, TMetric, TValidator, TStore>
@@ -2604,6 +2841,9 @@ where
             utp_controller: Arc::clone(&service.utp_controller),
             accept_queue: Arc::clone(&service.accept_queue),
             disable_poke: service.disable_poke,
+            protocol: service.protocol,
+            event_stream: service.event_stream.clone(),
+            gossip_handle: service.gossip_handle.clone(),
         }
     }
 }
@@ -2624,10 +2864,35 @@ where
             utp_controller: Arc::clone(&self.utp_controller),
             accept_queue: Arc::clone(&self.accept_queue),
             disable_poke: self.disable_poke,
+            protocol: self.protocol,
+            event_stream: self.event_stream.clone(),
+            gossip_handle: self.gossip_handle.clone(),
         }
     }
 }
 
+/// Builds the custom payload for Ping and Pong messages, containing the data radius of the store
+/// and, if `include_extensions` is set, the ping extensions of the local node.
+pub(crate) fn build_ping_payload<TStore: ContentStore>(
+    store: &RwLock<TStore>,
+    local_enr: &Enr,
+    ping_capabilities: &[String],
+    include_extensions: bool,
+) -> CustomPayload {
+    let store = store.read();
+    let payload = PingPongPayload::new(store.radius());
+    if !include_extensions {
+        return payload.into();
+    }
+    payload
+        .with_extensions(PingExtensions {
+            client_version: UtpEnr(local_enr.clone()).client(),
+            capabilities: Some(ping_capabilities.to_vec()),
+            storage_utilization: store.storage_utilization(),
+        })
+        .into()
+}
+
 fn decode_and_validate_content_payload<TContentKey>(
     accepted_keys: &[TContentKey],
     payload: Vec<u8>,
@@ -2651,7 +2916,7 @@ fn decode_and_validate_content_payload<TContentKey>(
 mod tests {
     use super::*;
 
-    use std::{net::SocketAddr, time::Instant};
+    use std::net::SocketAddr;
 
     use alloy_primitives::U256;
     use discv5::kbucket::Entry;
@@ -2665,13 +2930,15 @@ mod tests {
         discovery::{Discovery, NodeAddress},
         overlay::config::OverlayConfig,
         utils::db::setup_temp_dir,
+        utp_controller::UtpStallConfig,
     };
     use ethportal_api::types::{
-        cli::{DEFAULT_DISCOVERY_PORT, DEFAULT_UTP_TRANSFER_LIMIT},
+        cli::{GossipStrategy, DEFAULT_DISCOVERY_PORT, DEFAULT_UTP_TRANSFER_LIMIT},
         content_key::overlay::IdentityContentKey,
         distance::XorMetric,
         enr::generate_random_remote_enr,
         portal_wire::MAINNET,
+        utp_tuning::UtpConnectionTuning,
     };
     use trin_metrics::portalnet::PORTALNET_METRICS;
     use trin_storage::{DistanceFunction, MemoryContentStore};
@@ -2700,9 +2967,14 @@ mod tests {
             overlay_metrics: PORTALNET_METRICS.overlay(),
             protocol: "test".to_string(),
         };
+        let peer_stats = Arc::new(RwLock::new(PeerStatsTable::default()));
         let utp_controller = UtpController::new(
             DEFAULT_UTP_TRANSFER_LIMIT,
+            None,
+            UtpStallConfig::default(),
+            UtpConnectionTuning::default(),
             Arc::new(utp_socket),
+            Arc::clone(&peer_stats),
             metrics.clone(),
         );
         let utp_controller = Arc::new(utp_controller);
@@ -2720,13 +2992,22 @@ mod tests {
             overlay_config.bucket_filter,
         )));
 
+        let (gossip_handle, _) = GossipHandle::new(
+            Arc::clone(&kbuckets),
+            GossipStrategy::default(),
+            StdRng::from_entropy(),
+        );
         let protocol = ProtocolId::History;
         let active_outgoing_requests = Arc::new(RwLock::new(HashMap::new()));
         let peers_to_ping = HashSetDelay::default();
         let (command_tx, command_rx) = mpsc::unbounded_channel();
         let (response_tx, response_rx) = mpsc::unbounded_channel();
         let validator = Arc::new(MockValidator {});
-        let accept_queue = Arc::new(RwLock::new(AcceptQueue::default()));
+        let accept_queue = Arc::new(RwLock::new(AcceptQueue::new(
+            overlay_config.accept_queue_timeout,
+            metrics.clone(),
+            StdRng::from_entropy(),
+        )));
 
         OverlayService {
             discovery,
@@ -2757,6 +3038,10 @@ mod tests {
             event_stream: broadcast::channel(EVENT_STREAM_CHANNEL_CAPACITY).0,
             disable_poke: false,
             accept_queue,
+            ping_capabilities: vec![],
+            peer_stats,
+            gossip_handle,
+            rng: StdRng::from_entropy(),
         }
     }
 
@@ -2836,6 +3121,36 @@ mod tests {
         assert_pending!(poll_command_rx!(service));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn offer_to_stalled_peer() {
+        let mut service = build_service();
+
+        let (_, destination) = generate_random_remote_enr();
+        service
+            .peer_stats
+            .write()
+            .record_utp_stall(destination.node_id());
+
+        let (tx, rx) = oneshot::channel();
+        let request = OverlayRequest::new(
+            Request::PopulatedOffer(PopulatedOffer {
+                content_items: vec![(IdentityContentKey::random().to_bytes(), vec![0])],
+            }),
+            RequestDirection::Outgoing { destination },
+            Some(tx),
+            None,
+            None,
+        );
+        service.process_request(request);
+
+        assert!(matches!(
+            rx.await.unwrap(),
+            Err(OverlayRequestError::OfferError(_))
+        ));
+        assert!(service.active_outgoing_requests.read().is_empty());
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn process_request_failure() {
@@ -2953,6 +3268,88 @@ mod tests {
         assert_pending!(poll_command_rx!(service));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn process_pong_with_ping_extensions() {
+        let service = task::spawn(build_service());
+
+        let (_, source) = generate_random_remote_enr();
+        let key = kbucket::Key::from(source.node_id());
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        let node = Node::new(source.clone(), Distance::MAX);
+        let _ = service
+            .kbuckets
+            .write()
+            .insert_or_update(&key, node, status);
+
+        let data_radius = Distance::from(U256::from(1000));
+        let extensions = PingExtensions {
+            client_version: Some("t 0.1.0".to_string()),
+            capabilities: Some(vec!["history".to_string()]),
+            storage_utilization: None,
+        };
+        let pong = Pong {
+            enr_seq: source.seq(),
+            custom_payload: PingPongPayload::new(data_radius)
+                .with_extensions(extensions.clone())
+                .into(),
+        };
+        service.process_pong(pong, source.clone());
+
+        let node = match service.kbuckets.write().entry(&key) {
+            kbucket::Entry::Present(entry, _) => entry.value().clone(),
+            _ => panic!("Node should be in the routing table"),
+        };
+        assert_eq!(node.data_radius(), data_radius);
+        assert_eq!(node.ping_extensions(), Some(&extensions));
+
+        // A legacy payload updates the radius, but keeps the extensions.
+        let pong = Pong {
+            enr_seq: source.seq(),
+            custom_payload: CustomPayload::from(Distance::MAX.as_ssz_bytes()),
+        };
+        service.process_pong(pong, source);
+
+        let node = match service.kbuckets.write().entry(&key) {
+            kbucket::Entry::Present(entry, _) => entry.value().clone(),
+            _ => panic!("Node should be in the routing table"),
+        };
+        assert_eq!(node.data_radius(), Distance::MAX);
+        assert_eq!(node.ping_extensions(), Some(&extensions));
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn handle_ping_with_ping_extensions() {
+        let service = task::spawn(build_service());
+        let (_, source) = generate_random_remote_enr();
+
+        // Legacy ping is answered with legacy pong.
+        let ping = Ping {
+            enr_seq: source.seq(),
+            custom_payload: PingPongPayload::new(Distance::MAX).into(),
+        };
+        let pong = service.handle_ping(ping, &source.node_id(), RequestId::random());
+        let payload = PingPongPayload::try_from(&pong.custom_payload).unwrap();
+        assert_eq!(payload.data_radius, service.data_radius());
+        assert!(payload.extensions.is_none());
+
+        // Ping with extensions is answered with extensions.
+        let ping = Ping {
+            enr_seq: source.seq(),
+            custom_payload: PingPongPayload::new(Distance::MAX)
+                .with_extensions(PingExtensions::default())
+                .into(),
+        };
+        let pong = service.handle_ping(ping, &source.node_id(), RequestId::random());
+        let payload = PingPongPayload::try_from(&pong.custom_payload).unwrap();
+        assert_eq!(payload.data_radius, service.data_radius());
+        assert!(payload.extensions.is_some());
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn process_discovered_enrs_local_enr() {
@@ -3098,10 +3495,7 @@ mod tests {
 
         let (_, enr) = generate_random_remote_enr();
         let key = kbucket::Key::from(enr.node_id());
-        let peer = Node {
-            enr,
-            data_radius: Distance::MAX,
-        };
+        let peer = Node::new(enr, Distance::MAX);
         let _ = service
             .kbuckets
             .write()
@@ -3175,10 +3569,7 @@ mod tests {
         // The first node has a maximum radius, so the content SHOULD be offered.
         let (_, enr1) = generate_random_remote_enr();
         let key1 = kbucket::Key::from(enr1.node_id());
-        let peer1 = Node {
-            enr: enr1,
-            data_radius: Distance::MAX,
-        };
+        let peer1 = Node::new(enr1, Distance::MAX);
         let _ = service
             .kbuckets
             .write()
@@ -3187,10 +3578,7 @@ mod tests {
         // The second node has a radius of zero, so the content SHOULD NOT not be offered.
         let (_, enr2) = generate_random_remote_enr();
         let key2 = kbucket::Key::from(enr2.node_id());
-        let peer2 = Node {
-            enr: enr2,
-            data_radius: Distance::from(U256::ZERO),
-        };
+        let peer2 = Node::new(enr2, Distance::from(U256::ZERO));
         let _ = service
             .kbuckets
             .write()
@@ -3467,6 +3855,63 @@ mod tests {
         assert!(query.started().is_some());
     }
 
+    #[test_log::test(tokio::test)]
+    async fn set_query_tuning() {
+        let mut service = build_service();
+        let default_tuning = service.query_tuning();
+
+        let tuning = service.set_query_tuning(QueryTuning {
+            query_parallelism: Some(8),
+            query_timeout_ms: Some(30_000),
+            ..Default::default()
+        });
+        assert_eq!(
+            tuning,
+            QueryTuning {
+                query_parallelism: Some(8),
+                query_timeout_ms: Some(30_000),
+                ..default_tuning
+            }
+        );
+        assert_eq!(service.query_parallelism, 8);
+        assert_eq!(
+            service.find_content_query_pool.read().query_timeout(),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn set_query_tuning_times_out_queries_in_progress() {
+        let mut service = build_service();
+        let (_, bootnode) = generate_random_remote_enr();
+        service.add_bootnodes(vec![bootnode], true);
+        let (_, target_enr) = generate_random_remote_enr();
+        service.init_find_nodes_query(&target_enr.node_id(), None);
+
+        // The query sends a request to the bootnode, and waits for its response.
+        assert!(matches!(
+            service.find_node_query_pool.write().poll(),
+            QueryPoolState::Waiting(Some(_))
+        ));
+        assert!(matches!(
+            service.find_node_query_pool.write().poll(),
+            QueryPoolState::Waiting(None)
+        ));
+
+        // The shortest valid timeout has already elapsed for the query in progress.
+        let tuning = QueryTuning {
+            query_timeout_ms: Some(1),
+            ..Default::default()
+        };
+        tuning.validate().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        service.set_query_tuning(tuning);
+        assert!(matches!(
+            service.find_node_query_pool.write().poll(),
+            QueryPoolState::Timeout(..)
+        ));
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_advance_findnodes_query() {
         let mut service = build_service();
@@ -3655,10 +4100,7 @@ mod tests {
         let bootnode_key = kbucket::Key::from(bootnode_node_id);
 
         let data_radius = Distance::MAX;
-        let bootnode = Node {
-            enr: bootnode_enr.clone(),
-            data_radius,
-        };
+        let bootnode = Node::new(bootnode_enr.clone(), data_radius);
 
         let connection_direction = ConnectionDirection::Outgoing;
         let status = NodeStatus {
@@ -3723,10 +4165,7 @@ mod tests {
         let bootnode_key = kbucket::Key::from(bootnode_node_id);
 
         let data_radius = Distance::MAX;
-        let bootnode = Node {
-            enr: bootnode_enr.clone(),
-            data_radius,
-        };
+        let bootnode = Node::new(bootnode_enr.clone(), data_radius);
 
         let connection_direction = ConnectionDirection::Outgoing;
         let status = NodeStatus {
@@ -3754,7 +4193,7 @@ mod tests {
                 .expect("Query pool does not contain query");
             // Poll query to put into waiting state. Otherwise, `on_success` has no effect on the
             // query.
-            query.poll(Instant::now());
+            query.poll(tokio::time::Instant::now());
         }
 
         // Simulate a response from the bootnode.
@@ -3791,10 +4230,7 @@ mod tests {
         let bootnode_key = kbucket::Key::from(bootnode_node_id);
 
         let data_radius = Distance::MAX;
-        let bootnode = Node {
-            enr: bootnode_enr.clone(),
-            data_radius,
-        };
+        let bootnode = Node::new(bootnode_enr.clone(), data_radius);
 
         let connection_direction = ConnectionDirection::Outgoing;
         let status = NodeStatus {
@@ -3823,7 +4259,7 @@ mod tests {
 
             // Poll query to put into waiting state. Otherwise, `on_success` has no effect on the
             // query.
-            query.poll(Instant::now());
+            query.poll(tokio::time::Instant::now());
         }
 
         // Simulate a response from the bootnode.
@@ -3856,10 +4292,7 @@ mod tests {
         let bootnode_key = kbucket::Key::from(bootnode_node_id);
 
         let data_radius = Distance::MAX;
-        let bootnode = Node {
-            enr: bootnode_enr.clone(),
-            data_radius,
-        };
+        let bootnode = Node::new(bootnode_enr.clone(), data_radius);
 
         let connection_direction = ConnectionDirection::Outgoing;
         let status = NodeStatus {
@@ -3887,7 +4320,7 @@ mod tests {
                 .expect("Query pool does not contain query");
             // Poll query to put into waiting state. Otherwise, `on_success` has no effect on the
             // query.
-            query.poll(Instant::now());
+            query.poll(tokio::time::Instant::now());
         }
 
         // Simulate a response from the bootnode.
@@ -3921,10 +4354,7 @@ mod tests {
         let bootnode_key = kbucket::Key::from(bootnode_node_id);
 
         let data_radius = Distance::MAX;
-        let bootnode = Node {
-            enr: bootnode_enr.clone(),
-            data_radius,
-        };
+        let bootnode = Node::new(bootnode_enr.clone(), data_radius);
 
         let connection_direction = ConnectionDirection::Outgoing;
         let status = NodeStatus {
@@ -4007,6 +4437,118 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn fallback_find_content_cycles_through_fallback_peers() {
+        let mut service = build_service();
+
+        let content_key = IdentityContentKey::random();
+        let content = vec![0xef];
+        let (_, origin) = generate_random_remote_enr();
+        let (_, fallback_peer1) = generate_random_remote_enr();
+        let (_, fallback_peer2) = generate_random_remote_enr();
+        {
+            let mut accept_queue = service.accept_queue.write();
+            assert!(accept_queue.add_key_to_queue(&content_key, &origin));
+            assert!(!accept_queue.add_key_to_queue(&content_key, &fallback_peer1));
+            assert!(!accept_queue.add_key_to_queue(&content_key, &fallback_peer2));
+        }
+
+        let utp_processing = UtpProcessing::from(&service);
+        let fallback = tokio::spawn(OverlayService::<
+            _,
+            XorMetric,
+            MockValidator,
+            MemoryContentStore,
+        >::fallback_find_content(
+            content_key.clone(), utp_processing
+        ));
+
+        // The first fallback peer fails to respond.
+        let command = service.command_rx.recv().await.unwrap();
+        let OverlayCommand::Request(request) = command else {
+            panic!("Unexpected overlay command variant");
+        };
+        assert!(matches!(request.request, Request::FindContent(_)));
+        let RequestDirection::Outgoing { destination } = request.direction else {
+            panic!("Unexpected request direction");
+        };
+        assert_ne!(destination, origin);
+        let first_peer = destination;
+        let _ = request
+            .responder
+            .unwrap()
+            .send(Err(OverlayRequestError::InvalidResponse));
+
+        // The second fallback peer responds with the content.
+        let command = service.command_rx.recv().await.unwrap();
+        let OverlayCommand::Request(request) = command else {
+            panic!("Unexpected overlay command variant");
+        };
+        assert!(matches!(request.request, Request::FindContent(_)));
+        let RequestDirection::Outgoing { destination } = request.direction else {
+            panic!("Unexpected request direction");
+        };
+        assert_ne!(destination, origin);
+        assert_ne!(destination, first_peer);
+        let _ = request
+            .responder
+            .unwrap()
+            .send(Ok(Response::Content(Content::Content(content.clone()))));
+
+        fallback.await.unwrap().unwrap();
+        assert_eq!(
+            service.store.read().get(&content_key).unwrap(),
+            Some(content)
+        );
+        // The content key was removed from the accept queue.
+        assert!(service
+            .accept_queue
+            .write()
+            .add_key_to_queue(&content_key, &origin));
+    }
+
+    #[tokio::test]
+    async fn fallback_find_content_without_valid_content() {
+        let mut service = build_service();
+
+        let content_key = IdentityContentKey::random();
+        let (_, origin) = generate_random_remote_enr();
+        let (_, fallback_peer) = generate_random_remote_enr();
+        {
+            let mut accept_queue = service.accept_queue.write();
+            assert!(accept_queue.add_key_to_queue(&content_key, &origin));
+            assert!(!accept_queue.add_key_to_queue(&content_key, &fallback_peer));
+        }
+
+        let utp_processing = UtpProcessing::from(&service);
+        let fallback = tokio::spawn(OverlayService::<
+            _,
+            XorMetric,
+            MockValidator,
+            MemoryContentStore,
+        >::fallback_find_content(
+            content_key.clone(), utp_processing
+        ));
+
+        let command = service.command_rx.recv().await.unwrap();
+        let OverlayCommand::Request(request) = command else {
+            panic!("Unexpected overlay command variant");
+        };
+        assert_eq!(
+            request.direction,
+            RequestDirection::Outgoing {
+                destination: fallback_peer
+            }
+        );
+        let _ = request
+            .responder
+            .unwrap()
+            .send(Ok(Response::Content(Content::Enrs(vec![]))));
+
+        assert!(fallback.await.unwrap().is_err());
+        assert_eq!(service.store.read().get(&content_key).unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_event_stream() {
         // Get overlay service event stream
@@ -4019,4 +4561,19 @@ mod tests {
         let event = receiver.recv().await.unwrap();
         assert_eq!(event.payload, OverlayEvent::LightClientOptimisticUpdate);
     }
+
+    #[tokio::test]
+    async fn test_event_stream_peer_connected() {
+        let mut service = task::spawn(build_service());
+        let (sender, mut receiver) = broadcast::channel(1);
+        service.event_stream = sender;
+
+        let (_, enr) = generate_random_remote_enr();
+        let node = Node::new(enr.clone(), Distance::MAX);
+        service.connect_node(node, ConnectionDirection::Outgoing);
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.payload, OverlayEvent::PeerConnected(enr));
+        assert_eq!(event.destination, Some(vec![]));
+    }
 }
@@ -0,0 +1,156 @@
+use std::time::{Duration, SystemTime};
+
+use discv5::enr::NodeId;
+use lru::LruCache;
+
+/// The maximum number of peers whose stats are tracked. The least recently active peers are
+/// evicted first.
+const PEER_STATS_CAPACITY: usize = 4096;
+
+/// The activity that the overlay has observed for a single peer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerStats {
+    /// The time of the last request or response received from the peer.
+    pub last_seen: Option<SystemTime>,
+    /// The round trip time of the last successful ping of the peer.
+    pub last_ping_rtt: Option<Duration>,
+    /// The number of requests sent to the peer.
+    pub requests_sent: u64,
+    /// The number of responses received from the peer.
+    pub responses_received: u64,
+    /// The number of requests to the peer that failed (e.g. timed out or had invalid response).
+    pub requests_failed: u64,
+    /// The number of requests received from the peer.
+    pub requests_received: u64,
+    /// The number of uTP streams with the peer that were aborted because they stalled.
+    pub utp_stalls: u64,
+    /// The time of the last uTP stream with the peer that stalled.
+    pub last_utp_stall: Option<SystemTime>,
+}
+
+/// In-memory record of the activity of the peers the overlay communicates with.
+pub struct PeerStatsTable {
+    peers: LruCache<NodeId, PeerStats>,
+}
+
+impl Default for PeerStatsTable {
+    fn default() -> Self {
+        Self {
+            peers: LruCache::new(PEER_STATS_CAPACITY),
+        }
+    }
+}
+
+impl PeerStatsTable {
+    /// Returns the stats of the peer, if there are any.
+    pub fn get(&self, node_id: &NodeId) -> Option<PeerStats> {
+        self.peers.peek(node_id).cloned()
+    }
+
+    /// Records a request sent to the peer.
+    pub fn record_request_sent(&mut self, node_id: NodeId) {
+        self.entry(node_id).requests_sent += 1;
+    }
+
+    /// Records a response received from the peer. `ping_rtt` should be set if the response is a
+    /// pong.
+    pub fn record_response_received(&mut self, node_id: NodeId, ping_rtt: Option<Duration>) {
+        let stats = self.entry(node_id);
+        stats.responses_received += 1;
+        stats.last_seen = Some(SystemTime::now());
+        if ping_rtt.is_some() {
+            stats.last_ping_rtt = ping_rtt;
+        }
+    }
+
+    /// Records a failed request to the peer.
+    pub fn record_request_failed(&mut self, node_id: NodeId) {
+        self.entry(node_id).requests_failed += 1;
+    }
+
+    /// Records a request received from the peer.
+    pub fn record_request_received(&mut self, node_id: NodeId) {
+        let stats = self.entry(node_id);
+        stats.requests_received += 1;
+        stats.last_seen = Some(SystemTime::now());
+    }
+
+    /// Records a uTP stream with the peer that was aborted because it stalled.
+    pub fn record_utp_stall(&mut self, node_id: NodeId) {
+        let stats = self.entry(node_id);
+        stats.utp_stalls += 1;
+        stats.last_utp_stall = Some(SystemTime::now());
+    }
+
+    /// Returns whether a uTP stream with the peer stalled within the `period`.
+    pub fn has_recent_utp_stall(&self, node_id: &NodeId, period: Duration) -> bool {
+        self.peers
+            .peek(node_id)
+            .and_then(|stats| stats.last_utp_stall)
+            .and_then(|last_utp_stall| last_utp_stall.elapsed().ok())
+            .is_some_and(|elapsed| elapsed < period)
+    }
+
+    fn entry(&mut self, node_id: NodeId) -> &mut PeerStats {
+        if !self.peers.contains(&node_id) {
+            self.peers.put(node_id, PeerStats::default());
+        }
+        self.peers
+            .get_mut(&node_id)
+            .expect("Peer stats to be present after insertion")
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_activity() {
+        let mut table = PeerStatsTable::default();
+        let node_id = NodeId::random();
+        assert_eq!(table.get(&node_id), None);
+
+        table.record_request_sent(node_id);
+        table.record_request_sent(node_id);
+        table.record_response_received(node_id, Some(Duration::from_millis(10)));
+        table.record_response_received(node_id, None);
+        table.record_request_sent(node_id);
+        table.record_request_failed(node_id);
+        table.record_request_received(node_id);
+
+        let stats = table.get(&node_id).unwrap();
+        assert_eq!(stats.requests_sent, 3);
+        assert_eq!(stats.responses_received, 2);
+        assert_eq!(stats.requests_failed, 1);
+        assert_eq!(stats.requests_received, 1);
+        assert_eq!(stats.last_ping_rtt, Some(Duration::from_millis(10)));
+        assert!(stats.last_seen.is_some());
+    }
+
+    #[test]
+    fn record_utp_stall() {
+        let mut table = PeerStatsTable::default();
+        let node_id = NodeId::random();
+        assert!(!table.has_recent_utp_stall(&node_id, Duration::from_secs(60)));
+
+        table.record_utp_stall(node_id);
+        assert_eq!(table.get(&node_id).unwrap().utp_stalls, 1);
+        assert!(table.has_recent_utp_stall(&node_id, Duration::from_secs(60)));
+        assert!(!table.has_recent_utp_stall(&node_id, Duration::ZERO));
+        // A stall is not a sign of life.
+        assert_eq!(table.get(&node_id).unwrap().last_seen, None);
+    }
+
+    #[test]
+    fn failures_dont_update_last_seen() {
+        let mut table = PeerStatsTable::default();
+        let node_id = NodeId::random();
+
+        table.record_request_sent(node_id);
+        table.record_request_failed(node_id);
+
+        assert_eq!(table.get(&node_id).unwrap().last_seen, None);
+    }
+}
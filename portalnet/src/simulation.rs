@@ -0,0 +1,431 @@
+//! An in-process simulated network of overlay nodes.
+//!
+//! The nodes exchange TALKREQ messages and uTP packets over channels instead of UDP, so hundreds
+//! of nodes can run in a single test. The network injects latency and packet loss, and can be
+//! partitioned. All the randomness is derived from the seed of the network: the node keys, the
+//! packet loss, and the random choices of each node (e.g. the peers to gossip to, the buckets to
+//! refresh and the request ids). On a current-thread runtime with a paused clock, the tasks also
+//! run in a fixed order, so a run can be reproduced from its seed. The uTP library makes its own
+//! random choices (e.g. of the connection ids), so the runs that transfer content over uTP are
+//! only reproducible as long as these don't change the order of the events.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use discv5::{
+    enr::{CombinedKey, Enr as Discv5Enr, NodeId},
+    rpc::RequestId,
+    RequestError,
+};
+use lru::LruCache;
+use parking_lot::{Mutex, RwLock};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::sync::{mpsc, oneshot};
+use tracing::debug;
+use utp_rs::{socket::UtpSocket, udp::AsyncUdpSocket};
+
+use crate::{
+    discovery::{NodeAddress, ProtocolRequest, UtpEnr},
+    overlay::{config::OverlayConfig, protocol::OverlayProtocol},
+    transport::TalkReqTransport,
+};
+use ethportal_api::types::{
+    content_key::overlay::IdentityContentKey,
+    distance::XorMetric,
+    enr::Enr,
+    portal_wire::{Message, ProtocolId},
+};
+use trin_storage::{DistanceFunction, MemoryContentStore};
+use trin_validation::validator::MockValidator;
+
+/// The time after which a TALKREQ without a response fails, as in Discovery v5.
+pub const SIMULATED_TALK_REQ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The capacity of the `NodeAddress` cache of a simulated node.
+const NODE_ADDR_CACHE_CAPACITY: usize = 1024;
+
+/// The UDP port in the ENRs of the simulated nodes.
+const SIMULATED_UDP_PORT: u16 = 9009;
+
+/// The overlay protocol of a simulated node.
+pub type SimulatedOverlayProtocol =
+    OverlayProtocol<IdentityContentKey, XorMetric, MockValidator, MemoryContentStore>;
+
+/// The conditions of the links between the nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkConditions {
+    /// The one-way latency of a packet.
+    pub latency: Duration,
+    /// The probability that a packet is lost, between 0 and 1.
+    pub loss: f64,
+}
+
+/// A TALKREQ delivered to a simulated node.
+struct SimulatedTalkRequest {
+    id: RequestId,
+    source: NodeId,
+    protocol: ProtocolId,
+    body: ProtocolRequest,
+    response_tx: oneshot::Sender<Vec<u8>>,
+}
+
+/// The channels of a node in the network.
+#[derive(Clone)]
+struct Endpoint {
+    transport: Arc<SimulatedTransport>,
+    talk_req_tx: mpsc::UnboundedSender<SimulatedTalkRequest>,
+    utp_tx: mpsc::UnboundedSender<(Vec<u8>, UtpEnr)>,
+}
+
+struct NetworkState {
+    endpoints: HashMap<NodeId, Endpoint>,
+    /// The partition of each node. Nodes without a partition are in the partition 0.
+    partitions: HashMap<NodeId, usize>,
+    conditions: LinkConditions,
+    rng: StdRng,
+}
+
+/// An in-process network of simulated nodes.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+    state: Arc<Mutex<NetworkState>>,
+}
+
+impl SimulatedNetwork {
+    /// Creates an empty network, whose randomness is derived from `seed`.
+    pub fn new(seed: u64, conditions: LinkConditions) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(NetworkState {
+                endpoints: HashMap::new(),
+                partitions: HashMap::new(),
+                conditions,
+                rng: StdRng::seed_from_u64(seed),
+            })),
+        }
+    }
+
+    /// Sets the conditions of all the links.
+    pub fn set_conditions(&self, conditions: LinkConditions) {
+        self.state.lock().conditions = conditions;
+    }
+
+    /// Splits the network into partitions. The nodes of `groups[i]` are moved to the partition
+    /// `i + 1`, and the other nodes stay in the partition 0. Packets between partitions are lost.
+    pub fn partition(&self, groups: &[Vec<NodeId>]) {
+        let mut state = self.state.lock();
+        state.partitions.clear();
+        for (index, group) in groups.iter().enumerate() {
+            for node_id in group {
+                state.partitions.insert(*node_id, index + 1);
+            }
+        }
+    }
+
+    /// Removes all partitions.
+    pub fn heal(&self) {
+        self.state.lock().partitions.clear();
+    }
+
+    /// Returns the number of nodes in the network.
+    pub fn len(&self) -> usize {
+        self.state.lock().endpoints.len()
+    }
+
+    /// Returns whether the network has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().endpoints.is_empty()
+    }
+
+    /// Adds a node with a new ENR to the network, and returns its transport and uTP socket, and
+    /// the receiver of the TALKREQs to the node.
+    fn add_node(
+        &self,
+    ) -> (
+        Arc<SimulatedTransport>,
+        SimulatedUdpSocket,
+        mpsc::UnboundedReceiver<SimulatedTalkRequest>,
+    ) {
+        let mut state = self.state.lock();
+        let enr = loop {
+            let Ok(key) = CombinedKey::secp256k1_from_bytes(&mut state.rng.gen::<[u8; 32]>())
+            else {
+                continue;
+            };
+            let ip = Ipv4Addr::from(state.rng.gen::<u32>());
+            let Ok(enr) = Discv5Enr::builder()
+                .ip(ip.into())
+                .udp4(SIMULATED_UDP_PORT)
+                .build(&key)
+            else {
+                continue;
+            };
+            if !state.endpoints.contains_key(&enr.node_id()) {
+                break enr;
+            }
+        };
+
+        let transport = Arc::new(SimulatedTransport {
+            network: self.clone(),
+            local_enr: enr.clone(),
+            node_addr_cache: RwLock::new(LruCache::new(NODE_ADDR_CACHE_CAPACITY)),
+        });
+        let (talk_req_tx, talk_req_rx) = mpsc::unbounded_channel();
+        let (utp_tx, utp_rx) = mpsc::unbounded_channel();
+        state.endpoints.insert(
+            enr.node_id(),
+            Endpoint {
+                transport: Arc::clone(&transport),
+                talk_req_tx,
+                utp_tx,
+            },
+        );
+        let socket = SimulatedUdpSocket {
+            network: self.clone(),
+            local_enr: enr,
+            packets: utp_rx,
+        };
+        (transport, socket, talk_req_rx)
+    }
+
+    /// Spawns an overlay node in the network, with an in-memory store that accepts any content.
+    /// Unless the config sets a seed, the random choices of the node are seeded from the network.
+    pub async fn spawn_overlay(
+        &self,
+        protocol: ProtocolId,
+        mut config: OverlayConfig,
+    ) -> SimulatedNode {
+        let (transport, socket, mut talk_req_rx) = self.add_node();
+        if config.rng_seed.is_none() {
+            config.rng_seed = Some(self.state.lock().rng.gen());
+        }
+        let node_id = transport.local_enr.node_id();
+        let store = Arc::new(RwLock::new(MemoryContentStore::new(
+            node_id,
+            DistanceFunction::Xor,
+        )));
+        let utp_socket = Arc::new(UtpSocket::with_socket(socket));
+        let overlay = Arc::new(
+            OverlayProtocol::new(
+                config,
+                Arc::clone(&transport) as Arc<dyn TalkReqTransport>,
+                utp_socket,
+                Arc::clone(&store),
+                protocol,
+                Arc::new(MockValidator {}),
+            )
+            .await,
+        );
+
+        let handler = Arc::clone(&overlay);
+        tokio::spawn(async move {
+            while let Some(request) = talk_req_rx.recv().await {
+                if request.protocol != protocol {
+                    continue;
+                }
+                let overlay = Arc::clone(&handler);
+                tokio::spawn(async move {
+                    let reply = match overlay
+                        .process_one_raw_request(request.source, request.id, &request.body)
+                        .await
+                    {
+                        Ok(response) => Message::from(response).into(),
+                        Err(err) => {
+                            debug!(error = %err, "Error processing simulated request");
+                            vec![]
+                        }
+                    };
+                    let _ = request.response_tx.send(reply);
+                });
+            }
+        });
+
+        SimulatedNode {
+            overlay,
+            store,
+            transport,
+        }
+    }
+
+    /// Returns a new id for a TALKREQ.
+    fn request_id(&self) -> RequestId {
+        RequestId(self.state.lock().rng.gen::<u64>().to_be_bytes().to_vec())
+    }
+
+    /// Returns the latency of a packet from `source` to `target`, and the endpoint of `target`,
+    /// or `None` if the packet is lost.
+    fn route(&self, source: &NodeId, target: &NodeId) -> Option<(Duration, Endpoint)> {
+        let mut state = self.state.lock();
+        let endpoint = state.endpoints.get(target)?.clone();
+        let partition = |node_id| state.partitions.get(node_id).copied().unwrap_or_default();
+        if partition(source) != partition(target) {
+            return None;
+        }
+        let conditions = state.conditions;
+        if conditions.loss > 0.0 && state.rng.gen_bool(conditions.loss.min(1.0)) {
+            return None;
+        }
+        Some((conditions.latency, endpoint))
+    }
+}
+
+/// A node in a [`SimulatedNetwork`].
+pub struct SimulatedNode {
+    pub overlay: Arc<SimulatedOverlayProtocol>,
+    pub store: Arc<RwLock<MemoryContentStore>>,
+    pub transport: Arc<SimulatedTransport>,
+}
+
+impl SimulatedNode {
+    pub fn enr(&self) -> Enr {
+        self.transport.local_enr.clone()
+    }
+
+    pub fn node_id(&self) -> NodeId {
+        self.transport.local_enr.node_id()
+    }
+}
+
+/// The TALKREQ transport of a simulated node.
+pub struct SimulatedTransport {
+    network: SimulatedNetwork,
+    local_enr: Enr,
+    /// A cache of the nodes that the node exchanged messages with.
+    node_addr_cache: RwLock<LruCache<NodeId, NodeAddress>>,
+}
+
+impl SimulatedTransport {
+    fn observe(&self, enr: &Enr) {
+        self.put_cached_node_addr(NodeAddress {
+            enr: enr.clone(),
+            socket_addr: socket_addr(enr),
+        });
+    }
+}
+
+#[async_trait]
+impl TalkReqTransport for SimulatedTransport {
+    fn local_enr(&self) -> Enr {
+        self.local_enr.clone()
+    }
+
+    fn find_enr(&self, node_id: &NodeId) -> Option<Enr> {
+        self.cached_node_addr(node_id)
+            .map(|node_addr| node_addr.enr)
+    }
+
+    fn table_entries_enr(&self) -> Vec<Enr> {
+        self.node_addr_cache
+            .read()
+            .iter()
+            .map(|(_, node_addr)| node_addr.enr.clone())
+            .collect()
+    }
+
+    fn cached_node_addr(&self, node_id: &NodeId) -> Option<NodeAddress> {
+        self.node_addr_cache.write().get(node_id).cloned()
+    }
+
+    fn put_cached_node_addr(&self, node_addr: NodeAddress) -> Option<NodeAddress> {
+        self.node_addr_cache
+            .write()
+            .put(node_addr.enr.node_id(), node_addr)
+    }
+
+    async fn send_talk_req(
+        &self,
+        enr: Enr,
+        protocol: ProtocolId,
+        request: ProtocolRequest,
+    ) -> Result<Vec<u8>, RequestError> {
+        let local_node_id = self.local_enr.node_id();
+        let target = enr.node_id();
+
+        let Some((latency, endpoint)) = self.network.route(&local_node_id, &target) else {
+            tokio::time::sleep(SIMULATED_TALK_REQ_TIMEOUT).await;
+            return Err(RequestError::Timeout);
+        };
+        tokio::time::sleep(latency).await;
+        // The target learns the address of the source, as in a Discovery v5 session.
+        endpoint.transport.observe(&self.local_enr);
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = SimulatedTalkRequest {
+            id: self.network.request_id(),
+            source: local_node_id,
+            protocol,
+            body: request,
+            response_tx,
+        };
+        if endpoint.talk_req_tx.send(request).is_err() {
+            return Err(RequestError::Timeout);
+        }
+        let response = match tokio::time::timeout(
+            SIMULATED_TALK_REQ_TIMEOUT.saturating_sub(latency),
+            response_rx,
+        )
+        .await
+        {
+            Ok(Ok(response)) => response,
+            _ => return Err(RequestError::Timeout),
+        };
+
+        match self.network.route(&target, &local_node_id) {
+            Some((latency, _)) => tokio::time::sleep(latency).await,
+            None => {
+                tokio::time::sleep(SIMULATED_TALK_REQ_TIMEOUT).await;
+                return Err(RequestError::Timeout);
+            }
+        }
+        self.observe(&enr);
+        Ok(response)
+    }
+}
+
+/// The uTP socket of a simulated node.
+pub struct SimulatedUdpSocket {
+    network: SimulatedNetwork,
+    local_enr: Enr,
+    packets: mpsc::UnboundedReceiver<(Vec<u8>, UtpEnr)>,
+}
+
+#[async_trait]
+impl AsyncUdpSocket<UtpEnr> for SimulatedUdpSocket {
+    async fn send_to(&mut self, buf: &[u8], target: &UtpEnr) -> io::Result<usize> {
+        // Lost packets are silently dropped, as with UDP.
+        if let Some((latency, endpoint)) = self
+            .network
+            .route(&self.local_enr.node_id(), &target.node_id())
+        {
+            let packet = buf.to_vec();
+            let source = UtpEnr(self.local_enr.clone());
+            tokio::spawn(async move {
+                tokio::time::sleep(latency).await;
+                let _ = endpoint.utp_tx.send((packet, source));
+            });
+        }
+        Ok(buf.len())
+    }
+
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, UtpEnr)> {
+        match self.packets.recv().await {
+            Some((packet, source)) => {
+                let len = packet.len().min(buf.len());
+                buf[..len].copy_from_slice(&packet[..len]);
+                Ok((len, source))
+            }
+            None => Err(io::Error::from(io::ErrorKind::NotConnected)),
+        }
+    }
+}
+
+fn socket_addr(enr: &Enr) -> SocketAddr {
+    SocketAddr::V4(
+        enr.udp4_socket()
+            .unwrap_or(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)),
+    )
+}
@@ -1,6 +1,6 @@
 use core::time;
 use std::{
-    net::{IpAddr, SocketAddr, TcpStream, UdpSocket},
+    net::{IpAddr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket},
     thread,
 };
 use tracing::{debug, info, warn};
@@ -61,6 +61,51 @@ pub fn is_local_addr(external_ip: Option<IpAddr>) -> bool {
     false
 }
 
+/// Returns a globally routable IPv6 address of a local network interface.
+///
+/// IPv6 hosts are usually not behind a NAT, so the address of the interface is also the external
+/// address. The STUN server is only reachable over IPv4, and UPnP only maps IPv4 ports.
+pub fn ipv6_for_external() -> Option<Ipv6Addr> {
+    let network_interfaces = match local_ip_address::list_afinet_netifas() {
+        Ok(network_interfaces) => network_interfaces,
+        Err(err) => {
+            warn!(error = %err, "Error listing local network interfaces");
+            return None;
+        }
+    };
+    let external_ip = network_interfaces.into_iter().find_map(|(_, ip)| match ip {
+        IpAddr::V6(ip) if is_global_ipv6(&ip) => Some(ip),
+        _ => None,
+    });
+    match external_ip {
+        Some(ip) => debug!(%ip, "Found global IPv6 address of local network interface"),
+        None => warn!("No global IPv6 address found on local network interfaces"),
+    }
+    external_ip
+}
+
+/// Returns whether the IPv6 address is globally routable: it's not unspecified, loopback,
+/// link-local, unique local, multicast or an IPv4-mapped address.
+pub fn is_global_ipv6(ip: &Ipv6Addr) -> bool {
+    let first_segment = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.to_ipv4_mapped().is_some()
+        // link-local fe80::/10
+        || (first_segment & 0xffc0) == 0xfe80
+        // unique local fc00::/7
+        || (first_segment & 0xfe00) == 0xfc00
+        // documentation 2001:db8::/32
+        || (first_segment == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+/// Converts an IPv4-mapped IPv6 socket address, as observed on a dual-stack socket, to an IPv4
+/// socket address. Other addresses are returned unchanged.
+pub fn canonical_socket_addr(socket_addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(socket_addr.ip().to_canonical(), socket_addr.port())
+}
+
 pub fn upnp_for_external(listen_addr: SocketAddr) -> Option<SocketAddr> {
     info!("Connecting to UPnP gateway to map local address to external address");
     let gateway = match igd_next::search_gateway(Default::default()) {
@@ -128,3 +173,35 @@ pub fn upnp_for_external(listen_addr: SocketAddr) -> Option<SocketAddr> {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("2a01:4f8::1", true)]
+    #[case("::", false)]
+    #[case("::1", false)]
+    #[case("fe80::1", false)]
+    #[case("fd00::1", false)]
+    #[case("ff02::1", false)]
+    #[case("::ffff:10.0.0.1", false)]
+    #[case("2001:db8::1", false)]
+    fn test_is_global_ipv6(#[case] ip: Ipv6Addr, #[case] expected: bool) {
+        assert_eq!(is_global_ipv6(&ip), expected);
+    }
+
+    #[test]
+    fn test_canonical_socket_addr() {
+        let mapped: SocketAddr = "[::ffff:10.0.0.1]:9009".parse().unwrap();
+        assert_eq!(
+            canonical_socket_addr(mapped),
+            "10.0.0.1:9009".parse::<SocketAddr>().unwrap()
+        );
+        let ipv6: SocketAddr = "[2a01:4f8::1]:9009".parse().unwrap();
+        assert_eq!(canonical_socket_addr(ipv6), ipv6);
+    }
+}
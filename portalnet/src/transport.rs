@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use discv5::{enr::NodeId, RequestError};
+use tokio::sync::broadcast;
+
+use crate::discovery::{NodeAddress, ProtocolRequest};
+use ethportal_api::types::{enr::Enr, portal_wire::ProtocolId};
+
+/// The transport of the overlay networks: TALKREQ messages to remote nodes, and the records of the
+/// nodes that the transport knows about.
+///
+/// It's implemented over Discovery v5 by [`crate::discovery::Discovery`], and over channels by
+/// the in-process simulated network of the `simulation` module (with the `test-utils` feature).
+#[async_trait]
+pub trait TalkReqTransport: Send + Sync {
+    /// Returns the ENR of the local node.
+    fn local_enr(&self) -> Enr;
+
+    /// Looks up the ENR for `node_id`.
+    fn find_enr(&self, node_id: &NodeId) -> Option<Enr>;
+
+    /// Returns the ENRs in the routing table of the transport.
+    fn table_entries_enr(&self) -> Vec<Enr>;
+
+    /// Returns the cached `NodeAddress` or `None` if not cached.
+    fn cached_node_addr(&self, node_id: &NodeId) -> Option<NodeAddress>;
+
+    /// Put a `NodeAddress` into cache. If the key already exists in the cache, then it updates the
+    /// key's value and returns the old value. Otherwise, `None` is returned.
+    fn put_cached_node_addr(&self, node_addr: NodeAddress) -> Option<NodeAddress>;
+
+    /// Returns a receiver of the bootnodes that are learned after startup, e.g. from a refreshed
+    /// ENR tree, or `None` if the transport doesn't learn bootnodes after startup.
+    fn bootnode_updates(&self) -> Option<broadcast::Receiver<Vec<Enr>>> {
+        None
+    }
+
+    /// Sends a TALKREQ message to `enr`, and returns the TALKRESP.
+    async fn send_talk_req(
+        &self,
+        enr: Enr,
+        protocol: ProtocolId,
+        request: ProtocolRequest,
+    ) -> Result<Vec<u8>, RequestError>;
+}
@@ -0,0 +1,95 @@
+use std::net::IpAddr;
+
+use discv5::kbucket::Filter;
+
+use crate::types::node::Node;
+
+/// Limits the number of nodes in the same subnet, /24 for IPv4 and /64 for IPv6, so that a
+/// single operator can't fill the routing table (or a k-bucket) with its nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubnetLimitFilter {
+    limit: usize,
+}
+
+impl SubnetLimitFilter {
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl Filter<Node> for SubnetLimitFilter {
+    fn filter(
+        &self,
+        value_to_be_inserted: &Node,
+        other_vals: &mut dyn Iterator<Item = &Node>,
+    ) -> bool {
+        let subnets = node_subnets(value_to_be_inserted);
+        if subnets.is_empty() {
+            return true;
+        }
+        let count = other_vals
+            .filter(|node| {
+                node_subnets(node)
+                    .iter()
+                    .any(|subnet| subnets.contains(subnet))
+            })
+            .count();
+        count < self.limit
+    }
+}
+
+/// Returns the subnets of the IP addresses of the node's ENR.
+fn node_subnets(node: &Node) -> Vec<IpAddr> {
+    let mut subnets = vec![];
+    if let Some(ip) = node.enr.ip4() {
+        let [a, b, c, _] = ip.octets();
+        subnets.push(IpAddr::from([a, b, c, 0]));
+    }
+    if let Some(ip) = node.enr.ip6() {
+        let segments = ip.segments();
+        subnets.push(IpAddr::from([
+            segments[0],
+            segments[1],
+            segments[2],
+            segments[3],
+            0,
+            0,
+            0,
+            0,
+        ]));
+    }
+    subnets
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use discv5::enr::CombinedKey;
+    use ethportal_api::types::{distance::Distance, enr::Enr};
+
+    use super::*;
+
+    fn node(ip: Ipv4Addr) -> Node {
+        let enr = Enr::builder()
+            .ip4(ip)
+            .udp4(9009)
+            .build(&CombinedKey::generate_secp256k1())
+            .unwrap();
+        Node::new(enr, Distance::MAX)
+    }
+
+    #[test]
+    fn limits_nodes_in_same_subnet() {
+        let filter = SubnetLimitFilter::new(2);
+        let nodes = [
+            node(Ipv4Addr::new(10, 0, 0, 1)),
+            node(Ipv4Addr::new(10, 0, 0, 2)),
+        ];
+
+        assert!(filter.filter(&node(Ipv4Addr::new(10, 0, 0, 3)), &mut nodes[..1].iter()));
+        assert!(!filter.filter(&node(Ipv4Addr::new(10, 0, 0, 3)), &mut nodes.iter()));
+        assert!(filter.filter(&node(Ipv4Addr::new(10, 0, 1, 3)), &mut nodes.iter()));
+    }
+}
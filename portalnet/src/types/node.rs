@@ -1,6 +1,10 @@
 use std::fmt;
 
-use ethportal_api::types::{distance::Distance, enr::Enr};
+use ethportal_api::types::{
+    distance::Distance,
+    enr::Enr,
+    ping_payload::{PingExtensions, PingPongPayload},
+};
 
 /// A node in the overlay network routing table.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -9,12 +13,18 @@ pub struct Node {
     pub enr: Enr,
     /// The node's data radius.
     pub data_radius: Distance,
+    /// The extensions from the last Ping or Pong message of the node that carried them.
+    pub ping_extensions: Option<PingExtensions>,
 }
 
 impl Node {
     /// Creates a new node.
     pub fn new(enr: Enr, data_radius: Distance) -> Node {
-        Node { enr, data_radius }
+        Node {
+            enr,
+            data_radius,
+            ping_extensions: None,
+        }
     }
 
     /// Returns the ENR of the node.
@@ -36,6 +46,33 @@ impl Node {
     pub fn set_data_radius(&mut self, radius: Distance) {
         self.data_radius = radius;
     }
+
+    /// Returns the ping extensions of the node, if the node sent them.
+    pub fn ping_extensions(&self) -> Option<&PingExtensions> {
+        self.ping_extensions.as_ref()
+    }
+
+    /// Sets the ping extensions of the node.
+    pub fn set_ping_extensions(&mut self, ping_extensions: Option<PingExtensions>) {
+        self.ping_extensions = ping_extensions;
+    }
+
+    /// Updates the node with the custom payload of a Ping or Pong message.
+    ///
+    /// The ping extensions are only updated if the payload carries them, so that a legacy payload
+    /// doesn't erase the previously received information. Returns whether the node was changed.
+    pub fn apply_ping_payload(&mut self, payload: PingPongPayload) -> bool {
+        let mut changed = false;
+        if self.data_radius != payload.data_radius {
+            self.data_radius = payload.data_radius;
+            changed = true;
+        }
+        if payload.extensions.is_some() && self.ping_extensions != payload.extensions {
+            self.ping_extensions = payload.extensions;
+            changed = true;
+        }
+        changed
+    }
 }
 
 impl fmt::Display for Node {
@@ -8,8 +8,11 @@ use anyhow::anyhow;
 use directories::ProjectDirs;
 use discv5::enr::{CombinedKey, Enr, NodeId};
 use tempfile::TempDir;
-use tracing::debug;
+use tracing::{debug, info, warn};
 
+use super::keystore::{
+    keystore_path, read_keystore, restrict_permissions, write_keystore, KEYSTORE_PASSWORD_ENV_VAR,
+};
 use ethportal_api::{
     types::cli::DEFAULT_NETWORK,
     utils::bytes::{hex_decode, hex_encode},
@@ -48,17 +51,19 @@ pub fn configure_trin_data_dir(ephemeral: bool) -> anyhow::Result<PathBuf> {
 
 /// Configures active node data dir based on the provided private key.
 /// Returns the private key used to configure the node data dir.
-/// If no private key is provided, the application private key is used.
+/// If no private key is provided, the application private key is used, which is decrypted with
+/// the keystore password.
 pub fn configure_node_data_dir(
     trin_data_dir: PathBuf,
     private_key: Option<B256>,
     network_name: String,
+    keystore_password: Option<&str>,
 ) -> anyhow::Result<(PathBuf, B256)> {
     let pk = match private_key {
         // user has provided a custom private key...
         Some(val) => CombinedKey::secp256k1_from_bytes(val.0.clone().as_mut_slice())
             .map_err(|e| anyhow!("When building server key pair: {e:?}"))?,
-        None => get_application_private_key(&trin_data_dir)?,
+        None => get_application_private_key(&trin_data_dir, keystore_password)?,
     };
     let node_id = Enr::empty(&pk)?.node_id();
     let node_data_dir = get_node_data_dir(trin_data_dir, node_id, network_name);
@@ -94,19 +99,81 @@ fn get_default_data_dir() -> anyhow::Result<PathBuf> {
     }
 }
 
+/// Returns whether an application private key is stored in the trin data dir, either in the
+/// encrypted keystore or as plaintext.
+pub fn has_application_private_key(trin_data_dir: &Path) -> bool {
+    keystore_path(trin_data_dir).exists()
+        || trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists()
+}
+
 /// Returns application private key.
 /// If the private key does not exist (eg. brand new trin data dir),
 /// a random pk is generated and stored.
-fn get_application_private_key(trin_data_dir: &Path) -> anyhow::Result<CombinedKey> {
+///
+/// If a keystore password is provided, the private key is stored in the encrypted keystore, and
+/// a private key that was stored as plaintext is migrated into the keystore.
+pub fn get_application_private_key(
+    trin_data_dir: &Path,
+    keystore_password: Option<&str>,
+) -> anyhow::Result<CombinedKey> {
+    if keystore_path(trin_data_dir).exists() {
+        let password = keystore_password.ok_or_else(|| {
+            anyhow!("The private key is stored in an encrypted keystore, provide its password with --keystore-password-file or {KEYSTORE_PASSWORD_ENV_VAR}")
+        })?;
+        return read_keystore(trin_data_dir, password);
+    }
+
     let unsafe_private_key_file = trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME);
     if !unsafe_private_key_file.exists() {
         let pk = CombinedKey::generate_secp256k1();
+        if let Some(password) = keystore_password {
+            write_keystore(trin_data_dir, &pk, password)?;
+            return Ok(pk);
+        }
         let pk_hex = hex_encode(pk.encode());
         fs::write(&unsafe_private_key_file, pk_hex)?;
     }
-    let private_key = fs::read_to_string(unsafe_private_key_file)?;
+    let private_key = fs::read_to_string(&unsafe_private_key_file)?;
     let mut private_key = hex_decode(&private_key)?;
-    Ok(CombinedKey::secp256k1_from_bytes(&mut private_key)?)
+    let pk = CombinedKey::secp256k1_from_bytes(&mut private_key)?;
+
+    match keystore_password {
+        Some(password) => {
+            set_application_private_key(trin_data_dir, &pk, password)?;
+            info!("Migrated the plaintext private key into the encrypted keystore");
+        }
+        None => warn!(
+            "The private key is stored as plaintext, provide a keystore password with --keystore-password-file or {KEYSTORE_PASSWORD_ENV_VAR} to encrypt it"
+        ),
+    }
+    Ok(pk)
+}
+
+/// Stores the application private key in the encrypted keystore, replacing the existing
+/// application private key. A private key stored as plaintext is removed, once the keystore is
+/// verified to decrypt to the new private key.
+pub fn set_application_private_key(
+    trin_data_dir: &Path,
+    private_key: &CombinedKey,
+    keystore_password: &str,
+) -> anyhow::Result<()> {
+    write_keystore(trin_data_dir, private_key, keystore_password)?;
+    if read_keystore(trin_data_dir, keystore_password)?.encode() != private_key.encode() {
+        return Err(anyhow!(
+            "The keystore doesn't decrypt to the stored private key"
+        ));
+    }
+    let unsafe_private_key_file = trin_data_dir.join(UNSAFE_PRIVATE_KEY_FILE_NAME);
+    if unsafe_private_key_file.exists() {
+        fs::remove_file(unsafe_private_key_file)?;
+    }
+    Ok(())
+}
+
+/// Writes the private key as plaintext hex to the file, readable only by its owner.
+pub fn export_private_key(private_key: &CombinedKey, path: &Path) -> anyhow::Result<()> {
+    fs::write(path, hex_encode(private_key.encode()))?;
+    restrict_permissions(path)
 }
 
 #[cfg(test)]
@@ -119,10 +186,14 @@ pub mod test {
     #[serial]
     fn app_private_key() {
         let temp_dir = setup_temp_dir().unwrap();
-        let (_, active_pk) =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), None, "test".to_string())
-                .unwrap();
-        let app_pk = get_application_private_key(temp_dir.path()).unwrap();
+        let (_, active_pk) = configure_node_data_dir(
+            temp_dir.path().to_path_buf(),
+            None,
+            "test".to_string(),
+            None,
+        )
+        .unwrap();
+        let app_pk = get_application_private_key(temp_dir.path(), None).unwrap();
         let app_pk = B256::from_slice(&app_pk.encode());
         temp_dir.close().unwrap();
         assert_eq!(active_pk, app_pk);
@@ -134,9 +205,13 @@ pub mod test {
         let temp_dir = setup_temp_dir().unwrap();
         let pk = CombinedKey::generate_secp256k1();
         let pk = B256::from_slice(&pk.encode());
-        let (_, active_pk) =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), Some(pk), "test".to_string())
-                .unwrap();
+        let (_, active_pk) = configure_node_data_dir(
+            temp_dir.path().to_path_buf(),
+            Some(pk),
+            "test".to_string(),
+            None,
+        )
+        .unwrap();
         temp_dir.close().unwrap();
         assert_eq!(pk, active_pk);
     }
@@ -145,22 +220,74 @@ pub mod test {
     #[serial]
     fn activated_private_key_persists_over_reconfigurations() {
         let temp_dir = setup_temp_dir().unwrap();
-        let (_, app_pk) =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), None, "test".to_string())
-                .unwrap();
+        let (_, app_pk) = configure_node_data_dir(
+            temp_dir.path().to_path_buf(),
+            None,
+            "test".to_string(),
+            None,
+        )
+        .unwrap();
 
         // configure data dir to use a custom pk
         let pk = CombinedKey::generate_secp256k1();
         let pk = B256::from_slice(&pk.encode());
-        let _ =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), Some(pk), "test".to_string())
-                .unwrap();
+        let _ = configure_node_data_dir(
+            temp_dir.path().to_path_buf(),
+            Some(pk),
+            "test".to_string(),
+            None,
+        )
+        .unwrap();
 
         // reconfigure data dir with no pk, should use the original app pk
-        let (_, app_pk_2) =
-            configure_node_data_dir(temp_dir.path().to_path_buf(), None, "test".to_string())
-                .unwrap();
+        let (_, app_pk_2) = configure_node_data_dir(
+            temp_dir.path().to_path_buf(),
+            None,
+            "test".to_string(),
+            None,
+        )
+        .unwrap();
         temp_dir.close().unwrap();
         assert_eq!(app_pk, app_pk_2);
     }
+
+    #[test]
+    #[serial]
+    fn new_private_key_is_stored_in_keystore() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let (_, active_pk) = configure_node_data_dir(
+            temp_dir.path().to_path_buf(),
+            None,
+            "test".to_string(),
+            Some("password"),
+        )
+        .unwrap();
+        assert!(keystore_path(temp_dir.path()).exists());
+        assert!(!temp_dir.path().join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+
+        let app_pk = get_application_private_key(temp_dir.path(), Some("password")).unwrap();
+        assert_eq!(active_pk, B256::from_slice(&app_pk.encode()));
+
+        // The keystore can't be used without the password.
+        assert!(get_application_private_key(temp_dir.path(), None).is_err());
+        assert!(get_application_private_key(temp_dir.path(), Some("wrong")).is_err());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn plaintext_private_key_is_migrated_to_keystore() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let plaintext_pk = get_application_private_key(temp_dir.path(), None).unwrap();
+        assert!(temp_dir.path().join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+
+        let migrated_pk = get_application_private_key(temp_dir.path(), Some("password")).unwrap();
+        assert_eq!(migrated_pk.encode(), plaintext_pk.encode());
+        assert!(keystore_path(temp_dir.path()).exists());
+        assert!(!temp_dir.path().join(UNSAFE_PRIVATE_KEY_FILE_NAME).exists());
+
+        let app_pk = get_application_private_key(temp_dir.path(), Some("password")).unwrap();
+        assert_eq!(app_pk.encode(), plaintext_pk.encode());
+        temp_dir.close().unwrap();
+    }
 }
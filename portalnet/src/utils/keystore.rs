@@ -0,0 +1,164 @@
+use std::{
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use discv5::enr::CombinedKey;
+use rand::thread_rng;
+
+/// File name of the encrypted keystore, in the Web3 Secret Storage format.
+pub const KEYSTORE_FILE_NAME: &str = "keystore.json";
+
+/// Env var from which the keystore password is read, if no password file is provided.
+pub const KEYSTORE_PASSWORD_ENV_VAR: &str = "TRIN_KEYSTORE_PASSWORD";
+
+/// Returns the keystore password, read from the password file if provided, or else from the
+/// `TRIN_KEYSTORE_PASSWORD` env var. Returns `None` if neither is set.
+pub fn read_keystore_password(password_file: Option<&Path>) -> anyhow::Result<Option<String>> {
+    let password = match password_file {
+        Some(path) => {
+            let password = fs::read_to_string(path).map_err(|err| {
+                anyhow!(
+                    "Unable to read keystore password file {}: {err}",
+                    path.display()
+                )
+            })?;
+            // Ignore the trailing newline that most editors add to the file.
+            password.trim_end_matches(['\r', '\n']).to_string()
+        }
+        None => match env::var(KEYSTORE_PASSWORD_ENV_VAR) {
+            Ok(password) => password,
+            Err(_) => return Ok(None),
+        },
+    };
+    if password.is_empty() {
+        return Err(anyhow!("The keystore password must not be empty"));
+    }
+    Ok(Some(password))
+}
+
+/// Returns the path of the keystore in the trin data directory.
+pub fn keystore_path(trin_data_dir: &Path) -> PathBuf {
+    trin_data_dir.join(KEYSTORE_FILE_NAME)
+}
+
+/// Encrypts the private key with the password, and writes it to the keystore in the trin data
+/// directory, replacing an existing keystore.
+///
+/// The keystore is written into a temporary directory that only the owner can access, synced and
+/// then renamed over the existing keystore, so it's never readable by others, and a crash can't
+/// leave it partially written.
+pub fn write_keystore(
+    trin_data_dir: &Path,
+    private_key: &CombinedKey,
+    password: &str,
+) -> anyhow::Result<()> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix(".keystore")
+        .tempdir_in(trin_data_dir)
+        .map_err(|err| anyhow!("Unable to create temporary keystore directory: {err}"))?;
+    eth_keystore::encrypt_key(
+        temp_dir.path(),
+        &mut thread_rng(),
+        private_key.encode(),
+        password,
+        Some(KEYSTORE_FILE_NAME),
+    )
+    .map_err(|err| anyhow!("Unable to write keystore: {err}"))?;
+    let temp_path = keystore_path(temp_dir.path());
+    restrict_permissions(&temp_path)?;
+    File::open(&temp_path)?.sync_all()?;
+
+    let path = keystore_path(trin_data_dir);
+    fs::rename(&temp_path, &path)
+        .map_err(|err| anyhow!("Unable to write keystore {}: {err}", path.display()))?;
+    // Sync the directory, so the rename survives a crash.
+    #[cfg(unix)]
+    File::open(trin_data_dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Reads the keystore in the trin data directory, and decrypts the private key with the password.
+pub fn read_keystore(trin_data_dir: &Path, password: &str) -> anyhow::Result<CombinedKey> {
+    let path = keystore_path(trin_data_dir);
+    let mut private_key = eth_keystore::decrypt_key(&path, password)
+        .map_err(|err| anyhow!("Unable to decrypt keystore {}: {err}", path.display()))?;
+    CombinedKey::secp256k1_from_bytes(&mut private_key)
+        .map_err(|err| anyhow!("Invalid private key in keystore: {err:?}"))
+}
+
+/// Makes the file readable and writable only by its owner.
+pub fn restrict_permissions(path: &Path) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use serial_test::serial;
+
+    use crate::utils::db::setup_temp_dir;
+
+    #[test]
+    #[serial]
+    fn write_and_read_keystore() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let private_key = CombinedKey::generate_secp256k1();
+        write_keystore(temp_dir.path(), &private_key, "password").unwrap();
+
+        let decrypted = read_keystore(temp_dir.path(), "password").unwrap();
+        assert_eq!(decrypted.encode(), private_key.encode());
+
+        // The keystore is in the Web3 Secret Storage format.
+        let keystore: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(keystore_path(temp_dir.path())).unwrap())
+                .unwrap();
+        assert_eq!(keystore["version"], 3);
+        assert_eq!(keystore["crypto"]["cipher"], "aes-128-ctr");
+
+        assert!(read_keystore(temp_dir.path(), "wrong password").is_err());
+
+        // The keystore is replaced, without leaving the temporary directory behind.
+        let other_key = CombinedKey::generate_secp256k1();
+        write_keystore(temp_dir.path(), &other_key, "password").unwrap();
+        let decrypted = read_keystore(temp_dir.path(), "password").unwrap();
+        assert_eq!(decrypted.encode(), other_key.encode());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = fs::metadata(keystore_path(temp_dir.path())).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn read_password_from_file() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let password_file = temp_dir.path().join("password");
+        fs::write(&password_file, "secret\n").unwrap();
+        assert_eq!(
+            read_keystore_password(Some(&password_file)).unwrap(),
+            Some("secret".to_string())
+        );
+
+        fs::write(&password_file, "\n").unwrap();
+        assert!(read_keystore_password(Some(&password_file)).is_err());
+        assert!(read_keystore_password(Some(&temp_dir.path().join("missing"))).is_err());
+        temp_dir.close().unwrap();
+    }
+}
@@ -1,32 +1,159 @@
-use crate::discovery::UtpEnr;
+use crate::{
+    discovery::UtpEnr,
+    peer_stats::PeerStatsTable,
+    utp_transfer_limit::{AdaptiveTransferLimitConfig, TransferLimit},
+};
 use anyhow::anyhow;
-use lazy_static::lazy_static;
-use std::sync::Arc;
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use discv5::enr::NodeId;
+use ethportal_api::types::{
+    cli::{
+        DEFAULT_UTP_INBOUND_TIMEOUT_SECS, DEFAULT_UTP_MAX_STREAMS_PER_PEER,
+        DEFAULT_UTP_MIN_THROUGHPUT, DEFAULT_UTP_STALL_TIMEOUT_SECS,
+    },
+    utp_tuning::{UtpConnectionTuning, DEFAULT_UTP_MAX_PACKET_SIZE},
+};
+use parking_lot::{Mutex, RwLock};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::OwnedSemaphorePermit, time::Instant};
 use tracing::debug;
 use trin_metrics::{
     labels::{UtpDirectionLabel, UtpOutcomeLabel},
     overlay::OverlayMetricsReporter,
 };
 use utp_rs::{cid::ConnectionId, conn::ConnectionConfig, socket::UtpSocket};
+
 /// UtpController is meant to be a container which contains all code related to/for managing uTP
 /// streams We are implementing this because we want the utils of controlling uTP connection to be
 /// as contained as it can, instead of extending overlay_service even more.
-/// Currently we are implementing this to control the max utp_transfer_limit
+/// Currently we are implementing this to
+/// - control the max utp_transfer_limit, which adapts to the health of the transfers and the load
+///   of the host when adaptive (see [`TransferLimit`])
+/// - limit the number of concurrent streams with a single peer
+/// - kill uTP streams which won't send us data or are purposefully keeping the connection open,
+///   by aborting the streams whose throughput stays below a floor (see [`UtpStallConfig`])
+///
 /// But in the future this will be where we implement
 /// - thundering herd protection
-/// - killing bad uTP connections which won't send us data or is purposefully keeping the connection
-///   open
 pub struct UtpController {
-    inbound_utp_transfer_semaphore: Arc<Semaphore>,
-    outbound_utp_transfer_semaphore: Arc<Semaphore>,
+    inbound_transfer_limit: TransferLimit,
+    outbound_transfer_limit: TransferLimit,
     utp_socket: Arc<UtpSocket<UtpEnr>>,
+    /// The configuration of the uTP connections.
+    conn_config: ConnectionConfig,
+    stall_config: UtpStallConfig,
+    /// The number of active streams with each peer, in each direction.
+    inbound_peer_streams: Mutex<HashMap<NodeId, usize>>,
+    outbound_peer_streams: Mutex<HashMap<NodeId, usize>>,
+    peer_stats: Arc<RwLock<PeerStatsTable>>,
     metrics: OverlayMetricsReporter,
 }
 
-lazy_static! {
-    /// The default configuration to use for uTP connections.
-    pub static ref UTP_CONN_CFG: ConnectionConfig = ConnectionConfig { max_packet_size: 1024, ..Default::default()};
+/// Returns the configuration of uTP connections, with the tuning applied to the defaults.
+pub fn utp_conn_config(tuning: &UtpConnectionTuning) -> ConnectionConfig {
+    let mut config = ConnectionConfig {
+        max_packet_size: tuning
+            .max_packet_size
+            .unwrap_or(DEFAULT_UTP_MAX_PACKET_SIZE),
+        ..Default::default()
+    };
+    if let Some(timeout) = tuning.initial_timeout_ms {
+        config.initial_timeout = Duration::from_millis(timeout);
+    }
+    if let Some(timeout) = tuning.idle_timeout_ms {
+        config.max_idle_timeout = Duration::from_millis(timeout);
+    }
+    if let Some(attempts) = tuning.max_conn_attempts {
+        config.max_conn_attempts = attempts;
+    }
+    if let Some(window_size) = tuning.window_size {
+        config.window_size = window_size;
+    }
+    config
+}
+
+/// The configuration of the detection of stalled uTP streams.
+///
+/// An outbound stream only reports that the peer acknowledged the data once the stream is
+/// closed, so the throughput floor of an outbound stream is applied to the whole transfer: the
+/// stream is stalled when the peer doesn't acknowledge all data within `stall_timeout` plus the
+/// time it takes to transfer the data at `min_throughput`. uTP streams only report inbound data
+/// once the whole stream is read, so inbound streams are limited by `inbound_timeout` instead.
+/// Both outbound streams aborted by the throughput floor and inbound streams aborted by the
+/// timeout are counted as stalls of the peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UtpStallConfig {
+    /// The throughput floor of an outbound stream, in bytes per second, or 0 for no floor.
+    pub min_throughput: u64,
+    /// The time an outbound stream may take in addition to the time it takes to transfer the
+    /// data at the throughput floor, before it's aborted.
+    pub stall_timeout: Duration,
+    /// The maximum duration of an inbound stream.
+    pub inbound_timeout: Duration,
+    /// The maximum number of concurrent streams with a single peer, in each direction.
+    pub max_streams_per_peer: usize,
+}
+
+impl Default for UtpStallConfig {
+    fn default() -> Self {
+        Self {
+            min_throughput: DEFAULT_UTP_MIN_THROUGHPUT,
+            stall_timeout: Duration::from_secs(DEFAULT_UTP_STALL_TIMEOUT_SECS),
+            inbound_timeout: Duration::from_secs(DEFAULT_UTP_INBOUND_TIMEOUT_SECS),
+            max_streams_per_peer: DEFAULT_UTP_MAX_STREAMS_PER_PEER,
+        }
+    }
+}
+
+impl UtpStallConfig {
+    /// Returns the time within which the peer must acknowledge `data_len` bytes sent over an
+    /// outbound stream, or `None` if there is no throughput floor.
+    fn outbound_timeout(&self, data_len: usize) -> Option<Duration> {
+        if self.min_throughput == 0 {
+            return None;
+        }
+        Some(
+            self.stall_timeout
+                + Duration::from_secs_f64(data_len as f64 / self.min_throughput as f64),
+        )
+    }
+}
+
+/// A slot of the per-peer stream limit, released when dropped.
+struct PeerStreamGuard<'a> {
+    peer_streams: &'a Mutex<HashMap<NodeId, usize>>,
+    node_id: NodeId,
+}
+
+impl<'a> PeerStreamGuard<'a> {
+    /// Takes a slot for a stream with the peer, unless the peer already has `limit` streams.
+    fn acquire(
+        peer_streams: &'a Mutex<HashMap<NodeId, usize>>,
+        node_id: NodeId,
+        limit: usize,
+    ) -> Option<Self> {
+        let mut peer_streams_guard = peer_streams.lock();
+        let streams = peer_streams_guard.entry(node_id).or_default();
+        if *streams >= limit {
+            return None;
+        }
+        *streams += 1;
+        Some(Self {
+            peer_streams,
+            node_id,
+        })
+    }
+}
+
+impl Drop for PeerStreamGuard<'_> {
+    fn drop(&mut self) {
+        let mut peer_streams = self.peer_streams.lock();
+        if let Some(streams) = peer_streams.get_mut(&self.node_id) {
+            *streams = streams.saturating_sub(1);
+            if *streams == 0 {
+                peer_streams.remove(&self.node_id);
+            }
+        }
+    }
 }
 
 /// An enum for deciding to initiate the uTP connection as connecting or accepting.
@@ -40,13 +167,32 @@ enum UtpConnectionSide {
 impl UtpController {
     pub fn new(
         utp_transfer_limit: usize,
+        adaptive_transfer_limit: Option<AdaptiveTransferLimitConfig>,
+        stall_config: UtpStallConfig,
+        conn_tuning: UtpConnectionTuning,
         utp_socket: Arc<UtpSocket<UtpEnr>>,
+        peer_stats: Arc<RwLock<PeerStatsTable>>,
         metrics: OverlayMetricsReporter,
     ) -> Self {
+        let inbound_transfer_limit =
+            TransferLimit::new(utp_transfer_limit, adaptive_transfer_limit);
+        let outbound_transfer_limit =
+            TransferLimit::new(utp_transfer_limit, adaptive_transfer_limit);
+        metrics
+            .report_utp_transfer_limit(UtpDirectionLabel::Inbound, inbound_transfer_limit.limit());
+        metrics.report_utp_transfer_limit(
+            UtpDirectionLabel::Outbound,
+            outbound_transfer_limit.limit(),
+        );
         Self {
             utp_socket,
-            inbound_utp_transfer_semaphore: Arc::new(Semaphore::new(utp_transfer_limit)),
-            outbound_utp_transfer_semaphore: Arc::new(Semaphore::new(utp_transfer_limit)),
+            conn_config: utp_conn_config(&conn_tuning),
+            inbound_transfer_limit,
+            outbound_transfer_limit,
+            stall_config,
+            inbound_peer_streams: Mutex::new(HashMap::new()),
+            outbound_peer_streams: Mutex::new(HashMap::new()),
+            peer_stats,
             metrics,
         }
     }
@@ -56,31 +202,17 @@ impl UtpController {
     }
 
     /// Non-blocking method to try and acquire a permit for an outbound uTP transfer.
-    // `try_acquire_owned()` isn't blocking and will instantly return with
-    // `Some(TryAcquireError::NoPermits)` error if there isn't a permit available
+    // `try_acquire()` isn't blocking and will instantly return with `None` if there isn't a
+    // permit available
     pub fn get_outbound_semaphore(&self) -> Option<OwnedSemaphorePermit> {
-        match self
-            .outbound_utp_transfer_semaphore
-            .clone()
-            .try_acquire_owned()
-        {
-            Ok(permit) => Some(permit),
-            Err(_) => None,
-        }
+        self.outbound_transfer_limit.try_acquire()
     }
 
     /// Non-blocking method to try and acquire a permit for an inbound uTP transfer.
-    // `try_acquire_owned()` isn't blocking and will instantly return with
-    // `Some(TryAcquireError::NoPermits)` error if there isn't a permit available
+    // `try_acquire()` isn't blocking and will instantly return with `None` if there isn't a
+    // permit available
     pub fn get_inbound_semaphore(&self) -> Option<OwnedSemaphorePermit> {
-        match self
-            .inbound_utp_transfer_semaphore
-            .clone()
-            .try_acquire_owned()
-        {
-            Ok(permit) => Some(permit),
-            Err(_) => None,
-        }
+        self.inbound_transfer_limit.try_acquire()
     }
 
     pub async fn connect_inbound_stream(
@@ -116,34 +248,73 @@ impl UtpController {
         // stream.
         self.metrics
             .report_utp_active_inc(UtpDirectionLabel::Inbound);
+        let started = Instant::now();
+        let node_id = cid.peer.node_id();
+        let Some(_peer_stream) = PeerStreamGuard::acquire(
+            &self.inbound_peer_streams,
+            node_id,
+            self.stall_config.max_streams_per_peer,
+        ) else {
+            self.report_outcome(
+                UtpDirectionLabel::Inbound,
+                UtpOutcomeLabel::PeerLimit,
+                started,
+            );
+            debug!(cid.send, cid.recv, peer = ?cid.peer.client(), "too many inbound uTP streams with peer");
+            return Err(anyhow!(
+                "Unable to locate content on the network: too many inbound uTP streams with peer"
+            ));
+        };
         let (stream, message) = match side {
             UtpConnectionSide::Connect => (
                 self.utp_socket
-                    .connect_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .connect_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "connect inbound uTP stream",
             ),
             UtpConnectionSide::Accept => (
                 self.utp_socket
-                    .accept_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .accept_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "accept inbound uTP stream",
             ),
         };
         let mut stream = stream.map_err(|err| {
-            self.metrics.report_utp_outcome(
+            self.report_outcome(
                 UtpDirectionLabel::Inbound,
                 UtpOutcomeLabel::FailedConnection,
+                started,
             );
             debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to {message}");
             anyhow!("Unable to locate content on the network: unable to {message}")
         })?;
 
+        // The stream only reports the data once it's read to the end, so its throughput can't be
+        // observed. Idle streams are closed by the uTP idle timeout, and the inbound timeout
+        // limits the duration of slow streams. The timeout is long enough that the streams
+        // exceeding it are counted as stalls of the peer, which keeps the peer from being
+        // offered content for a while.
         let mut data = vec![];
-        stream.read_to_eof(&mut data).await
+        let Ok(result) = tokio::time::timeout(
+            self.stall_config.inbound_timeout,
+            stream.read_to_eof(&mut data),
+        )
+        .await
+        else {
+            self.report_outcome(
+                UtpDirectionLabel::Inbound,
+                UtpOutcomeLabel::TimedOut,
+                started,
+            );
+            self.peer_stats.write().record_utp_stall(node_id);
+            debug!(cid.send, cid.recv, peer = ?cid.peer.client(), "{message} timed out");
+            return Err(anyhow!(
+                "Unable to locate content on the network: {message} timed out"
+            ));
+        };
+        result
             .map_err(|err| {
-                self.metrics
-                    .report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::FailedDataTx);
+                self.report_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::FailedDataTx, started);
                 debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "error reading data from {message}");
                 anyhow!(
                     "Unable to locate content on the network: error reading data from {message}"
@@ -151,8 +322,11 @@ impl UtpController {
             })?;
 
         // report utp tx as successful, even if we go on to fail to process the payload
-        self.metrics
-            .report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::Success);
+        self.report_outcome(
+            UtpDirectionLabel::Inbound,
+            UtpOutcomeLabel::Success,
+            started,
+        );
         Ok(data)
     }
 
@@ -164,16 +338,36 @@ impl UtpController {
     ) -> bool {
         self.metrics
             .report_utp_active_inc(UtpDirectionLabel::Outbound);
+        let started = Instant::now();
+        let node_id = cid.peer.node_id();
+        let Some(_peer_stream) = PeerStreamGuard::acquire(
+            &self.outbound_peer_streams,
+            node_id,
+            self.stall_config.max_streams_per_peer,
+        ) else {
+            self.report_outcome(
+                UtpDirectionLabel::Outbound,
+                UtpOutcomeLabel::PeerLimit,
+                started,
+            );
+            debug!(
+                cid.send,
+                cid.recv,
+                peer = ?cid.peer.client(),
+                "Unable to establish uTP conn, too many outbound uTP streams with peer",
+            );
+            return false;
+        };
         let (stream, message) = match side {
             UtpConnectionSide::Connect => (
                 self.utp_socket
-                    .connect_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .connect_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "outbound connect with cid",
             ),
             UtpConnectionSide::Accept => (
                 self.utp_socket
-                    .accept_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .accept_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "outbound accept with cid",
             ),
@@ -181,9 +375,10 @@ impl UtpController {
         let mut stream = match stream {
             Ok(stream) => stream,
             Err(err) => {
-                self.metrics.report_utp_outcome(
+                self.report_outcome(
                     UtpDirectionLabel::Outbound,
                     UtpOutcomeLabel::FailedConnection,
+                    started,
                 );
                 debug!(
                     %err,
@@ -196,12 +391,38 @@ impl UtpController {
             }
         };
 
-        match stream.write(&data).await {
-            Ok(write_size) => {
+        // The stream only reports that the peer acknowledged the data once it's closed, so the
+        // throughput floor is applied to the whole transfer, including the close.
+        let transfer = async {
+            let write_size = stream.write(&data).await?;
+            // close uTP connection
+            let close = stream.close().await;
+            Ok::<_, std::io::Error>((write_size, close))
+        };
+        let outbound_timeout = self.stall_config.outbound_timeout(data.len());
+        let result = match outbound_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, transfer).await,
+            None => Ok(transfer.await),
+        };
+        let Ok(result) = result else {
+            self.report_stall(node_id, started);
+            debug!(
+                %cid.send,
+                %cid.recv,
+                peer = ?cid.peer.client(),
+                "Error sending content over uTP, in response to uTP stream stalled: {} bytes not acknowledged within {outbound_timeout:?}",
+                data.len()
+            );
+            return false;
+        };
+
+        let close = match result {
+            Ok((write_size, close)) => {
                 if write_size != data.len() {
-                    self.metrics.report_utp_outcome(
+                    self.report_outcome(
                         UtpDirectionLabel::Outbound,
                         UtpOutcomeLabel::FailedDataTx,
+                        started,
                     );
                     debug!(
                         %cid.send,
@@ -212,10 +433,14 @@ impl UtpController {
                     );
                     return false;
                 }
+                close
             }
             Err(err) => {
-                self.metrics
-                    .report_utp_outcome(UtpDirectionLabel::Outbound, UtpOutcomeLabel::FailedDataTx);
+                self.report_outcome(
+                    UtpDirectionLabel::Outbound,
+                    UtpOutcomeLabel::FailedDataTx,
+                    started,
+                );
                 debug!(
                     %err,
                     %cid.send,
@@ -225,12 +450,14 @@ impl UtpController {
                 );
                 return false;
             }
-        }
+        };
 
-        // close uTP connection
-        if let Err(err) = stream.close().await {
-            self.metrics
-                .report_utp_outcome(UtpDirectionLabel::Outbound, UtpOutcomeLabel::FailedShutdown);
+        if let Err(err) = close {
+            self.report_outcome(
+                UtpDirectionLabel::Outbound,
+                UtpOutcomeLabel::FailedShutdown,
+                started,
+            );
             debug!(
                 %err,
                 %cid.send,
@@ -240,8 +467,140 @@ impl UtpController {
             );
             return false;
         };
-        self.metrics
-            .report_utp_outcome(UtpDirectionLabel::Outbound, UtpOutcomeLabel::Success);
+        self.report_outcome(
+            UtpDirectionLabel::Outbound,
+            UtpOutcomeLabel::Success,
+            started,
+        );
         true
     }
+
+    /// Reports an outbound stream that was aborted because it stalled, to the metrics, the
+    /// transfer limit and the peer stats.
+    fn report_stall(&self, node_id: NodeId, started: Instant) {
+        self.report_outcome(
+            UtpDirectionLabel::Outbound,
+            UtpOutcomeLabel::Stalled,
+            started,
+        );
+        self.peer_stats.write().record_utp_stall(node_id);
+    }
+
+    /// Reports the outcome of a stream that started at `started`, to the metrics and the transfer
+    /// limit of its direction.
+    fn report_outcome(
+        &self,
+        direction: UtpDirectionLabel,
+        outcome: UtpOutcomeLabel,
+        started: Instant,
+    ) {
+        self.metrics.report_utp_outcome(direction, outcome);
+        let success = match outcome {
+            UtpOutcomeLabel::Success => true,
+            UtpOutcomeLabel::FailedConnection
+            | UtpOutcomeLabel::FailedDataTx
+            | UtpOutcomeLabel::FailedShutdown
+            | UtpOutcomeLabel::Stalled
+            | UtpOutcomeLabel::TimedOut => false,
+            // The stream was refused before it was established, so it doesn't reflect the health
+            // of the transfers.
+            UtpOutcomeLabel::PeerLimit => return,
+        };
+        let transfer_limit = match direction {
+            UtpDirectionLabel::Inbound => &self.inbound_transfer_limit,
+            UtpDirectionLabel::Outbound => &self.outbound_transfer_limit,
+        };
+        if let Some(limit) = transfer_limit.record(success, started.elapsed()) {
+            debug!(?direction, limit, protocol = %self.metrics.protocol, "Adjusted uTP transfer limit");
+            self.metrics.report_utp_transfer_limit(direction, limit);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utp_conn_config_defaults() {
+        let config = utp_conn_config(&UtpConnectionTuning::default());
+        let default_config = ConnectionConfig::default();
+        assert_eq!(config.max_packet_size, DEFAULT_UTP_MAX_PACKET_SIZE);
+        assert_eq!(config.initial_timeout, default_config.initial_timeout);
+        assert_eq!(config.max_idle_timeout, default_config.max_idle_timeout);
+        assert_eq!(config.max_conn_attempts, default_config.max_conn_attempts);
+        assert_eq!(config.window_size, default_config.window_size);
+    }
+
+    #[test]
+    fn utp_conn_config_tuning() {
+        let config = utp_conn_config(&UtpConnectionTuning {
+            max_packet_size: Some(512),
+            initial_timeout_ms: Some(3000),
+            idle_timeout_ms: Some(30000),
+            max_conn_attempts: Some(5),
+            window_size: Some(65536),
+        });
+        assert_eq!(config.max_packet_size, 512);
+        assert_eq!(config.initial_timeout, Duration::from_secs(3));
+        assert_eq!(config.max_idle_timeout, Duration::from_secs(30));
+        assert_eq!(config.max_conn_attempts, 5);
+        assert_eq!(config.window_size, 65536);
+    }
+
+    fn stall_config() -> UtpStallConfig {
+        UtpStallConfig {
+            min_throughput: 1000,
+            stall_timeout: Duration::from_secs(10),
+            inbound_timeout: Duration::from_secs(60),
+            max_streams_per_peer: 2,
+        }
+    }
+
+    #[test]
+    fn outbound_timeout() {
+        let config = stall_config();
+        assert_eq!(config.outbound_timeout(0), Some(Duration::from_secs(10)));
+        assert_eq!(config.outbound_timeout(5000), Some(Duration::from_secs(15)));
+        assert_eq!(
+            config.outbound_timeout(1_000_000),
+            Some(Duration::from_secs(1010))
+        );
+    }
+
+    #[test]
+    fn outbound_timeout_without_floor() {
+        let config = UtpStallConfig {
+            min_throughput: 0,
+            ..stall_config()
+        };
+        assert_eq!(config.outbound_timeout(1_000_000), None);
+    }
+
+    #[test]
+    fn peer_stream_limit() {
+        let peer_streams = Mutex::new(HashMap::new());
+        let node_id = NodeId::random();
+        let other_node_id = NodeId::random();
+
+        let first = PeerStreamGuard::acquire(&peer_streams, node_id, 2).unwrap();
+        let _second = PeerStreamGuard::acquire(&peer_streams, node_id, 2).unwrap();
+        assert!(PeerStreamGuard::acquire(&peer_streams, node_id, 2).is_none());
+        assert!(PeerStreamGuard::acquire(&peer_streams, other_node_id, 2).is_some());
+
+        drop(first);
+        assert!(PeerStreamGuard::acquire(&peer_streams, node_id, 2).is_some());
+    }
+
+    #[test]
+    fn peer_stream_guard_releases_slot() {
+        let peer_streams = Mutex::new(HashMap::new());
+        let node_id = NodeId::random();
+        {
+            let _guard = PeerStreamGuard::acquire(&peer_streams, node_id, 1).unwrap();
+            assert_eq!(peer_streams.lock().get(&node_id), Some(&1));
+        }
+        assert!(peer_streams.lock().is_empty());
+    }
 }
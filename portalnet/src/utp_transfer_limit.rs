@@ -0,0 +1,353 @@
+use std::{sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+/// The minimum number of transfers in a window before the limit is adjusted.
+const ADJUST_MIN_TRANSFERS: u64 = 20;
+
+/// The maximum duration of a window. The limit is adjusted at the end of the window, if there were
+/// any transfers.
+const ADJUST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The success rate below which the limit is decreased.
+const MIN_SUCCESS_RATE: f64 = 0.8;
+
+/// The success rate above which the limit may be increased.
+const GROW_SUCCESS_RATE: f64 = 0.95;
+
+/// The factor of the baseline latency above which the limit is decreased.
+const MAX_LATENCY_FACTOR: f64 = 2.0;
+
+/// The host load (1 minute load average per CPU) above which the limit is decreased.
+const MAX_HOST_LOAD: f64 = 1.0;
+
+/// The weight of the latest window in the baseline latency.
+const BASELINE_LATENCY_WEIGHT: f64 = 0.2;
+
+/// The bounds of an adaptive uTP transfer limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveTransferLimitConfig {
+    pub min: usize,
+    pub max: usize,
+}
+
+/// A limit of the concurrent uTP transfers in one direction.
+///
+/// The limit is either fixed, or adaptive. An adaptive limit grows while the transfers are
+/// healthy and the limit is reached, and shrinks when the success rate drops, the latency grows
+/// or the host is overloaded.
+pub struct TransferLimit {
+    semaphore: Arc<Semaphore>,
+    adaptive: Option<Mutex<AdaptiveLimit>>,
+    fixed_limit: usize,
+}
+
+impl TransferLimit {
+    /// Creates a limit, which is adaptive between the bounds of `adaptive` if set.
+    pub fn new(limit: usize, adaptive: Option<AdaptiveTransferLimitConfig>) -> Self {
+        match adaptive {
+            None => Self {
+                semaphore: Arc::new(Semaphore::new(limit)),
+                adaptive: None,
+                fixed_limit: limit,
+            },
+            Some(config) => {
+                // The semaphore has the maximum number of permits, and the permits above the
+                // current limit are held back.
+                let semaphore = Arc::new(Semaphore::new(config.max));
+                let mut adaptive = AdaptiveLimit {
+                    config,
+                    limit: limit.clamp(config.min, config.max),
+                    held_back: vec![],
+                    window: TransferWindow::new(Instant::now()),
+                    baseline_latency: None,
+                };
+                adaptive.hold_back_permits(&semaphore);
+                Self {
+                    semaphore,
+                    adaptive: Some(Mutex::new(adaptive)),
+                    fixed_limit: limit,
+                }
+            }
+        }
+    }
+
+    /// Returns the current limit.
+    pub fn limit(&self) -> usize {
+        match &self.adaptive {
+            Some(adaptive) => adaptive.lock().limit,
+            None => self.fixed_limit,
+        }
+    }
+
+    /// Non-blocking method to try and acquire a permit for a transfer.
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = self.semaphore.clone().try_acquire_owned().ok();
+        if permit.is_none() {
+            if let Some(adaptive) = &self.adaptive {
+                adaptive.lock().window.rejected += 1;
+            }
+        }
+        permit
+    }
+
+    /// Records the outcome of a transfer and its latency, and adjusts an adaptive limit at the
+    /// end of the window. Returns the new limit, if it changed.
+    pub fn record(&self, success: bool, latency: Duration) -> Option<usize> {
+        let mut adaptive = self.adaptive.as_ref()?.lock();
+        // Hold back the permits of the transfers that ended since the limit was decreased.
+        adaptive.hold_back_permits(&self.semaphore);
+        adaptive.window.record(success, latency);
+
+        let now = Instant::now();
+        if !adaptive.window.is_complete(now) {
+            return None;
+        }
+        let window = std::mem::replace(&mut adaptive.window, TransferWindow::new(now));
+        let limit = adaptive.limit;
+        adaptive.limit = adaptive.next_limit(&window, host_load());
+        if let Some(latency) = window.average_latency() {
+            adaptive.update_baseline_latency(latency);
+        }
+        adaptive.release_permits();
+        adaptive.hold_back_permits(&self.semaphore);
+        (adaptive.limit != limit).then_some(adaptive.limit)
+    }
+}
+
+/// The state of an adaptive limit.
+struct AdaptiveLimit {
+    config: AdaptiveTransferLimitConfig,
+    limit: usize,
+    /// The permits above the limit, which can't be acquired for transfers.
+    held_back: Vec<OwnedSemaphorePermit>,
+    window: TransferWindow,
+    /// The moving average of the latency of successful transfers.
+    baseline_latency: Option<Duration>,
+}
+
+impl AdaptiveLimit {
+    /// Returns the limit for the next window, given the transfers of the last window and the host
+    /// load.
+    fn next_limit(&self, window: &TransferWindow, host_load: Option<f64>) -> usize {
+        let success_rate = window.success_rate();
+        let latency_increased = match (window.average_latency(), self.baseline_latency) {
+            (Some(latency), Some(baseline)) => {
+                latency.as_secs_f64() > baseline.as_secs_f64() * MAX_LATENCY_FACTOR
+            }
+            _ => false,
+        };
+        let overloaded = host_load.is_some_and(|load| load > MAX_HOST_LOAD);
+
+        let limit = if success_rate < MIN_SUCCESS_RATE || latency_increased || overloaded {
+            // Decrease multiplicatively, by at least 1.
+            (self.limit * 3 / 4).min(self.limit.saturating_sub(1))
+        } else if success_rate >= GROW_SUCCESS_RATE && window.rejected > 0 {
+            // Only grow when the limit was reached, by at least 1.
+            self.limit + (self.limit / 10).max(1)
+        } else {
+            self.limit
+        };
+        limit.clamp(self.config.min, self.config.max)
+    }
+
+    fn update_baseline_latency(&mut self, latency: Duration) {
+        self.baseline_latency = Some(match self.baseline_latency {
+            Some(baseline) => {
+                baseline.mul_f64(1.0 - BASELINE_LATENCY_WEIGHT)
+                    + latency.mul_f64(BASELINE_LATENCY_WEIGHT)
+            }
+            None => latency,
+        });
+    }
+
+    /// Holds back the available permits above the limit.
+    fn hold_back_permits(&mut self, semaphore: &Arc<Semaphore>) {
+        while self.config.max - self.held_back.len() > self.limit {
+            match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => self.held_back.push(permit),
+                // The remaining permits are held back once the active transfers end.
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Releases the held back permits below the limit, which returns them to the semaphore.
+    fn release_permits(&mut self) {
+        self.held_back
+            .truncate(self.config.max.saturating_sub(self.limit));
+    }
+}
+
+/// The transfers of a window.
+struct TransferWindow {
+    start: Instant,
+    successes: u64,
+    failures: u64,
+    /// The number of transfers that were rejected because the limit was reached.
+    rejected: u64,
+    /// The total latency of the successful transfers.
+    total_latency: Duration,
+}
+
+impl TransferWindow {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            successes: 0,
+            failures: 0,
+            rejected: 0,
+            total_latency: Duration::ZERO,
+        }
+    }
+
+    fn record(&mut self, success: bool, latency: Duration) {
+        if success {
+            self.successes += 1;
+            self.total_latency += latency;
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    fn transfers(&self) -> u64 {
+        self.successes + self.failures
+    }
+
+    fn is_complete(&self, now: Instant) -> bool {
+        self.transfers() >= ADJUST_MIN_TRANSFERS
+            || (self.transfers() > 0 && now.duration_since(self.start) >= ADJUST_INTERVAL)
+    }
+
+    fn success_rate(&self) -> f64 {
+        match self.transfers() {
+            0 => 1.0,
+            transfers => self.successes as f64 / transfers as f64,
+        }
+    }
+
+    fn average_latency(&self) -> Option<Duration> {
+        (self.successes > 0).then(|| self.total_latency / self.successes as u32)
+    }
+}
+
+/// Returns the 1 minute load average per CPU of the host, if it's available (only on Linux).
+fn host_load() -> Option<f64> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+    let cpus = std::thread::available_parallelism().ok()?.get();
+    Some(load / cpus as f64)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    const CONFIG: AdaptiveTransferLimitConfig = AdaptiveTransferLimitConfig { min: 4, max: 40 };
+
+    fn adaptive_limit(limit: usize) -> AdaptiveLimit {
+        AdaptiveLimit {
+            config: CONFIG,
+            limit,
+            held_back: vec![],
+            window: TransferWindow::new(Instant::now()),
+            baseline_latency: Some(Duration::from_secs(1)),
+        }
+    }
+
+    fn window(successes: u64, failures: u64, rejected: u64, latency: Duration) -> TransferWindow {
+        let mut window = TransferWindow::new(Instant::now());
+        for _ in 0..successes {
+            window.record(true, latency);
+        }
+        for _ in 0..failures {
+            window.record(false, Duration::ZERO);
+        }
+        window.rejected = rejected;
+        window
+    }
+
+    #[rstest]
+    // Healthy and saturated: grow
+    #[case(20, 20, 0, 1, 1000, None, 22)]
+    #[case(20, 20, 0, 1, 1000, Some(0.5), 22)]
+    #[case(40, 20, 0, 1, 1000, None, 40)]
+    // Healthy, but not saturated: keep
+    #[case(20, 20, 0, 0, 1000, None, 20)]
+    // Failing: shrink
+    #[case(20, 15, 5, 1, 1000, None, 15)]
+    #[case(4, 15, 5, 1, 1000, None, 4)]
+    // Slow: shrink
+    #[case(20, 20, 0, 1, 3000, None, 15)]
+    // Overloaded: shrink
+    #[case(20, 20, 0, 1, 1000, Some(2.0), 15)]
+    #[case(5, 20, 0, 1, 1000, Some(2.0), 4)]
+    fn next_limit(
+        #[case] limit: usize,
+        #[case] successes: u64,
+        #[case] failures: u64,
+        #[case] rejected: u64,
+        #[case] latency_ms: u64,
+        #[case] host_load: Option<f64>,
+        #[case] expected: usize,
+    ) {
+        let adaptive = adaptive_limit(limit);
+        let window = window(
+            successes,
+            failures,
+            rejected,
+            Duration::from_millis(latency_ms),
+        );
+        assert_eq!(adaptive.next_limit(&window, host_load), expected);
+    }
+
+    #[test]
+    fn fixed_limit() {
+        let limit = TransferLimit::new(2, None);
+        let _first = limit.try_acquire().unwrap();
+        let _second = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+        assert_eq!(limit.record(true, Duration::from_secs(1)), None);
+        assert_eq!(limit.limit(), 2);
+    }
+
+    #[test]
+    fn adaptive_limit_holds_back_permits() {
+        let limit = TransferLimit::new(100, Some(CONFIG));
+        assert_eq!(limit.limit(), CONFIG.max);
+
+        let limit = TransferLimit::new(5, Some(CONFIG));
+        assert_eq!(limit.limit(), 5);
+        let permits: Vec<_> = (0..5).map(|_| limit.try_acquire().unwrap()).collect();
+        assert!(limit.try_acquire().is_none());
+        drop(permits);
+        assert!(limit.try_acquire().is_some());
+    }
+
+    #[test]
+    fn adaptive_limit_shrinks_with_active_transfers() {
+        let limit = TransferLimit::new(8, Some(CONFIG));
+        let mut permits: Vec<_> = (0..8).map(|_| limit.try_acquire().unwrap()).collect();
+
+        // All transfers of the window fail, so the limit decreases to 6, while all permits are in
+        // use.
+        let mut new_limit = None;
+        for _ in 0..ADJUST_MIN_TRANSFERS {
+            new_limit = new_limit.or(limit.record(false, Duration::ZERO));
+        }
+        assert_eq!(new_limit, Some(6));
+
+        // The permits of the ended transfers are held back, until the limit is reached.
+        permits.truncate(5);
+        assert!(limit.record(false, Duration::ZERO).is_none());
+        let _permit = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+    }
+}
@@ -0,0 +1,263 @@
+// Tests of the overlay dynamics in an in-process simulated network.
+//
+// The tests run on a current-thread runtime with a paused clock, which advances whenever all the
+// nodes are idle, so the simulated latencies and timeouts don't slow the tests down. All the
+// random choices of the nodes are derived from the seed of the network, so the routing tables
+// and the lookups are the same on every run. The gossiped content is transferred over uTP, whose
+// library makes its own random choices, so only bounds of the gossip coverage are asserted.
+
+use std::{future::Future, time::Duration};
+
+use discv5::enr::NodeId;
+use tokio::{runtime, time};
+
+use ethportal_api::{
+    types::{
+        content_key::overlay::IdentityContentKey,
+        distance::{Distance, Metric, XorMetric},
+        portal_wire::ProtocolId,
+    },
+    OverlayContentKey,
+};
+use portalnet::{
+    overlay::config::OverlayConfig,
+    simulation::{LinkConditions, SimulatedNetwork, SimulatedNode},
+};
+use trin_storage::ContentStore;
+
+const SEED: u64 = 0x5eed;
+const PING_QUEUE_INTERVAL: Duration = Duration::from_secs(30);
+/// The time the nodes are given to populate their routing tables.
+const BOOTSTRAP_DURATION: Duration = Duration::from_secs(5 * 60);
+/// The time gossip is given to propagate through the network.
+const GOSSIP_DURATION: Duration = Duration::from_secs(60);
+
+/// The pending entries of the routing tables time out on the system clock, which isn't paused,
+/// so they're kept pending for the whole simulation.
+const BUCKET_PENDING_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn overlay_config(bootnode: Option<&SimulatedNode>) -> OverlayConfig {
+    OverlayConfig {
+        bootnode_enrs: bootnode.map(|node| vec![node.enr()]).unwrap_or_default(),
+        ping_queue_interval: Some(PING_QUEUE_INTERVAL),
+        bucket_pending_timeout: BUCKET_PENDING_TIMEOUT,
+        ..Default::default()
+    }
+}
+
+/// Runs the simulation on a current-thread runtime with a paused clock, so that its tasks run in
+/// a fixed order.
+fn run_simulation<F: Future>(simulation: F) -> F::Output {
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .unwrap()
+        .block_on(simulation)
+}
+
+/// Spawns `num_nodes` nodes, which bootstrap from the first node.
+async fn spawn_nodes(network: &SimulatedNetwork, num_nodes: usize) -> Vec<SimulatedNode> {
+    let bootnode = network
+        .spawn_overlay(ProtocolId::History, overlay_config(None))
+        .await;
+    let mut nodes = Vec::with_capacity(num_nodes);
+    for _ in 1..num_nodes {
+        let node = network
+            .spawn_overlay(ProtocolId::History, overlay_config(Some(&bootnode)))
+            .await;
+        nodes.push(node);
+    }
+    nodes.insert(0, bootnode);
+    time::sleep(BOOTSTRAP_DURATION).await;
+    nodes
+}
+
+fn content(index: u8) -> (IdentityContentKey, Vec<u8>) {
+    let mut key = [0u8; 32];
+    key[0] = index;
+    key[31] = index.wrapping_mul(31);
+    (IdentityContentKey::new(key), vec![index; 64])
+}
+
+fn stored_by(nodes: &[SimulatedNode], key: &IdentityContentKey) -> usize {
+    nodes
+        .iter()
+        .filter(|node| matches!(node.store.read().get(key), Ok(Some(_))))
+        .count()
+}
+
+/// Returns the node ids of the routing table of each node.
+fn routing_tables(nodes: &[SimulatedNode]) -> Vec<Vec<NodeId>> {
+    nodes
+        .iter()
+        .map(|node| {
+            node.overlay
+                .routing_table_detail()
+                .nodes
+                .into_iter()
+                .map(|detail| detail.enr.node_id())
+                .collect()
+        })
+        .collect()
+}
+
+#[test_log::test]
+fn query_convergence() {
+    run_simulation(async {
+        let network = SimulatedNetwork::new(
+            SEED,
+            LinkConditions {
+                latency: Duration::from_millis(20),
+                loss: 0.0,
+            },
+        );
+        let nodes = spawn_nodes(&network, 200).await;
+        assert_eq!(network.len(), 200);
+
+        for index in 0..8 {
+            let (key, _) = content(index);
+            let target = key.content_id();
+            let closest = nodes
+                .iter()
+                .map(|node| node.node_id())
+                .min_by_key(|node_id| XorMetric::distance(&node_id.raw(), &target))
+                .unwrap();
+            let source = &nodes[(index as usize * 23 + 1) % nodes.len()];
+            if source.node_id() == closest {
+                continue;
+            }
+
+            let found = source.overlay.lookup_node(target.into()).await;
+            assert!(
+                found.iter().any(|enr| enr.node_id() == closest),
+                "Lookup of target {index} didn't find the closest node"
+            );
+        }
+    });
+}
+
+#[test_log::test]
+fn lossy_runs_are_reproducible() {
+    // Returns the routing tables after the bootstrap, and the results of a few lookups.
+    let simulate = || {
+        run_simulation(async {
+            let network = SimulatedNetwork::new(
+                SEED,
+                LinkConditions {
+                    latency: Duration::from_millis(20),
+                    loss: 0.05,
+                },
+            );
+            let nodes = spawn_nodes(&network, 64).await;
+            let mut lookups = vec![];
+            for index in 0..4 {
+                let (key, _) = content(index);
+                let source = &nodes[(index as usize * 13 + 1) % nodes.len()];
+                let found = source.overlay.lookup_node(key.content_id().into()).await;
+                lookups.push(found.iter().map(|enr| enr.node_id()).collect::<Vec<_>>());
+            }
+            (routing_tables(&nodes), lookups)
+        })
+    };
+
+    let (tables, lookups) = simulate();
+    assert!(tables.iter().all(|table| !table.is_empty()));
+    assert_eq!(simulate(), (tables, lookups));
+}
+
+#[test_log::test]
+fn gossip_coverage() {
+    run_simulation(async {
+        let network = SimulatedNetwork::new(
+            SEED,
+            LinkConditions {
+                latency: Duration::from_millis(20),
+                loss: 0.0,
+            },
+        );
+        let nodes = spawn_nodes(&network, 200).await;
+
+        let content: Vec<_> = (0..4).map(content).collect();
+        nodes[0].overlay.propagate_gossip(content.clone());
+        time::sleep(GOSSIP_DURATION).await;
+
+        // All the nodes have the maximum radius, so the content should reach most of them.
+        for (key, _) in content {
+            let coverage = stored_by(&nodes, &key);
+            assert!(
+                coverage * 4 >= nodes.len() * 3,
+                "Content {key} reached only {coverage} of {} nodes",
+                nodes.len()
+            );
+        }
+    });
+}
+
+#[test_log::test]
+fn radius_dynamics() {
+    run_simulation(async {
+        let network = SimulatedNetwork::new(SEED, LinkConditions::default());
+        let nodes = spawn_nodes(&network, 32).await;
+
+        // The shrunk radius of a node is learned by its peers when they ping it again.
+        let shrunk = &nodes[1];
+        shrunk.store.write().set_radius(Distance::ZERO);
+        time::sleep(PING_QUEUE_INTERVAL * 3).await;
+
+        let radii: Vec<_> = nodes
+            .iter()
+            .flat_map(|node| node.overlay.routing_table_detail().nodes)
+            .filter(|detail| detail.enr.node_id() == shrunk.node_id())
+            .map(|detail| detail.data_radius)
+            .collect();
+        assert!(!radii.is_empty());
+        assert!(radii.iter().all(|radius| radius.is_zero()));
+
+        // The node doesn't store gossiped content, while the other nodes do.
+        let (key, value) = content(1);
+        nodes[0]
+            .overlay
+            .propagate_gossip(vec![(key.clone(), value)]);
+        time::sleep(GOSSIP_DURATION).await;
+        assert!(matches!(shrunk.store.read().get(&key), Ok(None)));
+        assert!(stored_by(&nodes, &key) * 4 >= (nodes.len() - 1) * 3);
+    });
+}
+
+#[test_log::test]
+fn partition_and_heal() {
+    run_simulation(async {
+        let network = SimulatedNetwork::new(
+            SEED,
+            LinkConditions {
+                latency: Duration::from_millis(20),
+                loss: 0.0,
+            },
+        );
+        let nodes = spawn_nodes(&network, 32).await;
+        let (side_a, side_b) = nodes.split_at(nodes.len() / 2);
+        network.partition(&[side_b.iter().map(|node| node.node_id()).collect()]);
+
+        // Gossip doesn't cross the partition.
+        let (key, value) = content(1);
+        side_a[0]
+            .overlay
+            .propagate_gossip(vec![(key.clone(), value.clone())]);
+        time::sleep(GOSSIP_DURATION).await;
+        assert!(stored_by(side_a, &key) > 1);
+        assert_eq!(stored_by(side_b, &key), 0);
+
+        // Once the partition heals and the nodes reconnect, the content can be found from the
+        // other side.
+        network.heal();
+        time::sleep(PING_QUEUE_INTERVAL * 3).await;
+        let (found, _, _) = side_b[0]
+            .overlay
+            .lookup_content(key, false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found, value);
+    });
+}
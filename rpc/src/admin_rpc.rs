@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use ethportal_api::{
+    types::{
+        cli::{BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+        jsonrpc::{
+            endpoints::{BeaconEndpoint, HistoryEndpoint, StateEndpoint},
+            request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+        },
+        query_tuning::QueryTuning,
+        storage_allocation::StorageAllocation,
+    },
+    AdminApiServer,
+};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::{
+    errors::RpcServeError,
+    fetch::proxy_query_to_history_subnet,
+    jsonrpsee::core::{async_trait, RpcResult},
+    serde::from_value,
+    BeaconNetworkApi, StateNetworkApi,
+};
+
+pub struct AdminApi {
+    history_network: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+    state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    beacon_network: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+    /// The allocation used to split the total storage capacity between subnetworks
+    storage_allocation: StorageAllocation,
+}
+
+impl AdminApi {
+    pub fn new(
+        history_network: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+        state_network: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+        beacon_network: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+        storage_allocation: StorageAllocation,
+    ) -> Self {
+        Self {
+            history_network,
+            state_network,
+            beacon_network,
+            storage_allocation,
+        }
+    }
+
+    /// Returns the names of the enabled subnetworks.
+    fn enabled_subnetworks(&self) -> Vec<&'static str> {
+        let mut subnetworks = vec![];
+        if self.history_network.is_some() {
+            subnetworks.push(HISTORY_NETWORK);
+        }
+        if self.state_network.is_some() {
+            subnetworks.push(STATE_NETWORK);
+        }
+        if self.beacon_network.is_some() {
+            subnetworks.push(BEACON_NETWORK);
+        }
+        subnetworks
+    }
+
+    /// Returns the subnetworks that the request applies to: either the provided subnetwork or all
+    /// enabled subnetworks.
+    // Required for the ContentNotFound error type
+    #[allow(clippy::result_large_err)]
+    fn target_subnetworks(
+        &self,
+        subnetwork: Option<String>,
+    ) -> Result<Vec<&'static str>, RpcServeError> {
+        let enabled_subnetworks = self.enabled_subnetworks();
+        match subnetwork {
+            None => Ok(enabled_subnetworks),
+            Some(subnetwork) => match enabled_subnetworks
+                .into_iter()
+                .find(|enabled| *enabled == subnetwork)
+            {
+                Some(subnetwork) => Ok(vec![subnetwork]),
+                None => Err(RpcServeError::Message(format!(
+                    "Subnetwork {subnetwork} is not enabled"
+                ))),
+            },
+        }
+    }
+
+    /// Sends the endpoint of the subnetwork to the subnetwork.
+    async fn proxy_query_to_subnetwork(
+        &self,
+        subnetwork: &str,
+        history_endpoint: HistoryEndpoint,
+        state_endpoint: StateEndpoint,
+        beacon_endpoint: BeaconEndpoint,
+    ) -> Result<Value, RpcServeError> {
+        let result = match (
+            subnetwork,
+            &self.history_network,
+            &self.state_network,
+            &self.beacon_network,
+        ) {
+            (HISTORY_NETWORK, Some(network), _, _) => {
+                proxy_query_to_history_subnet(network, history_endpoint).await?
+            }
+            (STATE_NETWORK, _, Some(network), _) => {
+                StateNetworkApi::new(network.clone())
+                    .proxy_query_to_state_subnet(state_endpoint)
+                    .await?
+            }
+            (BEACON_NETWORK, _, _, Some(network)) => {
+                BeaconNetworkApi::new(network.clone())
+                    .proxy_query_to_beacon_subnet(beacon_endpoint)
+                    .await?
+            }
+            _ => {
+                return Err(RpcServeError::Message(format!(
+                    "Subnetwork {subnetwork} is not enabled"
+                )))
+            }
+        };
+        Ok(result)
+    }
+
+    async fn set_subnetwork_storage_capacity(
+        &self,
+        subnetwork: &str,
+        capacity_mb: u64,
+    ) -> Result<bool, RpcServeError> {
+        let result = self
+            .proxy_query_to_subnetwork(
+                subnetwork,
+                HistoryEndpoint::SetStorageCapacity(capacity_mb),
+                StateEndpoint::SetStorageCapacity(capacity_mb),
+                BeaconEndpoint::SetStorageCapacity(capacity_mb),
+            )
+            .await?;
+        from_value(result)
+    }
+
+    async fn set_subnetwork_query_tuning(
+        &self,
+        subnetwork: &str,
+        tuning: QueryTuning,
+    ) -> Result<QueryTuning, RpcServeError> {
+        let result = self
+            .proxy_query_to_subnetwork(
+                subnetwork,
+                HistoryEndpoint::SetQueryTuning(tuning),
+                StateEndpoint::SetQueryTuning(tuning),
+                BeaconEndpoint::SetQueryTuning(tuning),
+            )
+            .await?;
+        from_value(result)
+    }
+}
+
+#[async_trait]
+impl AdminApiServer for AdminApi {
+    /// Updates the storage capacity of the provided subnetwork. Without a subnetwork, the capacity
+    /// is the total storage capacity, split between the enabled subnetworks according to the
+    /// storage allocation, as on startup.
+    async fn set_storage_capacity(
+        &self,
+        capacity_mb: u64,
+        subnetwork: Option<String>,
+    ) -> RpcResult<bool> {
+        if subnetwork.is_some() {
+            for subnetwork in self.target_subnetworks(subnetwork)? {
+                self.set_subnetwork_storage_capacity(subnetwork, capacity_mb)
+                    .await?;
+            }
+            return Ok(true);
+        }
+
+        self.storage_allocation
+            .validate(capacity_mb)
+            .map_err(|err| RpcServeError::Message(err.to_string()))?;
+        let enabled_subnetworks: Vec<String> = self
+            .enabled_subnetworks()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        for subnetwork in self.enabled_subnetworks() {
+            let subnetwork_capacity_mb =
+                self.storage_allocation
+                    .capacity_mb(subnetwork, capacity_mb, &enabled_subnetworks);
+            self.set_subnetwork_storage_capacity(subnetwork, subnetwork_capacity_mb)
+                .await?;
+        }
+        Ok(true)
+    }
+
+    /// Updates the query tuning of the provided subnetwork, or of all enabled subnetworks.
+    async fn set_query_tuning(
+        &self,
+        tuning: QueryTuning,
+        subnetwork: Option<String>,
+    ) -> RpcResult<HashMap<String, QueryTuning>> {
+        let mut result = HashMap::new();
+        for subnetwork in self.target_subnetworks(subnetwork)? {
+            let tuning = self.set_subnetwork_query_tuning(subnetwork, tuning).await?;
+            result.insert(subnetwork.to_string(), tuning);
+        }
+        Ok(result)
+    }
+}
+
+impl std::fmt::Debug for AdminApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminApi").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Responds to the storage capacity updates of the history subnetwork, returning the updated
+    /// capacities.
+    fn history_network() -> (
+        mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+        mpsc::UnboundedReceiver<u64>,
+    ) {
+        let (history_tx, mut history_rx) = mpsc::unbounded_channel::<HistoryJsonRpcRequest>();
+        let (capacity_tx, capacity_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(request) = history_rx.recv().await {
+                if let HistoryEndpoint::SetStorageCapacity(capacity_mb) = request.endpoint {
+                    capacity_tx.send(capacity_mb).unwrap();
+                }
+                let _ = request.resp.send(Ok(json!(true)));
+            }
+        });
+        (history_tx, capacity_rx)
+    }
+
+    /// Responds to the storage capacity updates of the state subnetwork, returning the updated
+    /// capacities.
+    fn state_network() -> (
+        mpsc::UnboundedSender<StateJsonRpcRequest>,
+        mpsc::UnboundedReceiver<u64>,
+    ) {
+        let (state_tx, mut state_rx) = mpsc::unbounded_channel::<StateJsonRpcRequest>();
+        let (capacity_tx, capacity_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(request) = state_rx.recv().await {
+                if let StateEndpoint::SetStorageCapacity(capacity_mb) = request.endpoint {
+                    capacity_tx.send(capacity_mb).unwrap();
+                }
+                let _ = request.resp.send(Ok(json!(true)));
+            }
+        });
+        (state_tx, capacity_rx)
+    }
+
+    #[tokio::test]
+    async fn set_storage_capacity() {
+        // The allocation, the subnetwork to update, and the expected history and state capacities.
+        let cases = [
+            ("", None, 1000, Some(1000)),
+            ("beacon:100", None, 500, Some(500)),
+            ("history:30%", None, 300, Some(700)),
+            ("history:30%", Some(HISTORY_NETWORK), 1000, None),
+        ];
+        for (allocation, subnetwork, expected_history_mb, expected_state_mb) in cases {
+            let storage_allocation = match allocation {
+                "" => StorageAllocation::default(),
+                allocation => StorageAllocation::from_str(allocation).unwrap(),
+            };
+            let (history_tx, mut history_rx) = history_network();
+            let (state_tx, mut state_rx) = state_network();
+            let admin_api =
+                AdminApi::new(Some(history_tx), Some(state_tx), None, storage_allocation);
+
+            assert!(admin_api
+                .set_storage_capacity(1000, subnetwork.map(String::from))
+                .await
+                .unwrap());
+
+            assert_eq!(history_rx.try_recv().ok(), Some(expected_history_mb));
+            assert_eq!(state_rx.try_recv().ok(), expected_state_mb);
+        }
+    }
+
+    #[tokio::test]
+    async fn set_storage_capacity_below_allocation() {
+        let (history_tx, mut history_rx) = history_network();
+        let admin_api = AdminApi::new(
+            Some(history_tx),
+            None,
+            None,
+            StorageAllocation::from_str("history:500").unwrap(),
+        );
+
+        assert!(admin_api.set_storage_capacity(100, None).await.is_err());
+        assert!(history_rx.try_recv().is_err());
+    }
+}
@@ -9,7 +9,8 @@ use ethportal_api::{
         jsonrpc::{endpoints::BeaconEndpoint, request::BeaconJsonRpcRequest},
         portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
     },
-    BeaconContentKey, BeaconContentValue, BeaconNetworkApiServer, RoutingTableInfo,
+    BeaconContentKey, BeaconContentValue, BeaconNetworkApiServer, RoutingTableDetail,
+    RoutingTableInfo,
 };
 use serde_json::Value;
 use tokio::sync::mpsc;
@@ -85,6 +86,14 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
         Ok(result)
     }
 
+    /// Returns the details of each node in the overlay routing table.
+    async fn routing_table_detail(&self) -> RpcResult<RoutingTableDetail> {
+        let endpoint = BeaconEndpoint::RoutingTableDetail;
+        let result = self.proxy_query_to_beacon_subnet(endpoint).await?;
+        let result: RoutingTableDetail = from_value(result)?;
+        Ok(result)
+    }
+
     /// Write an Ethereum Node Record to the overlay routing table.
     async fn add_enr(&self, enr: Enr) -> RpcResult<bool> {
         let endpoint = BeaconEndpoint::AddEnr(enr);
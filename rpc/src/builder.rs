@@ -2,12 +2,17 @@ use crate::{
     errors::{RpcError, WsHttpSamePortError},
     jsonrpsee::{Methods, RpcModule},
     rpc_server::{RpcServerConfig, RpcServerHandle},
-    BeaconNetworkApi, Discv5Api, EthApi, HistoryNetworkApi, StateNetworkApi, Web3Api,
+    AdminApi, BeaconNetworkApi, Discv5Api, EthApi, HistoryNetworkApi, PortalSubscriptionApi,
+    StateNetworkApi, Web3Api,
 };
 use ethportal_api::{
-    types::jsonrpc::request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
-    BeaconNetworkApiServer, Discv5ApiServer, EthApiServer, HistoryNetworkApiServer,
-    StateNetworkApiServer, Web3ApiServer,
+    types::{
+        jsonrpc::request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+        storage_allocation::StorageAllocation,
+        subscription::SubscriptionEvent,
+    },
+    AdminApiServer, BeaconNetworkApiServer, Discv5ApiServer, EthApiServer, HistoryNetworkApiServer,
+    PortalSubscriptionApiServer, StateNetworkApiServer, Web3ApiServer,
 };
 use portalnet::discovery::Discovery;
 use serde::Deserialize;
@@ -17,7 +22,8 @@ use std::{
     sync::Arc,
 };
 use strum::{AsRefStr, EnumString, VariantNames};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
+use trin_validation::accumulator::PreMergeAccumulator;
 
 /// Represents RPC modules that are supported by Trin
 #[derive(
@@ -26,6 +32,8 @@ use tokio::sync::mpsc;
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "kebab-case")]
 pub enum PortalRpcModule {
+    /// `portal_` admin module
+    Admin,
     /// `portal_beacon` module
     Beacon,
     /// `discv5_` module
@@ -36,6 +44,8 @@ pub enum PortalRpcModule {
     History,
     /// `state` module
     State,
+    /// `portal_subscribe` module
+    Subscription,
     /// `web3_` module
     Web3,
 }
@@ -283,10 +293,16 @@ pub struct RpcModuleBuilder {
     discv5: Arc<Discovery>,
     /// History protocol
     history_tx: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+    /// Pre-merge accumulator of the network, used by the history module
+    pre_merge_acc: Option<Arc<PreMergeAccumulator>>,
     /// Beacon protocol
     beacon_tx: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
     /// State protocol
     state_tx: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    /// Overlay events published to the subscriptions
+    subscription_tx: Option<broadcast::Sender<SubscriptionEvent>>,
+    /// Allocation of the total storage capacity between subnetworks, used by the admin module
+    storage_allocation: StorageAllocation,
 }
 
 impl RpcModuleBuilder {
@@ -295,8 +311,11 @@ impl RpcModuleBuilder {
             modules: HashMap::new(),
             discv5,
             history_tx: None,
+            pre_merge_acc: None,
             beacon_tx: None,
             state_tx: None,
+            subscription_tx: None,
+            storage_allocation: StorageAllocation::default(),
         }
     }
 
@@ -308,6 +327,11 @@ impl RpcModuleBuilder {
         self
     }
 
+    pub fn with_pre_merge_acc(mut self, pre_merge_acc: Arc<PreMergeAccumulator>) -> Self {
+        self.pre_merge_acc = Some(pre_merge_acc);
+        self
+    }
+
     pub fn maybe_with_state(
         mut self,
         state_tx: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
@@ -342,6 +366,19 @@ impl RpcModuleBuilder {
         self
     }
 
+    pub fn with_subscriptions(
+        mut self,
+        subscription_tx: broadcast::Sender<SubscriptionEvent>,
+    ) -> Self {
+        self.subscription_tx = Some(subscription_tx);
+        self
+    }
+
+    pub fn with_storage_allocation(mut self, storage_allocation: StorageAllocation) -> Self {
+        self.storage_allocation = storage_allocation;
+        self
+    }
+
     /// Returns all installed methods
     pub fn methods(&self) -> Vec<Methods> {
         self.modules.values().cloned().collect()
@@ -404,6 +441,14 @@ impl RpcModuleBuilder {
                 self.modules
                     .entry(namespace)
                     .or_insert_with(|| match namespace {
+                        PortalRpcModule::Admin => AdminApi::new(
+                            self.history_tx.clone(),
+                            self.state_tx.clone(),
+                            self.beacon_tx.clone(),
+                            self.storage_allocation.clone(),
+                        )
+                        .into_rpc()
+                        .into(),
                         PortalRpcModule::Discv5 => {
                             Discv5Api::new(self.discv5.clone()).into_rpc().into()
                         }
@@ -412,14 +457,22 @@ impl RpcModuleBuilder {
                                 .history_tx
                                 .clone()
                                 .expect("History protocol not initialized");
-                            EthApi::new(history_tx).into_rpc().into()
+                            EthApi::new(history_tx, self.discv5.network_spec().chain_id())
+                                .into_rpc()
+                                .into()
                         }
                         PortalRpcModule::History => {
                             let history_tx = self
                                 .history_tx
                                 .clone()
                                 .expect("History protocol not initialized");
-                            HistoryNetworkApi::new(history_tx).into_rpc().into()
+                            let pre_merge_acc = self
+                                .pre_merge_acc
+                                .clone()
+                                .expect("Pre-merge accumulator not initialized");
+                            HistoryNetworkApi::new(history_tx, pre_merge_acc)
+                                .into_rpc()
+                                .into()
                         }
                         PortalRpcModule::Beacon => {
                             let beacon_tx = self
@@ -435,6 +488,15 @@ impl RpcModuleBuilder {
                                 .expect("State protocol not initialized");
                             StateNetworkApi::new(state_tx).into_rpc().into()
                         }
+                        PortalRpcModule::Subscription => {
+                            let subscription_tx = self
+                                .subscription_tx
+                                .clone()
+                                .expect("Subscription events not initialized");
+                            PortalSubscriptionApi::new(subscription_tx)
+                                .into_rpc()
+                                .into()
+                        }
                         PortalRpcModule::Web3 => Web3Api.into_rpc().into(),
                     })
                     .clone()
@@ -474,6 +536,7 @@ mod tests {
         }
         assert_rpc_module!
         (
+                "admin" =>  PortalRpcModule::Admin,
                 "beacon" =>  PortalRpcModule::Beacon,
                 "discv5" =>  PortalRpcModule::Discv5,
                 "history" =>  PortalRpcModule::History,
@@ -6,7 +6,6 @@ use ethportal_api::{
     types::{execution::block_body::BlockBody, jsonrpc::request::HistoryJsonRpcRequest},
     EthApiServer,
 };
-use trin_validation::constants::CHAIN_ID;
 
 use crate::{
     errors::RpcServeError,
@@ -16,18 +15,20 @@ use crate::{
 
 pub struct EthApi {
     network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    /// The id of the chain served by the portal network.
+    chain_id: u64,
 }
 
 impl EthApi {
-    pub fn new(network: mpsc::UnboundedSender<HistoryJsonRpcRequest>) -> Self {
-        Self { network }
+    pub fn new(network: mpsc::UnboundedSender<HistoryJsonRpcRequest>, chain_id: u64) -> Self {
+        Self { network, chain_id }
     }
 }
 
 #[async_trait]
 impl EthApiServer for EthApi {
     async fn chain_id(&self) -> RpcResult<U256> {
-        Ok(U256::from(CHAIN_ID))
+        Ok(U256::from(self.chain_id))
     }
 
     async fn get_block_by_hash(
@@ -5,11 +5,11 @@ use tokio::sync::mpsc;
 
 use ethportal_api::{
     types::{
-        execution::{block_body::BlockBody, header::Header},
+        execution::{accumulator::EpochAccumulator, block_body::BlockBody, header::Header},
         jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
     },
     utils::bytes::hex_decode,
-    ContentValue, HistoryContentKey, HistoryContentValue,
+    ContentValue, EpochAccumulatorKey, HistoryContentKey, HistoryContentValue,
 };
 
 use crate::errors::RpcServeError;
@@ -88,6 +88,22 @@ pub async fn find_block_body_by_hash(
     }
 }
 
+pub async fn find_epoch_accumulator(
+    network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    epoch_hash: B256,
+) -> Result<EpochAccumulator, RpcServeError> {
+    // Request the epoch accumulator from the history subnet.
+    let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey { epoch_hash });
+    let epoch_acc = find_content_by_hash(network, content_key).await?;
+
+    match epoch_acc {
+        HistoryContentValue::EpochAccumulator(epoch_acc) => Ok(epoch_acc),
+        wrong_val => Err(RpcServeError::Message(format!(
+            "Internal trin error: got back a non-accumulator from a key that must only point to epoch accumulators; got {wrong_val:?}"
+        ))),
+    }
+}
+
 async fn find_content_by_hash(
     network: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     content_key: HistoryContentKey,
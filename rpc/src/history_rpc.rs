@@ -1,25 +1,139 @@
-use crate::{fetch::proxy_query_to_history_subnet, serde::from_value};
+use std::{cmp::min, sync::Arc};
+
+use crate::{
+    errors::RpcServeError,
+    fetch::{find_epoch_accumulator, proxy_query_to_history_subnet},
+    serde::from_value,
+};
 
 use crate::jsonrpsee::core::{async_trait, RpcResult};
+use alloy_primitives::B256;
 use discv5::enr::NodeId;
 use ethportal_api::{
     types::{
         enr::Enr,
-        history::{ContentInfo, PaginateLocalContentInfo, TraceContentInfo},
+        history::{
+            ContentInfo, GetRangeInfo, GetRangeItem, GetRangeItemStatus, HistoryContentType,
+            PaginateLocalContentInfo, TraceContentInfo,
+        },
         jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
         portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
     },
-    HistoryContentKey, HistoryContentValue, HistoryNetworkApiServer, RoutingTableInfo,
+    HistoryContentKey, HistoryContentValue, HistoryNetworkApiServer, RoutingTableDetail,
+    RoutingTableInfo,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Semaphore};
+use trin_validation::{accumulator::PreMergeAccumulator, constants::EPOCH_SIZE};
+
+/// The maximum number of blocks fetched by a single GetRange request.
+const GET_RANGE_MAX_BLOCKS: u64 = 256;
+
+/// The maximum number of concurrent lookups of a single GetRange request.
+const GET_RANGE_MAX_CONCURRENT_LOOKUPS: usize = 16;
 
 pub struct HistoryNetworkApi {
     network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    pre_merge_acc: Arc<PreMergeAccumulator>,
 }
 
 impl HistoryNetworkApi {
-    pub fn new(network: mpsc::UnboundedSender<HistoryJsonRpcRequest>) -> Self {
-        Self { network }
+    pub fn new(
+        network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+        pre_merge_acc: Arc<PreMergeAccumulator>,
+    ) -> Self {
+        Self {
+            network,
+            pre_merge_acc,
+        }
+    }
+
+    /// Resolves the hashes of the blocks in the inclusive range, fetching each epoch accumulator
+    /// only once. Blocks whose epoch accumulator couldn't be found are resolved to the failure.
+    async fn resolve_block_hashes(
+        &self,
+        start_block: u64,
+        end_block: u64,
+    ) -> Result<Vec<(u64, Result<B256, (GetRangeItemStatus, String)>)>, RpcServeError> {
+        let mut block_hashes = vec![];
+        for epoch_index in start_block / EPOCH_SIZE..=end_block / EPOCH_SIZE {
+            let epoch_hash = *self
+                .pre_merge_acc
+                .historical_epochs
+                .get(epoch_index as usize)
+                .ok_or_else(|| {
+                    RpcServeError::Message(format!(
+                        "Epoch {epoch_index} is not in the pre-merge accumulator"
+                    ))
+                })?;
+            let epoch_acc = find_epoch_accumulator(&self.network, epoch_hash)
+                .await
+                .map_err(failed_lookup);
+            let epoch_start = epoch_index * EPOCH_SIZE;
+            let first = start_block.max(epoch_start);
+            let last = end_block.min(epoch_start + EPOCH_SIZE - 1);
+            for block_number in first..=last {
+                let block_hash = match &epoch_acc {
+                    Ok(epoch_acc) => epoch_acc
+                        .get((block_number - epoch_start) as usize)
+                        .map(|header_record| header_record.block_hash)
+                        .ok_or_else(|| {
+                            (
+                                GetRangeItemStatus::Error,
+                                format!("Block {block_number} is not in the epoch accumulator"),
+                            )
+                        }),
+                    Err((status, message)) => Err((
+                        *status,
+                        format!("Unable to resolve block hash, epoch accumulator lookup failed: {message}"),
+                    )),
+                };
+                block_hashes.push((block_number, block_hash));
+            }
+        }
+        Ok(block_hashes)
+    }
+}
+
+/// Looks up a single item of the GetRange endpoint.
+async fn get_range_item(
+    network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    block_number: u64,
+    content_type: HistoryContentType,
+    content_key: HistoryContentKey,
+) -> GetRangeItem {
+    let endpoint = HistoryEndpoint::RecursiveFindContent(content_key.clone());
+    let result = match proxy_query_to_history_subnet(&network, endpoint).await {
+        Ok(result) => match from_value(result) {
+            Ok(ContentInfo::Content { content, .. }) => Ok(content),
+            Ok(content_info) => Err((
+                GetRangeItemStatus::Error,
+                format!("Unexpected content info: {content_info:?}"),
+            )),
+            Err(err) => Err(failed_lookup(err)),
+        },
+        Err(err) => Err(failed_lookup(err)),
+    };
+    let (status, content, error) = match result {
+        Ok(content) => (GetRangeItemStatus::Found, Some(content), None),
+        Err((status, message)) => (status, None, Some(message)),
+    };
+    GetRangeItem {
+        block_number,
+        content_type,
+        content_key: Some(content_key),
+        status,
+        content,
+        error,
+    }
+}
+
+/// Converts the error of a failed lookup to the status and message of a GetRange item.
+fn failed_lookup(err: RpcServeError) -> (GetRangeItemStatus, String) {
+    match err {
+        RpcServeError::ContentNotFound { message, .. } => (GetRangeItemStatus::NotFound, message),
+        RpcServeError::Message(message) | RpcServeError::MethodNotFound(message) => {
+            (GetRangeItemStatus::Error, message)
+        }
     }
 }
 
@@ -33,6 +147,14 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Returns the details of each node in the overlay routing table.
+    async fn routing_table_detail(&self) -> RpcResult<RoutingTableDetail> {
+        let endpoint = HistoryEndpoint::RoutingTableDetail;
+        let result = proxy_query_to_history_subnet(&self.network, endpoint).await?;
+        let result: RoutingTableDetail = from_value(result)?;
+        Ok(result)
+    }
+
     /// Write an Ethereum Node Record to the overlay routing table.
     async fn add_enr(&self, enr: Enr) -> RpcResult<bool> {
         let endpoint = HistoryEndpoint::AddEnr(enr);
@@ -143,6 +265,79 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Fetch the selected content types for the inclusive range of pre-merge block numbers.
+    async fn get_range(
+        &self,
+        start_block: u64,
+        end_block: u64,
+        content_types: Vec<HistoryContentType>,
+    ) -> RpcResult<GetRangeInfo> {
+        if start_block > end_block {
+            return Err(RpcServeError::Message(format!(
+                "Invalid range: start block {start_block} is greater than end block {end_block}"
+            ))
+            .into());
+        }
+        // The merge block is the first block that isn't in the pre-merge accumulator.
+        if end_block >= self.pre_merge_acc.height() {
+            return Err(
+                RpcServeError::Message("Post-merge blocks are not supported.".to_string()).into(),
+            );
+        }
+        if content_types.is_empty() {
+            return Err(
+                RpcServeError::Message("No content types were selected.".to_string()).into(),
+            );
+        }
+
+        let page_end = min(end_block, start_block + GET_RANGE_MAX_BLOCKS - 1);
+        let next_block = (page_end < end_block).then_some(page_end + 1);
+
+        let semaphore = Arc::new(Semaphore::new(GET_RANGE_MAX_CONCURRENT_LOOKUPS));
+        let mut lookups = vec![];
+        for (block_number, block_hash) in self.resolve_block_hashes(start_block, page_end).await? {
+            for content_type in content_types.iter().copied() {
+                let block_hash = block_hash.clone();
+                let network = self.network.clone();
+                let semaphore = semaphore.clone();
+                lookups.push(tokio::spawn(async move {
+                    match block_hash {
+                        Ok(block_hash) => {
+                            let _permit = semaphore
+                                .acquire_owned()
+                                .await
+                                .expect("GetRange semaphore is never closed");
+                            get_range_item(
+                                network,
+                                block_number,
+                                content_type,
+                                content_type.content_key(block_hash),
+                            )
+                            .await
+                        }
+                        Err((status, message)) => GetRangeItem {
+                            block_number,
+                            content_type,
+                            content_key: None,
+                            status,
+                            content: None,
+                            error: Some(message),
+                        },
+                    }
+                }));
+            }
+        }
+
+        let mut items = Vec::with_capacity(lookups.len());
+        for lookup in lookups {
+            let item = lookup.await.map_err(|err| {
+                RpcServeError::Message(format!("GetRange lookup task failed: {err}"))
+            })?;
+            items.push(item);
+        }
+        Ok(GetRangeInfo { items, next_block })
+    }
+
     /// Send the provided content to interested peers. Clients may choose to send to some or all
     /// peers. Return the number of peers that the content was gossiped to.
     async fn gossip(
@@ -184,6 +379,20 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Send an OFFER request with the provided content to the designated peer. Return tracing
+    /// info.
+    async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+    ) -> RpcResult<TraceGossipInfo> {
+        let endpoint = HistoryEndpoint::TraceOffer(enr, content_key, content_value);
+        let result = proxy_query_to_history_subnet(&self.network, endpoint).await?;
+        let result: TraceGossipInfo = from_value(result)?;
+        Ok(result)
+    }
+
     /// Store content key with a content data to the local database.
     async fn store(
         &self,
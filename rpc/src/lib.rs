@@ -1,6 +1,7 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
+mod admin_rpc;
 mod beacon_rpc;
 mod builder;
 mod cors;
@@ -12,10 +13,12 @@ mod history_rpc;
 mod rpc_server;
 mod serde;
 mod state_rpc;
+mod subscription_rpc;
 mod web3_rpc;
 
 use crate::jsonrpsee::server::ServerBuilder;
 pub use crate::rpc_server::RpcServerHandle;
+use admin_rpc::AdminApi;
 use beacon_rpc::BeaconNetworkApi;
 pub use builder::{PortalRpcModule, RpcModuleBuilder, TransportRpcModuleConfig};
 use discv5_rpc::Discv5Api;
@@ -26,10 +29,12 @@ use ethportal_api::{
     types::{
         cli::{TrinConfig, Web3TransportType, BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
         jsonrpc::request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest, StateJsonRpcRequest},
+        subscription::SubscriptionEvent,
     },
 };
 use history_rpc::HistoryNetworkApi;
 use state_rpc::StateNetworkApi;
+use subscription_rpc::PortalSubscriptionApi;
 use web3_rpc::Web3Api;
 
 use crate::rpc_server::RpcServerConfig;
@@ -39,7 +44,8 @@ use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     sync::Arc,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
+use trin_validation::accumulator::PreMergeAccumulator;
 
 pub async fn launch_jsonrpc_server(
     trin_config: TrinConfig,
@@ -47,9 +53,16 @@ pub async fn launch_jsonrpc_server(
     history_handler: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
     state_handler: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
     beacon_handler: Option<mpsc::UnboundedSender<BeaconJsonRpcRequest>>,
+    subscription_tx: broadcast::Sender<SubscriptionEvent>,
+    pre_merge_acc: Arc<PreMergeAccumulator>,
 ) -> Result<RpcServerHandle, RpcError> {
-    // Discv5 and Web3 modules are enabled with every network
-    let mut modules = vec![PortalRpcModule::Discv5, PortalRpcModule::Web3];
+    // Admin, Discv5, Subscription and Web3 modules are enabled with every network
+    let mut modules = vec![
+        PortalRpcModule::Admin,
+        PortalRpcModule::Discv5,
+        PortalRpcModule::Subscription,
+        PortalRpcModule::Web3,
+    ];
 
     for network in trin_config.portal_subnetworks.iter() {
         match network.as_str() {
@@ -63,13 +76,18 @@ pub async fn launch_jsonrpc_server(
         }
     }
 
+    let storage_allocation = trin_config.storage_allocation.clone().unwrap_or_default();
+
     let handle: RpcServerHandle = match trin_config.web3_transport {
         Web3TransportType::IPC => {
             let transport = TransportRpcModuleConfig::default().with_ipc(modules);
             let transport_modules = RpcModuleBuilder::new(discv5)
                 .maybe_with_history(history_handler)
+                .with_pre_merge_acc(pre_merge_acc)
                 .maybe_with_beacon(beacon_handler)
                 .maybe_with_state(state_handler)
+                .with_subscriptions(subscription_tx)
+                .with_storage_allocation(storage_allocation)
                 .build(transport);
 
             RpcServerConfig::default()
@@ -92,8 +110,11 @@ pub async fn launch_jsonrpc_server(
 
             let transport_modules = RpcModuleBuilder::new(discv5)
                 .maybe_with_history(history_handler)
+                .with_pre_merge_acc(pre_merge_acc)
                 .maybe_with_beacon(beacon_handler)
                 .maybe_with_state(state_handler)
+                .with_subscriptions(subscription_tx)
+                .with_storage_allocation(storage_allocation)
                 .build(transport);
 
             let rpc_server_config = RpcServerConfig::default()
@@ -618,6 +618,7 @@ mod tests {
     use ethportal_api::types::portal_wire::MAINNET;
     use portalnet::{discovery::Discovery, utils::db::setup_temp_dir};
     use std::{io, sync::Arc};
+    use trin_validation::accumulator::PreMergeAccumulator;
 
     /// Localhost with port 0 so a free port is used.
     pub fn test_address() -> SocketAddr {
@@ -637,12 +638,15 @@ mod tests {
     pub fn test_rpc_builder() -> RpcModuleBuilder {
         let (history_tx, _) = tokio::sync::mpsc::unbounded_channel();
         let (beacon_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let (subscription_tx, _) = tokio::sync::broadcast::channel(1);
         let temp_dir = setup_temp_dir().unwrap().into_path();
         let discv5 =
             Arc::new(Discovery::new(Default::default(), temp_dir, MAINNET.clone()).unwrap());
         RpcModuleBuilder::new(discv5)
             .with_history(history_tx)
+            .with_pre_merge_acc(Arc::new(PreMergeAccumulator::default()))
             .with_beacon(beacon_tx)
+            .with_subscriptions(subscription_tx)
     }
 
     /// Launches a new server with http only with the given modules
@@ -6,7 +6,8 @@ use ethportal_api::{
         portal::{AcceptInfo, DataRadius, FindNodesInfo, PongInfo, TraceGossipInfo},
         state::{ContentInfo, PaginateLocalContentInfo, TraceContentInfo},
     },
-    RoutingTableInfo, StateContentKey, StateContentValue, StateNetworkApiServer,
+    RoutingTableDetail, RoutingTableInfo, StateContentKey, StateContentValue,
+    StateNetworkApiServer,
 };
 use serde_json::Value;
 use tokio::sync::mpsc;
@@ -59,6 +60,14 @@ impl StateNetworkApiServer for StateNetworkApi {
         Ok(result)
     }
 
+    /// Returns the details of each node in the overlay routing table.
+    async fn routing_table_detail(&self) -> RpcResult<RoutingTableDetail> {
+        let endpoint = StateEndpoint::RoutingTableDetail;
+        let result = self.proxy_query_to_state_subnet(endpoint).await?;
+        let result: RoutingTableDetail = from_value(result)?;
+        Ok(result)
+    }
+
     /// Write an Ethereum Node Record to the overlay routing table.
     async fn add_enr(&self, enr: Enr) -> RpcResult<bool> {
         let endpoint = StateEndpoint::AddEnr(enr);
@@ -204,6 +213,20 @@ impl StateNetworkApiServer for StateNetworkApi {
         Ok(result)
     }
 
+    /// Send an OFFER request with the provided content to the designated peer. Return tracing
+    /// info.
+    async fn trace_offer(
+        &self,
+        enr: Enr,
+        content_key: StateContentKey,
+        content_value: StateContentValue,
+    ) -> RpcResult<TraceGossipInfo> {
+        let endpoint = StateEndpoint::TraceOffer(enr, content_key, content_value);
+        let result = self.proxy_query_to_state_subnet(endpoint).await?;
+        let result: TraceGossipInfo = from_value(result)?;
+        Ok(result)
+    }
+
     /// Store content key with a content data to the local database.
     async fn store(
         &self,
@@ -0,0 +1,67 @@
+use ethportal_api::{
+    types::subscription::{SubscriptionEvent, SubscriptionKind},
+    PortalSubscriptionApiServer,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::jsonrpsee::{
+    core::{async_trait, SubscriptionResult},
+    PendingSubscriptionSink, SubscriptionMessage,
+};
+
+pub struct PortalSubscriptionApi {
+    subscription_tx: broadcast::Sender<SubscriptionEvent>,
+}
+
+impl PortalSubscriptionApi {
+    pub fn new(subscription_tx: broadcast::Sender<SubscriptionEvent>) -> Self {
+        Self { subscription_tx }
+    }
+}
+
+#[async_trait]
+impl PortalSubscriptionApiServer for PortalSubscriptionApi {
+    async fn subscribe(
+        &self,
+        pending: PendingSubscriptionSink,
+        kind: SubscriptionKind,
+    ) -> SubscriptionResult {
+        // Subscribe before accepting, so that no events are missed.
+        let mut events = self.subscription_tx.subscribe();
+        let sink = pending.accept().await?;
+
+        loop {
+            tokio::select! {
+                _ = sink.closed() => break,
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if event.kind() != Some(kind) {
+                            continue;
+                        }
+                        let message = SubscriptionMessage::from_json(&event)?;
+                        if sink.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            subscription = ?kind,
+                            skipped,
+                            "Subscription is lagging, events were dropped"
+                        );
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for PortalSubscriptionApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PortalSubscriptionApi")
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,109 @@
+use std::env;
+
+use anyhow::anyhow;
+
+use ethportal_api::types::{
+    cli::{ConfigCommandConfig, ConfigCommands, TrinConfig},
+    config_file::{OverlayTuning, OVERLAY_TABLE, PRUNING_TABLE},
+};
+use portalnet::overlay::config::OverlayConfig;
+use trin_storage::versioned::PruningConfig;
+
+/// Runs the `trin config` command.
+pub fn run_config_command(
+    trin_config: TrinConfig,
+    config_command_config: ConfigCommandConfig,
+) -> anyhow::Result<()> {
+    match config_command_config.command {
+        ConfigCommands::Dump => {
+            let table =
+                effective_config(&trin_config, TrinConfig::effective_args(env::args_os())?)?;
+            print!("{}", toml::to_string(&table)?);
+        }
+    }
+    Ok(())
+}
+
+/// Adds the effective `[overlay]` and `[pruning]` tables, with the defaults of the values that
+/// are not set in the config file, to the effective CLI flags.
+fn effective_config(
+    trin_config: &TrinConfig,
+    mut table: toml::Table,
+) -> anyhow::Result<toml::Table> {
+    let overlay = OverlayConfig::default()
+        .with_tuning(&trin_config.overlay)
+        .tuning();
+    let overlay = OverlayTuning {
+        // The subnet limits are not readable from the overlay config.
+        table_subnet_limit: trin_config.overlay.table_subnet_limit,
+        bucket_subnet_limit: trin_config.overlay.bucket_subnet_limit,
+        history: trin_config.overlay.history,
+        state: trin_config.overlay.state,
+        beacon: trin_config.overlay.beacon,
+        ..overlay
+    };
+    let pruning = PruningConfig::default()
+        .with_tuning(&trin_config.pruning)
+        .tuning();
+    table.insert(
+        OVERLAY_TABLE.to_string(),
+        toml::Value::try_from(overlay).map_err(|err| anyhow!("Invalid overlay tuning: {err}"))?,
+    );
+    table.insert(
+        PRUNING_TABLE.to_string(),
+        toml::Value::try_from(pruning).map_err(|err| anyhow!("Invalid pruning tuning: {err}"))?,
+    );
+    Ok(table)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use ethportal_api::types::{
+        cli::HISTORY_NETWORK, config_file::ConfigFile, query_tuning::QueryTuning,
+    };
+
+    #[test]
+    fn effective_config_round_trip() {
+        let args = ["trin", "--mb", "500"];
+        let trin_config = TrinConfig {
+            overlay: OverlayTuning {
+                query_parallelism: Some(5),
+                bucket_subnet_limit: Some(2),
+                history: QueryTuning {
+                    query_parallelism: Some(8),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..TrinConfig::new_from(args.iter()).unwrap()
+        };
+        let table = effective_config(
+            &trin_config,
+            TrinConfig::effective_args(args.iter()).unwrap(),
+        )
+        .unwrap();
+
+        let config_file: ConfigFile = toml::to_string(&table).unwrap().parse().unwrap();
+        assert_eq!(config_file.arg("mb").unwrap().as_integer(), Some(500));
+        assert_eq!(config_file.overlay.query_parallelism, Some(5));
+        assert_eq!(
+            config_file
+                .overlay
+                .query_tuning(HISTORY_NETWORK)
+                .query_parallelism,
+            Some(8)
+        );
+        assert_eq!(config_file.overlay.bucket_subnet_limit, Some(2));
+        assert_eq!(
+            config_file.overlay.query_num_results,
+            Some(OverlayConfig::default().query_num_results)
+        );
+        assert_eq!(
+            config_file.pruning.target_capacity_fraction,
+            Some(PruningConfig::DEFAULT_TARGET_CAPACITY_FRACTION)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use ethportal_api::{
+    types::{
+        cli::{DbCheckConfig, TrinConfig, BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+        portal_wire::ProtocolId,
+    },
+    OverlayContentKey,
+};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use trin_history::validation::ChainHistoryValidator;
+use trin_state::validation::StateValidator;
+use trin_storage::{
+    versioned::{create_store, ContentType, EntryCheck, IdIndexedV1Store, IdIndexedV1StoreConfig},
+    ContentId, PortalStorageConfig,
+};
+use trin_validation::{oracle::HeaderOracle, validator::Validator};
+
+use super::can_validate_history_offline;
+
+/// The number of entries that are loaded from the store at once.
+const CHECK_BATCH_SIZE: u64 = 1000;
+
+/// Checks the integrity of the stored content of all enabled subnetworks.
+///
+/// Each entry is checked for consistency (content id, distance and content size) and, where
+/// possible without access to the network, validated with the subnetwork's validator.
+///
+/// The beacon subnetwork isn't supported, as its content is stored by sync committee period
+/// rather than by content id and distance, so the check is refused if it's enabled.
+pub async fn check_db(
+    trin_config: &TrinConfig,
+    storage_config: PortalStorageConfig,
+    check_config: DbCheckConfig,
+) -> anyhow::Result<()> {
+    let is_enabled = |subnetwork: &str| {
+        trin_config
+            .portal_subnetworks
+            .iter()
+            .any(|enabled| enabled == subnetwork)
+    };
+    if is_enabled(BEACON_NETWORK) {
+        return Err(anyhow!(
+            "Checking the {BEACON_NETWORK} subnetwork isn't supported, remove it from \
+             --portal-subnetworks to check the {HISTORY_NETWORK} and {STATE_NETWORK} content"
+        ));
+    }
+    let header_oracle = Arc::new(RwLock::new(HeaderOracle::new_for_network(
+        &trin_config.network,
+    )?));
+
+    let mut corrupt_count = 0;
+    if is_enabled(HISTORY_NETWORK) {
+        let validator = ChainHistoryValidator {
+            header_oracle: header_oracle.clone(),
+        };
+        corrupt_count += check_subnetwork(
+            ContentType::History,
+            ProtocolId::History,
+            storage_config.clone(),
+            &validator,
+            can_validate_history_offline,
+            check_config.repair,
+        )
+        .await?;
+    }
+    if is_enabled(STATE_NETWORK) {
+        let validator = StateValidator {
+            header_oracle: header_oracle.clone(),
+        };
+        corrupt_count += check_subnetwork(
+            ContentType::State,
+            ProtocolId::State,
+            storage_config.clone(),
+            &validator,
+            |_| true,
+            check_config.repair,
+        )
+        .await?;
+    }
+
+    if corrupt_count > 0 && !check_config.repair {
+        warn!("Found {corrupt_count} corrupt entries. Run with --repair to delete them.");
+    }
+    Ok(())
+}
+
+/// Checks all entries of the subnetwork's store and returns the number of corrupt entries.
+///
+/// If `repair` is set, corrupt entries are deleted and usage stats are recalculated.
+async fn check_subnetwork<K, V>(
+    content_type: ContentType,
+    protocol_id: ProtocolId,
+    storage_config: PortalStorageConfig,
+    validator: &V,
+    can_validate: impl Fn(&K) -> bool,
+    repair: bool,
+) -> anyhow::Result<usize>
+where
+    K: OverlayContentKey + Send,
+    V: Validator<K>,
+{
+    let sql_connection_pool = storage_config.sql_connection_pool.clone();
+    let config = IdIndexedV1StoreConfig::new(content_type.clone(), protocol_id, storage_config);
+    let mut store: IdIndexedV1Store =
+        create_store(content_type.clone(), config, sql_connection_pool)?;
+    info!(%content_type, usage_stats = ?store.usage_stats(), "Checking stored content");
+
+    let mut checked_count = 0;
+    let mut not_validated_count = 0;
+    let mut corrupt: Vec<(ContentId, String)> = vec![];
+    let mut last_content_id = None;
+    loop {
+        let checks = store.check_entries::<K>(last_content_id.as_ref(), CHECK_BATCH_SIZE)?;
+        if checks.is_empty() {
+            break;
+        }
+        for check in checks {
+            checked_count += 1;
+            let content_id = match check {
+                EntryCheck::Consistent {
+                    content_id,
+                    content_key,
+                    content_value,
+                } => {
+                    if !can_validate(&content_key) {
+                        not_validated_count += 1;
+                    } else if let Err(err) = validator
+                        .validate_content(&content_key, &content_value)
+                        .await
+                    {
+                        corrupt.push((content_id.clone(), format!("Validation failed: {err}")));
+                    }
+                    content_id
+                }
+                EntryCheck::Corrupt { content_id, reason } => {
+                    corrupt.push((content_id.clone(), reason));
+                    content_id
+                }
+            };
+            last_content_id = Some(content_id);
+        }
+    }
+
+    for (content_id, reason) in &corrupt {
+        warn!(%content_type, content_id = %**content_id, "Corrupt entry: {reason}");
+    }
+    info!(
+        %content_type,
+        "Check finished: checked={checked_count} corrupt={} not_validated={not_validated_count}",
+        corrupt.len(),
+    );
+
+    if repair && !corrupt.is_empty() {
+        for (content_id, _) in &corrupt {
+            store.delete(content_id)?;
+        }
+        store.refresh_usage_stats()?;
+        info!(
+            %content_type,
+            usage_stats = ?store.usage_stats(),
+            "Deleted {} corrupt entries",
+            corrupt.len(),
+        );
+    }
+    Ok(corrupt.len())
+}
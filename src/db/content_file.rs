@@ -0,0 +1,148 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, ensure};
+use portal_bridge::types::e2s::Entry;
+
+// Content file format, based on e2store entries:
+//
+// content-file := Version | Subnetwork | (ContentKey | ContentValue)*
+//
+// Version      = { type: 0x3265, data: nil }
+// Subnetwork   = { type: 0x10,   data: utf8(subnetwork) }
+// ContentKey   = { type: 0x11,   data: content_key }
+// ContentValue = { type: 0x12,   data: content_value }
+
+const VERSION_ENTRY_TYPE: u16 = 0x3265;
+const SUBNETWORK_ENTRY_TYPE: u16 = 0x10;
+const CONTENT_KEY_ENTRY_TYPE: u16 = 0x11;
+const CONTENT_VALUE_ENTRY_TYPE: u16 = 0x12;
+
+/// Writes content key/value pairs of one subnetwork into a content file.
+pub struct ContentFileWriter {
+    writer: BufWriter<File>,
+}
+
+impl ContentFileWriter {
+    /// Creates the file and writes the file header.
+    pub fn create(path: &Path, subnetwork: &str) -> anyhow::Result<Self> {
+        let mut writer = Self {
+            writer: BufWriter::new(File::create(path)?),
+        };
+        writer.write_entry(Entry::new(VERSION_ENTRY_TYPE, vec![]))?;
+        writer.write_entry(Entry::new(
+            SUBNETWORK_ENTRY_TYPE,
+            subnetwork.as_bytes().to_vec(),
+        ))?;
+        Ok(writer)
+    }
+
+    pub fn write(&mut self, content_key: Vec<u8>, content_value: Vec<u8>) -> anyhow::Result<()> {
+        self.write_entry(Entry::new(CONTENT_KEY_ENTRY_TYPE, content_key))?;
+        self.write_entry(Entry::new(CONTENT_VALUE_ENTRY_TYPE, content_value))
+    }
+
+    /// Flushes all buffered content into the file.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    fn write_entry(&mut self, entry: Entry) -> anyhow::Result<()> {
+        Ok(self.writer.write_all(&entry.serialize()?)?)
+    }
+}
+
+/// Reads content key/value pairs from a content file.
+pub struct ContentFileReader {
+    reader: BufReader<File>,
+    subnetwork: String,
+}
+
+impl ContentFileReader {
+    /// Opens the file and reads the file header.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let version = Entry::read(&mut reader)?.ok_or(anyhow!("Content file is empty"))?;
+        ensure!(
+            version.header.type_ == VERSION_ENTRY_TYPE,
+            "Invalid content file: expected version entry, found type {}",
+            version.header.type_
+        );
+        let subnetwork = Entry::read(&mut reader)?.ok_or(anyhow!("Subnetwork entry missing"))?;
+        ensure!(
+            subnetwork.header.type_ == SUBNETWORK_ENTRY_TYPE,
+            "Invalid content file: expected subnetwork entry, found type {}",
+            subnetwork.header.type_
+        );
+
+        Ok(Self {
+            reader,
+            subnetwork: String::from_utf8(subnetwork.value)?,
+        })
+    }
+
+    /// The subnetwork of the stored content.
+    pub fn subnetwork(&self) -> &str {
+        &self.subnetwork
+    }
+
+    /// Reads the next content key/value pair, or returns `None` if there is no more content.
+    pub fn next_content(&mut self) -> anyhow::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let Some(content_key) = Entry::read(&mut self.reader)? else {
+            return Ok(None);
+        };
+        ensure!(
+            content_key.header.type_ == CONTENT_KEY_ENTRY_TYPE,
+            "Invalid content file: expected content key entry, found type {}",
+            content_key.header.type_
+        );
+        let Some(content_value) = Entry::read(&mut self.reader)? else {
+            bail!("Invalid content file: content value is missing");
+        };
+        ensure!(
+            content_value.header.type_ == CONTENT_VALUE_ENTRY_TYPE,
+            "Invalid content file: expected content value entry, found type {}",
+            content_value.header.type_
+        );
+        Ok(Some((content_key.value, content_value.value)))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("content.e2s");
+
+        let mut writer = ContentFileWriter::create(&path, "history").unwrap();
+        writer.write(vec![0, 1, 2], vec![3, 4, 5, 6]).unwrap();
+        writer.write(vec![7], vec![]).unwrap();
+        writer.finish().unwrap();
+
+        let mut reader = ContentFileReader::open(&path).unwrap();
+        assert_eq!(reader.subnetwork(), "history");
+        assert_eq!(
+            reader.next_content().unwrap(),
+            Some((vec![0, 1, 2], vec![3, 4, 5, 6]))
+        );
+        assert_eq!(reader.next_content().unwrap(), Some((vec![7], vec![])));
+        assert_eq!(reader.next_content().unwrap(), None);
+    }
+
+    #[test]
+    fn read_invalid_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("content.e2s");
+        std::fs::write(&path, Entry::new(0x2a, vec![1]).serialize().unwrap()).unwrap();
+
+        assert!(ContentFileReader::open(&path).is_err());
+    }
+}
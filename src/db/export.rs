@@ -0,0 +1,107 @@
+use ethportal_api::{
+    types::{
+        cli::{DbExportConfig, HISTORY_NETWORK, STATE_NETWORK},
+        portal_wire::ProtocolId,
+    },
+    HistoryContentKey, OverlayContentKey, StateContentKey,
+};
+use tracing::{info, warn};
+use trin_storage::{
+    versioned::{create_store, ContentType, EntryCheck, IdIndexedV1Store, IdIndexedV1StoreConfig},
+    PortalStorageConfig,
+};
+
+use super::content_file::ContentFileWriter;
+
+/// The number of entries that are loaded from the store at once.
+const EXPORT_BATCH_SIZE: u64 = 1000;
+
+/// Exports the stored content of one subnetwork into a content file.
+pub fn export_db(
+    storage_config: PortalStorageConfig,
+    export_config: DbExportConfig,
+) -> anyhow::Result<()> {
+    match export_config.subnetwork.as_str() {
+        HISTORY_NETWORK => export_subnetwork::<HistoryContentKey>(
+            ContentType::History,
+            ProtocolId::History,
+            storage_config,
+            export_config,
+        ),
+        STATE_NETWORK => export_subnetwork::<StateContentKey>(
+            ContentType::State,
+            ProtocolId::State,
+            storage_config,
+            export_config,
+        ),
+        subnetwork => Err(anyhow::anyhow!(
+            "Export is not supported for subnetwork: {subnetwork}"
+        )),
+    }
+}
+
+fn export_subnetwork<K: OverlayContentKey>(
+    content_type: ContentType,
+    protocol_id: ProtocolId,
+    storage_config: PortalStorageConfig,
+    export_config: DbExportConfig,
+) -> anyhow::Result<()> {
+    let node_id = storage_config.node_id;
+    let distance_fn = storage_config.distance_fn;
+    let sql_connection_pool = storage_config.sql_connection_pool.clone();
+    let config = IdIndexedV1StoreConfig::new(content_type.clone(), protocol_id, storage_config);
+    let store: IdIndexedV1Store = create_store(content_type.clone(), config, sql_connection_pool)?;
+    info!(
+        %content_type,
+        usage_stats = ?store.usage_stats(),
+        output = %export_config.output.display(),
+        "Exporting stored content",
+    );
+
+    let mut writer = ContentFileWriter::create(&export_config.output, &export_config.subnetwork)?;
+    let mut exported_count = 0;
+    let mut corrupt_count = 0;
+    let mut last_content_id = None;
+    loop {
+        let checks = store.check_entries::<K>(last_content_id.as_ref(), EXPORT_BATCH_SIZE)?;
+        if checks.is_empty() {
+            break;
+        }
+        for check in checks {
+            match check {
+                EntryCheck::Consistent {
+                    content_id,
+                    content_key,
+                    content_value,
+                } => {
+                    let content_key = content_key.to_bytes();
+                    let distance = distance_fn.distance(&node_id, &content_id);
+                    let is_selected = export_config
+                        .content_key_prefix
+                        .as_ref()
+                        .map_or(true, |prefix| content_key.starts_with(prefix))
+                        && export_config
+                            .min_distance
+                            .map_or(true, |min_distance| distance >= min_distance)
+                        && export_config
+                            .max_distance
+                            .map_or(true, |max_distance| distance <= max_distance);
+                    if is_selected {
+                        writer.write(content_key, content_value)?;
+                        exported_count += 1;
+                    }
+                    last_content_id = Some(content_id);
+                }
+                EntryCheck::Corrupt { content_id, reason } => {
+                    warn!(%content_type, content_id = %*content_id, "Skipping corrupt entry: {reason}");
+                    corrupt_count += 1;
+                    last_content_id = Some(content_id);
+                }
+            }
+        }
+    }
+    writer.finish()?;
+
+    info!(%content_type, "Export finished: exported={exported_count} corrupt={corrupt_count}");
+    Ok(())
+}
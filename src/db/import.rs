@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use ethportal_api::{
+    types::{
+        cli::{DbImportConfig, TrinConfig, HISTORY_NETWORK, STATE_NETWORK},
+        history::ContentInfo,
+        jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
+    },
+    ContentValue, HistoryContentKey, HistoryContentValue, OverlayContentKey, StateContentKey,
+};
+use parking_lot::RwLock;
+use tokio::sync::{mpsc, RwLock as TokioRwLock};
+use tracing::{info, warn};
+use trin_history::{storage::HistoryStorage, validation::ChainHistoryValidator};
+use trin_state::{storage::StateStorage, validation::StateValidator};
+use trin_storage::{
+    error::ContentStoreError, ContentStore, PortalStorageConfig, ShouldWeStoreContent,
+};
+use trin_validation::{oracle::HeaderOracle, validator::Validator};
+
+use super::{can_validate_history_offline, content_file::ContentFileReader};
+
+/// Imports the content from a content file, validating each entry before storing it.
+pub async fn import_db(
+    trin_config: &TrinConfig,
+    storage_config: PortalStorageConfig,
+    import_config: DbImportConfig,
+) -> anyhow::Result<()> {
+    let reader = ContentFileReader::open(&import_config.input)?;
+    let mut header_oracle = HeaderOracle::new_for_network(&trin_config.network)?;
+    match reader.subnetwork() {
+        HISTORY_NETWORK => {
+            let storage = Arc::new(RwLock::new(HistoryStorage::new(storage_config)?));
+            // Content that can't be validated on its own (e.g. block bodies) is validated against
+            // the content that is already stored (e.g. block headers).
+            header_oracle.history_jsonrpc_tx = Some(serve_local_history_content(storage.clone()));
+            let validator = ChainHistoryValidator {
+                header_oracle: Arc::new(TokioRwLock::new(header_oracle)),
+            };
+            import_subnetwork::<HistoryContentKey, _, _>(
+                &import_config,
+                storage,
+                &validator,
+                can_validate_history_offline,
+            )
+            .await
+        }
+        STATE_NETWORK => {
+            let storage = Arc::new(RwLock::new(StateStorage::new(storage_config)?));
+            let validator = StateValidator {
+                header_oracle: Arc::new(TokioRwLock::new(header_oracle)),
+            };
+            import_subnetwork::<StateContentKey, _, _>(&import_config, storage, &validator, |_| {
+                true
+            })
+            .await
+        }
+        subnetwork => Err(anyhow!(
+            "Import is not supported for subnetwork: {subnetwork}"
+        )),
+    }
+}
+
+/// Imports the content of one subnetwork.
+///
+/// The content is imported in two passes over the file. The first pass imports content that can
+/// be validated on its own, while the second pass imports the content whose validation depends on
+/// the content imported in the first pass.
+async fn import_subnetwork<K, S, V>(
+    import_config: &DbImportConfig,
+    storage: Arc<RwLock<S>>,
+    validator: &V,
+    can_validate_offline: impl Fn(&K) -> bool,
+) -> anyhow::Result<()>
+where
+    K: OverlayContentKey + Send,
+    S: ImportStore<K>,
+    V: Validator<K>,
+{
+    let mut imported_count = 0;
+    let mut already_stored_count = 0;
+    let mut invalid_count = 0;
+    let mut has_dependent_content = false;
+
+    for first_pass in [true, false] {
+        if !first_pass && !has_dependent_content {
+            break;
+        }
+        let mut reader = ContentFileReader::open(&import_config.input)?;
+        while let Some((content_key, content_value)) = reader.next_content()? {
+            let content_key = match K::try_from(content_key) {
+                Ok(content_key) => content_key,
+                Err(err) => {
+                    if first_pass {
+                        warn!("Skipping invalid content key: {err}");
+                        invalid_count += 1;
+                    }
+                    continue;
+                }
+            };
+            if can_validate_offline(&content_key) != first_pass {
+                has_dependent_content = true;
+                continue;
+            }
+
+            if storage
+                .read()
+                .is_key_within_radius_and_unavailable(&content_key)?
+                == ShouldWeStoreContent::AlreadyStored
+            {
+                already_stored_count += 1;
+                continue;
+            }
+            match validator
+                .validate_content(&content_key, &content_value)
+                .await
+            {
+                Ok(validation_result) => {
+                    if storage.write().import(
+                        content_key.clone(),
+                        content_value,
+                        validation_result.valid_for_storing,
+                    )? {
+                        imported_count += 1;
+                    } else {
+                        warn!(%content_key, "Skipping content that can't be proven canonical");
+                        invalid_count += 1;
+                    }
+                }
+                Err(err) => {
+                    warn!(%content_key, "Skipping invalid content: {err}");
+                    invalid_count += 1;
+                }
+            }
+        }
+    }
+
+    info!(
+        input = %import_config.input.display(),
+        "Import finished: imported={imported_count} already_stored={already_stored_count} invalid={invalid_count}"
+    );
+    Ok(())
+}
+
+/// Stores the imported content that passed validation.
+trait ImportStore<K>: ContentStore {
+    /// Stores the content, or returns `false` if content that isn't valid for storing can't be
+    /// imported.
+    fn import(
+        &mut self,
+        content_key: K,
+        content_value: Vec<u8>,
+        valid_for_storing: bool,
+    ) -> Result<bool, ContentStoreError>;
+}
+
+impl ImportStore<HistoryContentKey> for HistoryStorage {
+    fn import(
+        &mut self,
+        content_key: HistoryContentKey,
+        content_value: Vec<u8>,
+        valid_for_storing: bool,
+    ) -> Result<bool, ContentStoreError> {
+        if !valid_for_storing {
+            return Ok(false);
+        }
+        self.put(content_key, content_value)?;
+        Ok(true)
+    }
+}
+
+impl ImportStore<StateContentKey> for StateStorage {
+    fn import(
+        &mut self,
+        content_key: StateContentKey,
+        content_value: Vec<u8>,
+        valid_for_storing: bool,
+    ) -> Result<bool, ContentStoreError> {
+        if valid_for_storing {
+            self.put(content_key, content_value)?;
+        } else {
+            // Exported state content is in the stored form (without proofs), which the validator
+            // only checks against the hash from the content key.
+            self.put_stored(&content_key, &content_value)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Serves the history lookups of the `HeaderOracle` from the local store.
+fn serve_local_history_content(
+    storage: Arc<RwLock<HistoryStorage>>,
+) -> mpsc::UnboundedSender<HistoryJsonRpcRequest> {
+    let (history_jsonrpc_tx, mut history_jsonrpc_rx) =
+        mpsc::unbounded_channel::<HistoryJsonRpcRequest>();
+    tokio::spawn(async move {
+        while let Some(request) = history_jsonrpc_rx.recv().await {
+            let response = match request.endpoint {
+                HistoryEndpoint::RecursiveFindContent(content_key) => {
+                    lookup_local_history_content(&storage, &content_key)
+                }
+                endpoint => Err(format!("Unsupported endpoint: {endpoint:?}")),
+            };
+            let _ = request.resp.send(response);
+        }
+    });
+    history_jsonrpc_tx
+}
+
+fn lookup_local_history_content(
+    storage: &RwLock<HistoryStorage>,
+    content_key: &HistoryContentKey,
+) -> Result<serde_json::Value, String> {
+    let content = storage
+        .read()
+        .get(content_key)
+        .map_err(|err| err.to_string())?
+        .ok_or(format!("Content not found locally: {content_key}"))?;
+    let content = HistoryContentValue::decode(&content).map_err(|err| err.to_string())?;
+    serde_json::to_value(ContentInfo::Content {
+        content,
+        utp_transfer: false,
+    })
+    .map_err(|err| err.to_string())
+}
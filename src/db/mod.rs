@@ -0,0 +1,221 @@
+mod check;
+mod content_file;
+mod export;
+mod import;
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use discv5::enr::CombinedKey;
+use ethportal_api::{
+    types::{
+        cli::{DbCommands, DbConfig, TrinConfig},
+        enr::Enr,
+    },
+    HistoryContentKey,
+};
+use portalnet::utils::{
+    db::{configure_node_data_dir, configure_trin_data_dir},
+    keystore::read_keystore_password,
+};
+use trin_storage::{versioned::StorageBackend, PortalStorageConfig, BYTES_IN_MB_U64};
+
+use check::check_db;
+use export::export_db;
+use import::import_db;
+
+/// Runs the `trin db` command.
+pub async fn run_db_command(trin_config: TrinConfig, db_config: DbConfig) -> anyhow::Result<()> {
+    let storage_config = create_storage_config(&trin_config)?;
+    match db_config.command {
+        DbCommands::Check(check_config) => {
+            check_db(&trin_config, storage_config, check_config).await
+        }
+        DbCommands::Export(export_config) => export_db(storage_config, export_config),
+        DbCommands::Import(import_config) => {
+            import_db(&trin_config, storage_config, import_config).await
+        }
+    }
+}
+
+/// Creates the storage config for the database of the configured node.
+///
+/// The storage capacity is set to the maximum, so opening the store never prunes content.
+fn create_storage_config(trin_config: &TrinConfig) -> anyhow::Result<PortalStorageConfig> {
+    let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral)?;
+    let keystore_password = read_keystore_password(trin_config.keystore_password_file.as_deref())?;
+    let (node_data_dir, private_key) = configure_node_data_dir(
+        trin_data_dir,
+        trin_config.private_key,
+        trin_config.network.get_network_name().to_string(),
+        keystore_password.as_deref(),
+    )?;
+    let private_key = CombinedKey::secp256k1_from_bytes(private_key.0.clone().as_mut_slice())
+        .map_err(|err| anyhow!("Invalid private key: {err:?}"))?;
+    let node_id = Enr::empty(&private_key)?.node_id();
+
+    Ok(
+        PortalStorageConfig::new(u64::MAX / BYTES_IN_MB_U64, node_data_dir, node_id)?
+            .with_backend(StorageBackend::from_str(&trin_config.storage_backend)?),
+    )
+}
+
+/// Returns whether history content can be validated without access to the network.
+///
+/// Block bodies and receipts are validated against the header, which might not be stored locally.
+fn can_validate_history_offline(content_key: &HistoryContentKey) -> bool {
+    matches!(
+        content_key,
+        HistoryContentKey::BlockHeaderWithProof(_) | HistoryContentKey::EpochAccumulator(_)
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use ethportal_api::{
+        types::cli::{
+            DbCheckConfig, DbExportConfig, DbImportConfig, BEACON_NETWORK, HISTORY_NETWORK,
+            STATE_NETWORK,
+        },
+        utils::bytes::hex_decode,
+        StateContentKey,
+    };
+    use serde_json::Value;
+    use trin_history::storage::HistoryStorage;
+    use trin_state::storage::StateStorage;
+    use trin_storage::{test_utils::create_test_portal_storage_config_with_capacity, ContentStore};
+
+    use super::*;
+
+    const STORAGE_CAPACITY_MB: u64 = 10;
+
+    #[tokio::test]
+    async fn export_and_import_history() {
+        let (_source_dir, source_config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB).unwrap();
+        let (_target_dir, target_config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB).unwrap();
+        let file_dir = tempfile::tempdir().unwrap();
+        let path = file_dir.path().join("history.e2s");
+
+        let (content_key, content_value) = read_header_with_proof();
+        HistoryStorage::new(source_config.clone())
+            .unwrap()
+            .put(content_key.clone(), &content_value)
+            .unwrap();
+
+        export_db(source_config, export_config(HISTORY_NETWORK, path.clone())).unwrap();
+        import_db(
+            &TrinConfig::default(),
+            target_config.clone(),
+            DbImportConfig { input: path },
+        )
+        .await
+        .unwrap();
+
+        let storage = HistoryStorage::new(target_config).unwrap();
+        assert_eq!(storage.get(&content_key).unwrap(), Some(content_value));
+    }
+
+    #[tokio::test]
+    async fn export_and_import_state() {
+        let (_source_dir, source_config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB).unwrap();
+        let (_target_dir, target_config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB).unwrap();
+        let file_dir = tempfile::tempdir().unwrap();
+        let path = file_dir.path().join("state.e2s");
+
+        let test_cases: Vec<_> = [
+            "account_trie_node.yaml",
+            "contract_storage_trie_node.yaml",
+            "contract_bytecode.yaml",
+        ]
+        .into_iter()
+        .flat_map(read_state_test_cases)
+        .collect();
+        let mut storage = StateStorage::new(source_config.clone()).unwrap();
+        for (content_key, offer_value, _) in &test_cases {
+            storage.put(content_key.clone(), offer_value).unwrap();
+        }
+        drop(storage);
+
+        export_db(source_config, export_config(STATE_NETWORK, path.clone())).unwrap();
+        import_db(
+            &TrinConfig::default(),
+            target_config.clone(),
+            DbImportConfig { input: path },
+        )
+        .await
+        .unwrap();
+
+        let storage = StateStorage::new(target_config).unwrap();
+        for (content_key, _, lookup_value) in test_cases {
+            assert_eq!(storage.get(&content_key).unwrap(), Some(lookup_value));
+        }
+    }
+
+    #[tokio::test]
+    async fn check_rejects_beacon() {
+        let (_dir, config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB).unwrap();
+        let trin_config = TrinConfig {
+            portal_subnetworks: vec![HISTORY_NETWORK.to_string(), BEACON_NETWORK.to_string()],
+            ..Default::default()
+        };
+
+        let err = check_db(&trin_config, config, DbCheckConfig::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(BEACON_NETWORK));
+    }
+
+    fn export_config(subnetwork: &str, output: PathBuf) -> DbExportConfig {
+        DbExportConfig {
+            subnetwork: subnetwork.to_string(),
+            output,
+            content_key_prefix: None,
+            min_distance: None,
+            max_distance: None,
+        }
+    }
+
+    fn read_header_with_proof() -> (HistoryContentKey, Vec<u8>) {
+        let file = fs::read_to_string("trin-validation/src/assets/fluffy/header_with_proofs.json")
+            .unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let header_with_proof = &json["1000001"];
+        let content_key = hex_decode(header_with_proof["content_key"].as_str().unwrap()).unwrap();
+        let content_value = hex_decode(header_with_proof["value"].as_str().unwrap()).unwrap();
+        (
+            HistoryContentKey::try_from(content_key).unwrap(),
+            content_value,
+        )
+    }
+
+    /// Reads the content keys with their offer and lookup values.
+    fn read_state_test_cases(file_name: &str) -> Vec<(StateContentKey, Vec<u8>, Vec<u8>)> {
+        let file = fs::read_to_string(
+            PathBuf::from("portal-spec-tests/tests/mainnet/state/validation").join(file_name),
+        )
+        .unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&file).unwrap();
+        value
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|test_case| {
+                let read_bytes =
+                    |field: &str| hex_decode(test_case[field].as_str().unwrap()).unwrap();
+                (
+                    StateContentKey::try_from(read_bytes("content_key")).unwrap(),
+                    read_bytes("content_value_offer"),
+                    read_bytes("content_value_retrieval"),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, ensure};
+use discv5::enr::CombinedKey;
+use tracing::{info, warn};
+
+use ethportal_api::{
+    types::{
+        cli::{KeyCommands, KeyConfig, TrinConfig},
+        enr::Enr,
+    },
+    utils::bytes::{hex_decode, hex_encode},
+};
+use portalnet::utils::{
+    db::{
+        configure_trin_data_dir, export_private_key, get_application_private_key,
+        has_application_private_key, set_application_private_key,
+    },
+    keystore::{read_keystore_password, KEYSTORE_PASSWORD_ENV_VAR},
+};
+
+/// Runs the `trin key` command.
+pub fn run_key_command(trin_config: TrinConfig, key_config: KeyConfig) -> anyhow::Result<()> {
+    let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral)?;
+    let password = read_keystore_password(trin_config.keystore_password_file.as_deref())?
+        .ok_or_else(|| {
+            anyhow!("Provide the keystore password with --keystore-password-file or {KEYSTORE_PASSWORD_ENV_VAR}")
+        })?;
+
+    match key_config.command {
+        KeyCommands::Generate(generate_config) => {
+            ensure_replaceable(&trin_data_dir, generate_config.force)?;
+            let private_key = CombinedKey::generate_secp256k1();
+            set_application_private_key(&trin_data_dir, &private_key, &password)?;
+            info!(node_id = %node_id(&private_key)?, "Generated new private key");
+        }
+        KeyCommands::Import(import_config) => {
+            ensure_replaceable(&trin_data_dir, import_config.force)?;
+            let private_key = read_private_key(&import_config.input)?;
+            set_application_private_key(&trin_data_dir, &private_key, &password)?;
+            info!(node_id = %node_id(&private_key)?, "Imported private key");
+        }
+        KeyCommands::Export(export_config) => {
+            ensure!(
+                has_application_private_key(&trin_data_dir),
+                "No private key found in {}",
+                trin_data_dir.display()
+            );
+            let private_key = get_application_private_key(&trin_data_dir, Some(&password))?;
+            export_private_key(&private_key, &export_config.output)?;
+            warn!(
+                output = %export_config.output.display(),
+                "Exported the private key as plaintext, keep the file safe"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Fails if a private key is already stored, unless it may be replaced.
+fn ensure_replaceable(trin_data_dir: &Path, force: bool) -> anyhow::Result<()> {
+    ensure!(
+        force || !has_application_private_key(trin_data_dir),
+        "A private key is already stored in {}, use --force to replace it, which changes the node id",
+        trin_data_dir.display()
+    );
+    Ok(())
+}
+
+/// Reads a hex encoded private key from the file.
+fn read_private_key(path: &Path) -> anyhow::Result<CombinedKey> {
+    let private_key = fs::read_to_string(path)
+        .map_err(|err| anyhow!("Unable to read private key file {}: {err}", path.display()))?;
+    let mut private_key = hex_decode(private_key.trim())?;
+    ensure!(
+        private_key.len() == 32,
+        "Private key must be 32 bytes, found {} bytes",
+        private_key.len()
+    );
+    CombinedKey::secp256k1_from_bytes(&mut private_key)
+        .map_err(|err| anyhow!("Invalid private key: {err:?}"))
+}
+
+fn node_id(private_key: &CombinedKey) -> anyhow::Result<String> {
+    Ok(hex_encode(Enr::empty(private_key)?.node_id().raw()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use ethportal_api::types::cli::{KeyExportConfig, KeyGenerateConfig, KeyImportConfig};
+    use portalnet::utils::{db::setup_temp_dir, keystore::keystore_path};
+    use serial_test::serial;
+
+    fn key_config(command: KeyCommands) -> KeyConfig {
+        KeyConfig { command }
+    }
+
+    #[test]
+    #[serial]
+    fn generate_import_and_export_key() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let password_file = temp_dir.path().join("password");
+        fs::write(&password_file, "password").unwrap();
+        let trin_config = TrinConfig {
+            keystore_password_file: Some(password_file),
+            ..Default::default()
+        };
+
+        run_key_command(
+            trin_config.clone(),
+            key_config(KeyCommands::Generate(KeyGenerateConfig { force: false })),
+        )
+        .unwrap();
+        assert!(keystore_path(temp_dir.path()).exists());
+
+        // The generated key is not replaced without --force.
+        let generated_key = get_application_private_key(temp_dir.path(), Some("password")).unwrap();
+        assert!(run_key_command(
+            trin_config.clone(),
+            key_config(KeyCommands::Generate(KeyGenerateConfig { force: false })),
+        )
+        .is_err());
+
+        let imported_key = CombinedKey::generate_secp256k1();
+        let input = temp_dir.path().join("import.hex");
+        export_private_key(&imported_key, &input).unwrap();
+        run_key_command(
+            trin_config.clone(),
+            key_config(KeyCommands::Import(KeyImportConfig { input, force: true })),
+        )
+        .unwrap();
+        let app_key = get_application_private_key(temp_dir.path(), Some("password")).unwrap();
+        assert_eq!(app_key.encode(), imported_key.encode());
+        assert_ne!(app_key.encode(), generated_key.encode());
+
+        let output = temp_dir.path().join("export.hex");
+        run_key_command(
+            trin_config,
+            key_config(KeyCommands::Export(KeyExportConfig {
+                output: output.clone(),
+            })),
+        )
+        .unwrap();
+        assert_eq!(
+            read_private_key(&output).unwrap().encode(),
+            imported_key.encode()
+        );
+        temp_dir.close().unwrap();
+    }
+}
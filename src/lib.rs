@@ -1,30 +1,44 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
-use std::sync::Arc;
+pub mod config;
+pub mod db;
+pub mod key;
+
+use std::{str::FromStr, sync::Arc};
 
 use rpc::{launch_jsonrpc_server, RpcServerHandle};
-use tokio::sync::{mpsc, RwLock};
-use tracing::info;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tracing::{info, warn};
 use tree_hash::TreeHash;
 use utp_rs::socket::UtpSocket;
 
 #[cfg(windows)]
 use ethportal_api::types::cli::Web3TransportType;
 use ethportal_api::{
-    types::cli::{TrinConfig, BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+    types::{
+        bootnodes::{Bootnode, Bootnodes},
+        cli::{TrinConfig, BEACON_NETWORK, HISTORY_NETWORK, STATE_NETWORK},
+    },
     utils::bytes::hex_encode,
 };
 use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, Discv5UdpSocket},
-    events::PortalnetEvents,
-    utils::db::{configure_node_data_dir, configure_trin_data_dir},
+    enr_tree::{spawn_enr_tree_refresh, DnsTxtResolver, EnrTree, ENR_TREE_REFRESH_INTERVAL},
+    events::{PortalnetEvents, SUBSCRIPTION_CHANNEL_CAPACITY},
+    utils::{
+        db::{configure_node_data_dir, configure_trin_data_dir},
+        keystore::read_keystore_password,
+    },
 };
 use trin_beacon::initialize_beacon_network;
 use trin_history::initialize_history_network;
 use trin_state::initialize_state_network;
-use trin_storage::PortalStorageConfig;
+use trin_storage::{
+    versioned::{PruningConfig, StorageBackend},
+    PortalStorageConfig, RadiusConfig,
+};
 use trin_utils::version::get_trin_version;
 use trin_validation::oracle::HeaderOracle;
 
@@ -46,13 +60,37 @@ pub async fn run_trin(
     let trin_data_dir = configure_trin_data_dir(trin_config.ephemeral)?;
 
     // Configure node data dir based on the provided private key
+    let keystore_password = read_keystore_password(trin_config.keystore_password_file.as_deref())?;
     let (node_data_dir, private_key) = configure_node_data_dir(
         trin_data_dir,
         trin_config.private_key,
         trin_config.network.get_network_name().to_string(),
+        keystore_password.as_deref(),
     )?;
 
-    let portalnet_config = PortalnetConfig::new(&trin_config, private_key);
+    let mut portalnet_config = PortalnetConfig::new(&trin_config, private_key);
+
+    // Resolve the bootnodes of an ENR tree, which is refreshed periodically once discovery starts
+    let enr_tree = match &trin_config.bootnodes {
+        Bootnodes::EnrTree(link) => {
+            let mut tree =
+                EnrTree::new(link.clone(), Arc::new(DnsTxtResolver::from_system_conf()?));
+            let enrs = match tree.resolve().await {
+                Ok(enrs) => {
+                    info!(tree = %link, count = enrs.len(), "Resolved bootnodes from ENR tree");
+                    Some(enrs)
+                }
+                Err(err) => {
+                    warn!(%err, tree = %link, "Failed to resolve ENR tree, starting without bootnodes");
+                    None
+                }
+            };
+            portalnet_config.bootnodes =
+                Bootnodes::Custom(enrs.iter().flatten().cloned().map(Bootnode::from).collect());
+            Some((tree, enrs))
+        }
+        _ => None,
+    };
 
     // Initialize base discovery protocol
     let mut discovery = Discovery::new(
@@ -62,6 +100,14 @@ pub async fn run_trin(
     )?;
     let talk_req_rx = discovery.start().await?;
     let discovery = Arc::new(discovery);
+    if let Some((tree, enrs)) = enr_tree {
+        spawn_enr_tree_refresh(
+            tree,
+            enrs,
+            Arc::clone(&discovery),
+            ENR_TREE_REFRESH_INTERVAL,
+        );
+    }
 
     // Initialize prometheus metrics
     if let Some(addr) = trin_config.enable_metrics_with_url {
@@ -74,16 +120,25 @@ pub async fn run_trin(
     let utp_socket = UtpSocket::with_socket(discv5_utp_socket);
     let utp_socket = Arc::new(utp_socket);
 
+    let radius_config = match (trin_config.fixed_radius, trin_config.min_radius) {
+        (Some(radius), _) => RadiusConfig::Static(radius),
+        (None, Some(min_radius)) => RadiusConfig::Dynamic { min_radius },
+        (None, None) => RadiusConfig::default(),
+    };
     let storage_config = PortalStorageConfig::new(
         trin_config.mb.into(),
         node_data_dir,
         discovery.local_enr().node_id(),
-    )?;
+    )?
+    .with_radius_config(radius_config)
+    .with_backend(StorageBackend::from_str(&trin_config.storage_backend)?)
+    .with_pruning_config(PruningConfig::default().with_tuning(&trin_config.pruning));
 
     // Initialize validation oracle
-    let header_oracle = HeaderOracle::default();
+    let header_oracle = HeaderOracle::new_for_network(&trin_config.network)?;
     info!(hash_tree_root = %hex_encode(header_oracle.header_validator.pre_merge_acc.tree_hash_root().0),"Loaded
         pre-merge accumulator.");
+    let pre_merge_acc = Arc::new(header_oracle.header_validator.pre_merge_acc.clone());
     let header_oracle = Arc::new(RwLock::new(header_oracle));
 
     // Initialize state sub-network service and event handlers, if selected
@@ -96,7 +151,9 @@ pub async fn run_trin(
                 &discovery,
                 utp_socket.clone(),
                 portalnet_config.clone(),
-                storage_config.clone(),
+                storage_config
+                    .clone()
+                    .with_storage_capacity_mb(trin_config.storage_capacity_mb(STATE_NETWORK)),
                 header_oracle.clone(),
             )
             .await?
@@ -119,7 +176,9 @@ pub async fn run_trin(
             &discovery,
             utp_socket.clone(),
             portalnet_config.clone(),
-            storage_config.clone(),
+            storage_config
+                .clone()
+                .with_storage_capacity_mb(trin_config.storage_capacity_mb(BEACON_NETWORK)),
             header_oracle.clone(),
         )
         .await?
@@ -142,7 +201,9 @@ pub async fn run_trin(
             &discovery,
             utp_socket.clone(),
             portalnet_config.clone(),
-            storage_config.clone(),
+            storage_config
+                .clone()
+                .with_storage_capacity_mb(trin_config.storage_capacity_mb(HISTORY_NETWORK)),
             header_oracle.clone(),
         )
         .await?
@@ -150,6 +211,9 @@ pub async fn run_trin(
         (None, None, None, None, None)
     };
 
+    // Overlay events published to the JSON-RPC subscriptions
+    let (subscription_tx, _) = broadcast::channel(SUBSCRIPTION_CHANNEL_CAPACITY);
+
     // Launch JSON-RPC server
     let jsonrpc_trin_config = trin_config.clone();
     let jsonrpc_discovery = Arc::clone(&discovery);
@@ -159,6 +223,8 @@ pub async fn run_trin(
         history_jsonrpc_tx,
         state_jsonrpc_tx,
         beacon_jsonrpc_tx,
+        subscription_tx.clone(),
+        pre_merge_acc,
     )
     .await?;
 
@@ -181,6 +247,7 @@ pub async fn run_trin(
             (beacon_event_tx, beacon_event_stream),
             utp_talk_reqs_tx,
             trin_config.network.clone(),
+            subscription_tx,
         )
         .await;
         events.start().await;
@@ -1,15 +1,24 @@
 #![warn(clippy::unwrap_used)]
 
-use ethportal_api::types::cli::TrinConfig;
+use ethportal_api::types::cli::{TrinConfig, TrinConfigCommands};
 use tracing::error;
 use trin_utils::log::init_tracing_logger;
 
-use trin::run_trin;
+use trin::{config::run_config_command, db::run_db_command, key::run_key_command, run_trin};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     init_tracing_logger();
     let trin_config = TrinConfig::from_cli();
+    if let Some(TrinConfigCommands::Db(db_config)) = trin_config.command.clone() {
+        return Ok(run_db_command(trin_config, db_config).await?);
+    }
+    if let Some(TrinConfigCommands::Key(key_config)) = trin_config.command.clone() {
+        return Ok(run_key_command(trin_config, key_config)?);
+    }
+    if let Some(TrinConfigCommands::Config(config_command_config)) = trin_config.command.clone() {
+        return Ok(run_config_command(trin_config, config_command_config)?);
+    }
     let rpc_handle = run_trin(trin_config).await?;
 
     tokio::signal::ctrl_c()
@@ -42,6 +42,7 @@ async fn peertest_stateless() {
         peertest::scenarios::basic::test_delete_enr(protocol, &target, &peertest).await;
         peertest::scenarios::basic::test_lookup_enr(protocol, &peertest).await;
         peertest::scenarios::basic::test_ping(protocol, &target, &peertest).await;
+        peertest::scenarios::basic::test_routing_table_detail(protocol, &target, &peertest).await;
         peertest::scenarios::basic::test_find_nodes(protocol, &target, &peertest).await;
         peertest::scenarios::basic::test_find_nodes_zero_distance(protocol, &target, &peertest)
             .await;
@@ -148,6 +149,15 @@ async fn peertest_validate_block_body() {
     handle.stop().unwrap();
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn peertest_history_get_range() {
+    let (peertest, target, handle) = setup_peertest("mainnet").await;
+    peertest::scenarios::range::test_get_range(&target).await;
+    peertest.exit_all_nodes();
+    handle.stop().unwrap();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn peertest_validate_receipts() {
@@ -10,6 +10,7 @@ use ethportal_api::{
         portal::{AcceptInfo, FindNodesInfo, PongInfo},
         portal_wire::Content,
         query_trace::QueryTrace,
+        query_tuning::QueryTuning,
     },
     utils::bytes::hex_encode,
     BeaconContentKey, BeaconContentValue, OverlayContentKey, RawContentKey,
@@ -80,6 +81,14 @@ async fn complete_request(network: Arc<BeaconNetwork>, request: BeaconJsonRpcReq
             serde_json::to_value(network.overlay.routing_table_info())
                 .map_err(|err| err.to_string())
         }
+        BeaconEndpoint::RoutingTableDetail => {
+            serde_json::to_value(network.overlay.routing_table_detail())
+                .map_err(|err| err.to_string())
+        }
+        BeaconEndpoint::SetStorageCapacity(capacity_mb) => {
+            set_storage_capacity(network, capacity_mb).await
+        }
+        BeaconEndpoint::SetQueryTuning(tuning) => set_query_tuning(network, tuning).await,
         BeaconEndpoint::RecursiveFindNodes(node_id) => recursive_find_nodes(network, node_id).await,
     };
     let _ = request.resp.send(response);
@@ -200,6 +209,35 @@ async fn paginate_local_content_keys(
     response
 }
 
+/// Updates the query parameters of the overlay network.
+async fn set_query_tuning(
+    network: Arc<BeaconNetwork>,
+    tuning: QueryTuning,
+) -> Result<Value, String> {
+    match network.overlay.set_query_tuning(tuning).await {
+        Ok(tuning) => serde_json::to_value(tuning).map_err(|err| err.to_string()),
+        Err(err) => Err(format!("Error while setting query tuning: {err}")),
+    }
+}
+
+/// Updates the storage capacity of the local database.
+async fn set_storage_capacity(
+    network: Arc<BeaconNetwork>,
+    capacity_mb: u64,
+) -> Result<Value, String> {
+    match network
+        .overlay
+        .store
+        .write()
+        .set_storage_capacity(capacity_mb)
+    {
+        Ok(_) => Ok(Value::Bool(true)),
+        Err(err) => Err(format!(
+            "Database error while setting storage capacity to {capacity_mb} MB: {err}"
+        )),
+    }
+}
+
 /// Constructs a JSON call for the Store method.
 async fn store(
     network: Arc<BeaconNetwork>,
@@ -7,12 +7,13 @@ use utp_rs::socket::UtpSocket;
 
 use crate::{storage::BeaconStorage, sync::BeaconSync, validation::BeaconValidator};
 use ethportal_api::{
-    types::{distance::XorMetric, enr::Enr, portal_wire::ProtocolId},
+    types::{cli::BEACON_NETWORK, distance::XorMetric, enr::Enr, portal_wire::ProtocolId},
     BeaconContentKey,
 };
 use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, UtpEnr},
+    gossip::GossipConfig,
     overlay::{config::OverlayConfig, protocol::OverlayProtocol},
 };
 use trin_storage::PortalStorageConfig;
@@ -37,8 +38,18 @@ impl BeaconNetwork {
         let config = OverlayConfig {
             bootnode_enrs,
             utp_transfer_limit: portal_config.utp_transfer_limit,
+            utp_adaptive_transfer_limit: portal_config.utp_adaptive_transfer_limit,
+            utp_stall_config: portal_config.utp_stall_config,
+            utp_conn_tuning: portal_config.utp_conn_tuning,
+            ping_capabilities: portal_config.enabled_subnetworks,
+            gossip: GossipConfig {
+                strategy: portal_config.gossip_strategy,
+                ..Default::default()
+            },
+            accept_queue_timeout: portal_config.accept_queue_timeout,
             ..Default::default()
-        };
+        }
+        .with_tuning(&portal_config.overlay.for_subnetwork(BEACON_NETWORK));
         let storage = Arc::new(PLRwLock::new(BeaconStorage::new(storage_config)?));
         let validator = Arc::new(BeaconValidator { header_oracle });
         let overlay = OverlayProtocol::new(
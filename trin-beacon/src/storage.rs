@@ -6,32 +6,22 @@ use ethportal_api::{
             LIGHT_CLIENT_UPDATES_BY_RANGE_KEY_PREFIX,
         },
         content_value::beacon::{
-            ForkVersionedHistoricalSummariesWithProof, ForkVersionedLightClientFinalityUpdate,
-            ForkVersionedLightClientOptimisticUpdate, ForkVersionedLightClientUpdate,
-            LightClientUpdatesByRange,
+            ForkVersionedHistoricalSummariesWithProof, ForkVersionedLightClientBootstrap,
+            ForkVersionedLightClientFinalityUpdate, ForkVersionedLightClientOptimisticUpdate,
+            ForkVersionedLightClientUpdate, LightClientUpdatesByRange,
         },
         distance::Distance,
-        portal_wire::ProtocolId,
+        ping_payload::StorageUtilization,
     },
     BeaconContentKey, OverlayContentKey,
 };
-use r2d2::Pool;
-use r2d2_sqlite::{rusqlite, SqliteConnectionManager};
-use rusqlite::params;
 use ssz::{Decode, Encode};
 use ssz_types::{typenum::U128, VariableList};
-use std::path::PathBuf;
 use tracing::debug;
-use trin_metrics::storage::StorageMetricsReporter;
 use trin_storage::{
     error::ContentStoreError,
-    sql::{
-        CONTENT_KEY_LOOKUP_QUERY_BEACON, CONTENT_VALUE_LOOKUP_QUERY_BEACON, INSERT_LC_UPDATE_QUERY,
-        INSERT_QUERY_BEACON, LC_UPDATE_LOOKUP_QUERY, LC_UPDATE_PERIOD_LOOKUP_QUERY,
-        LC_UPDATE_TOTAL_SIZE_QUERY, TOTAL_DATA_SIZE_QUERY_BEACON,
-    },
-    utils::get_total_size_of_directory_in_bytes,
-    ContentStore, DataSize, PortalStorageConfig, ShouldWeStoreContent, BYTES_IN_MB_U64,
+    versioned::{create_store, BeaconV1Store, BeaconV1StoreConfig, ContentType},
+    ContentStore, PortalStorageConfig, ShouldWeStoreContent, BYTES_IN_MB_U64,
 };
 
 /// Store ephemeral light client data in memory
@@ -132,13 +122,10 @@ impl BeaconStorageCache {
     }
 }
 
-/// Storage layer for the state network. Encapsulates beacon network specific data and logic.
+/// Storage layer for the beacon network. Encapsulates beacon network specific data and logic.
 #[derive(Debug)]
 pub struct BeaconStorage {
-    node_data_dir: PathBuf,
-    sql_connection_pool: Pool<SqliteConnectionManager>,
-    storage_capacity_in_bytes: u64,
-    metrics: StorageMetricsReporter,
+    store: BeaconV1Store,
     cache: BeaconStorageCache,
 }
 
@@ -153,12 +140,7 @@ impl ContentStore for BeaconStorage {
 
         match beacon_content_key {
             BeaconContentKey::LightClientBootstrap(_) => {
-                let content_id = key.content_id();
-                self.lookup_content_value(content_id).map_err(|err| {
-                    ContentStoreError::Database(format!(
-                        "Error looking up LightClientBootstrap content value: {err:?}"
-                    ))
-                })
+                self.store.lookup_bootstrap(&key.content_id().into())
             }
             BeaconContentKey::LightClientUpdatesByRange(content_key) => {
                 let periods =
@@ -167,13 +149,7 @@ impl ContentStore for BeaconStorage {
                 let mut content: Vec<ForkVersionedLightClientUpdate> = Vec::new();
 
                 for period in periods {
-                    let result = self.lookup_lc_update_value(period).map_err(|err| {
-                        ContentStoreError::Database(format!(
-                            "Error looking up LightClientUpdate content value: {err:?}"
-                        ))
-                    })?;
-
-                    match result {
+                    match self.store.lookup_lc_update(period)? {
                         Some(result) => content.push(
                             ForkVersionedLightClientUpdate::from_ssz_bytes(result.as_slice())
                                 .map_err(|err| {
@@ -243,16 +219,7 @@ impl ContentStore for BeaconStorage {
 
         match beacon_content_key {
             BeaconContentKey::LightClientBootstrap(_) => {
-                let key = key.content_id();
-                let is_key_available = self
-                    .lookup_content_key(key)
-                    .map_err(|err| {
-                        ContentStoreError::Database(format!(
-                            "Error looking up content key: {err:?}"
-                        ))
-                    })?
-                    .is_some();
-                if is_key_available {
+                if self.store.has_bootstrap(&key.content_id().into())? {
                     return Ok(ShouldWeStoreContent::AlreadyStored);
                 }
                 Ok(ShouldWeStoreContent::Store)
@@ -264,15 +231,7 @@ impl ContentStore for BeaconStorage {
                     content_key.start_period..(content_key.start_period + content_key.count);
 
                 for period in periods {
-                    let is_period_available = self
-                        .lookup_lc_update_period(period)
-                        .map_err(|err| {
-                            ContentStoreError::Database(format!(
-                                "Error looking up lc update period: {err:?}"
-                            ))
-                        })?
-                        .is_some();
-                    if is_period_available {
+                    if self.store.has_lc_update(period)? {
                         return Ok(ShouldWeStoreContent::AlreadyStored);
                     }
                 }
@@ -316,71 +275,42 @@ impl ContentStore for BeaconStorage {
 
     /// The "radius" concept is not applicable for Beacon network, this is why we always return the
     /// max radius.
+    ///
+    /// The max radius is kept even when the storage is full, as the content isn't pruned by its
+    /// distance: the store prunes the content from the oldest sync committee periods instead. A
+    /// radius derived from the capacity wouldn't match what is kept, and would make peers stop
+    /// offering the content of the latest periods, which is the content we keep.
     fn radius(&self) -> Distance {
         Distance::MAX
     }
+
+    fn storage_utilization(&self) -> Option<StorageUtilization> {
+        Some(StorageUtilization {
+            used_bytes: self.store.usage_stats().total_entry_size_bytes,
+            capacity_bytes: self.store.storage_capacity_bytes(),
+        })
+    }
 }
 
 impl BeaconStorage {
     pub fn new(config: PortalStorageConfig) -> Result<Self, ContentStoreError> {
-        let storage = Self {
-            node_data_dir: config.node_data_dir,
-            sql_connection_pool: config.sql_connection_pool,
-            storage_capacity_in_bytes: config.storage_capacity_mb * BYTES_IN_MB_U64,
-            metrics: StorageMetricsReporter::new(ProtocolId::Beacon),
+        let sql_connection_pool = config.sql_connection_pool.clone();
+        let store = create_store(
+            ContentType::Beacon,
+            BeaconV1StoreConfig::new(config),
+            sql_connection_pool,
+        )?;
+        Ok(Self {
+            store,
             cache: BeaconStorageCache::new(),
-        };
-
-        // Report current storage capacity.
-        storage
-            .metrics
-            .report_storage_capacity_bytes(storage.storage_capacity_in_bytes as f64);
-
-        // Report current total storage usage.
-        let total_storage_usage = storage.get_total_storage_usage_in_bytes_on_disk()?;
-        storage
-            .metrics
-            .report_total_storage_usage_bytes(total_storage_usage as f64);
-
-        // Report total storage used by network content.
-        let network_content_storage_usage =
-            storage.get_total_storage_usage_in_bytes_from_network()?;
-        storage
-            .metrics
-            .report_content_data_storage_bytes(network_content_storage_usage as f64);
-
-        Ok(storage)
+        })
     }
 
-    fn db_insert(
-        &self,
-        content_id: &[u8; 32],
-        content_key: &Vec<u8>,
-        value: &Vec<u8>,
-    ) -> Result<usize, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        Ok(conn.execute(
-            INSERT_QUERY_BEACON,
-            params![
-                content_id.as_slice(),
-                content_key,
-                value,
-                32 + content_key.len() + value.len()
-            ],
-        )?)
-    }
-
-    fn db_insert_lc_update(&self, period: &u64, value: &Vec<u8>) -> Result<(), ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        let value_size = value.len();
-
-        match conn.execute(
-            INSERT_LC_UPDATE_QUERY,
-            params![period, value, 0, value_size],
-        ) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(err.into()),
-        }
+    /// Updates the storage capacity, pruning content from the oldest sync committee periods if
+    /// necessary.
+    pub fn set_storage_capacity(&mut self, capacity_mb: u64) -> Result<(), ContentStoreError> {
+        self.store
+            .set_storage_capacity(capacity_mb * BYTES_IN_MB_U64)
     }
 
     pub fn store(
@@ -393,11 +323,20 @@ impl BeaconStorage {
 
         match content_key.first() {
             Some(&LIGHT_CLIENT_BOOTSTRAP_KEY_PREFIX) => {
-                if let Err(err) = self.db_insert(&content_id, &content_key, value) {
+                let bootstrap = ForkVersionedLightClientBootstrap::from_ssz_bytes(value.as_slice())
+                    .map_err(|err| ContentStoreError::InvalidData {
+                        message: format!(
+                            "Error deserializing ForkVersionedLightClientBootstrap value: {err:?}"
+                        ),
+                    })?;
+                if let Err(err) = self.store.insert_bootstrap(
+                    &content_id.into(),
+                    content_key,
+                    value.clone(),
+                    bootstrap.sync_committee_period(),
+                ) {
                     debug!("Error writing light client bootstrap content ID {content_id:?} to beacon network db: {err:?}");
                     return Err(err);
-                } else {
-                    self.metrics.increase_entry_count();
                 }
             }
             Some(&LIGHT_CLIENT_UPDATES_BY_RANGE_KEY_PREFIX) => {
@@ -419,11 +358,10 @@ impl BeaconStorage {
                             })?;
 
                             for (period, value) in periods.zip(update_values.as_ref()) {
-                                if let Err(err) = self.db_insert_lc_update(&period, &value.encode())
+                                if let Err(err) =
+                                    self.store.insert_lc_update(period, value.encode())
                                 {
                                     debug!("Error writing light client update by range content ID {content_id:?} to beacon network db: {err:?}");
-                                } else {
-                                    self.metrics.increase_entry_count();
                                 }
                             }
                         }
@@ -476,10 +414,6 @@ impl BeaconStorage {
             }
         }
 
-        let total_bytes_on_disk = self.get_total_storage_usage_in_bytes_on_disk()?;
-        self.metrics
-            .report_total_storage_usage_bytes(total_bytes_on_disk as f64);
-
         Ok(())
     }
 
@@ -493,126 +427,9 @@ impl BeaconStorage {
         ))
     }
 
-    /// Public method for determining how much actual disk space is being used to store this node's
-    /// Portal Network data. Intended for analysis purposes. PortalStorage's capacity
-    /// decision-making is not based off of this method.
-    pub fn get_total_storage_usage_in_bytes_on_disk(&self) -> Result<u64, ContentStoreError> {
-        let storage_usage = get_total_size_of_directory_in_bytes(&self.node_data_dir)?;
-        Ok(storage_usage)
-    }
-
-    /// Internal method for measuring the total amount of requestable data that the node is storing.
-    fn get_total_storage_usage_in_bytes_from_network(&self) -> Result<u64, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-
-        let mut content_data_stmt = conn.prepare(TOTAL_DATA_SIZE_QUERY_BEACON)?;
-        let content_data_result = content_data_stmt.query_map([], |row| {
-            Ok(DataSize {
-                num_bytes: row.get(0)?,
-            })
-        });
-        let content_data_sum = match content_data_result?.next() {
-            Some(total) => total,
-            None => {
-                let err = "Unable to compute sum over content item sizes".to_string();
-                return Err(ContentStoreError::Database(err));
-            }
-        }?
-        .num_bytes;
-
-        let mut lc_update_stmt = conn.prepare(LC_UPDATE_TOTAL_SIZE_QUERY)?;
-        let lc_update_result = lc_update_stmt.query_map([], |row| {
-            Ok(DataSize {
-                num_bytes: row.get(0)?,
-            })
-        });
-        let lc_update_sum = match lc_update_result?.next() {
-            Some(total) => total,
-            None => {
-                let err = "Unable to compute sum over lc update item sizes".to_string();
-                return Err(ContentStoreError::Database(err));
-            }
-        }?
-        .num_bytes;
-
-        let sum = content_data_sum + lc_update_sum;
-        self.metrics.report_content_data_storage_bytes(sum);
-
-        Ok(sum as u64)
-    }
-
-    /// Public method for looking up a content key by its content id
-    pub fn lookup_content_key(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_KEY_LOOKUP_QUERY_BEACON)?;
-        let result: Result<Vec<BeaconContentKey>, ContentStoreError> = query
-            .query_map([id.as_slice()], |row| {
-                let row: Vec<u8> = row.get(0)?;
-                Ok(row)
-            })?
-            .map(|row| BeaconContentKey::try_from(row?).map_err(ContentStoreError::ContentKey))
-            .collect();
-
-        match result?.first() {
-            Some(val) => Ok(Some(val.into())),
-            None => Ok(None),
-        }
-    }
-
-    /// Public method for looking up a light client update by period number
-    pub fn lookup_lc_update_period(&self, period: u64) -> anyhow::Result<Option<u64>> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(LC_UPDATE_PERIOD_LOOKUP_QUERY)?;
-
-        let rows: Result<Vec<u64>, rusqlite::Error> = query
-            .query_map([period], |row| {
-                let row: u64 = row.get(0)?;
-                Ok(row)
-            })?
-            .collect();
-
-        match rows?.first() {
-            Some(val) => Ok(Some(*val)),
-            None => Ok(None),
-        }
-    }
-
-    /// Public method for looking up a content value by its content id
-    pub fn lookup_content_value(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_VALUE_LOOKUP_QUERY_BEACON)?;
-        let result: Result<Vec<Vec<u8>>, ContentStoreError> = query
-            .query_map([id.as_slice()], |row| {
-                let row: Vec<u8> = row.get(0)?;
-                Ok(row)
-            })?
-            .map(|row| row.map_err(ContentStoreError::Rusqlite))
-            .collect();
-
-        Ok(result?.first().map(|val| val.to_vec()))
-    }
-
-    /// Public method for looking up a  light client update value by period number
-    pub fn lookup_lc_update_value(&self, period: u64) -> anyhow::Result<Option<Vec<u8>>> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(LC_UPDATE_LOOKUP_QUERY)?;
-
-        let rows: Result<Vec<Vec<u8>>, rusqlite::Error> = query
-            .query_map([period], |row| {
-                let row: Vec<u8> = row.get(0)?;
-                Ok(row)
-            })?
-            .collect();
-
-        match rows?.first() {
-            Some(val) => Ok(Some(val.to_vec())),
-            None => Ok(None),
-        }
-    }
-
     /// Get a summary of the current state of storage
     pub fn get_summary_info(&self) -> String {
-        self.metrics.get_summary()
+        self.store.get_summary_info()
     }
 }
 
@@ -627,7 +444,7 @@ mod test {
             historical_summaries::{HistoricalSummariesStateProof, HistoricalSummariesWithProof},
         },
         light_client::{
-            finality_update::LightClientFinalityUpdate,
+            bootstrap::LightClientBootstrap, finality_update::LightClientFinalityUpdate,
             optimistic_update::LightClientOptimisticUpdate, update::LightClientUpdate,
         },
         types::content_key::beacon::{
@@ -646,10 +463,61 @@ mod test {
         let key = BeaconContentKey::LightClientBootstrap(LightClientBootstrapKey {
             block_hash: [1; 32],
         });
-        let value = vec![1, 2, 3, 4, 5];
+        let value = get_light_client_bootstrap(0).as_ssz_bytes();
         storage.put(key.clone(), &value).unwrap();
         let result = storage.get(&key).unwrap().unwrap();
         assert_eq!(result, value);
+
+        let should_store_content = storage.is_key_within_radius_and_unavailable(&key).unwrap();
+        assert_eq!(should_store_content, ShouldWeStoreContent::AlreadyStored);
+    }
+
+    #[test]
+    fn test_beacon_storage_put_invalid_bootstrap() {
+        let (_temp_dir, config) = create_test_portal_storage_config_with_capacity(10).unwrap();
+        let mut storage = BeaconStorage::new(config).unwrap();
+        let key = BeaconContentKey::LightClientBootstrap(LightClientBootstrapKey {
+            block_hash: [1; 32],
+        });
+        assert!(storage.put(key.clone(), vec![1, 2, 3, 4, 5]).is_err());
+        assert_eq!(storage.get(&key).unwrap(), None);
+    }
+
+    #[test]
+    fn test_beacon_storage_set_storage_capacity() {
+        let (_temp_dir, config) = create_test_portal_storage_config_with_capacity(10).unwrap();
+        let mut storage = BeaconStorage::new(config).unwrap();
+        let lc_update = get_light_client_update(0);
+        let update_size = lc_update.as_ssz_bytes().len() as u64;
+
+        // Store enough updates to use more than 1 MB
+        let count = BYTES_IN_MB_U64 / update_size + 10;
+        for start_period in 0..count {
+            let key = BeaconContentKey::LightClientUpdatesByRange(LightClientUpdatesByRangeKey {
+                start_period,
+                count: 1,
+            });
+            let value =
+                VariableList::<ForkVersionedLightClientUpdate, U128>::new(vec![lc_update.clone()])
+                    .unwrap();
+            storage.put(key, value.as_ssz_bytes()).unwrap();
+        }
+
+        storage.set_storage_capacity(1).unwrap();
+
+        // The oldest update should be pruned, while the latest should remain
+        let oldest_key =
+            BeaconContentKey::LightClientUpdatesByRange(LightClientUpdatesByRangeKey {
+                start_period: 0,
+                count: 1,
+            });
+        assert_eq!(storage.get(&oldest_key).unwrap(), None);
+        let latest_key =
+            BeaconContentKey::LightClientUpdatesByRange(LightClientUpdatesByRangeKey {
+                start_period: count - 1,
+                count: 1,
+            });
+        assert!(storage.get(&latest_key).unwrap().is_some());
     }
 
     #[test]
@@ -818,6 +686,23 @@ mod test {
         assert_eq!(result, value.as_ssz_bytes());
     }
 
+    // Valid number range for the test cases is 0..4
+    fn get_light_client_bootstrap(number: u8) -> ForkVersionedLightClientBootstrap {
+        let bootstrap = std::fs::read(format!(
+            "../test_assets/beacon/capella/LightClientBootstrap/ssz_random/case_{number}/serialized.ssz_snappy"
+        ))
+            .expect("cannot find test asset");
+        let mut decoder = snap::raw::Decoder::new();
+        let bootstrap = decoder.decompress_vec(&bootstrap).unwrap();
+        let bootstrap =
+            LightClientBootstrap::from_ssz_bytes(&bootstrap, ForkName::Capella).unwrap();
+
+        ForkVersionedLightClientBootstrap {
+            fork_name: ForkName::Capella,
+            bootstrap,
+        }
+    }
+
     // Valid number range for the test cases is 0..4
     fn get_light_client_update(number: u8) -> ForkVersionedLightClientUpdate {
         let lc_update = std::fs::read(format!(
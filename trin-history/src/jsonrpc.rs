@@ -9,6 +9,7 @@ use ethportal_api::{
         portal::{AcceptInfo, FindNodesInfo, PongInfo},
         portal_wire::Content,
         query_trace::QueryTrace,
+        query_tuning::QueryTuning,
     },
     utils::bytes::hex_encode,
     ContentValue, HistoryContentKey, OverlayContentKey, RawContentKey,
@@ -74,11 +75,22 @@ async fn complete_request(network: Arc<HistoryNetwork>, request: HistoryJsonRpcR
         HistoryEndpoint::Offer(enr, content_key, content_value) => {
             offer(network, enr, content_key, content_value).await
         }
+        HistoryEndpoint::TraceOffer(enr, content_key, content_value) => {
+            offer_trace(network, enr, content_key, content_value).await
+        }
         HistoryEndpoint::Ping(enr) => ping(network, enr).await,
         HistoryEndpoint::RoutingTableInfo => {
             serde_json::to_value(network.overlay.routing_table_info())
                 .map_err(|err| err.to_string())
         }
+        HistoryEndpoint::RoutingTableDetail => {
+            serde_json::to_value(network.overlay.routing_table_detail())
+                .map_err(|err| err.to_string())
+        }
+        HistoryEndpoint::SetStorageCapacity(capacity_mb) => {
+            set_storage_capacity(network, capacity_mb).await
+        }
+        HistoryEndpoint::SetQueryTuning(tuning) => set_query_tuning(network, tuning).await,
         HistoryEndpoint::RecursiveFindNodes(node_id) => {
             recursive_find_nodes(network, node_id).await
         }
@@ -206,6 +218,35 @@ async fn paginate_local_content_keys(
     response
 }
 
+/// Updates the query parameters of the overlay network.
+async fn set_query_tuning(
+    network: Arc<HistoryNetwork>,
+    tuning: QueryTuning,
+) -> Result<Value, String> {
+    match network.overlay.set_query_tuning(tuning).await {
+        Ok(tuning) => serde_json::to_value(tuning).map_err(|err| err.to_string()),
+        Err(err) => Err(format!("Error while setting query tuning: {err}")),
+    }
+}
+
+/// Updates the storage capacity of the local database.
+async fn set_storage_capacity(
+    network: Arc<HistoryNetwork>,
+    capacity_mb: u64,
+) -> Result<Value, String> {
+    match network
+        .overlay
+        .store
+        .write()
+        .set_storage_capacity(capacity_mb)
+    {
+        Ok(_) => Ok(Value::Bool(true)),
+        Err(err) => Err(format!(
+            "Database error while setting storage capacity to {capacity_mb} MB: {err}"
+        )),
+    }
+}
+
 /// Constructs a JSON call for the Store method.
 async fn store(
     network: Arc<HistoryNetwork>,
@@ -355,6 +396,22 @@ async fn offer(
     }
 }
 
+/// Constructs a JSON call for the TraceOffer method.
+async fn offer_trace(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_key: HistoryContentKey,
+    content_value: ethportal_api::HistoryContentValue,
+) -> Result<Value, String> {
+    let data = content_value.encode();
+    Ok(json!(
+        network
+            .overlay
+            .send_offer_trace(enr, content_key, data)
+            .await
+    ))
+}
+
 /// Constructs a JSON call for the Ping method.
 async fn ping(
     network: Arc<HistoryNetwork>,
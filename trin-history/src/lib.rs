@@ -4,7 +4,7 @@
 pub mod events;
 mod jsonrpc;
 pub mod network;
-mod storage;
+pub mod storage;
 pub mod utils;
 pub mod validation;
 
@@ -119,8 +119,12 @@ pub fn spawn_history_heartbeat(network: Arc<HistoryNetwork>) {
             let storage_log = network.overlay.store.read().get_summary_info();
             let message_log = network.overlay.get_message_summary();
             let utp_log = network.overlay.get_utp_summary();
+            let gossip_log = network.overlay.get_gossip_summary();
+            let accept_queue_log = network.overlay.get_accept_queue_summary();
             info!("reports~ data: {storage_log}; msgs: {message_log}");
             info!("reports~ utp: {utp_log}");
+            info!("reports~ gossip: {gossip_log}");
+            info!("reports~ accept queue: {accept_queue_log}");
         }
     });
 }
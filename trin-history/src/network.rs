@@ -6,12 +6,13 @@ use utp_rs::socket::UtpSocket;
 
 use crate::storage::HistoryStorage;
 use ethportal_api::{
-    types::{distance::XorMetric, enr::Enr, portal_wire::ProtocolId},
+    types::{cli::HISTORY_NETWORK, distance::XorMetric, enr::Enr, portal_wire::ProtocolId},
     HistoryContentKey,
 };
 use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, UtpEnr},
+    gossip::GossipConfig,
     overlay::{config::OverlayConfig, protocol::OverlayProtocol},
 };
 use trin_validation::oracle::HeaderOracle;
@@ -40,8 +41,18 @@ impl HistoryNetwork {
             bootnode_enrs,
             disable_poke: portal_config.disable_poke,
             utp_transfer_limit: portal_config.utp_transfer_limit,
+            utp_adaptive_transfer_limit: portal_config.utp_adaptive_transfer_limit,
+            utp_stall_config: portal_config.utp_stall_config,
+            utp_conn_tuning: portal_config.utp_conn_tuning,
+            ping_capabilities: portal_config.enabled_subnetworks,
+            gossip: GossipConfig {
+                strategy: portal_config.gossip_strategy,
+                ..Default::default()
+            },
+            accept_queue_timeout: portal_config.accept_queue_timeout,
             ..Default::default()
-        };
+        }
+        .with_tuning(&portal_config.overlay.for_subnetwork(HISTORY_NETWORK));
         let storage = Arc::new(PLRwLock::new(HistoryStorage::new(storage_config)?));
         let validator = Arc::new(ChainHistoryValidator { header_oracle });
         let overlay = OverlayProtocol::new(
@@ -1,11 +1,14 @@
 use ethportal_api::{
-    types::{distance::Distance, history::PaginateLocalContentInfo, portal_wire::ProtocolId},
+    types::{
+        distance::Distance, history::PaginateLocalContentInfo, ping_payload::StorageUtilization,
+        portal_wire::ProtocolId,
+    },
     OverlayContentKey,
 };
 use trin_storage::{
     error::ContentStoreError,
     versioned::{create_store, ContentType, IdIndexedV1Store, IdIndexedV1StoreConfig},
-    ContentId, ContentStore, PortalStorageConfig, ShouldWeStoreContent,
+    ContentId, ContentStore, PortalStorageConfig, ShouldWeStoreContent, BYTES_IN_MB_U64,
 };
 
 /// Storage layer for the history network. Encapsulates history network specific data and logic.
@@ -44,6 +47,13 @@ impl ContentStore for HistoryStorage {
     fn radius(&self) -> Distance {
         self.store.radius()
     }
+
+    fn storage_utilization(&self) -> Option<StorageUtilization> {
+        Some(StorageUtilization {
+            used_bytes: self.store.usage_stats().total_entry_size_bytes,
+            capacity_bytes: self.store.storage_capacity_bytes(),
+        })
+    }
 }
 
 impl HistoryStorage {
@@ -60,6 +70,12 @@ impl HistoryStorage {
         self.store.get_summary_info()
     }
 
+    /// Updates the storage capacity, pruning the content if necessary.
+    pub fn set_storage_capacity(&mut self, capacity_mb: u64) -> Result<(), ContentStoreError> {
+        self.store
+            .set_storage_capacity(capacity_mb * BYTES_IN_MB_U64)
+    }
+
     /// Returns a paginated list of all available content keys from local storage (from any
     /// subnetwork) according to the provided offset and limit.
     pub fn paginate(
@@ -92,7 +108,8 @@ pub mod test {
     const CAPACITY_MB: u64 = 2;
 
     fn get_active_node_id(temp_dir: PathBuf) -> NodeId {
-        let (_, mut pk) = configure_node_data_dir(temp_dir, None, "test".to_string()).unwrap();
+        let (_, mut pk) =
+            configure_node_data_dir(temp_dir, None, "test".to_string(), None).unwrap();
         let pk = CombinedKey::secp256k1_from_bytes(pk.0.as_mut_slice()).unwrap();
         Discv5Enr::empty(&pk).unwrap().node_id()
     }
@@ -47,6 +47,37 @@ impl From<UtpDirectionLabel> for MetricLabel {
     }
 }
 
+impl From<GossipOutcomeLabel> for MetricLabel {
+    fn from(label: GossipOutcomeLabel) -> Self {
+        match label {
+            GossipOutcomeLabel::Accepted => "accepted",
+            GossipOutcomeLabel::Declined => "declined",
+            GossipOutcomeLabel::Retried => "retried",
+            GossipOutcomeLabel::Dropped => "dropped",
+        }
+    }
+}
+
+impl From<AcceptQueueOutcomeLabel> for MetricLabel {
+    fn from(label: AcceptQueueOutcomeLabel) -> Self {
+        match label {
+            AcceptQueueOutcomeLabel::Expired => "expired",
+            AcceptQueueOutcomeLabel::FallbackStored => "fallback_stored",
+            AcceptQueueOutcomeLabel::Lost => "lost",
+        }
+    }
+}
+
+impl From<GossipQueueLabel> for MetricLabel {
+    fn from(label: GossipQueueLabel) -> Self {
+        match label {
+            GossipQueueLabel::Keys => "keys",
+            GossipQueueLabel::Peers => "peers",
+            GossipQueueLabel::InFlight => "in_flight",
+        }
+    }
+}
+
 impl From<UtpOutcomeLabel> for MetricLabel {
     fn from(label: UtpOutcomeLabel) -> Self {
         match label {
@@ -54,6 +85,9 @@ impl From<UtpOutcomeLabel> for MetricLabel {
             UtpOutcomeLabel::FailedConnection => "failed connection",
             UtpOutcomeLabel::FailedDataTx => "failed data tx",
             UtpOutcomeLabel::FailedShutdown => "failed shutdown",
+            UtpOutcomeLabel::Stalled => "stalled",
+            UtpOutcomeLabel::TimedOut => "timed out",
+            UtpOutcomeLabel::PeerLimit => "peer limit",
         }
     }
 }
@@ -149,4 +183,45 @@ pub enum UtpOutcomeLabel {
     FailedConnection,
     FailedDataTx,
     FailedShutdown,
+    /// uTP transfers that were aborted because their throughput stayed below the floor
+    Stalled,
+    /// uTP transfers that were aborted because they exceeded their maximum duration
+    TimedOut,
+    /// uTP transfers that were refused because of the limit of concurrent streams per peer
+    PeerLimit,
+}
+
+/// Gossip Offer Outcome Labels, counted per content key
+#[derive(Debug, Clone, Copy)]
+pub enum GossipOutcomeLabel {
+    /// Content keys accepted by the peer
+    Accepted,
+    /// Content keys declined by the peer
+    Declined,
+    /// Content keys queued again after a failed offer
+    Retried,
+    /// Content keys dropped after too many failed offers, or because the queue was full
+    Dropped,
+}
+
+/// Gossip Queue Labels
+#[derive(Debug, Clone, Copy)]
+pub enum GossipQueueLabel {
+    /// Content keys waiting to be offered
+    Keys,
+    /// Peers with content keys waiting to be offered
+    Peers,
+    /// Offers waiting for a response
+    InFlight,
+}
+
+/// Accept Queue Outcome Labels
+#[derive(Debug, Clone, Copy)]
+pub enum AcceptQueueOutcomeLabel {
+    /// Accepted content keys that timed out in the queue without being stored
+    Expired,
+    /// Accepted content keys that failed, but were stored after a fallback FINDCONTENT request
+    FallbackStored,
+    /// Accepted content keys that failed, and no fallback peer provided the content
+    Lost,
 }
@@ -6,7 +6,10 @@ use prometheus_exporter::{
     },
 };
 
-use crate::labels::{MessageDirectionLabel, MessageLabel, UtpDirectionLabel, UtpOutcomeLabel};
+use crate::labels::{
+    AcceptQueueOutcomeLabel, GossipOutcomeLabel, GossipQueueLabel, MessageDirectionLabel,
+    MessageLabel, UtpDirectionLabel, UtpOutcomeLabel,
+};
 use ethportal_api::types::portal_wire::{Request, Response};
 
 /// Contains metrics reporters for use in the overlay network
@@ -18,7 +21,11 @@ pub struct OverlayMetrics {
     pub message_total: IntCounterVec,
     pub utp_outcome_total: IntCounterVec,
     pub utp_active_gauge: IntGaugeVec,
+    pub utp_transfer_limit_gauge: IntGaugeVec,
     pub validation_total: IntCounterVec,
+    pub gossip_outcome_total: IntCounterVec,
+    pub gossip_queue_gauge: IntGaugeVec,
+    pub accept_queue_outcome_total: IntCounterVec,
 }
 
 impl OverlayMetrics {
@@ -47,6 +54,14 @@ impl OverlayMetrics {
             &["protocol", "direction"],
             registry
         )?;
+        let utp_transfer_limit_gauge = register_int_gauge_vec_with_registry!(
+            opts!(
+                "trin_utp_transfer_limit",
+                "track the limit of concurrent utp transfers outbound and inbound"
+            ),
+            &["protocol", "direction"],
+            registry
+        )?;
         let validation_total = register_int_counter_vec_with_registry!(
             opts!(
                 "trin_validation_total",
@@ -55,11 +70,39 @@ impl OverlayMetrics {
             &["protocol", "success"],
             registry
         )?;
+        let gossip_outcome_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_gossip_outcome_total",
+                "count the outcomes of the content keys of the scheduled gossip offers"
+            ),
+            &["protocol", "outcome"],
+            registry
+        )?;
+        let gossip_queue_gauge = register_int_gauge_vec_with_registry!(
+            opts!(
+                "trin_gossip_queue",
+                "track the size of the gossip scheduler queue"
+            ),
+            &["protocol", "type"],
+            registry
+        )?;
+        let accept_queue_outcome_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_accept_queue_outcome_total",
+                "count all accepted content keys that were not stored by the original offer"
+            ),
+            &["protocol", "outcome"],
+            registry
+        )?;
         Ok(Self {
             message_total,
             utp_outcome_total,
             utp_active_gauge,
+            utp_transfer_limit_gauge,
             validation_total,
+            gossip_outcome_total,
+            gossip_queue_gauge,
+            accept_queue_outcome_total,
         })
     }
 }
@@ -157,6 +200,22 @@ impl OverlayMetricsReporter {
             .dec();
     }
 
+    fn utp_transfer_limit(&self, direction: UtpDirectionLabel) -> u64 {
+        let labels: [&str; 2] = [&self.protocol, direction.into()];
+        self.overlay_metrics
+            .utp_transfer_limit_gauge
+            .with_label_values(&labels)
+            .get() as u64
+    }
+
+    pub fn report_utp_transfer_limit(&self, direction: UtpDirectionLabel, limit: usize) {
+        let labels: [&str; 2] = [&self.protocol, direction.into()];
+        self.overlay_metrics
+            .utp_transfer_limit_gauge
+            .with_label_values(&labels)
+            .set(limit as i64);
+    }
+
     //
     // Validations
     //
@@ -179,6 +238,84 @@ impl OverlayMetricsReporter {
             .inc();
     }
 
+    //
+    // Gossip
+    //
+
+    fn gossip_outcome_total(&self, outcome: GossipOutcomeLabel) -> u64 {
+        let labels: [&str; 2] = [&self.protocol, outcome.into()];
+        self.overlay_metrics
+            .gossip_outcome_total
+            .with_label_values(&labels)
+            .get()
+    }
+
+    pub fn report_gossip_outcome(&self, outcome: GossipOutcomeLabel, count: u64) {
+        let labels: [&str; 2] = [&self.protocol, outcome.into()];
+        self.overlay_metrics
+            .gossip_outcome_total
+            .with_label_values(&labels)
+            .inc_by(count);
+    }
+
+    fn gossip_queue(&self, queue: GossipQueueLabel) -> i64 {
+        let labels: [&str; 2] = [&self.protocol, queue.into()];
+        self.overlay_metrics
+            .gossip_queue_gauge
+            .with_label_values(&labels)
+            .get()
+    }
+
+    pub fn report_gossip_queue(&self, queue: GossipQueueLabel, size: usize) {
+        let labels: [&str; 2] = [&self.protocol, queue.into()];
+        self.overlay_metrics
+            .gossip_queue_gauge
+            .with_label_values(&labels)
+            .set(size as i64);
+    }
+
+    pub fn get_gossip_summary(&self) -> String {
+        format!(
+            "queue: keys={}, peers={}, in_flight={}; keys: accepted={}, declined={}, retried={}, dropped={}",
+            self.gossip_queue(GossipQueueLabel::Keys),
+            self.gossip_queue(GossipQueueLabel::Peers),
+            self.gossip_queue(GossipQueueLabel::InFlight),
+            self.gossip_outcome_total(GossipOutcomeLabel::Accepted),
+            self.gossip_outcome_total(GossipOutcomeLabel::Declined),
+            self.gossip_outcome_total(GossipOutcomeLabel::Retried),
+            self.gossip_outcome_total(GossipOutcomeLabel::Dropped),
+        )
+    }
+
+    //
+    // Accept Queue
+    //
+
+    fn accept_queue_outcome_total(&self, outcome: AcceptQueueOutcomeLabel) -> u64 {
+        let labels: [&str; 2] = [&self.protocol, outcome.into()];
+        self.overlay_metrics
+            .accept_queue_outcome_total
+            .with_label_values(&labels)
+            .get()
+    }
+
+    pub fn report_accept_queue_outcome(&self, outcome: AcceptQueueOutcomeLabel, count: u64) {
+        let labels: [&str; 2] = [&self.protocol, outcome.into()];
+        self.overlay_metrics
+            .accept_queue_outcome_total
+            .with_label_values(&labels)
+            .inc_by(count);
+    }
+
+    pub fn get_accept_queue_summary(&self) -> String {
+        format!(
+            "accepted keys: expired={}, fallback_stored={}, lost={}",
+            self.accept_queue_outcome_total(AcceptQueueOutcomeLabel::Expired),
+            self.accept_queue_outcome_total(AcceptQueueOutcomeLabel::FallbackStored),
+            self.accept_queue_outcome_total(AcceptQueueOutcomeLabel::Lost),
+        )
+    }
+
     pub fn get_utp_summary(&self) -> String {
         let inbound_success =
             self.utp_outcome_total(UtpDirectionLabel::Inbound, UtpOutcomeLabel::Success);
@@ -200,14 +337,31 @@ impl OverlayMetricsReporter {
             self.utp_outcome_total(UtpDirectionLabel::Outbound, UtpOutcomeLabel::FailedDataTx);
         let outbound_failed_shutdown =
             self.utp_outcome_total(UtpDirectionLabel::Outbound, UtpOutcomeLabel::FailedShutdown);
+        let inbound_stalled =
+            self.utp_outcome_total(UtpDirectionLabel::Inbound, UtpOutcomeLabel::Stalled);
+        let outbound_stalled =
+            self.utp_outcome_total(UtpDirectionLabel::Outbound, UtpOutcomeLabel::Stalled);
+        let inbound_timed_out =
+            self.utp_outcome_total(UtpDirectionLabel::Inbound, UtpOutcomeLabel::TimedOut);
+        let outbound_timed_out =
+            self.utp_outcome_total(UtpDirectionLabel::Outbound, UtpOutcomeLabel::TimedOut);
+        let inbound_peer_limit =
+            self.utp_outcome_total(UtpDirectionLabel::Inbound, UtpOutcomeLabel::PeerLimit);
+        let outbound_peer_limit =
+            self.utp_outcome_total(UtpDirectionLabel::Outbound, UtpOutcomeLabel::PeerLimit);
         let active_inbound = self.utp_active_streams(UtpDirectionLabel::Inbound);
         let active_outbound = self.utp_active_streams(UtpDirectionLabel::Outbound);
+        let inbound_limit = self.utp_transfer_limit(UtpDirectionLabel::Inbound);
+        let outbound_limit = self.utp_transfer_limit(UtpDirectionLabel::Outbound);
         format!(
-            "(in/out): active={} ({}/{}), success={} ({}/{}), failed={} ({}/{}) \
-            failed_connection={} ({}/{}), failed_data_tx={} ({}/{}), failed_shutdown={} ({}/{})",
+            "(in/out): active={} ({}/{}), limit=({}/{}), success={} ({}/{}), failed={} ({}/{}) \
+            failed_connection={} ({}/{}), failed_data_tx={} ({}/{}), failed_shutdown={} ({}/{}), \
+            stalled={} ({}/{}), timed_out={} ({}/{}), peer_limit={} ({}/{})",
             active_inbound + active_outbound,
             active_inbound,
             active_outbound,
+            inbound_limit,
+            outbound_limit,
             inbound_success + outbound_success,
             inbound_success,
             outbound_success,
@@ -216,9 +370,25 @@ impl OverlayMetricsReporter {
                 + inbound_failed_data_tx
                 + outbound_failed_data_tx
                 + inbound_failed_shutdown
-                + outbound_failed_shutdown,
-            inbound_failed_connection + inbound_failed_data_tx + inbound_failed_shutdown,
-            outbound_failed_connection + outbound_failed_data_tx + outbound_failed_shutdown,
+                + outbound_failed_shutdown
+                + inbound_stalled
+                + outbound_stalled
+                + inbound_timed_out
+                + outbound_timed_out
+                + inbound_peer_limit
+                + outbound_peer_limit,
+            inbound_failed_connection
+                + inbound_failed_data_tx
+                + inbound_failed_shutdown
+                + inbound_stalled
+                + inbound_timed_out
+                + inbound_peer_limit,
+            outbound_failed_connection
+                + outbound_failed_data_tx
+                + outbound_failed_shutdown
+                + outbound_stalled
+                + outbound_timed_out
+                + outbound_peer_limit,
             inbound_failed_connection + outbound_failed_connection,
             inbound_failed_connection,
             outbound_failed_connection,
@@ -228,6 +398,15 @@ impl OverlayMetricsReporter {
             inbound_failed_shutdown + outbound_failed_shutdown,
             inbound_failed_shutdown,
             outbound_failed_shutdown,
+            inbound_stalled + outbound_stalled,
+            inbound_stalled,
+            outbound_stalled,
+            inbound_timed_out + outbound_timed_out,
+            inbound_timed_out,
+            outbound_timed_out,
+            inbound_peer_limit + outbound_peer_limit,
+            inbound_peer_limit,
+            outbound_peer_limit,
         )
     }
 
@@ -16,6 +16,7 @@ use ethportal_api::{
         portal::{AcceptInfo, FindNodesInfo, PongInfo},
         portal_wire::Content,
         query_trace::QueryTrace,
+        query_tuning::QueryTuning,
         state::{ContentInfo, TraceContentInfo},
     },
     utils::bytes::hex_encode,
@@ -39,6 +40,7 @@ impl StateRequestHandler {
     async fn handle_request(network: Arc<StateNetwork>, request: StateJsonRpcRequest) {
         let response: Result<Value, String> = match request.endpoint {
             StateEndpoint::RoutingTableInfo => routing_table_info(network),
+            StateEndpoint::RoutingTableDetail => routing_table_detail(network),
             StateEndpoint::Ping(enr) => ping(network, enr).await,
             StateEndpoint::AddEnr(enr) => add_enr(network, enr),
             StateEndpoint::DeleteEnr(node_id) => delete_enr(network, node_id),
@@ -65,6 +67,12 @@ impl StateRequestHandler {
             StateEndpoint::Offer(enr, content_key, content_value) => {
                 offer(network, enr, content_key, content_value).await
             }
+            StateEndpoint::TraceOffer(enr, content_key, content_value) => Ok(json!(
+                network
+                    .overlay
+                    .send_offer_trace(enr, content_key, content_value.encode())
+                    .await
+            )),
             StateEndpoint::Gossip(content_key, content_value) => {
                 gossip(
                     network,
@@ -86,6 +94,10 @@ impl StateRequestHandler {
             StateEndpoint::PaginateLocalContentKeys(offset, limit) => {
                 paginate(network, offset, limit)
             }
+            StateEndpoint::SetStorageCapacity(capacity_mb) => {
+                set_storage_capacity(network, capacity_mb)
+            }
+            StateEndpoint::SetQueryTuning(tuning) => set_query_tuning(network, tuning).await,
         };
 
         let _ = request.resp.send(response);
@@ -96,6 +108,10 @@ fn routing_table_info(network: Arc<StateNetwork>) -> Result<Value, String> {
     serde_json::to_value(network.overlay.routing_table_info()).map_err(|err| err.to_string())
 }
 
+fn routing_table_detail(network: Arc<StateNetwork>) -> Result<Value, String> {
+    serde_json::to_value(network.overlay.routing_table_detail()).map_err(|err| err.to_string())
+}
+
 async fn ping(network: Arc<StateNetwork>, enr: Enr) -> Result<Value, String> {
     to_json_result(
         "Ping",
@@ -359,6 +375,28 @@ fn paginate(network: Arc<StateNetwork>, offset: u64, limit: u64) -> Result<Value
     )
 }
 
+fn set_storage_capacity(network: Arc<StateNetwork>, capacity_mb: u64) -> Result<Value, String> {
+    to_json_result(
+        "SetStorageCapacity",
+        network
+            .overlay
+            .store
+            .write()
+            .set_storage_capacity(capacity_mb)
+            .map(|_| true),
+    )
+}
+
+async fn set_query_tuning(
+    network: Arc<StateNetwork>,
+    tuning: QueryTuning,
+) -> Result<Value, String> {
+    to_json_result(
+        "SetQueryTuning",
+        network.overlay.set_query_tuning(tuning).await,
+    )
+}
+
 fn to_json_result(
     request: &str,
     result: Result<impl Serialize, impl Debug>,
@@ -6,12 +6,13 @@ use utp_rs::socket::UtpSocket;
 
 use crate::storage::StateStorage;
 use ethportal_api::{
-    types::{distance::XorMetric, portal_wire::ProtocolId},
+    types::{cli::STATE_NETWORK, distance::XorMetric, portal_wire::ProtocolId},
     StateContentKey,
 };
 use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, UtpEnr},
+    gossip::GossipConfig,
     overlay::{config::OverlayConfig, protocol::OverlayProtocol},
 };
 use trin_validation::oracle::HeaderOracle;
@@ -45,8 +46,18 @@ impl StateNetwork {
             bootnode_enrs: portal_config.bootnodes.into(),
             disable_poke: DISABLE_POKE,
             utp_transfer_limit: portal_config.utp_transfer_limit,
+            utp_adaptive_transfer_limit: portal_config.utp_adaptive_transfer_limit,
+            utp_stall_config: portal_config.utp_stall_config,
+            utp_conn_tuning: portal_config.utp_conn_tuning,
+            ping_capabilities: portal_config.enabled_subnetworks,
+            gossip: GossipConfig {
+                strategy: portal_config.gossip_strategy,
+                ..Default::default()
+            },
+            accept_queue_timeout: portal_config.accept_queue_timeout,
             ..Default::default()
-        };
+        }
+        .with_tuning(&portal_config.overlay.for_subnetwork(STATE_NETWORK));
         let storage = Arc::new(PLRwLock::new(StateStorage::new(storage_config)?));
         let validator = Arc::new(StateValidator { header_oracle });
         let overlay = OverlayProtocol::new(
@@ -4,6 +4,7 @@ use ethportal_api::{
         content_key::state::{AccountTrieNodeKey, ContractBytecodeKey, ContractStorageTrieNodeKey},
         content_value::state::{ContractBytecode, TrieNode},
         distance::Distance,
+        ping_payload::StorageUtilization,
         portal_wire::ProtocolId,
         state::PaginateLocalContentInfo,
     },
@@ -12,7 +13,7 @@ use ethportal_api::{
 use trin_storage::{
     error::ContentStoreError,
     versioned::{create_store, ContentType, IdIndexedV1Store, IdIndexedV1StoreConfig},
-    ContentId, ContentStore, PortalStorageConfig, ShouldWeStoreContent,
+    ContentId, ContentStore, PortalStorageConfig, ShouldWeStoreContent, BYTES_IN_MB_U64,
 };
 
 /// Storage layer for the state network. Encapsulates state network specific data and logic.
@@ -64,6 +65,13 @@ impl ContentStore for StateStorage {
     fn radius(&self) -> Distance {
         IdIndexedV1Store::radius(&self.store)
     }
+
+    fn storage_utilization(&self) -> Option<StorageUtilization> {
+        Some(StorageUtilization {
+            used_bytes: self.store.usage_stats().total_entry_size_bytes,
+            capacity_bytes: self.store.storage_capacity_bytes(),
+        })
+    }
 }
 
 impl StateStorage {
@@ -94,6 +102,53 @@ impl StateStorage {
         self.store.get_summary_info()
     }
 
+    /// Updates the storage capacity, pruning the content if necessary.
+    pub fn set_storage_capacity(&mut self, capacity_mb: u64) -> Result<(), ContentStoreError> {
+        self.store
+            .set_storage_capacity(capacity_mb * BYTES_IN_MB_U64)
+    }
+
+    /// Stores the content in the form in which it's stored locally (trie node or contract
+    /// bytecode, without proofs), e.g. when importing previously exported content.
+    ///
+    /// As the proof isn't available, the content is only checked against the hash from the
+    /// content key.
+    pub fn put_stored(
+        &mut self,
+        key: &StateContentKey,
+        value: &[u8],
+    ) -> Result<(), ContentStoreError> {
+        let value = StateContentValue::decode(value)?;
+        let (hash, expected_hash) = match (key, &value) {
+            (StateContentKey::AccountTrieNode(key), StateContentValue::TrieNode(value)) => {
+                (value.node.node_hash(), key.node_hash)
+            }
+            (StateContentKey::ContractStorageTrieNode(key), StateContentValue::TrieNode(value)) => {
+                (value.node.node_hash(), key.node_hash)
+            }
+            (
+                StateContentKey::ContractBytecode(key),
+                StateContentValue::ContractBytecode(value),
+            ) => (keccak256(&value.code[..]), key.code_hash),
+            _ => {
+                return Err(ContentStoreError::InvalidData {
+                    message: format!(
+                        "Expected content in the stored form, but received {value:?} instead"
+                    ),
+                })
+            }
+        };
+        if hash != expected_hash {
+            return Err(ContentStoreError::InvalidData {
+                message: format!(
+                    "Hash of the content ({hash}) doesn't match key's hash ({expected_hash})"
+                ),
+            });
+        }
+
+        self.store.insert(key, value.encode())
+    }
+
     fn put_account_trie_node(
         &mut self,
         content_key: &StateContentKey,
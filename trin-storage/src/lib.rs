@@ -4,13 +4,17 @@ pub mod test_utils;
 pub mod utils;
 pub mod versioned;
 
-use crate::utils::setup_sql;
+use crate::{
+    utils::setup_sql,
+    versioned::{PruningConfig, StorageBackend},
+};
 use alloy_primitives::B256;
 use discv5::enr::NodeId;
 use error::ContentStoreError;
 use ethportal_api::types::{
     content_key::overlay::OverlayContentKey,
     distance::{Distance, Metric, XorMetric},
+    ping_payload::StorageUtilization,
 };
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
@@ -35,6 +39,35 @@ impl DistanceFunction {
     }
 }
 
+/// The policy used by a content store to determine its data radius.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RadiusConfig {
+    /// The radius shrinks when the store is pruned and grows when there is free capacity again
+    /// (e.g. after content is deleted or storage capacity is raised). The radius never goes below
+    /// `min_radius`.
+    Dynamic { min_radius: Distance },
+    /// The radius is always the provided value, regardless of storage usage.
+    Static(Distance),
+}
+
+impl RadiusConfig {
+    /// Applies the policy to the radius calculated from the storage usage.
+    pub fn apply(&self, radius: Distance) -> Distance {
+        match self {
+            RadiusConfig::Dynamic { min_radius } => radius.max(*min_radius),
+            RadiusConfig::Static(radius) => *radius,
+        }
+    }
+}
+
+impl Default for RadiusConfig {
+    fn default() -> Self {
+        RadiusConfig::Dynamic {
+            min_radius: Distance::ZERO,
+        }
+    }
+}
+
 /// An enum which tells us if we should store or not store content, and if not why for better
 /// errors.
 #[derive(Debug, PartialEq)]
@@ -65,9 +98,15 @@ pub trait ContentStore {
 
     /// Returns the radius of the data store.
     fn radius(&self) -> Distance;
+
+    /// Returns the storage utilization of the data store, if it's known.
+    fn storage_utilization(&self) -> Option<StorageUtilization> {
+        None
+    }
 }
 
 /// An in-memory `ContentStore`.
+#[cfg(any(test, feature = "test-utils"))]
 pub struct MemoryContentStore {
     /// The content store.
     store: std::collections::HashMap<Vec<u8>, Vec<u8>>,
@@ -79,6 +118,7 @@ pub struct MemoryContentStore {
     radius: Distance,
 }
 
+#[cfg(any(test, feature = "test-utils"))]
 impl MemoryContentStore {
     /// Constructs a new `MemoryPortalContentStore`.
     pub fn new(node_id: NodeId, distance_fn: DistanceFunction) -> Self {
@@ -107,6 +147,7 @@ impl MemoryContentStore {
     }
 }
 
+#[cfg(any(test, feature = "test-utils"))]
 impl ContentStore for MemoryContentStore {
     fn get<K: OverlayContentKey>(&self, key: &K) -> Result<Option<Vec<u8>>, ContentStoreError> {
         let key = key.content_id();
@@ -152,7 +193,10 @@ pub struct PortalStorageConfig {
     pub node_id: NodeId,
     pub node_data_dir: PathBuf,
     pub distance_fn: DistanceFunction,
+    pub radius_config: RadiusConfig,
+    pub backend: StorageBackend,
     pub sql_connection_pool: Pool<SqliteConnectionManager>,
+    pub pruning_config: PruningConfig,
 }
 
 impl PortalStorageConfig {
@@ -167,9 +211,36 @@ impl PortalStorageConfig {
             node_id,
             node_data_dir,
             distance_fn: DistanceFunction::Xor,
+            radius_config: RadiusConfig::default(),
+            backend: StorageBackend::default(),
             sql_connection_pool,
+            pruning_config: PruningConfig::default(),
         })
     }
+
+    /// Sets the storage capacity, in megabytes.
+    pub fn with_storage_capacity_mb(mut self, storage_capacity_mb: u64) -> Self {
+        self.storage_capacity_mb = storage_capacity_mb;
+        self
+    }
+
+    /// Sets the policy used to determine the data radius.
+    pub fn with_radius_config(mut self, radius_config: RadiusConfig) -> Self {
+        self.radius_config = radius_config;
+        self
+    }
+
+    /// Sets the backend used for storing content.
+    pub fn with_backend(mut self, backend: StorageBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets the parameters of the pruning strategy.
+    pub fn with_pruning_config(mut self, pruning_config: PruningConfig) -> Self {
+        self.pruning_config = pruning_config;
+        self
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
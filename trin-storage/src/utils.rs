@@ -1,7 +1,5 @@
 use crate::{
-    error::ContentStoreError,
-    sql::{CREATE_QUERY_DB_BEACON, DROP_USAGE_STATS_DB, LC_UPDATE_CREATE_TABLE},
-    versioned::sql::STORE_INFO_CREATE_TABLE,
+    error::ContentStoreError, sql::DROP_USAGE_STATS_DB, versioned::sql::STORE_INFO_CREATE_TABLE,
     DATABASE_NAME,
 };
 use r2d2::Pool;
@@ -17,8 +15,6 @@ pub fn setup_sql(node_data_dir: &Path) -> Result<Pool<SqliteConnectionManager>,
     let manager = SqliteConnectionManager::file(sql_path);
     let pool = Pool::new(manager)?;
     let conn = pool.get()?;
-    conn.execute_batch(CREATE_QUERY_DB_BEACON)?;
-    conn.execute_batch(LC_UPDATE_CREATE_TABLE)?;
     conn.execute_batch(STORE_INFO_CREATE_TABLE)?;
     conn.execute_batch(DROP_USAGE_STATS_DB)?;
     Ok(pool)
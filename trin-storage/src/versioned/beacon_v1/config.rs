@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+use crate::{PortalStorageConfig, BYTES_IN_MB_U64};
+
+/// The config for the BeaconV1Store
+#[derive(Clone, Debug)]
+pub struct BeaconV1StoreConfig {
+    pub node_data_dir: PathBuf,
+    pub storage_capacity_bytes: u64,
+    pub sql_connection_pool: Pool<SqliteConnectionManager>,
+}
+
+impl BeaconV1StoreConfig {
+    pub fn new(config: PortalStorageConfig) -> Self {
+        Self {
+            node_data_dir: config.node_data_dir,
+            storage_capacity_bytes: config.storage_capacity_mb * BYTES_IN_MB_U64,
+            sql_connection_pool: config.sql_connection_pool,
+        }
+    }
+}
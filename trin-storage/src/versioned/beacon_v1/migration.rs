@@ -0,0 +1,151 @@
+use ethportal_api::{
+    types::content_value::beacon::ForkVersionedLightClientBootstrap, utils::bytes::hex_encode,
+};
+use rusqlite::named_params;
+use tracing::{info, warn};
+
+use super::{sql, BeaconV1StoreConfig};
+use crate::error::ContentStoreError;
+
+/// Migrates content from the legacy `beacon` and `lc_update` tables into the `BeaconV1Store`.
+///
+/// The sync committee period of each light client bootstrap is extracted from its content value.
+/// Bootstraps that can't be decoded are assigned period 0, which makes them first to be pruned.
+pub fn migrate_legacy_beacon_store(config: &BeaconV1StoreConfig) -> Result<(), ContentStoreError> {
+    info!("Migration of the legacy beacon store: started");
+
+    let mut conn = config.sql_connection_pool.get()?;
+    let transaction = conn.transaction()?;
+
+    transaction.execute_batch(sql::CREATE_TABLES)?;
+
+    let mut bootstrap_count = 0;
+    {
+        let mut lookup_query = transaction.prepare(sql::LEGACY_BOOTSTRAP_LOOKUP_ALL)?;
+        let mut insert_query = transaction.prepare(sql::BOOTSTRAP_INSERT)?;
+        let mut rows = lookup_query.query([])?;
+        while let Some(row) = rows.next()? {
+            let content_id: Vec<u8> = row.get("content_id")?;
+            let content_key: Vec<u8> = row.get("content_key")?;
+            let content_value: Vec<u8> = row.get("content_value")?;
+            let content_size: u64 = row.get("content_size")?;
+
+            let period = bootstrap_period(&content_value).unwrap_or_else(|| {
+                warn!(
+                    content_id = hex_encode(&content_id),
+                    "Unable to decode legacy light client bootstrap, using period 0"
+                );
+                0
+            });
+            insert_query.execute(named_params! {
+                ":content_id": content_id,
+                ":content_key": content_key,
+                ":content_value": content_value,
+                ":period": period,
+                ":content_size": content_size,
+            })?;
+            bootstrap_count += 1;
+        }
+    }
+
+    let lc_update_count = transaction.execute(sql::LEGACY_LC_UPDATE_MIGRATE, [])?;
+    transaction.execute_batch(sql::LEGACY_DROP_TABLES)?;
+    transaction.commit()?;
+
+    info!(
+        bootstraps = bootstrap_count,
+        lc_updates = lc_update_count,
+        "Migration of the legacy beacon store: done"
+    );
+    Ok(())
+}
+
+fn bootstrap_period(content_value: &[u8]) -> Option<u64> {
+    ForkVersionedLightClientBootstrap::decode(content_value)
+        .ok()
+        .map(|bootstrap| bootstrap.sync_committee_period())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::B256;
+    use anyhow::Result;
+    use rusqlite::params;
+
+    use crate::{
+        test_utils::{create_test_portal_storage_config_with_capacity, generate_random_bytes},
+        versioned::{create_store, BeaconV1Store, ContentType},
+        ContentId,
+    };
+
+    use super::*;
+
+    const LEGACY_CREATE_TABLES: &str = "
+        CREATE TABLE IF NOT EXISTS beacon (
+            content_id blob PRIMARY KEY,
+            content_key blob NOT NULL,
+            content_value blob NOT NULL,
+            content_size INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS lc_update (
+            period INTEGER PRIMARY KEY,
+            value BLOB NOT NULL,
+            score INTEGER NOT NULL,
+            update_size INTEGER
+        );";
+
+    #[test]
+    fn migrate() -> Result<()> {
+        let (_temp_dir, config) = create_test_portal_storage_config_with_capacity(10)?;
+        let sql_connection_pool = config.sql_connection_pool.clone();
+
+        let bootstrap_id = ContentId::from(B256::random());
+        let bootstrap_key = generate_random_bytes(33);
+        let bootstrap_value = generate_random_bytes(100);
+        let lc_update_value = generate_random_bytes(200);
+        {
+            let conn = sql_connection_pool.get()?;
+            conn.execute_batch(LEGACY_CREATE_TABLES)?;
+            conn.execute(
+                "INSERT INTO beacon (content_id, content_key, content_value, content_size)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    bootstrap_id.to_vec(),
+                    bootstrap_key,
+                    bootstrap_value,
+                    32 + 33 + 100
+                ],
+            )?;
+            conn.execute(
+                "INSERT INTO lc_update (period, value, score, update_size) VALUES (?1, ?2, ?3, ?4)",
+                params![7, lc_update_value, 1, 200],
+            )?;
+        }
+
+        let store: BeaconV1Store = create_store(
+            ContentType::Beacon,
+            BeaconV1StoreConfig::new(config),
+            sql_connection_pool.clone(),
+        )?;
+
+        assert_eq!(
+            store.lookup_bootstrap(&bootstrap_id)?,
+            Some(bootstrap_value)
+        );
+        assert_eq!(store.lookup_lc_update(7)?, Some(lc_update_value));
+        assert_eq!(store.usage_stats().entry_count, 2);
+        assert_eq!(
+            store.usage_stats().total_entry_size_bytes,
+            32 + 33 + 100 + 200
+        );
+
+        // Legacy tables should be dropped
+        let legacy_table_count: u64 = sql_connection_pool.get()?.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name IN ('beacon', 'lc_update')",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(legacy_table_count, 0);
+        Ok(())
+    }
+}
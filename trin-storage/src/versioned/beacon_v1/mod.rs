@@ -0,0 +1,7 @@
+mod config;
+mod migration;
+mod sql;
+mod store;
+
+pub use config::BeaconV1StoreConfig;
+pub use store::BeaconV1Store;
@@ -0,0 +1,79 @@
+//! The sql queries of the `BeaconV1Store`. The `bv1` table prefix stands for `beacon_v1`.
+
+pub const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS bv1_bootstrap (
+        content_id BLOB PRIMARY KEY,
+        content_key BLOB NOT NULL,
+        content_value BLOB NOT NULL,
+        period INTEGER NOT NULL,
+        content_size INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS bv1_bootstrap_period_idx ON bv1_bootstrap (period);
+    CREATE TABLE IF NOT EXISTS bv1_lc_update (
+        period INTEGER PRIMARY KEY,
+        content_value BLOB NOT NULL,
+        content_size INTEGER NOT NULL
+    );";
+
+pub const BOOTSTRAP_INSERT: &str = "
+    INSERT OR IGNORE INTO bv1_bootstrap (
+        content_id,
+        content_key,
+        content_value,
+        period,
+        content_size
+    )
+    VALUES (
+        :content_id,
+        :content_key,
+        :content_value,
+        :period,
+        :content_size
+    )";
+
+pub const BOOTSTRAP_LOOKUP_VALUE: &str = "
+    SELECT content_value FROM bv1_bootstrap WHERE content_id = :content_id LIMIT 1";
+
+pub const BOOTSTRAP_DELETE_PERIOD: &str = "
+    DELETE FROM bv1_bootstrap
+    WHERE period = :period
+    RETURNING content_size";
+
+pub const LC_UPDATE_INSERT: &str = "
+    INSERT OR IGNORE INTO bv1_lc_update (period, content_value, content_size)
+    VALUES (:period, :content_value, :content_size)";
+
+pub const LC_UPDATE_LOOKUP_VALUE: &str = "
+    SELECT content_value FROM bv1_lc_update WHERE period = :period LIMIT 1";
+
+pub const LC_UPDATE_DELETE_PERIOD: &str = "
+    DELETE FROM bv1_lc_update
+    WHERE period = :period
+    RETURNING content_size";
+
+/// Returns the oldest period of the stored bootstraps and light client updates.
+pub const OLDEST_PERIOD: &str = "
+    SELECT MIN(period) AS period FROM (
+        SELECT MIN(period) AS period FROM bv1_bootstrap
+        UNION ALL
+        SELECT MIN(period) AS period FROM bv1_lc_update
+    )";
+
+pub const ENTRY_COUNT_AND_SIZE: &str = "
+    SELECT
+        (SELECT COUNT(*) FROM bv1_bootstrap) + (SELECT COUNT(*) FROM bv1_lc_update) AS count,
+        (SELECT TOTAL(content_size) FROM bv1_bootstrap) +
+            (SELECT TOTAL(content_size) FROM bv1_lc_update) AS used_capacity";
+
+// The queries used for migrating the legacy beacon store
+
+pub const LEGACY_BOOTSTRAP_LOOKUP_ALL: &str = "
+    SELECT content_id, content_key, content_value, content_size FROM beacon";
+
+pub const LEGACY_LC_UPDATE_MIGRATE: &str = "
+    INSERT OR IGNORE INTO bv1_lc_update (period, content_value, content_size)
+    SELECT period, value, LENGTH(value) FROM lc_update";
+
+pub const LEGACY_DROP_TABLES: &str = "
+    DROP TABLE IF EXISTS beacon;
+    DROP TABLE IF EXISTS lc_update;";
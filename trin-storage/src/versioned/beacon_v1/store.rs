@@ -0,0 +1,497 @@
+use ethportal_api::types::portal_wire::ProtocolId;
+use rusqlite::{named_params, OptionalExtension};
+use tracing::{debug, error, warn};
+use trin_metrics::storage::StorageMetricsReporter;
+
+use super::{migration::migrate_legacy_beacon_store, sql, BeaconV1StoreConfig};
+use crate::{
+    error::ContentStoreError,
+    utils::get_total_size_of_directory_in_bytes,
+    versioned::{usage_stats::UsageStats, ContentType, StoreVersion, VersionedContentStore},
+    ContentId,
+};
+
+/// The fraction of storage capacity that we aim for when pruning.
+const TARGET_CAPACITY_FRACTION: f64 = 0.95;
+
+/// The store for the beacon network content that is kept long term: light client bootstraps and
+/// light client updates.
+///
+/// All content is indexed by its sync committee period. The store has a configurable capacity and
+/// once it uses more than storage capacity, it prunes the content from the oldest periods.
+///
+/// Other beacon content (e.g. latest finality and optimistic updates) is short lived and not
+/// stored here.
+#[derive(Debug)]
+pub struct BeaconV1Store {
+    /// The configuration.
+    config: BeaconV1StoreConfig,
+    /// The usage stats tracked manually.
+    usage_stats: UsageStats,
+    /// The Metrics for tracking performance.
+    metrics: StorageMetricsReporter,
+}
+
+impl VersionedContentStore for BeaconV1Store {
+    type Config = BeaconV1StoreConfig;
+
+    fn version() -> StoreVersion {
+        StoreVersion::BeaconV1
+    }
+
+    fn migrate_from(
+        content_type: &ContentType,
+        old_version: StoreVersion,
+        config: &Self::Config,
+    ) -> Result<(), ContentStoreError> {
+        if content_type == &ContentType::Beacon && old_version == StoreVersion::LegacyBeacon {
+            return migrate_legacy_beacon_store(config);
+        }
+        Err(ContentStoreError::UnsupportedStoreMigration {
+            old_version,
+            new_version: Self::version(),
+        })
+    }
+
+    fn create(content_type: ContentType, config: Self::Config) -> Result<Self, ContentStoreError> {
+        if content_type != ContentType::Beacon {
+            return Err(ContentStoreError::InvalidData {
+                message: format!("BeaconV1Store doesn't support {content_type} content type"),
+            });
+        }
+        config
+            .sql_connection_pool
+            .get()?
+            .execute_batch(sql::CREATE_TABLES)?;
+
+        let mut store = Self {
+            config,
+            usage_stats: UsageStats::default(),
+            metrics: StorageMetricsReporter::new(ProtocolId::Beacon),
+        };
+        store.init()?;
+        Ok(store)
+    }
+}
+
+impl BeaconV1Store {
+    /// Initializes variables and metrics, and prunes if necessary.
+    fn init(&mut self) -> Result<(), ContentStoreError> {
+        self.metrics
+            .report_storage_capacity_bytes(self.config.storage_capacity_bytes as f64);
+
+        self.init_usage_stats()?;
+
+        if self.should_prune() {
+            debug!(
+                Db = %ContentType::Beacon,
+                "High storage usage ({}) -> Pruning",
+                self.usage_stats.total_entry_size_bytes,
+            );
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    // PUBLIC FUNCTIONS
+
+    /// Returns whether light client bootstrap with the given content id is stored.
+    pub fn has_bootstrap(&self, content_id: &ContentId) -> Result<bool, ContentStoreError> {
+        Ok(self.lookup_bootstrap(content_id)?.is_some())
+    }
+
+    /// Returns light client bootstrap with the given content id, if stored.
+    pub fn lookup_bootstrap(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("lookup_bootstrap");
+
+        let value = self
+            .config
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                sql::BOOTSTRAP_LOOKUP_VALUE,
+                named_params! { ":content_id": content_id.to_vec() },
+                |row| row.get::<&str, Vec<u8>>("content_value"),
+            )
+            .optional()?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(value)
+    }
+
+    /// Inserts light client bootstrap from the provided sync committee period and prunes the db
+    /// if necessary.
+    pub fn insert_bootstrap(
+        &mut self,
+        content_id: &ContentId,
+        content_key: Vec<u8>,
+        content_value: Vec<u8>,
+        period: u64,
+    ) -> Result<(), ContentStoreError> {
+        let timer = self.metrics.start_process_timer("insert_bootstrap");
+
+        let content_size = (content_id.len() + content_key.len() + content_value.len()) as u64;
+        let inserted = self.config.sql_connection_pool.get()?.execute(
+            sql::BOOTSTRAP_INSERT,
+            named_params! {
+                ":content_id": content_id.to_vec(),
+                ":content_key": content_key,
+                ":content_value": content_value,
+                ":period": period,
+                ":content_size": content_size,
+            },
+        )?;
+        self.on_inserted(inserted, content_size)?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(())
+    }
+
+    /// Returns whether light client update for the given sync committee period is stored.
+    pub fn has_lc_update(&self, period: u64) -> Result<bool, ContentStoreError> {
+        Ok(self.lookup_lc_update(period)?.is_some())
+    }
+
+    /// Returns light client update for the given sync committee period, if stored.
+    pub fn lookup_lc_update(&self, period: u64) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("lookup_lc_update");
+
+        let value = self
+            .config
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                sql::LC_UPDATE_LOOKUP_VALUE,
+                named_params! { ":period": period },
+                |row| row.get::<&str, Vec<u8>>("content_value"),
+            )
+            .optional()?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(value)
+    }
+
+    /// Inserts light client update for the given sync committee period and prunes the db if
+    /// necessary.
+    pub fn insert_lc_update(
+        &mut self,
+        period: u64,
+        content_value: Vec<u8>,
+    ) -> Result<(), ContentStoreError> {
+        let timer = self.metrics.start_process_timer("insert_lc_update");
+
+        let content_size = content_value.len() as u64;
+        let inserted = self.config.sql_connection_pool.get()?.execute(
+            sql::LC_UPDATE_INSERT,
+            named_params! {
+                ":period": period,
+                ":content_value": content_value,
+                ":content_size": content_size,
+            },
+        )?;
+        self.on_inserted(inserted, content_size)?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(())
+    }
+
+    /// Updates the storage capacity, pruning the content if necessary.
+    pub fn set_storage_capacity(
+        &mut self,
+        storage_capacity_bytes: u64,
+    ) -> Result<(), ContentStoreError> {
+        debug!(
+            Db = %ContentType::Beacon,
+            "Changing storage capacity: {} -> {storage_capacity_bytes}",
+            self.config.storage_capacity_bytes,
+        );
+        self.config.storage_capacity_bytes = storage_capacity_bytes;
+        self.metrics
+            .report_storage_capacity_bytes(storage_capacity_bytes as f64);
+
+        if self.should_prune() {
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the storage capacity.
+    pub fn storage_capacity_bytes(&self) -> u64 {
+        self.config.storage_capacity_bytes
+    }
+
+    pub fn usage_stats(&self) -> UsageStats {
+        self.usage_stats.clone()
+    }
+
+    /// Returns metrics summary.
+    pub fn get_summary_info(&self) -> String {
+        let timer = self.metrics.start_process_timer("get_summary_info");
+
+        // Reports size of the entire database.
+        match get_total_size_of_directory_in_bytes(&self.config.node_data_dir) {
+            Ok(entire_db_size) => self
+                .metrics
+                .report_total_storage_usage_bytes(entire_db_size as f64),
+            Err(err) => warn!(Db = %ContentType::Beacon, "Error getting db size: {err}"),
+        }
+
+        self.metrics.stop_process_timer(timer);
+        self.metrics.get_summary()
+    }
+
+    // INTERNAL FUNCTIONS
+
+    /// Lookup and set `usage_stats`.
+    ///
+    /// This should be called only during initialization or when error occurs. Otherwise,
+    /// `usage_stats` should be updated manually when entries are inserted/deleted.
+    fn init_usage_stats(&mut self) -> Result<(), ContentStoreError> {
+        self.usage_stats = self.config.sql_connection_pool.get()?.query_row(
+            sql::ENTRY_COUNT_AND_SIZE,
+            [],
+            |row| {
+                let entry_count = row.get("count")?;
+                let used_capacity: f64 = row.get("used_capacity")?;
+                Ok(UsageStats::new(entry_count, used_capacity.round() as u64))
+            },
+        )?;
+        self.usage_stats.report_metrics(&self.metrics);
+        Ok(())
+    }
+
+    /// Updates usage stats after insert (if content was inserted) and prunes if necessary.
+    fn on_inserted(
+        &mut self,
+        inserted_count: usize,
+        content_size: u64,
+    ) -> Result<(), ContentStoreError> {
+        if inserted_count == 0 {
+            return Ok(());
+        }
+        self.usage_stats.entry_count += 1;
+        self.usage_stats.total_entry_size_bytes += content_size;
+        self.usage_stats.report_metrics(&self.metrics);
+
+        if self.should_prune() {
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    fn target_capacity_bytes(&self) -> u64 {
+        (self.config.storage_capacity_bytes as f64 * TARGET_CAPACITY_FRACTION).round() as u64
+    }
+
+    fn should_prune(&self) -> bool {
+        self.usage_stats
+            .is_above(self.config.storage_capacity_bytes)
+    }
+
+    /// Prunes content from the oldest sync committee periods, until used capacity drops to the
+    /// target capacity.
+    fn prune(&mut self) -> Result<(), ContentStoreError> {
+        let pruning_timer = self.metrics.start_process_timer("prune");
+        debug!(
+            Db = %ContentType::Beacon,
+            "Pruning start: count={} capacity={}",
+            self.usage_stats.entry_count,
+            self.usage_stats.total_entry_size_bytes,
+        );
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let target_capacity_bytes = self.target_capacity_bytes();
+        while self.usage_stats.is_above(target_capacity_bytes) {
+            let oldest_period = conn.query_row(sql::OLDEST_PERIOD, [], |row| {
+                row.get::<&str, Option<u64>>("period")
+            })?;
+            let Some(period) = oldest_period else {
+                error!(
+                    Db = %ContentType::Beacon,
+                    "Nothing to prune. This is not supposed to happen (we should be above target capacity)."
+                );
+                self.init_usage_stats()?;
+                break;
+            };
+
+            let mut deleted_content_sizes = vec![];
+            for delete_query in [sql::BOOTSTRAP_DELETE_PERIOD, sql::LC_UPDATE_DELETE_PERIOD] {
+                deleted_content_sizes.extend(
+                    conn.prepare(delete_query)?
+                        .query_map(named_params! { ":period": period }, |row| {
+                            row.get::<&str, u64>("content_size")
+                        })?
+                        .collect::<Result<Vec<u64>, rusqlite::Error>>()?,
+                );
+            }
+            debug!(
+                Db = %ContentType::Beacon,
+                "Pruned {} entries from period {period}",
+                deleted_content_sizes.len(),
+            );
+
+            self.usage_stats.entry_count -= deleted_content_sizes.len() as u64;
+            self.usage_stats.total_entry_size_bytes -= deleted_content_sizes.iter().sum::<u64>();
+            self.usage_stats.report_metrics(&self.metrics);
+        }
+
+        debug!(
+            Db = %ContentType::Beacon,
+            "Pruning end: count={} capacity={}",
+            self.usage_stats.entry_count,
+            self.usage_stats.total_entry_size_bytes,
+        );
+        self.metrics.stop_process_timer(pruning_timer);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::B256;
+    use anyhow::Result;
+
+    use crate::{
+        test_utils::{create_test_portal_storage_config_with_capacity, generate_random_bytes},
+        versioned::create_store,
+    };
+
+    use super::*;
+
+    const CONTENT_SIZE_BYTES: usize = 1_000;
+
+    fn create_store_with_capacity_bytes(
+        storage_capacity_bytes: u64,
+    ) -> Result<(tempfile::TempDir, BeaconV1Store)> {
+        let (temp_dir, config) = create_test_portal_storage_config_with_capacity(0)?;
+        let sql_connection_pool = config.sql_connection_pool.clone();
+        let config = BeaconV1StoreConfig {
+            storage_capacity_bytes,
+            ..BeaconV1StoreConfig::new(config)
+        };
+        let store = create_store(ContentType::Beacon, config, sql_connection_pool)?;
+        Ok((temp_dir, store))
+    }
+
+    /// Inserts bootstrap for the given period and returns its content id.
+    fn insert_bootstrap(store: &mut BeaconV1Store, period: u64) -> Result<ContentId> {
+        let content_id = ContentId::from(B256::random());
+        let content_key = generate_random_bytes(33);
+        let content_value =
+            generate_random_bytes(CONTENT_SIZE_BYTES - content_id.len() - content_key.len());
+        store.insert_bootstrap(&content_id, content_key, content_value, period)?;
+        Ok(content_id)
+    }
+
+    fn insert_lc_update(store: &mut BeaconV1Store, period: u64) -> Result<()> {
+        store.insert_lc_update(period, generate_random_bytes(CONTENT_SIZE_BYTES))?;
+        Ok(())
+    }
+
+    #[test]
+    fn insert_and_lookup() -> Result<()> {
+        let (_temp_dir, mut store) = create_store_with_capacity_bytes(1_000_000)?;
+
+        let content_id = ContentId::from(B256::random());
+        let value = vec![1, 2, 3];
+        assert!(!store.has_bootstrap(&content_id)?);
+        store.insert_bootstrap(&content_id, vec![0; 33], value.clone(), 5)?;
+        assert!(store.has_bootstrap(&content_id)?);
+        assert_eq!(store.lookup_bootstrap(&content_id)?, Some(value.clone()));
+
+        assert!(!store.has_lc_update(5)?);
+        store.insert_lc_update(5, value.clone())?;
+        assert!(store.has_lc_update(5)?);
+        assert_eq!(store.lookup_lc_update(5)?, Some(value));
+
+        assert_eq!(store.usage_stats(), UsageStats::new(2, 32 + 33 + 3 + 3));
+        Ok(())
+    }
+
+    #[test]
+    fn insert_duplicate() -> Result<()> {
+        let (_temp_dir, mut store) = create_store_with_capacity_bytes(1_000_000)?;
+
+        insert_lc_update(&mut store, 1)?;
+        let usage_stats = store.usage_stats();
+        insert_lc_update(&mut store, 1)?;
+        assert_eq!(store.usage_stats(), usage_stats);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_oldest_period() -> Result<()> {
+        // Capacity for 10 entries
+        let (_temp_dir, mut store) =
+            create_store_with_capacity_bytes(10 * CONTENT_SIZE_BYTES as u64)?;
+
+        let mut bootstraps = vec![];
+        for period in 0..5 {
+            bootstraps.push(insert_bootstrap(&mut store, period)?);
+            insert_lc_update(&mut store, period)?;
+        }
+        assert_eq!(store.usage_stats().entry_count, 10);
+
+        // Inserting one more should prune the oldest period
+        insert_lc_update(&mut store, 5)?;
+        assert_eq!(store.usage_stats().entry_count, 9);
+        assert!(!store.has_bootstrap(&bootstraps[0])?);
+        assert!(!store.has_lc_update(0)?);
+        for period in 1..5 {
+            assert!(store.has_bootstrap(&bootstraps[period as usize])?);
+            assert!(store.has_lc_update(period)?);
+        }
+        assert!(store.has_lc_update(5)?);
+        Ok(())
+    }
+
+    #[test]
+    fn lower_storage_capacity() -> Result<()> {
+        let (_temp_dir, mut store) = create_store_with_capacity_bytes(1_000_000)?;
+        for period in 0..10 {
+            insert_lc_update(&mut store, period)?;
+        }
+
+        store.set_storage_capacity(5 * CONTENT_SIZE_BYTES as u64)?;
+        assert_eq!(store.usage_stats().entry_count, 4);
+        for period in 0..6 {
+            assert!(!store.has_lc_update(period)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prune_on_create() -> Result<()> {
+        let (temp_dir, config) = create_test_portal_storage_config_with_capacity(1)?;
+        let sql_connection_pool = config.sql_connection_pool.clone();
+        let config = BeaconV1StoreConfig::new(config);
+
+        let mut store: BeaconV1Store = create_store(
+            ContentType::Beacon,
+            config.clone(),
+            sql_connection_pool.clone(),
+        )?;
+        for period in 0..100 {
+            insert_lc_update(&mut store, period)?;
+        }
+        drop(store);
+
+        let store: BeaconV1Store = create_store(
+            ContentType::Beacon,
+            BeaconV1StoreConfig {
+                storage_capacity_bytes: 10 * CONTENT_SIZE_BYTES as u64,
+                ..config
+            },
+            sql_connection_pool,
+        )?;
+        assert_eq!(store.usage_stats().entry_count, 9);
+        assert!(store.has_lc_update(99)?);
+        assert!(!store.has_lc_update(90)?);
+
+        drop(temp_dir);
+        Ok(())
+    }
+}
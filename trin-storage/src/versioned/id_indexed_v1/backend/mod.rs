@@ -0,0 +1,124 @@
+mod segment;
+mod sqlite;
+
+use std::fmt::Debug;
+
+use strum::{AsRefStr, Display, EnumString};
+
+use crate::{error::ContentStoreError, versioned::usage_stats::UsageStats, ContentId};
+
+pub use segment::SegmentBackend;
+pub use sqlite::SqliteBackend;
+
+/// The type of the backend used for storing content.
+///
+/// The metadata of the store (e.g. store version) is always stored in SQLite.
+#[derive(Clone, Copy, Debug, Default, Display, Eq, PartialEq, EnumString, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum StorageBackend {
+    /// The content is stored in the SQLite table.
+    #[default]
+    Sqlite,
+    /// The content is stored in the append-only segment file, while the index is kept in memory.
+    Segment,
+}
+
+/// The number of entries that are moved between backends at once.
+const MOVE_BATCH_SIZE: u64 = 1000;
+
+/// The entry stored by the backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendEntry {
+    pub content_id: ContentId,
+    pub content_key: Vec<u8>,
+    pub content_value: Vec<u8>,
+    /// The first 4 bytes of the distance between `NodeId` and content id.
+    pub distance_short: u32,
+    /// The size of the entry, as used for calculating used capacity.
+    pub content_size: u64,
+}
+
+/// The result of looking for the farthest content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FarthestEntry {
+    pub content_id: ContentId,
+    pub distance_short: u32,
+}
+
+/// The backend that is responsible for storing the content of the `IdIndexedV1Store`.
+///
+/// The backend should be indexed by content id, and it should support efficient lookup of the
+/// content that is farthest from the `NodeId`.
+pub trait IdIndexedV1Backend: Debug + Send + Sync {
+    /// Returns whether content with the given content id is stored.
+    fn has_content(&self, content_id: &ContentId) -> Result<bool, ContentStoreError>;
+
+    /// Returns the content key of the content with the given content id.
+    fn lookup_content_key(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError>;
+
+    /// Returns the content value of the content with the given content id.
+    fn lookup_content_value(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError>;
+
+    /// Inserts the entry. If content with the same content id is already stored, it's ignored.
+    fn insert(&mut self, entry: BackendEntry) -> Result<(), ContentStoreError>;
+
+    /// Deletes the content with the given content id and returns its size, if it was stored.
+    fn delete(&mut self, content_id: &ContentId) -> Result<Option<u64>, ContentStoreError>;
+
+    /// Deletes up to `limit` entries that are farthest from the `NodeId` and returns their sizes.
+    fn delete_farthest(&mut self, limit: u64) -> Result<Vec<u64>, ContentStoreError>;
+
+    /// Deletes all stored content.
+    fn clear(&mut self) -> Result<(), ContentStoreError>;
+
+    /// Returns the content that is farthest from the `NodeId`.
+    fn lookup_farthest(&self) -> Result<Option<FarthestEntry>, ContentStoreError>;
+
+    /// Returns the content keys ordered by content key, according to the provided offset and
+    /// limit.
+    fn paginate(&self, offset: u64, limit: u64) -> Result<Vec<Vec<u8>>, ContentStoreError>;
+
+    /// Returns up to `limit` entries ordered by content id, starting after the provided content id.
+    fn iterate(
+        &self,
+        after: Option<&ContentId>,
+        limit: u64,
+    ) -> Result<Vec<BackendEntry>, ContentStoreError>;
+
+    /// Returns the number and total size of stored entries.
+    ///
+    /// This can be slow, and the store should track usage stats manually.
+    fn usage_stats(&self) -> Result<UsageStats, ContentStoreError>;
+}
+
+/// Moves all content from the `source` backend into the `target` backend, and returns the number
+/// of moved entries.
+///
+/// The content is deleted from the source only after it's copied, and content that the target
+/// already stores is ignored, so the move can be repeated if it's interrupted.
+pub fn move_content(
+    source: &mut dyn IdIndexedV1Backend,
+    target: &mut dyn IdIndexedV1Backend,
+) -> Result<u64, ContentStoreError> {
+    let mut moved_count = 0;
+    let mut last_content_id = None;
+    loop {
+        let entries = source.iterate(last_content_id.as_ref(), MOVE_BATCH_SIZE)?;
+        let Some(last_entry) = entries.last() else {
+            break;
+        };
+        last_content_id = Some(last_entry.content_id.clone());
+        for entry in entries {
+            target.insert(entry)?;
+            moved_count += 1;
+        }
+    }
+    source.clear()?;
+    Ok(moved_count)
+}
@@ -0,0 +1,1267 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use alloy_primitives::B256;
+use tracing::{debug, info, warn};
+
+use super::{BackendEntry, FarthestEntry, IdIndexedV1Backend};
+use crate::{
+    error::ContentStoreError,
+    versioned::{id_indexed_v1::sql, usage_stats::UsageStats, ContentType},
+    ContentId,
+};
+
+/// The tag of the record that stores the content.
+const TAG_PUT: u8 = 0;
+/// The tag of the record that marks content as deleted.
+const TAG_DELETE: u8 = 1;
+
+/// The size of the record header: tag (1), content id (32), distance short (4), content size
+/// (8), key length (4), value length (4) and checksum (4).
+const HEADER_SIZE: u64 = 1 + 32 + 4 + 8 + 4 + 4 + 4;
+
+/// The offset of the checksum in the record header.
+const CHECKSUM_OFFSET: usize = HEADER_SIZE as usize - 4;
+
+/// The minimum number of bytes occupied by deleted records before the segment is compacted.
+const COMPACTION_MIN_DEAD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The number of bytes of records that a single step of the compaction goes through.
+const COMPACTION_STEP_BYTES: u64 = 8 * 1024 * 1024;
+
+/// The number of appended bytes after which the segment file is synced to disk.
+const SYNC_MAX_UNSYNCED_BYTES: u64 = 4 * 1024 * 1024;
+
+/// The time after which the appended records are synced to disk, on the next append.
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of leading bytes of the content key that are kept in memory, for pagination.
+const KEY_PREFIX_LEN: usize = 8;
+
+/// The leading bytes of the content key, padded with zeros.
+type KeyPrefix = [u8; KEY_PREFIX_LEN];
+
+/// Returns the prefix of the content key. The prefixes are ordered the same way as the keys,
+/// except that different keys can share the prefix.
+fn key_prefix(key: &[u8]) -> KeyPrefix {
+    let mut prefix = [0u8; KEY_PREFIX_LEN];
+    let len = key.len().min(KEY_PREFIX_LEN);
+    prefix[..len].copy_from_slice(&key[..len]);
+    prefix
+}
+
+/// The location of the stored content inside the segment file.
+#[derive(Clone, Debug)]
+struct IndexEntry {
+    /// The offset of the record (including header).
+    offset: u64,
+    key_len: u32,
+    value_len: u32,
+    distance_short: u32,
+    content_size: u64,
+}
+
+impl IndexEntry {
+    fn key_offset(&self) -> u64 {
+        self.offset + HEADER_SIZE
+    }
+
+    fn value_offset(&self) -> u64 {
+        self.key_offset() + self.key_len as u64
+    }
+
+    fn record_len(&self) -> u64 {
+        HEADER_SIZE + self.key_len as u64 + self.value_len as u64
+    }
+}
+
+/// The header of a single record.
+struct RecordHeader {
+    tag: u8,
+    content_id: B256,
+    distance_short: u32,
+    content_size: u64,
+    key_len: u32,
+    value_len: u32,
+}
+
+impl RecordHeader {
+    /// Encodes the header, including the checksum of the record with the key and the value.
+    fn encode(&self, key: &[u8], value: &[u8]) -> [u8; HEADER_SIZE as usize] {
+        let mut bytes = [0u8; HEADER_SIZE as usize];
+        bytes[0] = self.tag;
+        bytes[1..33].copy_from_slice(self.content_id.as_slice());
+        bytes[33..37].copy_from_slice(&self.distance_short.to_be_bytes());
+        bytes[37..45].copy_from_slice(&self.content_size.to_be_bytes());
+        bytes[45..49].copy_from_slice(&self.key_len.to_be_bytes());
+        bytes[49..53].copy_from_slice(&self.value_len.to_be_bytes());
+        let checksum = record_checksum(&bytes, key, value);
+        bytes[CHECKSUM_OFFSET..].copy_from_slice(&checksum.to_be_bytes());
+        bytes
+    }
+
+    /// Decodes the header, and returns it with the checksum of the record.
+    fn decode(bytes: &[u8; HEADER_SIZE as usize]) -> (Self, u32) {
+        let u32_at = |start: usize| {
+            u32::from_be_bytes(bytes[start..start + 4].try_into().expect("to have 4 bytes"))
+        };
+        let header = Self {
+            tag: bytes[0],
+            content_id: B256::from_slice(&bytes[1..33]),
+            distance_short: u32_at(33),
+            content_size: u64::from_be_bytes(bytes[37..45].try_into().expect("to have 8 bytes")),
+            key_len: u32_at(45),
+            value_len: u32_at(49),
+        };
+        (header, u32_at(CHECKSUM_OFFSET))
+    }
+}
+
+/// Returns the CRC32 checksum of the record, computed over the header (without the checksum),
+/// the key and the value.
+fn record_checksum(header: &[u8; HEADER_SIZE as usize], key: &[u8], value: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header[..CHECKSUM_OFFSET]);
+    hasher.update(key);
+    hasher.update(value);
+    hasher.finalize()
+}
+
+/// The backend that stores content in the append-only segment file.
+///
+/// Every insert and delete appends a record at the end of the file, while the index (content id,
+/// distance and content key ordering) is kept in memory and rebuilt from the file on startup.
+/// Only the prefix of each content key is kept in memory, and the keys that share the prefix are
+/// read from the file when they are paginated.
+///
+/// The appended records are synced to disk once 4 MiB of them accumulate, or on the first append
+/// at least a second after the last sync. A crash of the process doesn't lose any records, but a
+/// crash of the system can lose the records appended since the last sync that the OS didn't
+/// write back yet. Lost inserts are stored again once offered, and lost deletes are pruned again.
+///
+/// Every record has a checksum. An incomplete or corrupted record at the end of the file (e.g.
+/// caused by a crash) is discarded on startup. A corrupted record in the middle of the file is
+/// skipped if a valid record follows it, and otherwise the segment file fails to open, as the
+/// records after it can't be found.
+///
+/// Once deleted records occupy more space than the live ones, the live records are rewritten
+/// into the new file (compaction). The compaction proceeds in bounded steps, one on every
+/// delete, so that pruning doesn't stall while the whole file is rewritten.
+#[derive(Debug)]
+pub struct SegmentBackend {
+    /// The path to the segment file.
+    path: PathBuf,
+    /// The segment file. Reads require seeking, so it's guarded by the mutex.
+    file: Mutex<File>,
+    /// The size of the segment file.
+    file_size: u64,
+    /// The index of all stored content, ordered by content id.
+    index: BTreeMap<B256, IndexEntry>,
+    /// The content ids ordered by distance, used for pruning.
+    distance_index: BTreeSet<(u32, B256)>,
+    /// The content ids ordered by the prefix of their content key, used for pagination.
+    key_index: BTreeSet<(KeyPrefix, B256)>,
+    /// The total size of all live records (including headers).
+    live_bytes: u64,
+    /// The number of bytes appended since the segment file was last synced to disk.
+    unsynced_bytes: u64,
+    /// When the segment file was last synced to disk.
+    last_sync: Instant,
+    /// The compaction in progress.
+    compaction: Option<Compaction>,
+}
+
+/// The compaction in progress, which copies the records that are still live into the new
+/// segment file.
+#[derive(Debug)]
+struct Compaction {
+    /// The writer of the new segment file.
+    writer: BufWriter<File>,
+    /// The offset of the next record to go through in the current segment file.
+    cursor: u64,
+    /// The size of the new segment file.
+    size: u64,
+    /// The offsets of the copied content in the new segment file.
+    offsets: HashMap<B256, u64>,
+}
+
+impl SegmentBackend {
+    /// Opens (or creates) the segment file for the content type and rebuilds the index.
+    pub fn new(
+        content_type: &ContentType,
+        node_data_dir: &Path,
+    ) -> Result<Self, ContentStoreError> {
+        Self::open(segment_path(content_type, node_data_dir))
+    }
+
+    /// Returns whether the segment file for the content type exists.
+    pub fn exists(content_type: &ContentType, node_data_dir: &Path) -> bool {
+        segment_path(content_type, node_data_dir).exists()
+    }
+
+    fn open(path: PathBuf) -> Result<Self, ContentStoreError> {
+        let compaction_path = compaction_path(&path);
+        if compaction_path.exists() {
+            warn!(
+                path = %compaction_path.display(),
+                "Removing the segment file of an interrupted compaction",
+            );
+            fs::remove_file(&compaction_path)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let mut backend = Self {
+            path,
+            file: Mutex::new(file),
+            file_size: 0,
+            index: BTreeMap::new(),
+            distance_index: BTreeSet::new(),
+            key_index: BTreeSet::new(),
+            live_bytes: 0,
+            unsynced_bytes: 0,
+            last_sync: Instant::now(),
+            compaction: None,
+        };
+        backend.rebuild_index()?;
+        Ok(backend)
+    }
+
+    /// Reads the entire segment file and rebuilds in-memory index.
+    fn rebuild_index(&mut self) -> Result<(), ContentStoreError> {
+        let mut file = self.lock_file()?;
+        let total_size = file.seek(SeekFrom::End(0))?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(&*file);
+
+        let mut offset = 0;
+        let mut index = BTreeMap::new();
+        let mut keys = HashMap::new();
+        let mut distance_index = BTreeSet::new();
+        let mut live_bytes = 0;
+        // The offset of the skipped corrupted record, until a valid record follows it.
+        let mut skipped = None;
+        while offset < total_size {
+            let mut header_bytes = [0u8; HEADER_SIZE as usize];
+            if let Err(err) = reader.read_exact(&mut header_bytes) {
+                if err.kind() == ErrorKind::UnexpectedEof {
+                    break;
+                }
+                return Err(err.into());
+            }
+            let (header, checksum) = RecordHeader::decode(&header_bytes);
+            let payload_len = header.key_len as u64 + header.value_len as u64;
+            let record_end = offset + HEADER_SIZE + payload_len;
+            if record_end > total_size {
+                break;
+            }
+
+            let key = match header.tag {
+                TAG_PUT | TAG_DELETE => {
+                    let mut key = vec![0u8; header.key_len as usize];
+                    reader.read_exact(&mut key)?;
+                    let mut value = vec![0u8; header.value_len as usize];
+                    reader.read_exact(&mut value)?;
+                    (record_checksum(&header_bytes, &key, &value) == checksum).then_some(key)
+                }
+                _ => {
+                    reader.seek(SeekFrom::Start(record_end))?;
+                    None
+                }
+            };
+            let Some(key) = key else {
+                if skipped.is_some()
+                    || record_end == total_size
+                    || is_zero_tail(&mut reader, &header_bytes, record_end)?
+                {
+                    break;
+                }
+                warn!(
+                    path = %self.path.display(),
+                    "Skipping corrupted record at offset {offset} of the segment file",
+                );
+                skipped = Some(offset);
+                offset = record_end;
+                continue;
+            };
+            skipped = None;
+
+            match header.tag {
+                TAG_PUT => {
+                    let entry = IndexEntry {
+                        offset,
+                        key_len: header.key_len,
+                        value_len: header.value_len,
+                        distance_short: header.distance_short,
+                        content_size: header.content_size,
+                    };
+                    live_bytes += entry.record_len();
+                    distance_index.insert((entry.distance_short, header.content_id));
+                    keys.insert(header.content_id, key_prefix(&key));
+                    if let Some(old) = index.insert(header.content_id, entry) {
+                        // Shouldn't happen, as duplicates are ignored on insert.
+                        live_bytes -= old.record_len();
+                    }
+                }
+                // TAG_DELETE, as other tags are rejected above.
+                _ => {
+                    if let Some(entry) = index.remove(&header.content_id) {
+                        live_bytes -= entry.record_len();
+                        distance_index.remove(&(entry.distance_short, header.content_id));
+                        keys.remove(&header.content_id);
+                    }
+                }
+            }
+            offset = record_end;
+        }
+        drop(reader);
+
+        if let Some(skipped) = skipped {
+            return Err(ContentStoreError::Database(format!(
+                "Corrupted record at offset {skipped} of the segment file {} isn't followed by a \
+                 valid record",
+                self.path.display(),
+            )));
+        }
+        if offset < total_size {
+            warn!(
+                path = %self.path.display(),
+                "Discarding incomplete or corrupted records at the end of the segment file ({} bytes)",
+                total_size - offset,
+            );
+            file.set_len(offset)?;
+            file.sync_all()?;
+        }
+        drop(file);
+
+        self.file_size = offset;
+        self.index = index;
+        self.distance_index = distance_index;
+        self.key_index = keys
+            .into_iter()
+            .map(|(content_id, prefix)| (prefix, content_id))
+            .collect();
+        self.live_bytes = live_bytes;
+        Ok(())
+    }
+
+    fn lock_file(&self) -> Result<std::sync::MutexGuard<'_, File>, ContentStoreError> {
+        self.file.lock().map_err(|err| {
+            ContentStoreError::Database(format!("Segment file lock poisoned: {err}"))
+        })
+    }
+
+    /// Appends bytes at the end of the segment file and returns the offset where they start.
+    ///
+    /// The file is synced to disk once enough bytes are appended, or enough time passed since
+    /// the last sync.
+    fn append(&mut self, bytes: &[u8]) -> Result<u64, ContentStoreError> {
+        let offset = self.file_size;
+        let unsynced_bytes = self.unsynced_bytes + bytes.len() as u64;
+        let sync =
+            unsynced_bytes >= SYNC_MAX_UNSYNCED_BYTES || self.last_sync.elapsed() >= SYNC_INTERVAL;
+        let mut file = self.lock_file()?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(bytes)?;
+        if sync {
+            file.sync_data()?;
+        }
+        drop(file);
+        self.file_size += bytes.len() as u64;
+        if sync {
+            self.unsynced_bytes = 0;
+            self.last_sync = Instant::now();
+        } else {
+            self.unsynced_bytes = unsynced_bytes;
+        }
+        Ok(offset)
+    }
+
+    /// Returns the stored content with the prefix of its key, and the tombstone record that
+    /// marks it as deleted.
+    ///
+    /// The index isn't changed, as the tombstone has to be appended to the segment file before
+    /// the content is removed from the index (see `remove_from_index`).
+    fn prepare_delete(
+        &self,
+        content_id: &B256,
+    ) -> Result<Option<PreparedDelete>, ContentStoreError> {
+        let Some(entry) = self.index.get(content_id).cloned() else {
+            return Ok(None);
+        };
+        let key = read_at(
+            &mut *self.lock_file()?,
+            entry.key_offset(),
+            entry.key_len.min(KEY_PREFIX_LEN as u32),
+        )?;
+        let tombstone = RecordHeader {
+            tag: TAG_DELETE,
+            content_id: *content_id,
+            distance_short: entry.distance_short,
+            content_size: 0,
+            key_len: 0,
+            value_len: 0,
+        }
+        .encode(&[], &[]);
+        Ok(Some(PreparedDelete {
+            content_id: *content_id,
+            entry,
+            key_prefix: key_prefix(&key),
+            tombstone,
+        }))
+    }
+
+    /// Removes the deleted content from the in-memory index, once its tombstone is appended.
+    fn remove_from_index(&mut self, delete: &PreparedDelete) {
+        self.index.remove(&delete.content_id);
+        self.distance_index
+            .remove(&(delete.entry.distance_short, delete.content_id));
+        self.key_index
+            .remove(&(delete.key_prefix, delete.content_id));
+        self.live_bytes -= delete.entry.record_len();
+    }
+
+    /// Starts the compaction if deleted records occupy too much space, and takes a step of the
+    /// compaction in progress.
+    fn maybe_compact(&mut self) -> Result<(), ContentStoreError> {
+        if self.compaction.is_none() {
+            let dead_bytes = self.file_size - self.live_bytes;
+            if dead_bytes < COMPACTION_MIN_DEAD_BYTES || dead_bytes <= self.live_bytes {
+                return Ok(());
+            }
+            self.start_compaction()?;
+        }
+        self.compaction_step(COMPACTION_STEP_BYTES)
+    }
+
+    /// Creates the new segment file, which the live records are copied into.
+    fn start_compaction(&mut self) -> Result<(), ContentStoreError> {
+        info!(
+            path = %self.path.display(),
+            "Compacting segment file: size={} live={}",
+            self.file_size,
+            self.live_bytes,
+        );
+        let writer = BufWriter::new(File::create(compaction_path(&self.path))?);
+        self.compaction = Some(Compaction {
+            writer,
+            cursor: 0,
+            size: 0,
+            offsets: HashMap::with_capacity(self.index.len()),
+        });
+        Ok(())
+    }
+
+    /// Goes through at least `max_bytes` of records (or to the end of the segment file), copying
+    /// the live ones into the new segment file. Once the end is reached, the new segment file
+    /// replaces the current one.
+    ///
+    /// The records appended meanwhile are copied as well, once the compaction reaches them. The
+    /// tombstones of the content that is already copied are copied too.
+    fn compaction_step(&mut self, max_bytes: u64) -> Result<(), ContentStoreError> {
+        let Some(mut compaction) = self.compaction.take() else {
+            return Ok(());
+        };
+        let mut file = self.lock_file()?;
+        let mut step_bytes = 0;
+        while compaction.cursor < self.file_size && step_bytes < max_bytes {
+            let mut header_bytes = [0u8; HEADER_SIZE as usize];
+            file.seek(SeekFrom::Start(compaction.cursor))?;
+            file.read_exact(&mut header_bytes)?;
+            let (header, _) = RecordHeader::decode(&header_bytes);
+            let record_len = HEADER_SIZE + header.key_len as u64 + header.value_len as u64;
+            match header.tag {
+                TAG_PUT => {
+                    let is_live = self
+                        .index
+                        .get(&header.content_id)
+                        .is_some_and(|entry| entry.offset == compaction.cursor);
+                    if is_live {
+                        let record = read_at(&mut file, compaction.cursor, record_len as u32)?;
+                        compaction.writer.write_all(&record)?;
+                        compaction
+                            .offsets
+                            .insert(header.content_id, compaction.size);
+                        compaction.size += record_len;
+                    }
+                }
+                TAG_DELETE => {
+                    if compaction.offsets.remove(&header.content_id).is_some() {
+                        compaction.writer.write_all(&header_bytes)?;
+                        compaction.size += record_len;
+                    }
+                }
+                // The corrupted records skipped on startup.
+                _ => {}
+            }
+            compaction.cursor += record_len;
+            step_bytes += record_len;
+        }
+        let finished = compaction.cursor >= self.file_size;
+        drop(file);
+
+        if finished {
+            self.finish_compaction(compaction)
+        } else {
+            self.compaction = Some(compaction);
+            Ok(())
+        }
+    }
+
+    /// Replaces the segment file with the new one, and updates the offsets of the content.
+    fn finish_compaction(&mut self, compaction: Compaction) -> Result<(), ContentStoreError> {
+        let Compaction {
+            writer,
+            size,
+            offsets,
+            ..
+        } = compaction;
+        let new_file = writer.into_inner().map_err(|err| err.into_error())?;
+        new_file.sync_all()?;
+        drop(new_file);
+
+        let compaction_path = compaction_path(&self.path);
+        if offsets.len() != self.index.len()
+            || !self
+                .index
+                .keys()
+                .all(|content_id| offsets.contains_key(content_id))
+        {
+            fs::remove_file(&compaction_path)?;
+            return Err(ContentStoreError::Database(format!(
+                "Compaction of the segment file {} didn't copy all the live records",
+                self.path.display(),
+            )));
+        }
+
+        let mut file = self.lock_file()?;
+        fs::rename(&compaction_path, &self.path)?;
+        *file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        drop(file);
+
+        debug!(
+            path = %self.path.display(),
+            "Compaction finished: size {} -> {size}",
+            self.file_size,
+        );
+        for (content_id, entry) in self.index.iter_mut() {
+            entry.offset = offsets[content_id];
+        }
+        self.file_size = size;
+        self.unsynced_bytes = 0;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for SegmentBackend {
+    fn drop(&mut self) {
+        if self.unsynced_bytes == 0 {
+            return;
+        }
+        let result = self
+            .lock_file()
+            .and_then(|file| file.sync_data().map_err(ContentStoreError::from));
+        if let Err(err) = result {
+            warn!(path = %self.path.display(), "Error syncing the segment file: {err}");
+        }
+    }
+}
+
+/// The content that is about to be deleted.
+struct PreparedDelete {
+    content_id: B256,
+    entry: IndexEntry,
+    key_prefix: KeyPrefix,
+    tombstone: [u8; HEADER_SIZE as usize],
+}
+
+/// Returns the path to the segment file for the content type.
+fn segment_path(content_type: &ContentType, node_data_dir: &Path) -> PathBuf {
+    node_data_dir.join(format!("{}.seg", sql::table_name(content_type)))
+}
+
+/// Returns the path to the new segment file, written by the compaction.
+fn compaction_path(segment_path: &Path) -> PathBuf {
+    segment_path.with_extension("seg.compact")
+}
+
+/// Reads `len` bytes from the file, starting at `offset`.
+fn read_at(file: &mut File, offset: u64, len: u32) -> Result<Vec<u8>, ContentStoreError> {
+    let mut bytes = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Returns whether the record with the given header, and everything after it, are zeros (e.g.
+/// the file was extended, but the appended records weren't written before a crash).
+///
+/// Otherwise, the reader is moved back to the end of the record.
+fn is_zero_tail(
+    reader: &mut BufReader<&File>,
+    header_bytes: &[u8; HEADER_SIZE as usize],
+    record_end: u64,
+) -> Result<bool, ContentStoreError> {
+    if header_bytes.iter().any(|byte| *byte != 0) {
+        return Ok(false);
+    }
+    let mut buf = [0u8; 8192];
+    loop {
+        let len = reader.read(&mut buf)?;
+        if len == 0 {
+            return Ok(true);
+        }
+        if buf[..len].iter().any(|byte| *byte != 0) {
+            reader.seek(SeekFrom::Start(record_end))?;
+            return Ok(false);
+        }
+    }
+}
+
+impl IdIndexedV1Backend for SegmentBackend {
+    fn has_content(&self, content_id: &ContentId) -> Result<bool, ContentStoreError> {
+        Ok(self.index.contains_key(&**content_id))
+    }
+
+    fn lookup_content_key(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        let Some(entry) = self.index.get(&**content_id) else {
+            return Ok(None);
+        };
+        let key = read_at(&mut *self.lock_file()?, entry.key_offset(), entry.key_len)?;
+        Ok(Some(key))
+    }
+
+    fn lookup_content_value(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        let Some(entry) = self.index.get(&**content_id) else {
+            return Ok(None);
+        };
+        let value = read_at(
+            &mut *self.lock_file()?,
+            entry.value_offset(),
+            entry.value_len,
+        )?;
+        Ok(Some(value))
+    }
+
+    fn insert(&mut self, entry: BackendEntry) -> Result<(), ContentStoreError> {
+        let content_id = *entry.content_id;
+        if self.index.contains_key(&content_id) {
+            return Ok(());
+        }
+        let key_len =
+            u32::try_from(entry.content_key.len()).map_err(|_| ContentStoreError::InvalidData {
+                message: "Content key is too big".to_string(),
+            })?;
+        let value_len = u32::try_from(entry.content_value.len()).map_err(|_| {
+            ContentStoreError::InvalidData {
+                message: "Content value is too big".to_string(),
+            }
+        })?;
+
+        let header = RecordHeader {
+            tag: TAG_PUT,
+            content_id,
+            distance_short: entry.distance_short,
+            content_size: entry.content_size,
+            key_len,
+            value_len,
+        };
+        let mut record = Vec::with_capacity(
+            HEADER_SIZE as usize + entry.content_key.len() + entry.content_value.len(),
+        );
+        record.extend_from_slice(&header.encode(&entry.content_key, &entry.content_value));
+        record.extend_from_slice(&entry.content_key);
+        record.extend_from_slice(&entry.content_value);
+        let offset = self.append(&record)?;
+
+        let index_entry = IndexEntry {
+            offset,
+            key_len,
+            value_len,
+            distance_short: entry.distance_short,
+            content_size: entry.content_size,
+        };
+        self.live_bytes += index_entry.record_len();
+        self.distance_index
+            .insert((entry.distance_short, content_id));
+        self.key_index
+            .insert((key_prefix(&entry.content_key), content_id));
+        self.index.insert(content_id, index_entry);
+        Ok(())
+    }
+
+    fn delete(&mut self, content_id: &ContentId) -> Result<Option<u64>, ContentStoreError> {
+        let Some(delete) = self.prepare_delete(content_id)? else {
+            return Ok(None);
+        };
+        self.append(&delete.tombstone)?;
+        self.remove_from_index(&delete);
+        self.maybe_compact()?;
+        Ok(Some(delete.entry.content_size))
+    }
+
+    fn delete_farthest(&mut self, limit: u64) -> Result<Vec<u64>, ContentStoreError> {
+        let farthest = self
+            .distance_index
+            .iter()
+            .rev()
+            .take(limit as usize)
+            .map(|(_, content_id)| *content_id)
+            .collect::<Vec<_>>();
+
+        let mut deletes = Vec::with_capacity(farthest.len());
+        let mut tombstones = Vec::with_capacity(farthest.len() * HEADER_SIZE as usize);
+        for content_id in farthest {
+            if let Some(delete) = self.prepare_delete(&content_id)? {
+                tombstones.extend_from_slice(&delete.tombstone);
+                deletes.push(delete);
+            }
+        }
+        self.append(&tombstones)?;
+        let deleted_content_sizes: Vec<u64> = deletes
+            .iter()
+            .map(|delete| {
+                self.remove_from_index(delete);
+                delete.entry.content_size
+            })
+            .collect();
+        self.maybe_compact()?;
+        Ok(deleted_content_sizes)
+    }
+
+    fn clear(&mut self) -> Result<(), ContentStoreError> {
+        if self.compaction.take().is_some() {
+            fs::remove_file(compaction_path(&self.path))?;
+        }
+        let file = self.lock_file()?;
+        file.set_len(0)?;
+        file.sync_all()?;
+        drop(file);
+        self.file_size = 0;
+        self.index.clear();
+        self.distance_index.clear();
+        self.key_index.clear();
+        self.live_bytes = 0;
+        self.unsynced_bytes = 0;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+
+    fn lookup_farthest(&self) -> Result<Option<FarthestEntry>, ContentStoreError> {
+        Ok(self
+            .distance_index
+            .last()
+            .map(|(distance_short, content_id)| FarthestEntry {
+                content_id: ContentId::from(*content_id),
+                distance_short: *distance_short,
+            }))
+    }
+
+    fn paginate(&self, offset: u64, limit: u64) -> Result<Vec<Vec<u8>>, ContentStoreError> {
+        let (offset, limit) = (offset as usize, limit as usize);
+        let mut keys = Vec::with_capacity(limit.min(self.key_index.len()));
+        if limit == 0 {
+            return Ok(keys);
+        }
+
+        // The keys that share the prefix are read from the file, and sorted.
+        let mut file = self.lock_file()?;
+        let mut position = 0;
+        let mut key_index = self.key_index.iter().peekable();
+        while let Some((prefix, content_id)) = key_index.next() {
+            let mut group = vec![*content_id];
+            while let Some((_, content_id)) = key_index.next_if(|(next, _)| next == prefix) {
+                group.push(*content_id);
+            }
+            let group_end = position + group.len();
+            if group_end > offset {
+                let mut group_keys = group
+                    .iter()
+                    .map(|content_id| {
+                        let entry = &self.index[content_id];
+                        read_at(&mut file, entry.key_offset(), entry.key_len)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                group_keys.sort();
+                let skip = offset.saturating_sub(position);
+                let take = limit - keys.len();
+                keys.extend(group_keys.into_iter().skip(skip).take(take));
+                if keys.len() == limit {
+                    break;
+                }
+            }
+            position = group_end;
+        }
+        Ok(keys)
+    }
+
+    fn iterate(
+        &self,
+        after: Option<&ContentId>,
+        limit: u64,
+    ) -> Result<Vec<BackendEntry>, ContentStoreError> {
+        let lower_bound = match after {
+            Some(content_id) => Bound::Excluded(**content_id),
+            None => Bound::Unbounded,
+        };
+        let mut file = self.lock_file()?;
+        self.index
+            .range((lower_bound, Bound::Unbounded))
+            .take(limit as usize)
+            .map(|(content_id, entry)| {
+                Ok(BackendEntry {
+                    content_id: ContentId::from(*content_id),
+                    content_key: read_at(&mut file, entry.key_offset(), entry.key_len)?,
+                    content_value: read_at(&mut file, entry.value_offset(), entry.value_len)?,
+                    distance_short: entry.distance_short,
+                    content_size: entry.content_size,
+                })
+            })
+            .collect()
+    }
+
+    fn usage_stats(&self) -> Result<UsageStats, ContentStoreError> {
+        Ok(UsageStats::new(
+            self.index.len() as u64,
+            self.index.values().map(|entry| entry.content_size).sum(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    use crate::test_utils::generate_random_bytes;
+
+    use super::*;
+
+    fn generate_entry(distance_short: u32) -> BackendEntry {
+        let content_id = ContentId::from(B256::random());
+        let content_key = generate_random_bytes(33);
+        let content_value = generate_random_bytes(100);
+        let content_size = (32 + content_key.len() + content_value.len()) as u64;
+        BackendEntry {
+            content_id,
+            content_key,
+            content_value,
+            distance_short,
+            content_size,
+        }
+    }
+
+    fn open(temp_dir: &TempDir) -> Result<SegmentBackend> {
+        Ok(SegmentBackend::new(&ContentType::State, temp_dir.path())?)
+    }
+
+    #[test]
+    fn insert_lookup_delete() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let entry = generate_entry(1);
+        backend.insert(entry.clone())?;
+        assert!(backend.has_content(&entry.content_id)?);
+        assert_eq!(
+            backend.lookup_content_key(&entry.content_id)?,
+            Some(entry.content_key.clone())
+        );
+        assert_eq!(
+            backend.lookup_content_value(&entry.content_id)?,
+            Some(entry.content_value.clone())
+        );
+        assert_eq!(
+            backend.usage_stats()?,
+            UsageStats::new(1, entry.content_size)
+        );
+
+        assert_eq!(backend.delete(&entry.content_id)?, Some(entry.content_size));
+        assert!(!backend.has_content(&entry.content_id)?);
+        assert_eq!(backend.lookup_content_value(&entry.content_id)?, None);
+        assert_eq!(backend.delete(&entry.content_id)?, None);
+        assert_eq!(backend.usage_stats()?, UsageStats::default());
+        Ok(())
+    }
+
+    #[test]
+    fn insert_duplicate_is_ignored() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let entry = generate_entry(1);
+        backend.insert(entry.clone())?;
+        backend.insert(BackendEntry {
+            content_value: vec![1, 2, 3],
+            ..entry.clone()
+        })?;
+
+        assert_eq!(
+            backend.lookup_content_value(&entry.content_id)?,
+            Some(entry.content_value)
+        );
+        assert_eq!(backend.usage_stats()?.entry_count, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn reopen() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries = (0..10).map(generate_entry).collect::<Vec<_>>();
+        {
+            let mut backend = open(&temp_dir)?;
+            for entry in &entries {
+                backend.insert(entry.clone())?;
+            }
+            backend.delete(&entries[0].content_id)?;
+        }
+
+        let backend = open(&temp_dir)?;
+        assert!(!backend.has_content(&entries[0].content_id)?);
+        for entry in &entries[1..] {
+            assert_eq!(
+                backend.lookup_content_value(&entry.content_id)?,
+                Some(entry.content_value.clone())
+            );
+        }
+        assert_eq!(backend.usage_stats()?.entry_count, 9);
+        assert_eq!(backend.paginate(0, 100)?.len(), 9);
+        Ok(())
+    }
+
+    #[test]
+    fn delete_farthest() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let entries = (0..10).map(generate_entry).collect::<Vec<_>>();
+        for entry in &entries {
+            backend.insert(entry.clone())?;
+        }
+
+        assert_eq!(
+            backend.lookup_farthest()?,
+            Some(FarthestEntry {
+                content_id: entries[9].content_id.clone(),
+                distance_short: 9,
+            })
+        );
+
+        assert_eq!(backend.delete_farthest(3)?.len(), 3);
+        for entry in &entries[7..] {
+            assert!(!backend.has_content(&entry.content_id)?);
+        }
+        assert_eq!(
+            backend
+                .lookup_farthest()?
+                .map(|farthest| farthest.distance_short),
+            Some(6)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn paginate_ordered_by_key() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let mut keys = vec![];
+        for i in 0..10 {
+            let entry = generate_entry(i);
+            keys.push(entry.content_key.clone());
+            backend.insert(entry)?;
+        }
+        keys.sort();
+
+        assert_eq!(backend.paginate(0, 4)?, keys[0..4]);
+        assert_eq!(backend.paginate(4, 4)?, keys[4..8]);
+        assert_eq!(backend.paginate(8, 4)?, keys[8..]);
+        Ok(())
+    }
+
+    #[test]
+    fn paginate_keys_with_shared_prefix() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let mut keys = vec![];
+        for i in 0..10 {
+            let mut entry = generate_entry(i);
+            if i % 2 == 0 {
+                entry.content_key[..KEY_PREFIX_LEN].fill(0);
+            }
+            if i == 4 {
+                entry.content_key.truncate(3);
+            }
+            keys.push(entry.content_key.clone());
+            backend.insert(entry)?;
+        }
+        keys.sort();
+
+        let pages = (0..4)
+            .map(|page| backend.paginate(page * 3, 3))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(pages.concat(), keys);
+        assert_eq!(backend.paginate(0, 100)?, keys);
+        assert_eq!(backend.paginate(2, 0)?, Vec::<Vec<u8>>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn iterate_ordered_by_content_id() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let mut entries = (0..10).map(generate_entry).collect::<Vec<_>>();
+        for entry in &entries {
+            backend.insert(entry.clone())?;
+        }
+        entries.sort_by_key(|entry| *entry.content_id);
+
+        assert_eq!(backend.iterate(None, 4)?, entries[0..4]);
+        assert_eq!(
+            backend.iterate(Some(&entries[3].content_id), 4)?,
+            entries[4..8]
+        );
+        assert_eq!(
+            backend.iterate(Some(&entries[7].content_id), 4)?,
+            entries[8..]
+        );
+        assert_eq!(backend.iterate(Some(&entries[9].content_id), 4)?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn compact() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+
+        let entries = (0..10).map(generate_entry).collect::<Vec<_>>();
+        for entry in &entries {
+            backend.insert(entry.clone())?;
+        }
+        for entry in &entries[..5] {
+            backend.delete(&entry.content_id)?;
+        }
+        let size_before = backend.file_size;
+
+        backend.start_compaction()?;
+        backend.compaction_step(COMPACTION_STEP_BYTES)?;
+        assert!(backend.compaction.is_none());
+        assert_eq!(backend.file_size, backend.live_bytes);
+        assert!(backend.file_size < size_before);
+        assert_eq!(fs::metadata(&backend.path)?.len(), backend.file_size);
+        for entry in &entries[5..] {
+            assert_eq!(
+                backend.lookup_content_value(&entry.content_id)?,
+                Some(entry.content_value.clone())
+            );
+        }
+
+        // Check that compacted file is valid
+        drop(backend);
+        let backend = open(&temp_dir)?;
+        assert_eq!(backend.usage_stats()?.entry_count, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn compaction_in_steps() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut backend = open(&temp_dir)?;
+        let record_len = HEADER_SIZE + 33 + 100;
+
+        let mut entries = (0..10).map(generate_entry).collect::<Vec<_>>();
+        for entry in &entries {
+            backend.insert(entry.clone())?;
+        }
+        backend.delete(&entries[0].content_id)?;
+        assert!(backend.compaction.is_none());
+
+        // Every step goes through a single record
+        backend.start_compaction()?;
+        backend.compaction_step(1)?;
+        backend.compaction_step(1)?;
+        let compaction = backend
+            .compaction
+            .as_ref()
+            .expect("compaction to be running");
+        assert_eq!(compaction.cursor, 2 * record_len);
+        assert_eq!(compaction.size, record_len);
+
+        // Content inserted during the compaction is copied once the compaction reaches it, while
+        // content deleted before it's reached isn't copied
+        let entry = generate_entry(10);
+        backend.insert(entry.clone())?;
+        entries.push(entry);
+        backend.delete(&entries[5].content_id)?;
+        assert!(backend.compaction.is_none());
+
+        // Content deleted after it's copied is deleted by copying its tombstone
+        backend.start_compaction()?;
+        backend.compaction_step(1)?;
+        backend.delete(&entries[1].content_id)?;
+        assert!(backend.compaction.is_none());
+        assert!(!compaction_path(&backend.path).exists());
+        assert_eq!(fs::metadata(&backend.path)?.len(), backend.file_size);
+
+        let deleted = [0, 1, 5];
+        let check = |backend: &SegmentBackend| -> Result<()> {
+            for (i, entry) in entries.iter().enumerate() {
+                let expected = (!deleted.contains(&i)).then(|| entry.content_value.clone());
+                assert_eq!(backend.lookup_content_value(&entry.content_id)?, expected);
+            }
+            assert_eq!(backend.usage_stats()?.entry_count, 8);
+            Ok(())
+        };
+        check(&backend)?;
+
+        // Check that compacted file is valid
+        drop(backend);
+        check(&open(&temp_dir)?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn discard_incomplete_record() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries = (0..3).map(generate_entry).collect::<Vec<_>>();
+        let path = {
+            let mut backend = open(&temp_dir)?;
+            for entry in &entries {
+                backend.insert(entry.clone())?;
+            }
+            backend.path.clone()
+        };
+
+        // Simulate crash while writing the last record
+        let file_size = fs::metadata(&path)?.len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_len(file_size - 10)?;
+
+        let backend = open(&temp_dir)?;
+        assert!(backend.has_content(&entries[0].content_id)?);
+        assert!(backend.has_content(&entries[1].content_id)?);
+        assert!(!backend.has_content(&entries[2].content_id)?);
+        assert_eq!(fs::metadata(&path)?.len(), backend.file_size);
+        Ok(())
+    }
+
+    #[test]
+    fn discard_corrupted_record() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries = (0..3).map(generate_entry).collect::<Vec<_>>();
+        let path = {
+            let mut backend = open(&temp_dir)?;
+            for entry in &entries {
+                backend.insert(entry.clone())?;
+            }
+            backend.path.clone()
+        };
+
+        // Simulate torn write of the last record, which has the right length but wrong content
+        let mut bytes = fs::read(&path)?;
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, bytes)?;
+
+        let backend = open(&temp_dir)?;
+        assert!(backend.has_content(&entries[1].content_id)?);
+        assert!(!backend.has_content(&entries[2].content_id)?);
+        assert_eq!(fs::metadata(&path)?.len(), backend.file_size);
+        Ok(())
+    }
+
+    #[test]
+    fn discard_garbage_tail() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries = (0..3).map(generate_entry).collect::<Vec<_>>();
+        let path = {
+            let mut backend = open(&temp_dir)?;
+            for entry in &entries {
+                backend.insert(entry.clone())?;
+            }
+            backend.path.clone()
+        };
+        let file_size = fs::metadata(&path)?.len();
+
+        // Simulate garbage written at the end of the file
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(&[0xff; 2 * HEADER_SIZE as usize])?;
+        drop(file);
+
+        let backend = open(&temp_dir)?;
+        assert_eq!(backend.usage_stats()?.entry_count, 3);
+        assert_eq!(backend.file_size, file_size);
+        assert_eq!(fs::metadata(&path)?.len(), file_size);
+        Ok(())
+    }
+
+    #[test]
+    fn skip_corrupted_record_mid_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries = (0..3).map(generate_entry).collect::<Vec<_>>();
+        let path = {
+            let mut backend = open(&temp_dir)?;
+            for entry in &entries {
+                backend.insert(entry.clone())?;
+            }
+            backend.path.clone()
+        };
+        let file_size = fs::metadata(&path)?.len();
+
+        // Simulate bit rot in the value of the middle record
+        let mut bytes = fs::read(&path)?;
+        let record_len = HEADER_SIZE as usize + 33 + 100;
+        bytes[record_len + HEADER_SIZE as usize + 33 + 5] ^= 0xff;
+        fs::write(&path, bytes)?;
+
+        let backend = open(&temp_dir)?;
+        assert!(backend.has_content(&entries[0].content_id)?);
+        assert!(!backend.has_content(&entries[1].content_id)?);
+        assert!(backend.has_content(&entries[2].content_id)?);
+        assert_eq!(backend.file_size, file_size);
+        assert_eq!(fs::metadata(&path)?.len(), file_size);
+        Ok(())
+    }
+
+    #[test]
+    fn fail_on_unreadable_records_mid_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = {
+            let mut backend = open(&temp_dir)?;
+            for entry in (0..3).map(generate_entry) {
+                backend.insert(entry)?;
+            }
+            backend.path.clone()
+        };
+
+        // Corrupt the key length of the first record, so the records after it can't be found
+        let mut bytes = fs::read(&path)?;
+        bytes[45..49].copy_from_slice(&34u32.to_be_bytes());
+        fs::write(&path, &bytes)?;
+
+        assert!(open(&temp_dir).is_err());
+        assert_eq!(fs::read(&path)?, bytes);
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{named_params, OptionalExtension};
+
+use super::{BackendEntry, FarthestEntry, IdIndexedV1Backend};
+use crate::{
+    error::ContentStoreError,
+    versioned::{id_indexed_v1::sql, usage_stats::UsageStats, ContentType},
+    ContentId,
+};
+
+/// The backend that stores content in the SQLite table.
+///
+/// Different SQL table is created for each `ContentType`, with content-id as a primary key.
+#[derive(Debug)]
+pub struct SqliteBackend {
+    content_type: ContentType,
+    sql_connection_pool: Pool<SqliteConnectionManager>,
+}
+
+impl SqliteBackend {
+    /// Creates the backend, creating the table and indexes if they don't already exist.
+    pub fn new(
+        content_type: ContentType,
+        sql_connection_pool: Pool<SqliteConnectionManager>,
+    ) -> Result<Self, ContentStoreError> {
+        maybe_create_table_and_indexes(&content_type, &sql_connection_pool)?;
+        Ok(Self {
+            content_type,
+            sql_connection_pool,
+        })
+    }
+}
+
+impl IdIndexedV1Backend for SqliteBackend {
+    fn has_content(&self, content_id: &ContentId) -> Result<bool, ContentStoreError> {
+        Ok(self
+            .sql_connection_pool
+            .get()?
+            .prepare(&sql::lookup_key(&self.content_type))?
+            .exists(named_params! { ":content_id": content_id.to_vec() })?)
+    }
+
+    fn lookup_content_key(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        Ok(self
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                &sql::lookup_key(&self.content_type),
+                named_params! { ":content_id": content_id.to_vec() },
+                |row| row.get::<&str, Vec<u8>>("content_key"),
+            )
+            .optional()?)
+    }
+
+    fn lookup_content_value(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        Ok(self
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                &sql::lookup_value(&self.content_type),
+                named_params! { ":content_id": content_id.to_vec() },
+                |row| row.get::<&str, Vec<u8>>("content_value"),
+            )
+            .optional()?)
+    }
+
+    fn insert(&mut self, entry: BackendEntry) -> Result<(), ContentStoreError> {
+        self.sql_connection_pool.get()?.execute(
+            &sql::insert(&self.content_type),
+            named_params! {
+                ":content_id": entry.content_id.to_vec(),
+                ":content_key": entry.content_key,
+                ":content_value": entry.content_value,
+                ":distance_short": entry.distance_short,
+                ":content_size": entry.content_size,
+            },
+        )?;
+        Ok(())
+    }
+
+    fn delete(&mut self, content_id: &ContentId) -> Result<Option<u64>, ContentStoreError> {
+        Ok(self
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                &sql::delete(&self.content_type),
+                named_params! { ":content_id": content_id.to_vec() },
+                |row| row.get::<_, u64>("content_size"),
+            )
+            .optional()?)
+    }
+
+    fn delete_farthest(&mut self, limit: u64) -> Result<Vec<u64>, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        let mut delete_query = conn.prepare(&sql::delete_farthest(&self.content_type))?;
+        let deleted_content_sizes = delete_query
+            .query_map(named_params! { ":limit": limit }, |row| {
+                row.get("content_size")
+            })?
+            .collect::<Result<Vec<u64>, rusqlite::Error>>()?;
+        Ok(deleted_content_sizes)
+    }
+
+    fn clear(&mut self) -> Result<(), ContentStoreError> {
+        self.sql_connection_pool
+            .get()?
+            .execute(&sql::delete_all(&self.content_type), [])?;
+        Ok(())
+    }
+
+    fn lookup_farthest(&self) -> Result<Option<FarthestEntry>, ContentStoreError> {
+        Ok(self
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                &sql::lookup_farthest(&self.content_type),
+                named_params! { ":limit": 1 },
+                |row| {
+                    Ok(FarthestEntry {
+                        content_id: row.get("content_id")?,
+                        distance_short: row.get("distance_short")?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    fn paginate(&self, offset: u64, limit: u64) -> Result<Vec<Vec<u8>>, ContentStoreError> {
+        let conn = self.sql_connection_pool.get()?;
+        let content_keys = conn
+            .prepare(&sql::paginate(&self.content_type))?
+            .query_map(
+                named_params! {
+                    ":limit": limit,
+                    ":offset": offset,
+                },
+                |row| row.get::<&str, Vec<u8>>("content_key"),
+            )?
+            .collect::<Result<Vec<Vec<u8>>, rusqlite::Error>>()?;
+        Ok(content_keys)
+    }
+
+    fn iterate(
+        &self,
+        after: Option<&ContentId>,
+        limit: u64,
+    ) -> Result<Vec<BackendEntry>, ContentStoreError> {
+        // Empty blob is ordered before any content id
+        let after = after
+            .map(|content_id| content_id.to_vec())
+            .unwrap_or_default();
+        let conn = self.sql_connection_pool.get()?;
+        let entries = conn
+            .prepare(&sql::iterate(&self.content_type))?
+            .query_map(
+                named_params! {
+                    ":content_id": after,
+                    ":limit": limit,
+                },
+                |row| {
+                    Ok(BackendEntry {
+                        content_id: row.get("content_id")?,
+                        content_key: row.get("content_key")?,
+                        content_value: row.get("content_value")?,
+                        distance_short: row.get("distance_short")?,
+                        content_size: row.get("content_size")?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<BackendEntry>, rusqlite::Error>>()?;
+        Ok(entries)
+    }
+
+    fn usage_stats(&self) -> Result<UsageStats, ContentStoreError> {
+        Ok(self.sql_connection_pool.get()?.query_row(
+            &sql::entry_count_and_size(&self.content_type),
+            [],
+            |row| {
+                let entry_count = row.get("count")?;
+                let used_capacity: f64 = row.get("used_capacity")?;
+                Ok(UsageStats::new(entry_count, used_capacity.round() as u64))
+            },
+        )?)
+    }
+}
+
+/// Creates table and indexes if they don't already exist.
+fn maybe_create_table_and_indexes(
+    content_type: &ContentType,
+    pool: &Pool<SqliteConnectionManager>,
+) -> Result<(), ContentStoreError> {
+    let conn = pool.get()?;
+    conn.execute_batch(&sql::create_table(content_type))?;
+    Ok(())
+}
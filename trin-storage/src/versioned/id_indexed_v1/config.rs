@@ -5,7 +5,10 @@ use ethportal_api::types::portal_wire::ProtocolId;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 
-use crate::{versioned::ContentType, DistanceFunction, PortalStorageConfig, BYTES_IN_MB_U64};
+use crate::{
+    versioned::{ContentType, StorageBackend},
+    DistanceFunction, PortalStorageConfig, RadiusConfig, BYTES_IN_MB_U64,
+};
 
 use super::pruning_strategy::PruningConfig;
 
@@ -19,6 +22,8 @@ pub struct IdIndexedV1StoreConfig {
     pub storage_capacity_bytes: u64,
     pub sql_connection_pool: Pool<SqliteConnectionManager>,
     pub distance_fn: DistanceFunction,
+    pub radius_config: RadiusConfig,
+    pub backend: StorageBackend,
     pub pruning_config: PruningConfig,
 }
 
@@ -36,8 +41,9 @@ impl IdIndexedV1StoreConfig {
             storage_capacity_bytes: config.storage_capacity_mb * BYTES_IN_MB_U64,
             sql_connection_pool: config.sql_connection_pool,
             distance_fn: config.distance_fn,
-            // consider making this a parameter if we start using non-default value
-            pruning_config: PruningConfig::default(),
+            radius_config: config.radius_config,
+            backend: config.backend,
+            pruning_config: config.pruning_config,
         }
     }
 }
@@ -7,6 +7,9 @@ use crate::{
 
 use super::IdIndexedV1StoreConfig;
 
+/// Migrates the legacy history store into the SQL table of the `IdIndexedV1Store`.
+///
+/// If the store uses a different backend, the content is moved into it when the store is created.
 pub fn migrate_legacy_history_store(
     config: &IdIndexedV1StoreConfig,
 ) -> Result<(), ContentStoreError> {
@@ -55,7 +58,10 @@ mod tests {
 
     use crate::{
         test_utils::{create_test_portal_storage_config_with_capacity, generate_random_bytes},
-        versioned::{usage_stats::UsageStats, IdIndexedV1Store, VersionedContentStore},
+        versioned::{
+            id_indexed_v1::StorageBackend, usage_stats::UsageStats, IdIndexedV1Store,
+            VersionedContentStore,
+        },
     };
 
     use super::*;
@@ -174,4 +180,47 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn legacy_history_with_content_into_segment() -> Result<()> {
+        let (_temp_dir, config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB)?;
+
+        let mut key_value_map = HashMap::new();
+
+        // initialize legacy store
+        legacy_history::create_store(&config)?;
+        for _ in 0..10 {
+            let (key, value) = generate_key_value_with_content_size();
+            legacy_history::store(&config, &key, &value)?;
+            key_value_map.insert(key, value);
+        }
+
+        // migrate
+        let config = IdIndexedV1StoreConfig {
+            backend: StorageBackend::Segment,
+            ..IdIndexedV1StoreConfig::new(ContentType::History, ProtocolId::History, config)
+        };
+        migrate_legacy_history_store(&config)?;
+
+        // create IdIndexedV1Store and verify that content was moved into the segment file
+        let store = IdIndexedV1Store::create(ContentType::History, config.clone())?;
+        for (key, value) in key_value_map.into_iter() {
+            assert_eq!(
+                store.lookup_content_value(&key.content_id().into())?,
+                Some(value),
+            );
+        }
+        let sql_entry_count: u64 = config.sql_connection_pool.get()?.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {}",
+                sql::table_name(&ContentType::History)
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(sql_entry_count, 0);
+
+        Ok(())
+    }
 }
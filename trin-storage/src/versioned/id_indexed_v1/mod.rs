@@ -1,8 +1,11 @@
+mod backend;
 mod config;
 mod migration;
 mod pruning_strategy;
 pub(super) mod sql;
 mod store;
 
+pub use backend::StorageBackend;
 pub use config::IdIndexedV1StoreConfig;
-pub use store::IdIndexedV1Store;
+pub use pruning_strategy::PruningConfig;
+pub use store::{EntryCheck, IdIndexedV1Store};
@@ -1,5 +1,6 @@
 use std::{fmt::Debug, ops::Range, time::Duration};
 
+use ethportal_api::types::config_file::PruningTuning;
 use tracing::debug;
 
 use crate::versioned::usage_stats::UsageStats;
@@ -60,6 +61,42 @@ impl PruningConfig {
             optimal_pruning_duration_range,
         }
     }
+
+    /// Overrides the values with the ones set in the `[pruning]` table of the config file.
+    ///
+    /// Panics if the resulting parameters are invalid (see [PruningConfig::new]).
+    pub fn with_tuning(self, tuning: &PruningTuning) -> Self {
+        let duration_range = self.optimal_pruning_duration_range;
+        let start = tuning
+            .min_optimal_pruning_duration_ms
+            .map_or(duration_range.start, Duration::from_millis);
+        let end = tuning
+            .max_optimal_pruning_duration_ms
+            .map_or(duration_range.end, Duration::from_millis);
+        Self::new(
+            tuning
+                .target_capacity_fraction
+                .unwrap_or(self.target_capacity_fraction),
+            tuning
+                .max_pruning_count_change_fraction
+                .unwrap_or(self.max_pruning_count_change_fraction),
+            start..end,
+        )
+    }
+
+    /// Returns the parameters, in the format of the `[pruning]` table of the config file.
+    pub fn tuning(&self) -> PruningTuning {
+        PruningTuning {
+            target_capacity_fraction: Some(self.target_capacity_fraction),
+            max_pruning_count_change_fraction: Some(self.max_pruning_count_change_fraction),
+            min_optimal_pruning_duration_ms: Some(
+                self.optimal_pruning_duration_range.start.as_millis() as u64,
+            ),
+            max_optimal_pruning_duration_ms: Some(
+                self.optimal_pruning_duration_range.end.as_millis() as u64,
+            ),
+        }
+    }
 }
 
 impl Default for PruningConfig {
@@ -101,6 +138,11 @@ impl PruningStrategy {
         }
     }
 
+    /// Updates the storage capacity.
+    pub fn set_storage_capacity_bytes(&mut self, storage_capacity_bytes: u64) {
+        self.config.storage_capacity_bytes = storage_capacity_bytes;
+    }
+
     /// The capacity that we aim for when pruning.
     pub fn target_capacity_bytes(&self) -> u64 {
         (self.config.storage_capacity_bytes as f64
@@ -202,7 +244,10 @@ mod tests {
     use r2d2_sqlite::SqliteConnectionManager;
     use rstest::rstest;
 
-    use crate::{versioned::ContentType, DistanceFunction};
+    use crate::{
+        versioned::{ContentType, StorageBackend},
+        DistanceFunction, RadiusConfig,
+    };
 
     use super::*;
 
@@ -221,6 +266,8 @@ mod tests {
             storage_capacity_bytes,
             sql_connection_pool: Pool::new(SqliteConnectionManager::memory()).unwrap(),
             distance_fn: DistanceFunction::Xor,
+            radius_config: RadiusConfig::default(),
+            backend: StorageBackend::default(),
             pruning_config: PruningConfig::default(),
         };
         PruningStrategy::new(config)
@@ -361,4 +408,31 @@ mod tests {
         // max_pruning_count should have decreased by DEFAULT_CHANGE_FRACTION (20%)
         assert_eq!(pruning_strategy.max_pruning_count, 80);
     }
+
+    #[test]
+    fn pruning_config_with_tuning() {
+        let pruning_config = PruningConfig::default().with_tuning(&PruningTuning {
+            target_capacity_fraction: Some(0.9),
+            max_optimal_pruning_duration_ms: Some(500),
+            ..Default::default()
+        });
+        assert_eq!(pruning_config.target_capacity_fraction, 0.9);
+        assert_eq!(
+            pruning_config.max_pruning_count_change_fraction,
+            PruningConfig::DEFAULT_CHANGE_FRACTION
+        );
+        assert_eq!(
+            pruning_config.optimal_pruning_duration_range,
+            PruningConfig::DEFAULT_OPTIMAL_PRUNING_DURATION_RANGE.start..Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "optimal_pruning_duration_range")]
+    fn pruning_config_with_invalid_tuning() {
+        PruningConfig::default().with_tuning(&PruningTuning {
+            min_optimal_pruning_duration_ms: Some(500),
+            ..Default::default()
+        });
+    }
 }
@@ -52,6 +52,10 @@ pub fn delete(content_type: &ContentType) -> String {
     )
 }
 
+pub fn delete_all(content_type: &ContentType) -> String {
+    format!("DELETE FROM {}", table_name(content_type))
+}
+
 pub fn lookup_key(content_type: &ContentType) -> String {
     format!(
         "SELECT content_key FROM {} WHERE content_id = :content_id LIMIT 1",
@@ -99,6 +103,16 @@ pub fn paginate(content_type: &ContentType) -> String {
     )
 }
 
+pub fn iterate(content_type: &ContentType) -> String {
+    format!(
+        "SELECT content_id, content_key, content_value, distance_short, content_size FROM {}
+        WHERE content_id > :content_id
+        ORDER BY content_id
+        LIMIT :limit",
+        table_name(content_type)
+    )
+}
+
 pub fn entry_count_and_size(content_type: &ContentType) -> String {
     format!(
         "SELECT COUNT(*) as count, TOTAL(content_size) as used_capacity FROM {}",
@@ -1,14 +1,17 @@
 use std::time::Instant;
 
+use alloy_primitives::U256;
 use ethportal_api::types::distance::Distance;
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{named_params, types::Type, OptionalExtension};
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info, warn};
 use trin_metrics::storage::StorageMetricsReporter;
 
 use super::{
-    migration::migrate_legacy_history_store, pruning_strategy::PruningStrategy, sql,
+    backend::{
+        move_content, BackendEntry, FarthestEntry, IdIndexedV1Backend, SegmentBackend,
+        SqliteBackend, StorageBackend,
+    },
+    migration::migrate_legacy_history_store,
+    pruning_strategy::PruningStrategy,
     IdIndexedV1StoreConfig,
 };
 use crate::{
@@ -18,12 +21,6 @@ use crate::{
     ContentId,
 };
 
-/// The result of looking for the farthest content.
-struct FarthestQueryResult {
-    content_id: ContentId,
-    distance_u32: u32,
-}
-
 /// The result of the pagination lookup.
 #[derive(Debug, PartialEq, Eq)]
 pub struct PaginateResult<K> {
@@ -33,17 +30,40 @@ pub struct PaginateResult<K> {
     pub entry_count: u64,
 }
 
+/// The result of checking the integrity of a single stored entry.
+#[derive(Debug, PartialEq, Eq)]
+pub enum EntryCheck<K> {
+    /// The stored metadata (content id, distance and size) matches the content key/value.
+    ///
+    /// The content key/value pair itself is not validated.
+    Consistent {
+        content_id: ContentId,
+        content_key: K,
+        content_value: Vec<u8>,
+    },
+    /// The entry is corrupt and should be deleted.
+    Corrupt {
+        content_id: ContentId,
+        reason: String,
+    },
+}
+
 /// The store for storing content key/value pairs.
 ///
-/// Different SQL table is created for each `ContentType`, with content-id as a primary key.
-/// It has a configurable capacity and it will prune data that is farthest from the `NodeId` once
-/// it uses more than storage capacity.
+/// The content is stored using the configured `StorageBackend` (by default, different SQL table
+/// is created for each `ContentType`, with content-id as a primary key). It has a configurable
+/// capacity and it will prune data that is farthest from the `NodeId` once it uses more than
+/// storage capacity.
 #[derive(Debug)]
 pub struct IdIndexedV1Store {
     /// The configuration.
     config: IdIndexedV1StoreConfig,
-    /// The maximum distance between `NodeId` and content id that store should keep. Updated
-    /// dynamically after pruning to the farthest distance still stored.
+    /// The backend that stores the content.
+    backend: Box<dyn IdIndexedV1Backend>,
+    /// The maximum distance between `NodeId` and content id that store should keep. Unless
+    /// configured otherwise with `RadiusConfig`, it is updated dynamically: it shrinks after
+    /// pruning to the farthest distance still stored, and grows when used capacity drops below
+    /// target capacity.
     radius: Distance,
     /// The strategy for deciding when and how much to prune.
     pruning_strategy: PruningStrategy,
@@ -75,7 +95,36 @@ impl VersionedContentStore for IdIndexedV1Store {
     }
 
     fn create(content_type: ContentType, config: Self::Config) -> Result<Self, ContentStoreError> {
-        maybe_create_table_and_indexes(&content_type, &config.sql_connection_pool)?;
+        let sqlite_backend =
+            || SqliteBackend::new(content_type.clone(), config.sql_connection_pool.clone());
+        let segment_backend = || SegmentBackend::new(&content_type, &config.node_data_dir);
+        let mut backend: Box<dyn IdIndexedV1Backend> = match config.backend {
+            StorageBackend::Sqlite => Box::new(sqlite_backend()?),
+            StorageBackend::Segment => Box::new(segment_backend()?),
+        };
+        // The backend that isn't configured might store content, e.g. if the backend of the data
+        // directory was switched, or if the legacy history store was migrated into SQL table.
+        let other_backend: Option<Box<dyn IdIndexedV1Backend>> = match config.backend {
+            StorageBackend::Sqlite
+                if SegmentBackend::exists(&content_type, &config.node_data_dir) =>
+            {
+                Some(Box::new(segment_backend()?))
+            }
+            StorageBackend::Sqlite => None,
+            StorageBackend::Segment => Some(Box::new(sqlite_backend()?)),
+        };
+        // Move that content into the configured backend, so it isn't orphaned.
+        if let Some(mut other_backend) = other_backend {
+            if other_backend.usage_stats()?.entry_count > 0 {
+                info!(
+                    %content_type,
+                    backend = %config.backend,
+                    "Moving content stored by the other backend",
+                );
+                let moved_count = move_content(other_backend.as_mut(), backend.as_mut())?;
+                info!(%content_type, "Moved {moved_count} entries");
+            }
+        }
 
         let protocol_id = config.network;
 
@@ -83,6 +132,7 @@ impl VersionedContentStore for IdIndexedV1Store {
 
         let mut store = Self {
             config,
+            backend,
             radius: Distance::MAX,
             pruning_strategy,
             usage_stats: UsageStats::default(),
@@ -124,8 +174,7 @@ impl IdIndexedV1Store {
                 Db = %self.config.content_type,
                 "Storage capacity is 0 -> Using ZERO radius",
             );
-            self.radius = Distance::ZERO;
-            self.metrics.report_radius(self.radius);
+            self.set_radius(Distance::ZERO);
         } else {
             debug!(
                 Db = %self.config.content_type,
@@ -133,8 +182,7 @@ impl IdIndexedV1Store {
                 self.usage_stats.total_entry_size_bytes,
                 self.pruning_strategy.target_capacity_bytes()
             );
-            self.radius = Distance::MAX;
-            self.metrics.report_radius(self.radius);
+            self.set_radius(Distance::MAX);
         }
 
         // Check that distance to the farthest content is what is stored. This is a simple check
@@ -142,10 +190,10 @@ impl IdIndexedV1Store {
         let farthest = self.lookup_farthest()?;
         if let Some(farthest) = farthest {
             let distance = self.distance_to_content_id(&farthest.content_id);
-            if farthest.distance_u32 != distance.big_endian_u32() {
+            if farthest.distance_short != distance.big_endian_u32() {
                 return Err(ContentStoreError::Database(format!(
                     "Distance to the farthest (short: 0x{:08X}) didn't match expected distance ({distance})!",
-                    farthest.distance_u32
+                    farthest.distance_short
                 )));
             }
         }
@@ -171,12 +219,7 @@ impl IdIndexedV1Store {
     pub fn has_content(&self, content_id: &ContentId) -> Result<bool, ContentStoreError> {
         let timer = self.metrics.start_process_timer("has_content");
 
-        let has_content = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .prepare(&sql::lookup_key(&self.config.content_type))?
-            .exists(named_params! { ":content_id": content_id.to_vec() })?;
+        let has_content = self.backend.has_content(content_id)?;
 
         self.metrics.stop_process_timer(timer);
         Ok(has_content)
@@ -190,20 +233,10 @@ impl IdIndexedV1Store {
         let timer = self.metrics.start_process_timer("lookup_content_key");
 
         let key = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .query_row(
-                &sql::lookup_key(&self.config.content_type),
-                named_params! { ":content_id": content_id.to_vec() },
-                |row| {
-                    let bytes: Vec<u8> = row.get("content_key")?;
-                    K::try_from(bytes).map_err(|e| {
-                        rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, e.into())
-                    })
-                },
-            )
-            .optional()?;
+            .backend
+            .lookup_content_key(content_id)?
+            .map(K::try_from)
+            .transpose()?;
 
         self.metrics.stop_process_timer(timer);
         Ok(key)
@@ -216,16 +249,7 @@ impl IdIndexedV1Store {
     ) -> Result<Option<Vec<u8>>, ContentStoreError> {
         let timer = self.metrics.start_process_timer("lookup_content_value");
 
-        let value = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .query_row(
-                &sql::lookup_value(&self.config.content_type),
-                named_params! { ":content_id": content_id.to_vec() },
-                |row| row.get::<&str, Vec<u8>>("content_value"),
-            )
-            .optional()?;
+        let value = self.backend.lookup_content_value(content_id)?;
 
         self.metrics.stop_process_timer(timer);
         Ok(value)
@@ -251,25 +275,21 @@ impl IdIndexedV1Store {
             });
         }
 
-        let content_id = content_id.to_vec();
         let content_key = content_key.to_bytes();
-        let content_size = content_id.len() + content_key.len() + content_value.len();
+        let content_size = (content_id.len() + content_key.len() + content_value.len()) as u64;
 
         let insert_timer = self.metrics.start_process_timer("insert");
-        self.config.sql_connection_pool.get()?.execute(
-            &sql::insert(&self.config.content_type),
-            named_params! {
-                ":content_id": content_id,
-                ":content_key": content_key,
-                ":content_value": content_value,
-                ":distance_short": distance.big_endian_u32(),
-                ":content_size": content_size,
-            },
-        )?;
+        self.backend.insert(BackendEntry {
+            content_id: content_id.into(),
+            content_key,
+            content_value,
+            distance_short: distance.big_endian_u32(),
+            content_size,
+        })?;
         self.metrics.stop_process_timer(insert_timer);
 
         self.usage_stats.entry_count += 1;
-        self.usage_stats.total_entry_size_bytes += content_size as u64;
+        self.usage_stats.total_entry_size_bytes += content_size;
         self.usage_stats.report_metrics(&self.metrics);
 
         if self.pruning_strategy.should_prune(&self.usage_stats) {
@@ -284,22 +304,14 @@ impl IdIndexedV1Store {
     pub fn delete(&mut self, content_id: &ContentId) -> Result<(), ContentStoreError> {
         let timer = self.metrics.start_process_timer("delete");
 
-        let content_size = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .query_row(
-                &sql::delete(&self.config.content_type),
-                named_params! { ":content_id": content_id.to_vec() },
-                |row| row.get::<_, u64>("content_size"),
-            )
-            .optional()?;
+        let content_size = self.backend.delete(content_id)?;
 
         match content_size {
             Some(content_size) => {
                 self.usage_stats.entry_count -= 1;
                 self.usage_stats.total_entry_size_bytes -= content_size;
                 self.usage_stats.report_metrics(&self.metrics);
+                self.maybe_grow_radius();
             }
             None => {
                 debug!(Db = %self.config.content_type,
@@ -312,6 +324,45 @@ impl IdIndexedV1Store {
         Ok(())
     }
 
+    /// Updates the storage capacity.
+    ///
+    /// Prunes the db if new storage capacity is lower than used capacity, or grows the radius if
+    /// there is enough free capacity.
+    pub fn set_storage_capacity(
+        &mut self,
+        storage_capacity_bytes: u64,
+    ) -> Result<(), ContentStoreError> {
+        debug!(
+            Db = %self.config.content_type,
+            "Changing storage capacity: {} -> {storage_capacity_bytes}",
+            self.config.storage_capacity_bytes,
+        );
+        self.config.storage_capacity_bytes = storage_capacity_bytes;
+        self.pruning_strategy
+            .set_storage_capacity_bytes(storage_capacity_bytes);
+        self.metrics
+            .report_storage_capacity_bytes(storage_capacity_bytes as f64);
+
+        if self.pruning_strategy.should_prune(&self.usage_stats) {
+            self.prune()?;
+        } else if self
+            .pruning_strategy
+            .is_usage_above_target_capacity(&self.usage_stats)
+        {
+            self.set_radius_to_farthest()?;
+        } else if storage_capacity_bytes == 0 {
+            self.set_radius(Distance::ZERO);
+        } else {
+            self.maybe_grow_radius();
+        }
+        Ok(())
+    }
+
+    /// Returns the storage capacity.
+    pub fn storage_capacity_bytes(&self) -> u64 {
+        self.config.storage_capacity_bytes
+    }
+
     /// Returns a paginated list of all locally available content keys, according to the provided
     /// offset and limit.
     pub fn paginate<K: ethportal_api::OverlayContentKey>(
@@ -321,22 +372,12 @@ impl IdIndexedV1Store {
     ) -> Result<PaginateResult<K>, ContentStoreError> {
         let timer = self.metrics.start_process_timer("paginate");
 
-        let conn = self.config.sql_connection_pool.get()?;
-        let content_keys = conn
-            .prepare(&sql::paginate(&self.config.content_type))?
-            .query_map(
-                named_params! {
-                    ":limit": limit,
-                    ":offset": offset,
-                },
-                |row| {
-                    let bytes = row.get::<&str, Vec<u8>>("content_key")?;
-                    K::try_from(bytes).map_err(|e| {
-                        rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, e.into())
-                    })
-                },
-            )?
-            .collect::<Result<Vec<K>, rusqlite::Error>>()?;
+        let content_keys = self
+            .backend
+            .paginate(offset, limit)?
+            .into_iter()
+            .map(K::try_from)
+            .collect::<Result<Vec<K>, _>>()?;
 
         self.metrics.stop_process_timer(timer);
         Ok(PaginateResult {
@@ -349,6 +390,34 @@ impl IdIndexedV1Store {
         self.usage_stats.clone()
     }
 
+    /// Checks the integrity of up to `limit` entries, ordered by content id and starting after
+    /// the provided content id.
+    ///
+    /// It checks that content key can be decoded and that content id, distance and content size
+    /// match the stored values.
+    pub fn check_entries<K: ethportal_api::OverlayContentKey>(
+        &self,
+        after: Option<&ContentId>,
+        limit: u64,
+    ) -> Result<Vec<EntryCheck<K>>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("check_entries");
+
+        let checks = self
+            .backend
+            .iterate(after, limit)?
+            .into_iter()
+            .map(|entry| self.check_entry(entry))
+            .collect();
+
+        self.metrics.stop_process_timer(timer);
+        Ok(checks)
+    }
+
+    /// Recalculates usage stats from the stored content.
+    pub fn refresh_usage_stats(&mut self) -> Result<(), ContentStoreError> {
+        self.init_usage_stats()
+    }
+
     /// Returns metrics summary.
     pub fn get_summary_info(&self) -> String {
         let timer = self.metrics.start_process_timer("get_summary_info");
@@ -374,69 +443,137 @@ impl IdIndexedV1Store {
     fn init_usage_stats(&mut self) -> Result<(), ContentStoreError> {
         let timer = self.metrics.start_process_timer("init_usage_stats");
 
-        self.usage_stats = self.config.sql_connection_pool.get()?.query_row(
-            &sql::entry_count_and_size(&self.config.content_type),
-            [],
-            |row| {
-                let entry_count = row.get("count")?;
-                let used_capacity: f64 = row.get("used_capacity")?;
-                Ok(UsageStats::new(entry_count, used_capacity.round() as u64))
-            },
-        )?;
+        self.usage_stats = self.backend.usage_stats()?;
         self.usage_stats.report_metrics(&self.metrics);
 
         self.metrics.stop_process_timer(timer);
         Ok(())
     }
 
+    /// Checks that stored entry is consistent.
+    fn check_entry<K: ethportal_api::OverlayContentKey>(
+        &self,
+        entry: BackendEntry,
+    ) -> EntryCheck<K> {
+        let BackendEntry {
+            content_id,
+            content_key,
+            content_value,
+            distance_short,
+            content_size,
+        } = entry;
+
+        let expected_content_size =
+            (content_id.len() + content_key.len() + content_value.len()) as u64;
+        let content_key = match K::try_from(content_key) {
+            Ok(content_key) => content_key,
+            Err(err) => {
+                return EntryCheck::Corrupt {
+                    content_id,
+                    reason: format!("Invalid content key: {err}"),
+                }
+            }
+        };
+        let expected_content_id = ContentId::from(content_key.content_id());
+        let reason = if content_id != expected_content_id {
+            Some(format!(
+                "Content id doesn't match content key (expected: {})",
+                *expected_content_id
+            ))
+        } else if distance_short != self.distance_to_content_id(&content_id).big_endian_u32() {
+            Some(format!(
+                "Distance (short: 0x{distance_short:08X}) doesn't match distance to content id"
+            ))
+        } else if content_size != expected_content_size {
+            Some(format!(
+                "Content size ({content_size}) doesn't match expected size \
+                ({expected_content_size})"
+            ))
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => EntryCheck::Corrupt { content_id, reason },
+            None => EntryCheck::Consistent {
+                content_id,
+                content_key,
+                content_value,
+            },
+        }
+    }
+
     /// Returns the farthest content in the table.
-    fn lookup_farthest(&self) -> Result<Option<FarthestQueryResult>, ContentStoreError> {
+    fn lookup_farthest(&self) -> Result<Option<FarthestEntry>, ContentStoreError> {
         let timer = self.metrics.start_process_timer("lookup_farthest");
-        let farthest = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .query_row(
-                &sql::lookup_farthest(&self.config.content_type),
-                named_params! { ":limit": 1 },
-                |row| {
-                    Ok(FarthestQueryResult {
-                        content_id: row.get("content_id")?,
-                        distance_u32: row.get("distance_short")?,
-                    })
-                },
-            )
-            .optional()?;
+        let farthest = self.backend.lookup_farthest()?;
 
         self.metrics.stop_process_timer(timer);
         Ok(farthest)
     }
 
+    /// Sets `self.radius` to the provided value, after applying the configured `RadiusConfig`.
+    fn set_radius(&mut self, radius: Distance) {
+        self.radius = self.config.radius_config.apply(radius);
+        self.metrics.report_radius(self.radius);
+    }
+
     /// Sets `self.radius` to the distance to the farthest stored content.
     ///
     /// If no content is found, it sets radius to `Distance::MAX`.
     fn set_radius_to_farthest(&mut self) -> Result<(), ContentStoreError> {
-        match self.lookup_farthest()? {
+        let radius = match self.lookup_farthest()? {
             None => {
                 if self.config.storage_capacity_bytes == 0 {
                     debug!(
                         Db = %self.config.content_type,
                         "Farthest not found and storage capacity is 0",
                     );
-                    self.radius = Distance::ZERO;
+                    Distance::ZERO
                 } else {
                     error!(Db = %self.config.content_type, "Farthest not found!");
-                    self.radius = Distance::MAX;
+                    Distance::MAX
                 }
             }
-            Some(farthest) => {
-                self.radius = self.distance_to_content_id(&farthest.content_id);
-            }
-        }
-        self.metrics.report_radius(self.radius);
+            Some(farthest) => self.distance_to_content_id(&farthest.content_id),
+        };
+        self.set_radius(radius);
         Ok(())
     }
 
+    /// Grows `self.radius` if used capacity is below target capacity.
+    ///
+    /// Assuming that content is uniformly distributed, the used capacity is proportional to the
+    /// radius. Therefore, the radius is scaled by the ratio between target and used capacity.
+    fn maybe_grow_radius(&mut self) {
+        if self.config.storage_capacity_bytes == 0
+            || self
+                .pruning_strategy
+                .is_usage_above_target_capacity(&self.usage_stats)
+        {
+            return;
+        }
+
+        let target_capacity_bytes = self.pruning_strategy.target_capacity_bytes();
+        let used_capacity_bytes = self.usage_stats.total_entry_size_bytes;
+        let radius = if used_capacity_bytes == 0 {
+            Distance::MAX
+        } else {
+            let radius = (*self.radius / U256::from(used_capacity_bytes))
+                .saturating_mul(U256::from(target_capacity_bytes));
+            Distance::from(radius).max(self.radius)
+        };
+
+        if radius != self.radius {
+            debug!(
+                Db = %self.config.content_type,
+                "Used capacity ({used_capacity_bytes}) is below target capacity \
+                ({target_capacity_bytes}) -> Growing radius",
+            );
+            self.set_radius(radius);
+        }
+    }
+
     /// Prunes database and updates `radius`.
     fn prune(&mut self) -> Result<(), ContentStoreError> {
         if !self.pruning_strategy.should_prune(&self.usage_stats) {
@@ -452,9 +589,6 @@ impl IdIndexedV1Store {
             self.usage_stats.total_entry_size_bytes,
         );
 
-        let conn = self.config.sql_connection_pool.get()?;
-        let mut delete_query = conn.prepare(&sql::delete_farthest(&self.config.content_type))?;
-
         while self.pruning_strategy.should_prune(&self.usage_stats) {
             let to_delete = self.pruning_strategy.get_pruning_count(&self.usage_stats);
 
@@ -468,11 +602,7 @@ impl IdIndexedV1Store {
 
             let pruning_start_time = Instant::now();
             let delete_timer = self.metrics.start_process_timer("prune_delete");
-            let deleted_content_sizes = delete_query
-                .query_map(named_params! { ":limit": to_delete }, |row| {
-                    row.get("content_size")
-                })?
-                .collect::<Result<Vec<u64>, rusqlite::Error>>()?;
+            let deleted_content_sizes = self.backend.delete_farthest(to_delete)?;
             self.metrics.stop_process_timer(delete_timer);
             self.pruning_strategy
                 .observe_pruning_duration(pruning_start_time.elapsed());
@@ -489,10 +619,6 @@ impl IdIndexedV1Store {
             self.usage_stats.total_entry_size_bytes -= deleted_content_sizes.iter().sum::<u64>();
             self.usage_stats.report_metrics(&self.metrics);
         }
-        // Free connection.
-        drop(delete_query);
-        drop(conn);
-
         // Update radius to the current farthest content
         self.set_radius_to_farthest()?;
 
@@ -506,27 +632,20 @@ impl IdIndexedV1Store {
     }
 }
 
-/// Creates table and indexes if they don't already exist.
-fn maybe_create_table_and_indexes(
-    content_type: &ContentType,
-    pool: &Pool<SqliteConnectionManager>,
-) -> Result<(), ContentStoreError> {
-    let conn = pool.get()?;
-    conn.execute_batch(&sql::create_table(content_type))?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use discv5::enr::NodeId;
     use ethportal_api::{types::portal_wire::ProtocolId, IdentityContentKey, OverlayContentKey};
     use rand::Rng;
+    use rstest::rstest;
     use tempfile::TempDir;
 
     use crate::{
-        test_utils::generate_random_bytes, utils::setup_sql,
-        versioned::id_indexed_v1::pruning_strategy::PruningConfig, DistanceFunction,
+        test_utils::generate_random_bytes,
+        utils::setup_sql,
+        versioned::id_indexed_v1::{pruning_strategy::PruningConfig, sql},
+        DistanceFunction, RadiusConfig,
     };
 
     use super::*;
@@ -546,6 +665,8 @@ mod tests {
             node_id: NodeId::random(),
             node_data_dir: temp_dir.path().to_path_buf(),
             distance_fn: DistanceFunction::Xor,
+            radius_config: RadiusConfig::default(),
+            backend: StorageBackend::default(),
             sql_connection_pool: setup_sql(temp_dir.path()).unwrap(),
             storage_capacity_bytes,
             pruning_config: PruningConfig::default(),
@@ -578,21 +699,25 @@ mod tests {
 
     // Creates table and content at approximate middle distance (first byte distance is 0.80).
     fn create_and_populate_table(config: &IdIndexedV1StoreConfig, count: u64) -> Result<()> {
-        maybe_create_table_and_indexes(&config.content_type, &config.sql_connection_pool)?;
+        let mut backend = SqliteBackend::new(
+            config.content_type.clone(),
+            config.sql_connection_pool.clone(),
+        )?;
         for _ in 0..count {
             let (key, value) = generate_key_value(config, 0x80);
             let id = key.content_id();
-            let content_size = id.len() + key.to_bytes().len() + value.len();
-            config
-                .sql_connection_pool
-                .get()?
-                .execute(&sql::insert(&config.content_type), named_params! {
-                    ":content_id": id.as_slice(),
-                    ":content_key": key.to_bytes(),
-                    ":content_value": value,
-                    ":distance_short": config.distance_fn.distance(&config.node_id, &id).big_endian_u32(),
-                    ":content_size": content_size,
-                })?;
+            let content_key = key.to_bytes();
+            let content_size = (id.len() + content_key.len() + value.len()) as u64;
+            backend.insert(BackendEntry {
+                content_id: id.into(),
+                content_key,
+                content_value: value,
+                distance_short: config
+                    .distance_fn
+                    .distance(&config.node_id, &id)
+                    .big_endian_u32(),
+                content_size,
+            })?;
         }
         Ok(())
     }
@@ -824,10 +949,15 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn prune_simple() -> Result<()> {
+    #[rstest]
+    fn prune_simple(
+        #[values(StorageBackend::Sqlite, StorageBackend::Segment)] backend: StorageBackend,
+    ) -> Result<()> {
         let temp_dir = TempDir::new()?;
-        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let config = IdIndexedV1StoreConfig {
+            backend,
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
         let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
 
         assert_eq!(store.radius(), Distance::MAX);
@@ -886,6 +1016,89 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn segment_backend_reopen() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            backend: StorageBackend::Segment,
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+
+        let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+        let mut content = vec![];
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value.clone())?;
+            content.push((key, value));
+        }
+        let (deleted_key, _) = content.remove(0);
+        store.delete(&deleted_key.content_id().into())?;
+        let usage_stats = store.usage_stats();
+        drop(store);
+
+        // Check that content and usage stats are restored from the segment file
+        let store = IdIndexedV1Store::create(ContentType::State, config)?;
+        assert_eq!(store.usage_stats(), usage_stats);
+        assert!(!store.has_content(&deleted_key.content_id().into())?);
+        for (key, value) in content {
+            let id = ContentId::from(key.content_id());
+            assert_eq!(store.lookup_content_key(&id)?, Some(key));
+            assert_eq!(store.lookup_content_value(&id)?, Some(value));
+        }
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::sqlite_to_segment(StorageBackend::Sqlite, StorageBackend::Segment)]
+    #[case::segment_to_sqlite(StorageBackend::Segment, StorageBackend::Sqlite)]
+    fn switch_backend(
+        #[case] old_backend: StorageBackend,
+        #[case] new_backend: StorageBackend,
+    ) -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            backend: old_backend,
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+
+        let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+        let mut content = vec![];
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value.clone())?;
+            content.push((key, value));
+        }
+        let usage_stats = store.usage_stats();
+        drop(store);
+
+        // Check that the content is moved into the new backend
+        let config = IdIndexedV1StoreConfig {
+            backend: new_backend,
+            ..config
+        };
+        let store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+        assert_eq!(store.usage_stats(), usage_stats);
+        for (key, value) in &content {
+            let id = ContentId::from(key.content_id());
+            assert_eq!(store.lookup_content_value(&id)?, Some(value.clone()));
+        }
+
+        // Check that the content was deleted from the old backend
+        let old_usage_stats = match old_backend {
+            StorageBackend::Sqlite => {
+                SqliteBackend::new(ContentType::State, config.sql_connection_pool.clone())?
+                    .usage_stats()?
+            }
+            StorageBackend::Segment => {
+                SegmentBackend::new(&ContentType::State, &config.node_data_dir)?.usage_stats()?
+            }
+        };
+        assert_eq!(old_usage_stats, UsageStats::default());
+
+        Ok(())
+    }
+
     #[test]
     fn prune_different_sizes_elements() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1070,6 +1283,133 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn radius_grows_after_delete() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+
+        // Insert 101 keys, which triggers pruning down to 95 elements (target capacity)
+        let mut keys = vec![];
+        for _ in 0..101 {
+            let (key, value) = generate_key_value(&config, 0x80);
+            store.insert(&key, value)?;
+            keys.push(key);
+        }
+        assert_eq!(store.usage_stats.entry_count, 95);
+        let pruned_radius = store.radius();
+        assert!(pruned_radius < Distance::MAX);
+
+        // Delete 20 keys that are still stored and check that radius increased
+        let mut deleted = 0;
+        for key in keys {
+            let id = ContentId::from(key.content_id());
+            if deleted < 20 && store.has_content(&id)? {
+                store.delete(&id)?;
+                deleted += 1;
+            }
+        }
+        assert_eq!(store.usage_stats.entry_count, 75);
+        assert!(store.radius() > pruned_radius);
+
+        Ok(())
+    }
+
+    #[test]
+    fn radius_static() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let radius = Distance::from(U256::from(0xFF) << 248);
+        let config = IdIndexedV1StoreConfig {
+            radius_config: RadiusConfig::Static(radius),
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+        assert_eq!(store.radius(), radius);
+
+        // Insert more than storage capacity and check that radius didn't change
+        for _ in 0..150 {
+            let (key, value) = generate_key_value(&config, 0x80);
+            store.insert(&key, value)?;
+        }
+        assert!(store.usage_stats.total_entry_size_bytes <= config.storage_capacity_bytes);
+        assert_eq!(store.radius(), radius);
+
+        Ok(())
+    }
+
+    #[test]
+    fn radius_dynamic_with_min_radius() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let min_radius = Distance::from(U256::from(0xF0) << 248);
+        let config = IdIndexedV1StoreConfig {
+            radius_config: RadiusConfig::Dynamic { min_radius },
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+        assert_eq!(store.radius(), Distance::MAX);
+
+        // Insert more than storage capacity at close distance, so farthest is below min radius
+        for _ in 0..150 {
+            let (key, value) = generate_key_value(&config, 0x10);
+            store.insert(&key, value)?;
+        }
+        assert!(store.usage_stats.total_entry_size_bytes <= config.storage_capacity_bytes);
+        assert_eq!(store.radius(), min_radius);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lower_storage_capacity() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+
+        // fill 50% of storage with 50 items, 1% each
+        create_and_populate_table(&config, 50)?;
+        let mut store = IdIndexedV1Store::create(ContentType::State, config)?;
+        assert_eq!(store.radius(), Distance::MAX);
+
+        // Lower storage capacity to 40 items and check that we pruned to target capacity
+        store.set_storage_capacity(40 * CONTENT_DEFAULT_SIZE_BYTES)?;
+        assert_eq!(
+            store.storage_capacity_bytes(),
+            40 * CONTENT_DEFAULT_SIZE_BYTES
+        );
+        assert_eq!(store.usage_stats.entry_count, 38);
+        assert_eq!(
+            store.usage_stats.total_entry_size_bytes,
+            store.pruning_strategy.target_capacity_bytes()
+        );
+        assert!(store.radius() < Distance::MAX);
+
+        // Lower storage capacity to 0 and check that everything is pruned
+        store.set_storage_capacity(0)?;
+        assert_eq!(store.usage_stats, UsageStats::default());
+        assert_eq!(store.radius(), Distance::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raise_storage_capacity() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+
+        // fill storage above full capacity, which triggers pruning
+        create_and_populate_table(&config, 110)?;
+        let mut store = IdIndexedV1Store::create(ContentType::State, config)?;
+        let pruned_radius = store.radius();
+        assert!(pruned_radius < Distance::MAX);
+
+        // Raise storage capacity and check that radius increased and nothing is pruned
+        let usage_stats = store.usage_stats();
+        store.set_storage_capacity(STORAGE_CAPACITY_10000_ITEMS)?;
+        assert_eq!(store.usage_stats(), usage_stats);
+        assert!(store.radius() > pruned_radius);
+
+        Ok(())
+    }
+
     #[test]
     fn pagination_empty() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1157,4 +1497,64 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn check_entries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store = IdIndexedV1Store::create(ContentType::State, config.clone())?;
+
+        let mut content_ids = vec![];
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value)?;
+            content_ids.push(ContentId::from(key.content_id()));
+        }
+        content_ids.sort_by_key(|content_id| **content_id);
+
+        // Corrupt two entries
+        let conn = config.sql_connection_pool.get()?;
+        let table_name = sql::table_name(&config.content_type);
+        conn.execute(
+            &format!("UPDATE {table_name} SET content_size = 1 WHERE content_id = ?1"),
+            [content_ids[2].to_vec()],
+        )?;
+        conn.execute(
+            &format!("UPDATE {table_name} SET distance_short = 0 WHERE content_id = ?1"),
+            [content_ids[7].to_vec()],
+        )?;
+
+        // Check in two pages
+        let mut checks = store.check_entries::<IdentityContentKey>(None, 5)?;
+        let last_content_id = match checks.last() {
+            Some(EntryCheck::Consistent { content_id, .. }) => content_id.clone(),
+            other => panic!("Expected consistent entry, got: {other:?}"),
+        };
+        checks.extend(store.check_entries::<IdentityContentKey>(Some(&last_content_id), 5)?);
+        assert_eq!(checks.len(), 10);
+
+        let corrupt = checks
+            .iter()
+            .filter_map(|check| match check {
+                EntryCheck::Consistent { .. } => None,
+                EntryCheck::Corrupt { content_id, .. } => Some(content_id.clone()),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            corrupt,
+            vec![content_ids[2].clone(), content_ids[7].clone()]
+        );
+
+        // Delete corrupt entries and refresh usage stats
+        for content_id in &corrupt {
+            store.delete(content_id)?;
+        }
+        store.refresh_usage_stats()?;
+        assert_eq!(
+            store.usage_stats(),
+            UsageStats::new(8, 8 * CONTENT_DEFAULT_SIZE_BYTES)
+        );
+
+        Ok(())
+    }
 }
@@ -1,3 +1,4 @@
+mod beacon_v1;
 mod id_indexed_v1;
 pub mod sql;
 pub mod store;
@@ -7,7 +8,10 @@ mod utils;
 use rusqlite::types::{FromSql, FromSqlError, ValueRef};
 use strum::{AsRefStr, Display, EnumString};
 
-pub use id_indexed_v1::{IdIndexedV1Store, IdIndexedV1StoreConfig};
+pub use beacon_v1::{BeaconV1Store, BeaconV1StoreConfig};
+pub use id_indexed_v1::{
+    EntryCheck, IdIndexedV1Store, IdIndexedV1StoreConfig, PruningConfig, StorageBackend,
+};
 pub use store::VersionedContentStore;
 pub use utils::create_store;
 
@@ -21,6 +25,7 @@ pub use utils::create_store;
 pub enum ContentType {
     History,
     State,
+    Beacon,
 }
 
 /// The version of the store. There should be exactly one implementation of the
@@ -37,8 +42,13 @@ pub enum StoreVersion {
     /// The content from different subnetwork (expressed with `ContentType`)
     /// uses different table. NOTE: implementation is in progress.
     IdIndexedV1,
+    /// The store for the beacon network content (light client bootstraps and updates), that
+    /// prunes content by the sync committee period.
+    BeaconV1,
     /// The original SQLite version of the history storage store.
     LegacyHistory,
+    /// The original SQLite version of the beacon storage store.
+    LegacyBeacon,
 }
 
 impl FromSql for StoreVersion {
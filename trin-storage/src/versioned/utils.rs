@@ -64,6 +64,16 @@ fn get_default_store_version(
                 Ok(None)
             }
         }
+        ContentType::Beacon => {
+            let exists = conn
+                .prepare(sql::TABLE_EXISTS)?
+                .exists(named_params! {":table_name": "beacon"})?;
+            if exists {
+                Ok(Some(StoreVersion::LegacyBeacon))
+            } else {
+                Ok(None)
+            }
+        }
         _ => Ok(None),
     }
 }
@@ -119,6 +129,22 @@ pub mod test {
         Ok(())
     }
 
+    #[test]
+    fn get_store_version_default_beacon() -> Result<()> {
+        let (_temp_dir, config) =
+            create_test_portal_storage_config_with_capacity(STORAGE_CAPACITY_MB)?;
+        let conn = config.sql_connection_pool.get()?;
+
+        let create_dummy_beacon_table_sql = "CREATE TABLE beacon (content_id blob PRIMARY KEY);";
+        conn.execute(create_dummy_beacon_table_sql, [])?;
+
+        assert_eq!(
+            get_store_version(&ContentType::Beacon, &conn)?,
+            Some(StoreVersion::LegacyBeacon)
+        );
+        Ok(())
+    }
+
     #[test]
     fn insert_store_verion() -> Result<()> {
         let (_temp_dir, config) =
@@ -1,5 +1,8 @@
 use alloy_primitives::{B256, U256};
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
@@ -35,6 +38,21 @@ pub type HistoricalEpochRoots = VariableList<tree_hash::Hash256, typenum::U13107
 #[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, Deserialize, Serialize, TreeHash)]
 pub struct PreMergeAccumulator {
     pub historical_epochs: HistoricalEpochRoots,
+    /// The number of the merge block of the chain, which isn't part of the SSZ container.
+    #[ssz(skip_serializing, skip_deserializing)]
+    #[tree_hash(skip_hashing)]
+    #[serde(skip)]
+    merge_block: MergeBlock,
+}
+
+/// The number of the merge block, which defaults to the mainnet merge block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct MergeBlock(u64);
+
+impl Default for MergeBlock {
+    fn default() -> Self {
+        Self(MERGE_BLOCK_NUMBER)
+    }
 }
 
 impl Default for PreMergeAccumulator {
@@ -57,9 +75,36 @@ impl PreMergeAccumulator {
             .map_err(|err| anyhow!("Unable to decode pre-merge accumulator: {err:?}"))
     }
 
+    /// Loads a pre-merge accumulator, e.g. of a private network, from a file on disk.
+    pub fn try_from_path(path: &Path) -> anyhow::Result<PreMergeAccumulator> {
+        let raw = fs::read(path).map_err(|err| {
+            anyhow!(
+                "Unable to read pre-merge accumulator at path {}: {err}",
+                path.display()
+            )
+        })?;
+        PreMergeAccumulator::from_ssz_bytes(&raw)
+            .map_err(|err| anyhow!("Unable to decode pre-merge accumulator: {err:?}"))
+    }
+
+    /// Sets the number of the merge block of the chain, which is the mainnet merge block unless
+    /// set.
+    pub fn with_merge_block(mut self, merge_block: u64) -> Self {
+        self.merge_block = MergeBlock(merge_block);
+        self
+    }
+
     /// Number of the last block to be included in the accumulator
     pub fn height(&self) -> u64 {
-        MERGE_BLOCK_NUMBER
+        self.merge_block.0
+    }
+
+    /// Returns the hash of the epoch accumulator of the epoch.
+    pub(crate) fn epoch_hash(&self, epoch_index: u64) -> anyhow::Result<B256> {
+        self.historical_epochs
+            .get(epoch_index as usize)
+            .copied()
+            .ok_or_else(|| anyhow!("Epoch {epoch_index} is not in the pre-merge accumulator"))
     }
 
     pub(crate) fn get_epoch_index_of_header(&self, header: &Header) -> u64 {
@@ -71,12 +116,12 @@ impl PreMergeAccumulator {
         block_number: u64,
         history_jsonrpc_tx: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     ) -> anyhow::Result<B256> {
-        if block_number > MERGE_BLOCK_NUMBER {
+        if block_number > self.height() {
             return Err(anyhow!("Post-merge blocks are not supported."));
         }
         let rel_index = block_number % EPOCH_SIZE;
         let epoch_index = block_number / EPOCH_SIZE;
-        let epoch_hash = self.historical_epochs[epoch_index as usize];
+        let epoch_hash = self.epoch_hash(epoch_index)?;
         let epoch_acc = self
             .lookup_epoch_acc(epoch_hash, history_jsonrpc_tx)
             .await?;
@@ -120,19 +165,17 @@ impl PreMergeAccumulator {
         header: &Header,
         history_jsonrpc_tx: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     ) -> anyhow::Result<[B256; 15]> {
-        if header.number > MERGE_BLOCK_NUMBER {
+        if header.number > self.height() {
             return Err(anyhow!("Unable to generate proof for post-merge header."));
         }
         // Fetch epoch accumulator for header
         let epoch_index = self.get_epoch_index_of_header(header);
-        let epoch_hash = self.historical_epochs[epoch_index as usize];
+        let epoch_hash = self.epoch_hash(epoch_index)?;
         let epoch_acc = self
             .lookup_epoch_acc(epoch_hash, history_jsonrpc_tx)
             .await?;
 
         // Validate epoch accumulator hash matches historical hash from pre-merge accumulator
-        let epoch_index = self.get_epoch_index_of_header(header);
-        let epoch_hash = self.historical_epochs[epoch_index as usize];
         if epoch_acc.tree_hash_root() != epoch_hash {
             return Err(anyhow!(
                 "Epoch acc hash sourced from network doesn't match historical hash in pre-merge acc."
@@ -1,21 +1,24 @@
 use crate::{
     accumulator::PreMergeAccumulator,
-    constants::{
-        CAPELLA_FORK_EPOCH, EPOCH_SIZE, MERGE_BLOCK_NUMBER, SHANGHAI_BLOCK_NUMBER, SLOTS_PER_EPOCH,
-    },
+    constants::{EPOCH_SIZE, SLOTS_PER_EPOCH},
     historical_roots_acc::HistoricalRootsAccumulator,
     merkle::proof::verify_merkle_proof,
 };
 use alloy_primitives::B256;
-use anyhow::anyhow;
+use anyhow::{anyhow, ensure};
 use ethportal_api::{
     consensus::historical_summaries::HistoricalSummaries,
-    types::execution::header_with_proof::{
-        BeaconBlockBodyProof, BeaconBlockHeaderProof, BlockHeaderProof, HeaderWithProof,
-        HistoricalRootsBlockProof, HistoricalSummariesBlockProof,
+    types::{
+        execution::header_with_proof::{
+            BeaconBlockBodyProof, BeaconBlockHeaderProof, BlockHeaderProof, HeaderWithProof,
+            HistoricalRootsBlockProof, HistoricalSummariesBlockProof,
+        },
+        network_spec::ForkParams,
+        portal_wire::NetworkSpec,
     },
     Header,
 };
+use tree_hash::TreeHash;
 
 /// HeaderValidator is responsible for validating pre-merge and post-merge headers with their
 /// respective proofs.
@@ -25,6 +28,10 @@ pub struct HeaderValidator {
     pub pre_merge_acc: PreMergeAccumulator,
     /// Historical roots accumulator used to validate post-merge/pre-Capella headers.
     pub historical_roots_acc: HistoricalRootsAccumulator,
+    /// The forks of the chain, which determine the proofs that headers are validated with.
+    pub forks: ForkParams,
+    /// The expected hash of the genesis header, if the network sets one.
+    pub genesis_hash: Option<B256>,
 }
 
 impl HeaderValidator {
@@ -35,20 +42,63 @@ impl HeaderValidator {
         Self {
             pre_merge_acc,
             historical_roots_acc,
+            forks: ForkParams::default(),
+            genesis_hash: None,
+        }
+    }
+
+    /// Creates a validator for the chain of the network, with the pre-merge accumulator, forks and
+    /// genesis hash of its spec.
+    pub fn new_for_network(network: &NetworkSpec) -> anyhow::Result<Self> {
+        let pre_merge_acc = match network.pre_merge_acc_path() {
+            Some(path) => PreMergeAccumulator::try_from_path(path)?,
+            None => PreMergeAccumulator::default(),
+        };
+        if let Some(expected_hash) = network.pre_merge_acc_hash() {
+            let hash = pre_merge_acc.tree_hash_root();
+            ensure!(
+                hash == expected_hash,
+                "Pre-merge accumulator hash {hash} doesn't match the hash of the network spec {expected_hash}"
+            );
         }
+        let forks = network.forks();
+        ensure!(
+            forks.merge_block < forks.shanghai_block,
+            "Merge block {} must be before the Shanghai block {}",
+            forks.merge_block,
+            forks.shanghai_block
+        );
+        let epochs = pre_merge_acc.historical_epochs.len() as u64;
+        ensure!(
+            epochs == forks.merge_block / EPOCH_SIZE + 1,
+            "Pre-merge accumulator has {epochs} epochs, which don't end at the merge block {}",
+            forks.merge_block
+        );
+
+        Ok(Self {
+            pre_merge_acc: pre_merge_acc.with_merge_block(forks.merge_block),
+            historical_roots_acc: HistoricalRootsAccumulator::default(),
+            forks: *forks,
+            genesis_hash: network.genesis_hash(),
+        })
     }
 
     pub fn validate_header_with_proof(&self, hwp: &HeaderWithProof) -> anyhow::Result<()> {
+        if let Some(genesis_hash) = self.genesis_hash {
+            ensure!(
+                hwp.header.number != 0 || hwp.header.hash() == genesis_hash,
+                "Genesis header doesn't match the genesis hash of the network"
+            );
+        }
         match &hwp.proof {
             BlockHeaderProof::PreMergeAccumulatorProof(proof) => {
-                if hwp.header.number > MERGE_BLOCK_NUMBER {
+                if hwp.header.number > self.forks.merge_block {
                     return Err(anyhow!("Invalid proof type found for post-merge header."));
                 }
                 // Look up historical epoch hash for header from pre-merge accumulator
                 let gen_index = calculate_generalized_index(&hwp.header);
-                let epoch_index =
-                    self.pre_merge_acc.get_epoch_index_of_header(&hwp.header) as usize;
-                let epoch_hash = self.pre_merge_acc.historical_epochs[epoch_index];
+                let epoch_index = self.pre_merge_acc.get_epoch_index_of_header(&hwp.header);
+                let epoch_hash = self.pre_merge_acc.epoch_hash(epoch_index)?;
 
                 match verify_merkle_proof(
                     hwp.header.hash(),
@@ -64,7 +114,7 @@ impl HeaderValidator {
                 }
             }
             BlockHeaderProof::None(_) => {
-                if hwp.header.number <= MERGE_BLOCK_NUMBER {
+                if hwp.header.number <= self.forks.merge_block {
                     Err(anyhow!("Missing accumulator proof for pre-merge header."))
                 } else {
                     // Skip validation for post-merge headers until proof format is finalized
@@ -74,7 +124,7 @@ impl HeaderValidator {
             BlockHeaderProof::HistoricalRootsBlockProof(proof) => self
                 .verify_post_merge_pre_capella_header(hwp.header.number, hwp.header.hash(), proof),
             BlockHeaderProof::HistoricalSummariesBlockProof(_) => {
-                if hwp.header.number < SHANGHAI_BLOCK_NUMBER {
+                if hwp.header.number < self.forks.shanghai_block {
                     return Err(anyhow!(
                         "Invalid HistoricalSummariesBlockProof found for pre-Shanghai header."
                     ));
@@ -92,12 +142,12 @@ impl HeaderValidator {
         header_hash: B256,
         proof: &HistoricalRootsBlockProof,
     ) -> anyhow::Result<()> {
-        if block_number <= MERGE_BLOCK_NUMBER {
+        if block_number <= self.forks.merge_block {
             return Err(anyhow!(
                 "Invalid HistoricalRootsBlockProof found for pre-merge header."
             ));
         }
-        if block_number >= SHANGHAI_BLOCK_NUMBER {
+        if block_number >= self.forks.shanghai_block {
             return Err(anyhow!(
                 "Invalid HistoricalRootsBlockProof found for post-Shanghai header."
             ));
@@ -145,7 +195,7 @@ impl HeaderValidator {
         proof: &HistoricalSummariesBlockProof,
         historical_summaries: HistoricalSummaries,
     ) -> anyhow::Result<()> {
-        if block_number < SHANGHAI_BLOCK_NUMBER {
+        if block_number < self.forks.shanghai_block {
             return Err(anyhow!(
                 "Invalid HistoricalSummariesBlockProof found for pre-Shanghai header."
             ));
@@ -166,7 +216,7 @@ impl HeaderValidator {
         let block_root_index = proof.slot % EPOCH_SIZE;
         let gen_index = EPOCH_SIZE + block_root_index;
         let historical_summary_index =
-            (proof.slot - CAPELLA_FORK_EPOCH * SLOTS_PER_EPOCH) / EPOCH_SIZE;
+            (proof.slot - self.forks.capella_epoch * SLOTS_PER_EPOCH) / EPOCH_SIZE;
         let historical_summary =
             historical_summaries[historical_summary_index as usize].block_summary_root;
 
@@ -231,7 +281,7 @@ fn calculate_generalized_index(header: &Header) -> u64 {
 #[allow(clippy::unwrap_used)]
 mod test {
     use super::*;
-    use std::{fs, str::FromStr};
+    use std::{env, fs, path::PathBuf, str::FromStr};
 
     use alloy_primitives::{Address, Bloom, B256, U256};
     use alloy_rlp::Decodable;
@@ -239,9 +289,8 @@ mod test {
     use serde_json::{json, Value};
     use ssz::{Decode, Encode};
     use tokio::sync::mpsc;
-    use tree_hash::TreeHash;
 
-    use crate::constants::DEFAULT_PRE_MERGE_ACC_HASH;
+    use crate::constants::{DEFAULT_PRE_MERGE_ACC_HASH, MERGE_BLOCK_NUMBER, SHANGHAI_BLOCK_NUMBER};
     use ethportal_api::{
         types::{
             execution::{
@@ -251,6 +300,7 @@ mod test {
                 },
             },
             jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
+            portal_wire::MAINNET,
         },
         utils::bytes::{hex_decode, hex_encode},
         HistoryContentKey,
@@ -517,6 +567,72 @@ mod test {
         assert!(validator_result.is_err());
     }
 
+    #[test]
+    fn header_validator_for_mainnet() {
+        let header_validator = HeaderValidator::new_for_network(&MAINNET).unwrap();
+        assert_eq!(header_validator, get_mainnet_header_validator());
+    }
+
+    #[tokio::test]
+    async fn header_validator_uses_forks_of_network() {
+        let acc_path = write_pre_merge_acc(1_000_000);
+        let network = devnet_spec(&format!(
+            "pre-merge-accumulator-path = \"{}\"\n[forks]\nmerge-block = 1000000",
+            acc_path.display()
+        ));
+        let header_validator = HeaderValidator::new_for_network(&network).unwrap();
+        fs::remove_file(&acc_path).unwrap();
+        assert_eq!(header_validator.pre_merge_acc.height(), 1_000_000);
+        let hwp = HeaderWithProof {
+            header: get_header(1_000_001),
+            proof: BlockHeaderProof::None(SszNone::default()),
+        };
+        header_validator.validate_header_with_proof(&hwp).unwrap();
+    }
+
+    #[test]
+    fn header_validator_rejects_pre_merge_acc_not_ending_at_merge_block() {
+        let network = devnet_spec("[forks]\nmerge-block = 1000000");
+        let err = HeaderValidator::new_for_network(&network).unwrap_err();
+        assert!(err.to_string().contains("don't end at the merge block"));
+    }
+
+    #[test]
+    fn header_validator_rejects_merge_block_after_shanghai() {
+        let network = devnet_spec(&format!("[forks]\nshanghai-block = {MERGE_BLOCK_NUMBER}"));
+        let err = HeaderValidator::new_for_network(&network).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("must be before the Shanghai block"));
+    }
+
+    #[test]
+    fn header_validator_rejects_pre_merge_acc_hash_mismatch() {
+        let network = devnet_spec(&format!("pre-merge-accumulator-hash = \"{}\"", B256::ZERO));
+        let err = HeaderValidator::new_for_network(&network).unwrap_err();
+        assert!(err.to_string().contains("doesn't match the hash"));
+    }
+
+    #[test]
+    fn header_validator_validates_genesis_hash() {
+        let genesis = generate_random_header(&0);
+        let network = devnet_spec(&format!("genesis-hash = \"{}\"", genesis.hash()));
+        let header_validator = HeaderValidator::new_for_network(&network).unwrap();
+
+        let proof = BlockHeaderProof::PreMergeAccumulatorProof(PreMergeAccumulatorProof {
+            proof: [B256::ZERO; 15],
+        });
+        let hwp = HeaderWithProof {
+            header: generate_random_header(&0),
+            proof,
+        };
+        assert!(header_validator
+            .validate_header_with_proof(&hwp)
+            .unwrap_err()
+            .to_string()
+            .contains("Genesis header doesn't match"));
+    }
+
     //
     // Testing utils
     //
@@ -529,6 +645,31 @@ mod test {
         header_validator
     }
 
+    /// Returns the spec of a devnet, with the extra lines of the spec file.
+    fn devnet_spec(extra: &str) -> NetworkSpec {
+        format!(
+            "name = \"devnet\"\nchain-id = 7\n{extra}\n\
+            [protocol-ids]\nstate = \"0x5F0A\"\nhistory = \"0x5F0B\"\nbeacon = \"0x5F0C\"\n"
+        )
+        .parse()
+        .unwrap()
+    }
+
+    /// Writes the epochs of the mainnet pre-merge accumulator up to the merge block to a file.
+    fn write_pre_merge_acc(merge_block: u64) -> PathBuf {
+        let mut pre_merge_acc = PreMergeAccumulator::default();
+        pre_merge_acc.historical_epochs = pre_merge_acc.historical_epochs
+            [..=(merge_block / EPOCH_SIZE) as usize]
+            .to_vec()
+            .into();
+        let path = env::temp_dir().join(format!(
+            "trin-pre-merge-acc-{}-{merge_block}.bin",
+            std::process::id()
+        ));
+        fs::write(&path, pre_merge_acc.as_ssz_bytes()).unwrap();
+        path
+    }
+
     pub(crate) fn get_header(number: u64) -> Header {
         let file = fs::read_to_string("./src/assets/header_rlps.json").unwrap();
         let json: Value = serde_json::from_str(&file).unwrap();
@@ -12,6 +12,7 @@ use ethportal_api::{
             endpoints::HistoryEndpoint,
             request::{BeaconJsonRpcRequest, HistoryJsonRpcRequest},
         },
+        portal_wire::NetworkSpec,
     },
     BlockHeaderKey, HistoryContentKey, HistoryContentValue,
 };
@@ -44,6 +45,15 @@ impl HeaderOracle {
         }
     }
 
+    /// Creates an oracle that validates the chain of the network.
+    pub fn new_for_network(network: &NetworkSpec) -> anyhow::Result<Self> {
+        Ok(Self {
+            history_jsonrpc_tx: None,
+            beacon_jsonrpc_tx: None,
+            header_validator: HeaderValidator::new_for_network(network)?,
+        })
+    }
+
     // Only serves pre-block hashes aka. portal-network verified data only
     pub async fn get_hash_at_height(&self, block_number: u64) -> anyhow::Result<B256> {
         self.header_validator
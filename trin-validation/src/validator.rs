@@ -1,3 +1,4 @@
+#[cfg(any(test, feature = "test-utils"))]
 use ethportal_api::types::content_key::overlay::IdentityContentKey;
 
 /// The result of the content key/value validation.
@@ -54,8 +55,10 @@ pub trait Validator<TContentKey: Send> {
 }
 
 /// For use in tests where no validation needs to be performed.
+#[cfg(any(test, feature = "test-utils"))]
 pub struct MockValidator {}
 
+#[cfg(any(test, feature = "test-utils"))]
 impl Validator<IdentityContentKey> for MockValidator {
     async fn validate_content(
         &self,
@@ -20,6 +20,7 @@ async fn main() {
         udp_listen_address,
         config.rpc_listen_address,
         config.rpc_port,
+        config.utp_conn_tuning,
     )
     .await
     .unwrap();
@@ -1,39 +1,188 @@
-use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder, rpc_params};
+use clap::Parser;
+use jsonrpsee::{
+    core::client::ClientT,
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
 use rand::{thread_rng, Rng};
 use trin_utils::log::init_tracing_logger;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use ethportal_api::utils::bytes::hex_encode;
+use ethportal_api::{types::utp_tuning::UtpConnectionTuning, utils::bytes::hex_encode};
+use utp_testing::DEFAULT_TEST_APP_UTP_CONN_TUNING;
 
 const SERVER_ADDR: &str = "193.167.100.100:9041";
 const CLIENT_ADDR: &str = "193.167.0.100:9042";
 
+/// The time to wait for a payload, on top of the time to connect and to transfer the payload at
+/// the minimum throughput.
+const PAYLOAD_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+/// The throughput below which a transfer is considered stuck, in bytes per second.
+const MIN_PAYLOAD_THROUGHPUT: u64 = 10_000;
+
+/// The interval at which the client and server are polled for the status of the transfer.
+const PAYLOAD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The max packet sizes of the sweep, in bytes.
+const SWEEP_MAX_PACKET_SIZES: [u16; 2] = [512, 1024];
+
+/// The initial timeouts of the sweep, in milliseconds.
+const SWEEP_INITIAL_TIMEOUTS_MS: [u64; 3] = [500, 1250, 3000];
+
+/// The idle timeouts of the sweep, in milliseconds.
+const SWEEP_IDLE_TIMEOUTS_MS: [u64; 2] = [16000, 32000];
+
+/// The window sizes of the sweep, in bytes. `None` is the default of the uTP implementation.
+const SWEEP_WINDOW_SIZES: [Option<u32>; 2] = [None, Some(256 * 1024)];
+
+/// The maximum connection attempts of the sweep.
+const SWEEP_MAX_CONN_ATTEMPTS: [usize; 2] = [3, 6];
+
+#[derive(Debug, Parser)]
+#[command(name = "uTP test suite CLI")]
+struct TestSuiteConfig {
+    /// Sweep the uTP connection tuning, instead of using the defaults of the test apps. Combined
+    /// with a network simulator scenario, this measures which tuning works best under the
+    /// simulated loss and latency.
+    #[arg(long)]
+    sweep: bool,
+
+    /// The size of the payload sent from the client to the server, in bytes.
+    #[arg(long, default_value_t = 10_000)]
+    payload_size: usize,
+}
+
 /// Test suite for testing uTP protocol with network simulator
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_tracing_logger();
-    send_10k_bytes().await?;
+    let config = TestSuiteConfig::parse();
+
+    let client_rpc = HttpClientBuilder::default().build(format!("http://{CLIENT_ADDR}"))?;
+    let server_rpc = HttpClientBuilder::default().build(format!("http://{SERVER_ADDR}"))?;
+
+    if !config.sweep {
+        let elapsed = send_payload(
+            &client_rpc,
+            &server_rpc,
+            config.payload_size,
+            &DEFAULT_TEST_APP_UTP_CONN_TUNING,
+        )
+        .await?;
+        println!(
+            "Sent {} bytes uTP payload from client to server in {elapsed:?}: OK",
+            config.payload_size
+        );
+        return Ok(());
+    }
+
+    let mut results = vec![];
+    for tuning in sweep_tunings() {
+        set_utp_conn_tuning(&client_rpc, &tuning).await?;
+        set_utp_conn_tuning(&server_rpc, &tuning).await?;
+        let result = send_payload(&client_rpc, &server_rpc, config.payload_size, &tuning).await;
+        if let Err(err) = &result {
+            println!("Failed to send uTP payload with {tuning:?}: {err}");
+            // Abort the transfer, so that it doesn't skew the timing of the next tuning.
+            cancel_utp_transfers(&client_rpc).await?;
+            cancel_utp_transfers(&server_rpc).await?;
+        }
+        results.push((tuning, result));
+    }
+
+    println!(
+        "Sweep results of sending {} bytes uTP payload from client to server:",
+        config.payload_size
+    );
+    for (tuning, result) in results {
+        let result = match result {
+            Ok(elapsed) => format!("OK in {elapsed:?}"),
+            Err(_) => "FAILED".to_string(),
+        };
+        println!(
+            "max_packet_size={:?} initial_timeout_ms={:?} idle_timeout_ms={:?} window_size={:?} max_conn_attempts={:?}: {result}",
+            tuning.max_packet_size,
+            tuning.initial_timeout_ms,
+            tuning.idle_timeout_ms,
+            tuning.window_size,
+            tuning.max_conn_attempts,
+        );
+    }
 
     Ok(())
 }
 
-/// Send 10k bytes payload from client to server
-async fn send_10k_bytes() -> anyhow::Result<()> {
-    println!("Sending 10k bytes uTP payload from client to server...");
-    let client_url = format!("http://{CLIENT_ADDR}");
-    let client_rpc = HttpClientBuilder::default().build(client_url)?;
-    let client_enr: String = client_rpc
-        .request("local_enr", rpc_params![])
-        .await
-        .unwrap();
-
-    let server_url = format!("http://{SERVER_ADDR}");
-    let server_rpc = HttpClientBuilder::default().build(server_url)?;
-    let server_enr: String = server_rpc
-        .request("local_enr", rpc_params![])
-        .await
-        .unwrap();
+/// Returns the tunings of the sweep, on top of the defaults of the test apps.
+fn sweep_tunings() -> Vec<UtpConnectionTuning> {
+    let mut tunings = vec![];
+    for max_packet_size in SWEEP_MAX_PACKET_SIZES {
+        for initial_timeout_ms in SWEEP_INITIAL_TIMEOUTS_MS {
+            for idle_timeout_ms in SWEEP_IDLE_TIMEOUTS_MS {
+                for window_size in SWEEP_WINDOW_SIZES {
+                    for max_conn_attempts in SWEEP_MAX_CONN_ATTEMPTS {
+                        tunings.push(DEFAULT_TEST_APP_UTP_CONN_TUNING.merge(
+                            &UtpConnectionTuning {
+                                max_packet_size: Some(max_packet_size),
+                                initial_timeout_ms: Some(initial_timeout_ms),
+                                idle_timeout_ms: Some(idle_timeout_ms),
+                                max_conn_attempts: Some(max_conn_attempts),
+                                window_size,
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    tunings
+}
+
+async fn set_utp_conn_tuning(rpc: &HttpClient, tuning: &UtpConnectionTuning) -> anyhow::Result<()> {
+    let response: String = rpc
+        .request("set_utp_conn_tuning", rpc_params!(tuning))
+        .await?;
+    anyhow::ensure!(response == "true", "Unexpected response: {response}");
+    Ok(())
+}
+
+async fn cancel_utp_transfers(rpc: &HttpClient) -> anyhow::Result<()> {
+    let response: String = rpc.request("cancel_utp_transfers", rpc_params![]).await?;
+    anyhow::ensure!(response == "true", "Unexpected response: {response}");
+    Ok(())
+}
+
+/// Returns the status of the uTP transfer with the receive connection ID.
+async fn utp_transfer_status(rpc: &HttpClient, cid_recv: u16) -> anyhow::Result<String> {
+    Ok(rpc
+        .request("get_utp_transfer_status", rpc_params!(cid_recv))
+        .await?)
+}
+
+/// Returns the time after which the transfer of the payload is considered failed: the time to
+/// connect, plus the time to transfer the payload at the minimum throughput, with one idle timeout
+/// to recover from packet loss.
+fn payload_timeout(payload_size: usize, tuning: &UtpConnectionTuning) -> Duration {
+    let initial_timeout = Duration::from_millis(tuning.initial_timeout_ms.unwrap_or_default());
+    let idle_timeout = Duration::from_millis(tuning.idle_timeout_ms.unwrap_or_default());
+    let max_conn_attempts = tuning.max_conn_attempts.unwrap_or_default() as u32;
+    let transfer_time =
+        Duration::from_secs_f64(payload_size as f64 / MIN_PAYLOAD_THROUGHPUT as f64);
+    initial_timeout * max_conn_attempts + idle_timeout + transfer_time + PAYLOAD_TIMEOUT_MARGIN
+}
+
+/// Sends a random payload from client to server, and returns the time it took for the transfer to
+/// complete on both sides.
+async fn send_payload(
+    client_rpc: &HttpClient,
+    server_rpc: &HttpClient,
+    payload_size: usize,
+    tuning: &UtpConnectionTuning,
+) -> anyhow::Result<Duration> {
+    println!("Sending {payload_size} bytes uTP payload from client to server...");
+    let client_enr: String = client_rpc.request("local_enr", rpc_params![]).await?;
+    let server_enr: String = server_rpc.request("local_enr", rpc_params![]).await?;
 
     let client_cid_recv: u16 = thread_rng().gen();
     let client_cid_send = client_cid_recv.wrapping_add(1);
@@ -44,38 +193,41 @@ async fn send_10k_bytes() -> anyhow::Result<()> {
 
     // Add client enr to allowed server uTP connections
     let params = rpc_params!(client_enr, server_cid_send, server_cid_recv);
-    let response: String = server_rpc.request("prepare_to_recv", params).await.unwrap();
-    assert_eq!(response, "true");
+    let response: String = server_rpc.request("prepare_to_recv", params).await?;
+    anyhow::ensure!(response == "true", "Unexpected response: {response}");
 
     // Send uTP payload from client to server
-    let payload: Vec<u8> = vec![thread_rng().gen(); 10_000];
-
-    let params = rpc_params!(
-        server_enr,
-        client_cid_send,
-        client_cid_recv,
-        payload.clone()
-    );
-    let response: String = client_rpc
-        .request("send_utp_payload", params)
-        .await
-        .unwrap();
-
-    assert_eq!(response, "true");
-
-    // Sleep to allow time for uTP transmission
-    tokio::time::sleep(Duration::from_secs(16)).await;
-
-    // Verify received uTP payload
-    let utp_payload: String = server_rpc
-        .request("get_utp_payload", rpc_params![])
-        .await
-        .unwrap();
-    let expected_payload = hex_encode(payload);
-
-    assert_eq!(expected_payload, utp_payload);
-
-    println!("Sent 10k bytes uTP payload from client to server: OK");
-
-    Ok(())
+    let payload: Vec<u8> = (0..payload_size).map(|_| thread_rng().gen()).collect();
+    let expected_payload = hex_encode(&payload);
+
+    let started = Instant::now();
+    let params = rpc_params!(server_enr, client_cid_send, client_cid_recv, payload);
+    let response: String = client_rpc.request("send_utp_payload", params).await?;
+    anyhow::ensure!(response == "true", "Unexpected response: {response}");
+
+    // Poll the client and server until the transfer completed or failed on either side.
+    let deadline = started + payload_timeout(payload_size, tuning);
+    loop {
+        let sent = utp_transfer_status(client_rpc, client_cid_recv).await?;
+        let received = utp_transfer_status(server_rpc, server_cid_recv).await?;
+        if let Some(err) = sent.strip_prefix("failed: ") {
+            anyhow::bail!("Client failed to send the uTP payload: {err}");
+        }
+        if let Some(err) = received.strip_prefix("failed: ") {
+            anyhow::bail!("Server failed to receive the uTP payload: {err}");
+        }
+        if sent == "completed" && received == "completed" {
+            let elapsed = started.elapsed();
+            let utp_payload: String = server_rpc.request("get_utp_payload", rpc_params![]).await?;
+            anyhow::ensure!(
+                utp_payload == expected_payload,
+                "Server received a different uTP payload"
+            );
+            return Ok(elapsed);
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!("uTP payload wasn't transferred in {:?}", started.elapsed());
+        }
+        tokio::time::sleep(PAYLOAD_POLL_INTERVAL).await;
+    }
 }
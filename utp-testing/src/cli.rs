@@ -1,4 +1,5 @@
 use clap::Parser;
+use ethportal_api::types::utp_tuning::UtpConnectionTuning;
 
 #[derive(Debug, Parser)]
 #[command(name = "utP test app CLI")]
@@ -14,4 +15,8 @@ pub struct TestAppConfig {
 
     #[arg(long, required = true)]
     pub rpc_port: u16,
+
+    /// The tuning of the uTP connections, which overrides the defaults of the test app.
+    #[command(flatten)]
+    pub utp_conn_tuning: UtpConnectionTuning,
 }
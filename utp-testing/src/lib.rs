@@ -11,6 +11,7 @@ use ethportal_api::{
     types::{
         enr::Enr,
         portal_wire::{ProtocolId, MAINNET},
+        utp_tuning::UtpConnectionTuning,
     },
     utils::bytes::{hex_encode, hex_encode_upper},
 };
@@ -23,13 +24,51 @@ use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, UtpEnr},
     utils::db::setup_temp_dir,
+    utp_controller::utp_conn_config,
 };
-use std::{io::ErrorKind, net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
-use tokio::sync::{
-    mpsc::{self, Receiver},
-    RwLock,
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
 };
-use utp_rs::{conn::ConnectionConfig, socket::UtpSocket};
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver},
+        RwLock,
+    },
+    task::JoinHandle,
+};
+use utp_rs::{cid::ConnectionId, conn::ConnectionConfig, socket::UtpSocket};
+
+/// The default tuning of the uTP connections of the test app.
+pub const DEFAULT_TEST_APP_UTP_CONN_TUNING: UtpConnectionTuning = UtpConnectionTuning {
+    max_packet_size: Some(1024),
+    initial_timeout_ms: Some(1250),
+    idle_timeout_ms: Some(16000),
+    max_conn_attempts: Some(3),
+    window_size: None,
+};
+
+/// The status of a uTP transfer of the test app.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransferStatus {
+    Pending,
+    Completed,
+    Failed(String),
+}
+
+impl fmt::Display for TransferStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Completed => write!(f, "completed"),
+            Self::Failed(err) => write!(f, "failed: {err}"),
+        }
+    }
+}
 
 /// uTP test app
 pub struct TestApp {
@@ -37,6 +76,11 @@ pub struct TestApp {
     pub utp_socket: Arc<UtpSocket<UtpEnr>>,
     pub utp_talk_req_tx: mpsc::UnboundedSender<TalkRequest>,
     pub utp_payload: Arc<RwLock<Vec<Vec<u8>>>>,
+    pub utp_conn_tuning: Arc<RwLock<UtpConnectionTuning>>,
+    /// The status of the uTP transfers, by receive connection ID.
+    pub utp_transfers: Arc<RwLock<HashMap<u16, TransferStatus>>>,
+    /// The tasks of the uTP transfers, aborted by `cancel_utp_transfers`.
+    pub utp_transfer_tasks: Arc<RwLock<Vec<JoinHandle<()>>>>,
 }
 
 #[async_trait]
@@ -71,33 +115,23 @@ impl RpcServer for TestApp {
 
         let utp = Arc::clone(&self.utp_socket);
         let payload_store = Arc::clone(&self.utp_payload);
-        tokio::spawn(async move {
-            let utp_config = ConnectionConfig {
-                max_packet_size: 1024,
-                max_conn_attempts: 3,
-                max_idle_timeout: Duration::from_secs(16),
-                initial_timeout: Duration::from_millis(1250),
-                ..Default::default()
-            };
-            let mut conn = utp.accept_with_cid(cid, utp_config).await.unwrap();
-            let mut data = vec![];
-            let n = conn.read_to_eof(&mut data).await.unwrap();
-
-            tracing::info!("read {n} bytes from uTP stream");
-
-            // Since switching to one-way FIN-ACK, closing after reading is not allowed. We only
-            // explicitly close after write() now, and close after reading should error.
-            match conn.close().await {
-                Ok(_) => panic!("Closing after reading should have errored, but succeeded"),
-                Err(e) => {
-                    // The stream will already be disconnected by the read_to_eof() call, so we
-                    // expect a NotConnected error here.
-                    assert_eq!(e.kind(), ErrorKind::NotConnected);
+        let utp_config = utp_conn_config(&*self.utp_conn_tuning.read().await);
+        let transfers = Arc::clone(&self.utp_transfers);
+        transfers
+            .write()
+            .await
+            .insert(cid_recv, TransferStatus::Pending);
+        let task = tokio::spawn(async move {
+            let status = match recv_payload(&utp, cid, utp_config).await {
+                Ok(data) => {
+                    payload_store.write().await.push(data);
+                    TransferStatus::Completed
                 }
-            }
-
-            payload_store.write().await.push(data);
+                Err(err) => TransferStatus::Failed(err.to_string()),
+            };
+            transfers.write().await.insert(cid_recv, status);
         });
+        self.utp_transfer_tasks.write().await.push(task);
 
         Ok("true".to_string())
     }
@@ -118,25 +152,92 @@ impl RpcServer for TestApp {
         self.discovery.add_enr(dst_enr).unwrap();
 
         let utp = Arc::clone(&self.utp_socket);
-        let utp_config = ConnectionConfig {
-            max_packet_size: 1024,
-            max_conn_attempts: 3,
-            max_idle_timeout: Duration::from_secs(16),
-            initial_timeout: Duration::from_millis(1250),
-            ..Default::default()
-        };
-        tokio::spawn(async move {
-            let mut conn = utp.connect_with_cid(cid, utp_config).await.unwrap();
+        let utp_config = utp_conn_config(&*self.utp_conn_tuning.read().await);
+        let transfers = Arc::clone(&self.utp_transfers);
+        transfers
+            .write()
+            .await
+            .insert(cid_recv, TransferStatus::Pending);
+        let task = tokio::spawn(async move {
+            let status = match send_payload(&utp, cid, utp_config, &payload).await {
+                Ok(()) => TransferStatus::Completed,
+                Err(err) => TransferStatus::Failed(err.to_string()),
+            };
+            transfers.write().await.insert(cid_recv, status);
+        });
+        self.utp_transfer_tasks.write().await.push(task);
 
-            conn.write(&payload).await.unwrap();
+        Ok("true".to_string())
+    }
 
-            conn.close().await.unwrap();
-        });
+    async fn get_utp_transfer_status(&self, cid_recv: u16) -> RpcResult<String> {
+        let status = self
+            .utp_transfers
+            .read()
+            .await
+            .get(&cid_recv)
+            .map(ToString::to_string)
+            .unwrap_or_else(|| TransferStatus::Failed("unknown transfer".to_string()).to_string());
+        Ok(status)
+    }
+
+    async fn cancel_utp_transfers(&self) -> RpcResult<String> {
+        for task in self.utp_transfer_tasks.write().await.drain(..) {
+            task.abort();
+        }
+        for status in self.utp_transfers.write().await.values_mut() {
+            if *status == TransferStatus::Pending {
+                *status = TransferStatus::Failed("cancelled".to_string());
+            }
+        }
+        Ok("true".to_string())
+    }
 
+    async fn set_utp_conn_tuning(&self, tuning: UtpConnectionTuning) -> RpcResult<String> {
+        tracing::info!(?tuning, "set uTP connection tuning");
+        *self.utp_conn_tuning.write().await = DEFAULT_TEST_APP_UTP_CONN_TUNING.merge(&tuning);
         Ok("true".to_string())
     }
 }
 
+/// Accepts the uTP connection and reads the payload until the sender closes the stream.
+async fn recv_payload(
+    utp: &UtpSocket<UtpEnr>,
+    cid: ConnectionId<UtpEnr>,
+    config: ConnectionConfig,
+) -> io::Result<Vec<u8>> {
+    let mut conn = utp.accept_with_cid(cid, config).await?;
+    let mut data = vec![];
+    let n = conn.read_to_eof(&mut data).await?;
+
+    tracing::info!("read {n} bytes from uTP stream");
+
+    // Since switching to one-way FIN-ACK, closing after reading is not allowed. We only
+    // explicitly close after write() now, and close after reading should error.
+    match conn.close().await {
+        Ok(_) => Err(io::Error::new(
+            ErrorKind::Other,
+            "Closing after reading should have errored, but succeeded",
+        )),
+        // The stream will already be disconnected by the read_to_eof() call, so we expect a
+        // NotConnected error here.
+        Err(err) if err.kind() == ErrorKind::NotConnected => Ok(data),
+        Err(err) => Err(err),
+    }
+}
+
+/// Connects to the peer, and writes the payload to the uTP stream.
+async fn send_payload(
+    utp: &UtpSocket<UtpEnr>,
+    cid: ConnectionId<UtpEnr>,
+    config: ConnectionConfig,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut conn = utp.connect_with_cid(cid, config).await?;
+    conn.write(payload).await?;
+    conn.close().await
+}
+
 impl TestApp {
     pub async fn start(&self, mut talk_req_rx: Receiver<TalkRequest>) {
         let utp_talk_reqs_tx = self.utp_talk_req_tx.clone();
@@ -162,6 +263,7 @@ pub async fn run_test_app(
     socket_addr: SocketAddr,
     rpc_addr: String,
     rpc_port: u16,
+    utp_conn_tuning: UtpConnectionTuning,
 ) -> anyhow::Result<(SocketAddr, Enr, ServerHandle)> {
     let config = PortalnetConfig {
         listen_port: udp_port,
@@ -186,6 +288,11 @@ pub async fn run_test_app(
         utp_socket,
         utp_talk_req_tx,
         utp_payload: Arc::new(RwLock::new(Vec::new())),
+        utp_conn_tuning: Arc::new(RwLock::new(
+            DEFAULT_TEST_APP_UTP_CONN_TUNING.merge(&utp_conn_tuning),
+        )),
+        utp_transfers: Arc::new(RwLock::new(HashMap::new())),
+        utp_transfer_tasks: Arc::new(RwLock::new(Vec::new())),
     };
 
     test_app.start(talk_req_rx).await;
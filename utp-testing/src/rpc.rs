@@ -1,4 +1,5 @@
 use crate::{rpc, RpcResult};
+use ethportal_api::types::utp_tuning::UtpConnectionTuning;
 
 /// JSON-RPC endpoint for client and server
 #[rpc(server, client)]
@@ -21,4 +22,18 @@ pub trait Rpc {
         cid_recv: u16,
         payload: Vec<u8>,
     ) -> RpcResult<String>;
+
+    /// Returns the status of the uTP transfer with the receive connection ID: `pending`,
+    /// `completed` or `failed: <error>`.
+    #[method(name = "get_utp_transfer_status")]
+    async fn get_utp_transfer_status(&self, cid_recv: u16) -> RpcResult<String>;
+
+    /// Aborts the uTP transfers that are still running.
+    #[method(name = "cancel_utp_transfers")]
+    async fn cancel_utp_transfers(&self) -> RpcResult<String>;
+
+    /// Sets the tuning of the uTP connections that are established after the call, on top of the
+    /// defaults of the test app.
+    #[method(name = "set_utp_conn_tuning")]
+    async fn set_utp_conn_tuning(&self, tuning: UtpConnectionTuning) -> RpcResult<String>;
 }